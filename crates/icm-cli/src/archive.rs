@@ -10,8 +10,8 @@
 //! under the same row.
 
 use icm_core::transcript::Role;
-use icm_core::TranscriptStore;
-use icm_store::Store;
+use icm_core::{IcmError, TranscriptStore};
+use icm_store::{SessionContextInsert, Store};
 use serde_json::Value;
 
 use crate::config::ArchiveConfig;
@@ -69,6 +69,28 @@ pub fn agent_label_from_env() -> String {
     "unknown".into()
 }
 
+/// Resolve the hook's session id and make sure its `sessions` row
+/// exists (agent label, project from `cwd`). `None` when the payload
+/// carries no id or the row can't be created — callers skip quietly.
+fn ensure_hook_session(store: &Store, json: &Value) -> Option<String> {
+    let session_id = session_id_from_stdin(json)?;
+
+    let project = json
+        .get("cwd")
+        .and_then(|v| v.as_str())
+        .map(|p| std::fs::canonicalize(p).unwrap_or_else(|_| std::path::PathBuf::from(p)))
+        .as_ref()
+        .and_then(|p| p.file_name())
+        .map(|n| n.to_string_lossy().to_string());
+
+    let agent = agent_label_from_env();
+    if let Err(e) = store.ensure_session(&session_id, &agent, project.as_deref(), None) {
+        eprintln!("[icm archive] ensure_session failed: {e}");
+        return None;
+    }
+    Some(session_id)
+}
+
 /// Archive a single event. No-op when `[archive].enabled = false`.
 ///
 /// Errors are swallowed and logged on stderr so a failure to archive
@@ -85,27 +107,52 @@ pub fn record_event(
     if !cfg.enabled || content.is_empty() {
         return;
     }
-    let Some(session_id) = session_id_from_stdin(json) else {
+    let Some(session_id) = ensure_hook_session(store, json) else {
         return;
     };
 
-    let project = json
-        .get("cwd")
-        .and_then(|v| v.as_str())
-        .map(|p| std::fs::canonicalize(p).unwrap_or_else(|_| std::path::PathBuf::from(p)))
-        .as_ref()
-        .and_then(|p| p.file_name())
-        .map(|n| n.to_string_lossy().to_string());
+    let capped = cap_bytes(content, cfg.effective_max_bytes());
+    if let Err(e) = store.record_message(&session_id, role, capped, tool_name, None, None) {
+        eprintln!("[icm archive] record_message failed: {e}");
+    }
+}
 
-    let agent = agent_label_from_env();
-    if let Err(e) = store.ensure_session(&session_id, &agent, project.as_deref(), None) {
-        eprintln!("[icm archive] ensure_session failed: {e}");
+/// Snapshot what one hook fire injected into the session and how many
+/// facts it extracted. No-op when `[archive].context = false` or when
+/// the fire neither injected nor extracted anything.
+///
+/// Same contract as [`record_event`]: errors are logged, never raised.
+/// Backends without a context log (Postgres, OpenSearch) are skipped
+/// silently — that's a deployment choice, not a hook failure.
+#[allow(clippy::too_many_arguments)]
+pub fn record_context(
+    store: &Store,
+    cfg: &ArchiveConfig,
+    json: &Value,
+    event: &str,
+    query: Option<&str>,
+    injected: &str,
+    memory_ids: &[String],
+    facts_extracted: usize,
+) {
+    if !cfg.context || (injected.is_empty() && facts_extracted == 0) {
         return;
     }
+    let Some(session_id) = ensure_hook_session(store, json) else {
+        return;
+    };
 
-    let capped = cap_bytes(content, cfg.effective_max_bytes());
-    if let Err(e) = store.record_message(&session_id, role, capped, tool_name, None, None) {
-        eprintln!("[icm archive] record_message failed: {e}");
+    let ctx = SessionContextInsert {
+        session_id,
+        event: event.to_string(),
+        query: query.filter(|q| !q.is_empty()).map(str::to_string),
+        injected: cap_bytes(injected, cfg.effective_max_bytes()).to_string(),
+        memory_ids: memory_ids.to_vec(),
+        facts_extracted,
+    };
+    match store.record_session_context(&ctx) {
+        Ok(_) | Err(IcmError::Unsupported(_)) => {}
+        Err(e) => eprintln!("[icm archive] record_session_context failed: {e}"),
     }
 }
 
@@ -169,6 +216,7 @@ mod tests {
         let cfg = ArchiveConfig {
             enabled: true,
             max_bytes_per_event: 0,
+            ..Default::default()
         };
         let v: Value = serde_json::from_str(r#"{"cwd":"/tmp"}"#).unwrap();
         record_event(&store, &cfg, &v, Role::User, "hello", None);
@@ -185,6 +233,7 @@ mod tests {
         let cfg = ArchiveConfig {
             enabled: true,
             max_bytes_per_event: 0,
+            ..Default::default()
         };
         let v: Value = serde_json::from_str(r#"{"session_id":"s1","cwd":"/tmp"}"#).unwrap();
         record_event(&store, &cfg, &v, Role::User, "first turn", None);
@@ -209,6 +258,7 @@ mod tests {
         let cfg = ArchiveConfig {
             enabled: true,
             max_bytes_per_event: 16,
+            ..Default::default()
         };
         let v: Value = serde_json::from_str(r#"{"session_id":"s1","cwd":"/tmp"}"#).unwrap();
         record_event(
@@ -227,4 +277,36 @@ mod tests {
             msgs[0].content.len()
         );
     }
+
+    #[test]
+    fn record_context_logs_injection_even_with_archive_off() {
+        let store = Store::in_memory().unwrap();
+        let cfg = ArchiveConfig::default(); // enabled = false, context = true
+        let v: Value = serde_json::from_str(r#"{"session_id":"s1","cwd":"/tmp"}"#).unwrap();
+        let ids = vec!["m1".to_string()];
+        record_context(&store, &cfg, &v, "prompt", Some("q"), "- fact", &ids, 0);
+        // Nothing injected, nothing extracted: no row.
+        record_context(&store, &cfg, &v, "post", None, "", &[], 0);
+        record_context(&store, &cfg, &v, "post", None, "", &[], 3);
+
+        let rows = store.session_context("s1").unwrap();
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0].memory_ids, ids);
+        assert_eq!(rows[1].facts_extracted, 3);
+        // The session row records the agent and start time for `icm sessions`.
+        assert_eq!(store.list_sessions(None, 10).unwrap().len(), 1);
+        assert!(store.list_session_messages("s1", 10, 0).unwrap().is_empty());
+    }
+
+    #[test]
+    fn record_context_is_noop_when_disabled() {
+        let store = Store::in_memory().unwrap();
+        let cfg = ArchiveConfig {
+            context: false,
+            ..Default::default()
+        };
+        let v: Value = serde_json::from_str(r#"{"session_id":"s1","cwd":"/tmp"}"#).unwrap();
+        record_context(&store, &cfg, &v, "start", None, "pack", &[], 0);
+        assert!(store.list_sessions(None, 10).unwrap().is_empty());
+    }
 }
@@ -0,0 +1,413 @@
+//! Long-horizon memory quality benchmark (`icm bench-decay`).
+//!
+//! Replays a synthetic stream of agent sessions over N simulated days on
+//! an in-memory store: seeded facts are stored on day 0 and recalled now
+//! and then, every session adds tool-output noise, and every day ends
+//! with a decay pass, a prune and (optionally) a consolidation sweep —
+//! the real store operations, not a model of them. The report tracks how
+//! many seeded facts survive against how much noise does, so decay and
+//! prune defaults can be tuned on evidence instead of intuition.
+//!
+//! Unlike `icm decay --simulate`, which projects the user's own memories
+//! forward without writes or recalls, this runs a closed-loop scenario
+//! and never touches the user's database.
+
+use std::collections::HashSet;
+
+use anyhow::Result;
+use icm_core::{Importance, Memory, MemoryStore, Reinforcement};
+use icm_store::Store;
+
+/// Seeded facts recalled per session.
+const RECALLS_PER_SESSION: usize = 3;
+
+/// Share of recalls that go to the "hot" half of the seeded facts. The
+/// cold half is what decay defaults are really tuned against: facts
+/// that matter but rarely come up.
+const HOT_RECALL_SHARE: f64 = 0.8;
+
+const FACT_TOPIC: &str = "decisions-sim";
+const NOISE_TOPIC: &str = "context-sim";
+
+#[derive(Debug, Clone)]
+pub struct DecayBenchOptions {
+    pub days: u32,
+    pub sessions_per_day: usize,
+    pub facts: usize,
+    pub noise_per_session: usize,
+    pub factor: f32,
+    pub threshold: f32,
+    /// Consolidate topics that reach this many memories at the end of
+    /// each day; 0 disables consolidation.
+    pub consolidate: usize,
+    pub reinforcement: Reinforcement,
+    pub seed: u64,
+}
+
+/// Store state at the end of one simulated day.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Checkpoint {
+    pub day: u32,
+    pub memories: usize,
+    pub hot_kept: usize,
+    pub cold_kept: usize,
+    pub noise_kept: usize,
+    pub noise_stored: usize,
+}
+
+#[derive(Debug, Clone)]
+pub struct DecayBenchReport {
+    pub opts: DecayBenchOptions,
+    pub checkpoints: Vec<Checkpoint>,
+    /// Seeded facts per importance after the last day, most important
+    /// first: (importance, total, kept). Levels with no fact are left out.
+    pub by_importance: Vec<(Importance, usize, usize)>,
+}
+
+/// Public entry point used by `main.rs` to dispatch the CLI command.
+pub fn cmd_bench_decay(opts: DecayBenchOptions) -> Result<()> {
+    let report = simulate(opts)?;
+    print!("{}", report.render());
+    Ok(())
+}
+
+/// Run the scenario and collect a checkpoint every quarter of the run.
+pub fn simulate(opts: DecayBenchOptions) -> Result<DecayBenchReport> {
+    let store = Store::in_memory()?.with_reinforcement(opts.reinforcement);
+    let mut rng = Rng::new(opts.seed);
+
+    let facts = seed_facts(opts.facts);
+    let mut fact_ids = Vec::with_capacity(facts.len());
+    for m in &facts {
+        fact_ids.push(store.store(m.clone())?);
+    }
+    let hot = fact_ids.len().div_ceil(2);
+
+    let checkpoint_days = checkpoint_days(opts.days);
+    let mut checkpoints = Vec::new();
+    let mut noise_stored = 0;
+    for day in 1..=opts.days {
+        for _ in 0..opts.sessions_per_day {
+            // Recall: the store only reinforces ids that still exist, so
+            // a pruned fact simply stops being found.
+            if !fact_ids.is_empty() {
+                let recalled: Vec<&str> = (0..RECALLS_PER_SESSION)
+                    .map(|_| {
+                        let i = if hot == fact_ids.len() || rng.chance(HOT_RECALL_SHARE) {
+                            rng.below(hot)
+                        } else {
+                            hot + rng.below(fact_ids.len() - hot)
+                        };
+                        fact_ids[i].as_str()
+                    })
+                    .collect();
+                store.batch_update_access(&recalled)?;
+            }
+            for _ in 0..opts.noise_per_session {
+                store.store(noise_memory(noise_stored, &mut rng))?;
+                noise_stored += 1;
+            }
+        }
+
+        store.apply_decay(opts.factor)?;
+        store.prune(opts.threshold)?;
+        if opts.consolidate > 0 {
+            for (topic, count) in store.list_topics()? {
+                if count >= opts.consolidate {
+                    store.auto_consolidate_with_opts(&topic, opts.consolidate, None, false)?;
+                }
+            }
+        }
+
+        if checkpoint_days.contains(&day) {
+            let markers = surviving_markers(&store)?;
+            let kept = |range: std::ops::Range<usize>| {
+                range.filter(|&i| markers.contains(&fact_marker(i))).count()
+            };
+            checkpoints.push(Checkpoint {
+                day,
+                memories: store.count()?,
+                hot_kept: kept(0..hot),
+                cold_kept: kept(hot..facts.len()),
+                noise_kept: (0..noise_stored)
+                    .filter(|&i| markers.contains(&noise_marker(i)))
+                    .count(),
+                noise_stored,
+            });
+        }
+    }
+
+    let markers = surviving_markers(&store)?;
+    let by_importance = [
+        Importance::Critical,
+        Importance::High,
+        Importance::Medium,
+        Importance::Low,
+    ]
+    .into_iter()
+    .map(|imp| {
+        let seeded: Vec<usize> = (0..facts.len())
+            .filter(|&i| facts[i].importance == imp)
+            .collect();
+        let kept = seeded
+            .iter()
+            .filter(|&&i| markers.contains(&fact_marker(i)))
+            .count();
+        (imp, seeded.len(), kept)
+    })
+    .filter(|&(_, total, _)| total > 0)
+    .collect();
+
+    Ok(DecayBenchReport {
+        opts,
+        checkpoints,
+        by_importance,
+    })
+}
+
+/// Days reported: each quarter of the run.
+fn checkpoint_days(days: u32) -> Vec<u32> {
+    let mut out: Vec<u32> = (1..=4).map(|q| (days * q).div_ceil(4)).collect();
+    out.retain(|&d| d > 0);
+    out.dedup();
+    out
+}
+
+/// Seeded facts: one in ten critical, three in ten high, the rest
+/// medium; every fifth is a preference, the others project decisions.
+fn seed_facts(n: usize) -> Vec<Memory> {
+    (0..n)
+        .map(|i| {
+            let importance = match i % 10 {
+                0 => Importance::Critical,
+                1..=3 => Importance::High,
+                _ => Importance::Medium,
+            };
+            let topic = if i % 5 == 4 {
+                "preferences"
+            } else {
+                FACT_TOPIC
+            };
+            Memory::new(
+                topic.into(),
+                format!(
+                    "Decision {}: keep the retry budget per endpoint",
+                    fact_marker(i)
+                ),
+                importance,
+            )
+        })
+        .collect()
+}
+
+/// Tool-output noise, the kind auto-extraction stores: two in three low
+/// importance, the rest medium.
+fn noise_memory(i: usize, rng: &mut Rng) -> Memory {
+    let importance = if rng.chance(2.0 / 3.0) {
+        Importance::Low
+    } else {
+        Importance::Medium
+    };
+    Memory::new(
+        NOISE_TOPIC.into(),
+        format!(
+            "Ran the test suite, {} warnings {}",
+            rng.below(20),
+            noise_marker(i)
+        ),
+        importance,
+    )
+}
+
+fn fact_marker(i: usize) -> String {
+    format!("fact-{i:05}")
+}
+
+fn noise_marker(i: usize) -> String {
+    format!("noise-{i:06}")
+}
+
+/// Every marker still present in some summary. Consolidation folds
+/// summaries together, so a fact survives as long as its text does.
+fn surviving_markers(store: &Store) -> Result<HashSet<String>> {
+    const PAGE: usize = 500;
+    let mut markers = HashSet::new();
+    let mut offset = 0;
+    loop {
+        let page = store.get_all(PAGE, offset)?;
+        offset += page.len();
+        for m in &page {
+            markers.extend(
+                m.summary
+                    .split(|c: char| c.is_whitespace() || c == '|' || c == ':')
+                    .filter(|w| w.starts_with("fact-") || w.starts_with("noise-"))
+                    .map(str::to_string),
+            );
+        }
+        if page.len() < PAGE {
+            break;
+        }
+    }
+    Ok(markers)
+}
+
+fn pct(part: usize, whole: usize) -> f64 {
+    if whole == 0 {
+        100.0
+    } else {
+        part as f64 * 100.0 / whole as f64
+    }
+}
+
+impl DecayBenchReport {
+    pub fn render(&self) -> String {
+        let o = &self.opts;
+        let hot = o.facts.div_ceil(2);
+        let cold = o.facts - hot;
+        let mut out = format!(
+            "Decay benchmark: {} days x {} sessions, {} seeded facts ({hot} hot / {cold} cold), \
+             {} noise memories per session\n",
+            o.days, o.sessions_per_day, o.facts, o.noise_per_session
+        );
+        out.push_str(&format!(
+            "factor {}, prune threshold {}, consolidation {}, reinforcement {}\n",
+            o.factor,
+            o.threshold,
+            if o.consolidate > 0 {
+                format!("at {} memories", o.consolidate)
+            } else {
+                "off".into()
+            },
+            if o.reinforcement.is_off() {
+                "off".into()
+            } else {
+                o.reinforcement.rate.to_string()
+            },
+        ));
+
+        out.push_str("\n   day  memories  facts kept     hot    cold  noise kept\n");
+        for c in &self.checkpoints {
+            out.push_str(&format!(
+                "  {:>4}  {:>8}  {:>9.1}%  {:>5.1}%  {:>5.1}%  {:>9.1}%\n",
+                c.day,
+                c.memories,
+                pct(c.hot_kept + c.cold_kept, o.facts),
+                pct(c.hot_kept, hot),
+                pct(c.cold_kept, cold),
+                pct(c.noise_kept, c.noise_stored),
+            ));
+        }
+
+        if !self.by_importance.is_empty() {
+            out.push_str(&format!(
+                "\nSeeded facts by importance, after {} days:\n  importance  total  kept\n",
+                o.days
+            ));
+            for (imp, total, kept) in &self.by_importance {
+                out.push_str(&format!(
+                    "  {:<10}  {:>5}  {:>4}\n",
+                    imp.to_string(),
+                    total,
+                    kept
+                ));
+            }
+        }
+        out.push_str(
+            "\nA good setting keeps the facts (cold ones especially) while noise fades.\n",
+        );
+        out
+    }
+}
+
+/// xorshift64*: deterministic per seed, no dependency, plenty for picking
+/// recalls and importances. Shared with `icm bench`'s fixture.
+pub(crate) struct Rng(u64);
+
+impl Rng {
+    pub(crate) fn new(seed: u64) -> Self {
+        // Zero is xorshift's fixed point.
+        Self(seed.max(1))
+    }
+
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        self.0.wrapping_mul(0x2545_f491_4f6c_dd1d)
+    }
+
+    pub(crate) fn below(&mut self, n: usize) -> usize {
+        (self.next() % n as u64) as usize
+    }
+
+    fn chance(&mut self, p: f64) -> bool {
+        (self.next() >> 11) as f64 / (1u64 << 53) as f64 <= p
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn opts() -> DecayBenchOptions {
+        DecayBenchOptions {
+            days: 60,
+            sessions_per_day: 2,
+            facts: 20,
+            noise_per_session: 5,
+            factor: 0.95,
+            threshold: 0.1,
+            consolidate: 0,
+            reinforcement: Reinforcement::default(),
+            seed: 7,
+        }
+    }
+
+    #[test]
+    fn checkpoints_are_quarter_days() {
+        assert_eq!(checkpoint_days(90), vec![23, 45, 68, 90]);
+        assert_eq!(checkpoint_days(2), vec![1, 2]);
+        assert!(checkpoint_days(0).is_empty());
+    }
+
+    #[test]
+    fn facts_outlive_noise_with_default_settings() {
+        let report = simulate(opts()).unwrap();
+        assert_eq!(report.checkpoints.len(), 4);
+        let last = report.checkpoints.last().unwrap();
+        assert_eq!(last.day, 60);
+        assert_eq!(last.noise_stored, 60 * 2 * 5);
+        let facts_kept = pct(last.hot_kept + last.cold_kept, 20);
+        let noise_kept = pct(last.noise_kept, last.noise_stored);
+        assert!(
+            facts_kept > noise_kept,
+            "facts {facts_kept}% vs noise {noise_kept}%"
+        );
+        // Critical and high facts are never pruned.
+        for (imp, total, kept) in &report.by_importance {
+            if imp.is_prune_protected() {
+                assert_eq!(total, kept, "{imp} facts were lost");
+            }
+        }
+    }
+
+    #[test]
+    fn same_seed_same_report() {
+        let a = simulate(opts()).unwrap();
+        let b = simulate(opts()).unwrap();
+        assert_eq!(a.checkpoints, b.checkpoints);
+    }
+
+    #[test]
+    fn consolidation_caps_topic_size() {
+        let report = simulate(DecayBenchOptions {
+            consolidate: 10,
+            ..opts()
+        })
+        .unwrap();
+        let last = report.checkpoints.last().unwrap();
+        // Each topic holds fewer than 10 memories after the sweep.
+        assert!(last.memories < 3 * 10, "{} memories", last.memories);
+        assert!(report.render().contains("consolidation at 10 memories"));
+    }
+}
@@ -260,7 +260,7 @@ fn synthetic_memories(n: usize) -> Vec<Memory> {
             .iter()
             .map(|s| (*s).to_string())
             .collect();
-        m.source = MemorySource::Manual;
+        m.source = MemorySource::default();
         m.related_ids = Vec::new();
         m.embedding = None;
         m.scope = Scope::User;
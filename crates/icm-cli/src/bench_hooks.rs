@@ -0,0 +1,284 @@
+//! End-to-end latency benchmark for the editor hooks (`icm bench-hooks`).
+//!
+//! Fires `icm hook start|prompt|post` the way an editor does — a fresh
+//! process per fire, hook JSON on stdin — against throwaway databases of
+//! several sizes, with and without embeddings. Each run uses the user's
+//! own config, so the p50/p95 reported are the numbers their editor will
+//! see and can be checked against its hook timeout.
+
+use std::io::Write;
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::time::Instant;
+
+use anyhow::{bail, Context, Result};
+use icm_core::{Importance, Memory, MemoryStore};
+use icm_store::Store;
+
+/// Hooks exercised, in the order an agent session fires them.
+const HOOKS: [&str; 3] = ["start", "prompt", "post"];
+
+/// Project name the fixture memories and hook payloads share, so the
+/// project-scoped hooks have something to match.
+const PROJECT: &str = "bench-hooks";
+
+pub struct BenchHooksOptions {
+    pub sizes: Vec<usize>,
+    pub iterations: usize,
+    /// Also run with embeddings (the config has them on and
+    /// `--no-embeddings` was not passed).
+    pub embeddings: bool,
+    pub offline: bool,
+    pub timeout_ms: u64,
+}
+
+/// Public entry point used by `main.rs` to dispatch the CLI command.
+pub fn cmd_bench_hooks(opts: &BenchHooksOptions) -> Result<()> {
+    if opts.iterations == 0 {
+        bail!("--iterations must be at least 1");
+    }
+    let exe = std::env::current_exe().context("locate the icm binary")?;
+    let dir = std::env::temp_dir().join(format!("icm-bench-hooks-{}", std::process::id()));
+    let _cleanup = crate::CleanupDir(dir.clone());
+    let cwd = dir.join(PROJECT);
+    std::fs::create_dir_all(&cwd).context("create bench project dir")?;
+
+    let modes: &[bool] = if opts.embeddings {
+        &[false, true]
+    } else {
+        &[false]
+    };
+
+    println!(
+        "Hook latency: {} fires per hook (after 1 warm-up), timeout {} ms",
+        opts.iterations, opts.timeout_ms
+    );
+    println!();
+    println!(
+        "{:>7}  {:<10}  {:<7}  {:>9}  {:>9}  {:>9}",
+        "size", "embeddings", "hook", "p50 ms", "p95 ms", "max ms"
+    );
+    println!("{}", "-".repeat(62));
+
+    let mut over = Vec::new();
+    for &size in &opts.sizes {
+        let db = dir.join(format!("bench-{size}.db"));
+        seed_store(&db, size)?;
+        for &embeddings in modes {
+            for hook in HOOKS {
+                let mut samples = Vec::with_capacity(opts.iterations);
+                for i in 0..=opts.iterations {
+                    let ms = fire_hook(&exe, &db, &cwd, hook, i, embeddings, opts.offline)?;
+                    // Fire 0 warms the page cache (and pulls the model on
+                    // a first run); it is not a steady-state sample.
+                    if i > 0 {
+                        samples.push(ms);
+                    }
+                }
+                samples.sort_by(|a, b| a.total_cmp(b));
+                let p95 = percentile(&samples, 95.0);
+                println!(
+                    "{:>7}  {:<10}  {:<7}  {:>9.1}  {:>9.1}  {:>9.1}",
+                    size,
+                    if embeddings { "on" } else { "off" },
+                    hook,
+                    percentile(&samples, 50.0),
+                    p95,
+                    samples[samples.len() - 1],
+                );
+                if p95 > opts.timeout_ms as f64 {
+                    over.push(format!(
+                        "{hook} (size {size}, embeddings {})",
+                        if embeddings { "on" } else { "off" }
+                    ));
+                }
+            }
+        }
+    }
+
+    println!();
+    if over.is_empty() {
+        println!("All hooks stay under {} ms at p95.", opts.timeout_ms);
+    } else {
+        println!("p95 over {} ms: {}", opts.timeout_ms, over.join(", "));
+        println!(
+            "Try `[hooks.prompt] fast = true`, a smaller `[hooks.start] max_tokens`, \
+             or an async `[extraction.summarizer]` provider."
+        );
+    }
+    Ok(())
+}
+
+/// Seed `size` synthetic memories into a fresh database at `path`: a mix
+/// of project decisions, errors and context plus global preferences,
+/// spread over importances so the wake-up pack has real candidates.
+fn seed_store(path: &Path, size: usize) -> Result<()> {
+    let store = Store::new(path)?;
+    for m in synthetic_memories(size) {
+        store.store(m)?;
+    }
+    Ok(())
+}
+
+fn synthetic_memories(n: usize) -> Vec<Memory> {
+    let topics = [
+        format!("decisions-{PROJECT}"),
+        format!("errors-resolved-{PROJECT}"),
+        format!("context-{PROJECT}"),
+        "preferences".to_string(),
+    ];
+    let subjects = [
+        "database migrations",
+        "authentication flow",
+        "error handling",
+        "deployment pipeline",
+        "caching layer",
+        "API pagination",
+    ];
+    let importances = [
+        Importance::Critical,
+        Importance::High,
+        Importance::Medium,
+        Importance::Low,
+    ];
+    (0..n)
+        .map(|i| {
+            let subject = subjects[i % subjects.len()];
+            let mut m = Memory::new(
+                topics[i % topics.len()].clone(),
+                format!("Note {i} on the {subject}: keep it simple and document the trade-offs"),
+                importances[i % importances.len()],
+            );
+            m.keywords = vec![subject.replace(' ', "-"), format!("kw{}", i % 50)];
+            m
+        })
+        .collect()
+}
+
+/// The stdin payload an editor sends to `hook`. `i` varies the prompt and
+/// tool output so dedup and caches don't flatten the samples.
+fn hook_payload(hook: &str, cwd: &Path, i: usize) -> serde_json::Value {
+    let base = serde_json::json!({
+        "session_id": format!("bench-hooks-{i}"),
+        "cwd": cwd.to_string_lossy(),
+    });
+    let extra = match hook {
+        "start" => serde_json::json!({ "source": "startup" }),
+        "prompt" => serde_json::json!({
+            "prompt": format!("How do we handle the caching layer and error handling? ({i})"),
+        }),
+        _ => serde_json::json!({
+            "tool_name": "Bash",
+            "tool_response": {
+                "output": format!(
+                    "error: connection pool exhausted (run {i})\n\
+                     We decided to use a bounded pool of 16 connections instead."
+                ),
+            },
+        }),
+    };
+    let mut payload = base;
+    if let (Some(obj), serde_json::Value::Object(extra)) = (payload.as_object_mut(), extra) {
+        obj.extend(extra);
+    }
+    payload
+}
+
+/// Run one hook process to completion and return its wall time in ms.
+fn fire_hook(
+    exe: &Path,
+    db: &Path,
+    cwd: &Path,
+    hook: &str,
+    i: usize,
+    embeddings: bool,
+    offline: bool,
+) -> Result<f64> {
+    let mut cmd = Command::new(exe);
+    cmd.arg("--db").arg(db);
+    if !embeddings {
+        cmd.arg("--no-embeddings");
+    }
+    if offline {
+        cmd.arg("--offline");
+    }
+    cmd.args(["hook", hook]);
+    // Extract on every post fire: that is the slow one an editor
+    // times out on, not the counter-only fires in between.
+    if hook == "post" {
+        cmd.args(["--every", "1"]);
+    }
+    cmd.current_dir(cwd)
+        .env_remove("ICM_DB_BACKEND")
+        .env_remove("ICM_NO_EMBEDDINGS")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped());
+
+    let payload = hook_payload(hook, cwd, i).to_string();
+    let started = Instant::now();
+    let mut child = cmd.spawn().context("spawn icm hook")?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(payload.as_bytes())?;
+    }
+    let out = child.wait_with_output()?;
+    let ms = started.elapsed().as_secs_f64() * 1000.0;
+    if !out.status.success() {
+        bail!(
+            "icm hook {hook} failed ({}): {}",
+            out.status,
+            String::from_utf8_lossy(&out.stderr).trim()
+        );
+    }
+    Ok(ms)
+}
+
+/// Nearest-rank percentile of an ascending, non-empty slice.
+fn percentile(sorted: &[f64], p: f64) -> f64 {
+    let rank = (p / 100.0 * sorted.len() as f64).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn percentile_is_nearest_rank() {
+        let v: Vec<f64> = (1..=20).map(f64::from).collect();
+        assert_eq!(percentile(&v, 50.0), 10.0);
+        assert_eq!(percentile(&v, 95.0), 19.0);
+        assert_eq!(percentile(&v, 100.0), 20.0);
+        assert_eq!(percentile(&[7.0], 95.0), 7.0);
+    }
+
+    #[test]
+    fn payloads_carry_what_each_hook_reads() {
+        let cwd = Path::new("/tmp/bench-hooks");
+        let start = hook_payload("start", cwd, 0);
+        assert_eq!(start["source"], "startup");
+        assert_eq!(start["cwd"], "/tmp/bench-hooks");
+        let prompt = hook_payload("prompt", cwd, 1);
+        assert!(prompt["prompt"].as_str().unwrap().contains("(1)"));
+        let post = hook_payload("post", cwd, 2);
+        assert_eq!(post["tool_name"], "Bash");
+        assert!(post["tool_response"]["output"]
+            .as_str()
+            .unwrap()
+            .contains("run 2"));
+    }
+
+    #[test]
+    fn seeded_store_has_the_requested_size() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = dir.path().join("bench.db");
+        seed_store(&db, 12).unwrap();
+        let store = Store::new(&db).unwrap();
+        assert_eq!(store.count().unwrap(), 12);
+        assert!(store
+            .list_topics()
+            .unwrap()
+            .iter()
+            .any(|(t, _)| t == "preferences"));
+    }
+}
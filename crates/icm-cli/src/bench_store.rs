@@ -0,0 +1,401 @@
+//! Storage performance benchmark (`icm bench`).
+//!
+//! Seeds an in-memory store from a fixed seed — ids, timestamps, text,
+//! keywords and vectors all derive from it, nothing from the clock or
+//! the network — then times store, search and decay. A run can be saved
+//! as JSON and later runs compared against it, so CI can track
+//! performance across commits without a model download.
+
+use std::path::Path;
+use std::time::Instant;
+
+use anyhow::{bail, Context, Result};
+use chrono::{Duration, TimeZone, Utc};
+use icm_core::{Importance, Memory, MemoryStore};
+use icm_store::Store;
+use serde::{Deserialize, Serialize};
+
+use crate::bench_decay::Rng;
+
+const DIMS: usize = 384;
+const SEARCH_ITERS: usize = 100;
+
+const TOPICS: [&str; 5] = [
+    "architecture",
+    "preferences",
+    "errors-resolved",
+    "context-project",
+    "decisions",
+];
+const QUERIES: [&str; 5] = [
+    "database architecture",
+    "authentication flow",
+    "error handling",
+    "user preferences",
+    "deployment config",
+];
+const WORDS: [&str; 16] = [
+    "database",
+    "architecture",
+    "authentication",
+    "flow",
+    "error",
+    "handling",
+    "user",
+    "preferences",
+    "deployment",
+    "config",
+    "cache",
+    "retry",
+    "schema",
+    "migration",
+    "token",
+    "latency",
+];
+
+pub struct BenchOptions<'a> {
+    pub count: usize,
+    pub seed: u64,
+    /// Write this run as JSON for a later `--compare`.
+    pub save: Option<&'a Path>,
+    /// Print deltas against a run saved with `--save`.
+    pub compare: Option<&'a Path>,
+    /// With `compare`, fail when any per-op time is this many percent
+    /// slower than the baseline.
+    pub max_regression: Option<f64>,
+}
+
+/// One saved benchmark run.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct BenchRun {
+    pub count: usize,
+    pub seed: u64,
+    pub dims: usize,
+    pub platform: String,
+    pub results: Vec<BenchResult>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct BenchResult {
+    pub name: String,
+    pub ops: usize,
+    pub total_ms: f64,
+}
+
+impl BenchResult {
+    fn per_op_ms(&self) -> f64 {
+        self.total_ms / self.ops.max(1) as f64
+    }
+}
+
+/// Public entry point used by `main.rs` to dispatch the CLI command.
+pub fn cmd_bench(opts: &BenchOptions<'_>) -> Result<()> {
+    // Read the baseline first: a bad path should fail before the run.
+    let baseline = opts.compare.map(load_run).transpose()?;
+
+    let run = run_bench(opts.count, opts.seed)?;
+
+    println!(
+        "ICM Benchmark ({} memories, {}d embeddings, seed {})",
+        run.count, run.dims, run.seed
+    );
+    println!("{}", "─".repeat(58));
+    for r in &run.results {
+        print_bench_row(&r.name, r.ops, r.total_ms);
+    }
+    println!("{}", "─".repeat(58));
+    println!("DB size: in-memory (N/A)");
+    println!("Platform: {}", run.platform);
+
+    if let Some(path) = opts.save {
+        let json = serde_json::to_string_pretty(&run)?;
+        std::fs::write(path, json).with_context(|| format!("write {}", path.display()))?;
+        println!("Saved run to {}", path.display());
+    }
+
+    if let (Some(base), Some(path)) = (&baseline, opts.compare) {
+        println!();
+        print!(
+            "{}",
+            render_comparison(&run, base, &path.display().to_string())
+        );
+        if let Some(max) = opts.max_regression {
+            let slower = regressions(&run, base, max);
+            if !slower.is_empty() {
+                bail!(
+                    "per-op time regressed more than {max}% vs {}: {}",
+                    path.display(),
+                    slower.join(", ")
+                );
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Seed two in-memory stores (with and without vectors) from `seed` and
+/// time every operation.
+fn run_bench(count: usize, seed: u64) -> Result<BenchRun> {
+    let memories = synthetic_memories(count, seed);
+    let mut results = Vec::new();
+    let mut timed = |name: &str, ops: usize, f: &mut dyn FnMut() -> Result<()>| -> Result<()> {
+        let t0 = Instant::now();
+        f()?;
+        results.push(BenchResult {
+            name: name.into(),
+            ops,
+            total_ms: t0.elapsed().as_secs_f64() * 1000.0,
+        });
+        Ok(())
+    };
+
+    let store_plain = Store::in_memory()?;
+    timed("Store (no embeddings)", count, &mut || {
+        for m in &memories {
+            let mut m = m.clone();
+            m.embedding = None;
+            store_plain.store(m)?;
+        }
+        Ok(())
+    })?;
+
+    let store_vec = Store::in_memory()?;
+    timed("Store (with embeddings)", count, &mut || {
+        for m in &memories {
+            store_vec.store(m.clone())?;
+        }
+        Ok(())
+    })?;
+
+    timed("FTS5 search", SEARCH_ITERS, &mut || {
+        for i in 0..SEARCH_ITERS {
+            store_vec.search_fts(QUERIES[i % QUERIES.len()], 10)?;
+        }
+        Ok(())
+    })?;
+
+    let query_emb = vec![0.1_f32; DIMS];
+    timed("Vector search (KNN)", SEARCH_ITERS, &mut || {
+        for _ in 0..SEARCH_ITERS {
+            store_vec.search_by_embedding(&query_emb, 10)?;
+        }
+        Ok(())
+    })?;
+
+    timed("Hybrid search", SEARCH_ITERS, &mut || {
+        for i in 0..SEARCH_ITERS {
+            store_vec.search_hybrid(QUERIES[i % QUERIES.len()], &query_emb, 10)?;
+        }
+        Ok(())
+    })?;
+
+    timed("Decay (batch)", 1, &mut || {
+        store_vec.apply_decay(0.95)?;
+        Ok(())
+    })?;
+
+    Ok(BenchRun {
+        count,
+        seed,
+        dims: DIMS,
+        platform: format!("{}-{}", std::env::consts::ARCH, std::env::consts::OS),
+        results,
+    })
+}
+
+/// The fixture for `seed`: same seed, byte-identical memories — ids and
+/// timestamps included, so nothing depends on when the run happens.
+fn synthetic_memories(count: usize, seed: u64) -> Vec<Memory> {
+    let mut rng = Rng::new(seed);
+    let base = Utc.with_ymd_and_hms(2026, 1, 1, 0, 0, 0).unwrap();
+    (0..count)
+        .map(|i| {
+            let topic = TOPICS[rng.below(TOPICS.len())];
+            let words: Vec<&str> = (0..8).map(|_| WORDS[rng.below(WORDS.len())]).collect();
+            let importance = match rng.below(4) {
+                0 => Importance::Critical,
+                1 => Importance::High,
+                2 => Importance::Medium,
+                _ => Importance::Low,
+            };
+            let mut m = Memory::new(
+                topic.into(),
+                format!("Benchmark memory {i} about {topic}: {}", words.join(" ")),
+                importance,
+            );
+            m.id = format!("01HZZBENCH{i:016}");
+            let ts = base + Duration::minutes(i as i64);
+            m.created_at = ts;
+            m.updated_at = ts;
+            m.last_accessed = ts;
+            m.keywords = vec![
+                format!("kw{}", rng.below(50)),
+                format!("bench{}", rng.below(20)),
+            ];
+            m.embedding = Some(
+                (0..DIMS)
+                    .map(|_| 0.1 + rng.below(1000) as f32 * 0.0001)
+                    .collect(),
+            );
+            m
+        })
+        .collect()
+}
+
+fn load_run(path: &Path) -> Result<BenchRun> {
+    let text = std::fs::read_to_string(path).with_context(|| format!("read {}", path.display()))?;
+    serde_json::from_str(&text).with_context(|| format!("parse {}", path.display()))
+}
+
+/// Per-op delta for every operation in `run`; operations missing from
+/// the baseline are shown without one.
+fn render_comparison(run: &BenchRun, base: &BenchRun, label: &str) -> String {
+    let mut out = format!("Compared with {label}\n");
+    if (base.count, base.seed, base.dims) != (run.count, run.seed, run.dims) {
+        out.push_str(&format!(
+            "note: baseline ran {} memories, seed {}, {}d; deltas are not like-for-like\n",
+            base.count, base.seed, base.dims
+        ));
+    }
+    if base.platform != run.platform {
+        out.push_str(&format!("note: baseline platform was {}\n", base.platform));
+    }
+    out.push_str(&format!(
+        "{:<24} {:>12} {:>12} {:>9}\n",
+        "", "baseline/op", "now/op", "delta"
+    ));
+    for r in &run.results {
+        match base.results.iter().find(|b| b.name == r.name) {
+            Some(b) => out.push_str(&format!(
+                "{:<24} {:>12} {:>12} {:>+8.1}%\n",
+                r.name,
+                format_duration(b.per_op_ms()),
+                format_duration(r.per_op_ms()),
+                delta_pct(b, r),
+            )),
+            None => out.push_str(&format!(
+                "{:<24} {:>12} {:>12} {:>9}\n",
+                r.name,
+                "-",
+                format_duration(r.per_op_ms()),
+                "new"
+            )),
+        }
+    }
+    out
+}
+
+fn delta_pct(base: &BenchResult, now: &BenchResult) -> f64 {
+    let b = base.per_op_ms();
+    if b <= 0.0 {
+        return 0.0;
+    }
+    (now.per_op_ms() - b) / b * 100.0
+}
+
+/// Operations whose per-op time is more than `max_pct` percent slower
+/// than the baseline.
+fn regressions(run: &BenchRun, base: &BenchRun, max_pct: f64) -> Vec<String> {
+    run.results
+        .iter()
+        .filter_map(|r| {
+            let b = base.results.iter().find(|b| b.name == r.name)?;
+            let d = delta_pct(b, r);
+            (d > max_pct).then(|| format!("{} ({d:+.1}%)", r.name))
+        })
+        .collect()
+}
+
+fn print_bench_row(label: &str, ops: usize, total_ms: f64) {
+    let per_op = total_ms / ops as f64;
+    let (total_str, per_str) = (format_duration(total_ms), format_duration(per_op));
+    println!(
+        "{:<24} {:>6} ops {:>12} {:>12}/op",
+        label, ops, total_str, per_str
+    );
+}
+
+fn format_duration(ms: f64) -> String {
+    if ms < 0.001 {
+        format!("{:.1} ns", ms * 1_000_000.0)
+    } else if ms < 1.0 {
+        format!("{:.1} µs", ms * 1000.0)
+    } else if ms < 1000.0 {
+        format!("{:.1} ms", ms)
+    } else {
+        format!("{:.2} s", ms / 1000.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(results: &[(&str, f64)]) -> BenchRun {
+        BenchRun {
+            count: 10,
+            seed: 42,
+            dims: DIMS,
+            platform: "x86_64-linux".into(),
+            results: results
+                .iter()
+                .map(|(name, ms)| BenchResult {
+                    name: (*name).into(),
+                    ops: 10,
+                    total_ms: *ms,
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn same_seed_same_fixture() {
+        let a = synthetic_memories(20, 7);
+        let b = synthetic_memories(20, 7);
+        let c = synthetic_memories(20, 8);
+        for (x, y) in a.iter().zip(&b) {
+            assert_eq!(x.id, y.id);
+            assert_eq!(x.summary, y.summary);
+            assert_eq!(x.created_at, y.created_at);
+            assert_eq!(x.embedding, y.embedding);
+        }
+        assert!(a.iter().zip(&c).any(|(x, y)| x.summary != y.summary));
+    }
+
+    #[test]
+    fn comparison_shows_deltas_and_flags_regressions() {
+        let base = run(&[("FTS5 search", 10.0), ("Decay (batch)", 10.0)]);
+        let now = run(&[
+            ("FTS5 search", 15.0),
+            ("Decay (batch)", 9.0),
+            ("Hybrid search", 1.0),
+        ]);
+        let out = render_comparison(&now, &base, "baseline.json");
+        assert!(out.contains("+50.0%"), "{out}");
+        assert!(out.contains("-10.0%"), "{out}");
+        assert!(out
+            .lines()
+            .any(|l| l.starts_with("Hybrid search") && l.ends_with("new")));
+        assert!(!out.contains("not like-for-like"));
+
+        assert_eq!(regressions(&now, &base, 20.0), vec!["FTS5 search (+50.0%)"]);
+        assert!(regressions(&now, &base, 60.0).is_empty());
+    }
+
+    #[test]
+    fn comparison_warns_on_mismatched_runs() {
+        let base = run(&[("FTS5 search", 10.0)]);
+        let mut now = base.clone();
+        now.seed = 1;
+        assert!(render_comparison(&now, &base, "b.json").contains("not like-for-like"));
+    }
+
+    #[test]
+    fn saved_run_round_trips() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("baseline.json");
+        let r = run(&[("Store (no embeddings)", 3.5)]);
+        std::fs::write(&path, serde_json::to_string_pretty(&r).unwrap()).unwrap();
+        assert_eq!(load_run(&path).unwrap(), r);
+    }
+}
@@ -318,6 +318,7 @@ pub fn sync_memory(creds: &Credentials, memory: &Memory) -> Result<()> {
         "keywords": memory.keywords,
         "importance": memory.importance.to_string(),
         "scope": memory.scope.to_string(),
+        "author": memory.author,
         "source": serde_json::to_value(&memory.source).ok(),
         "createdAt": memory.created_at.to_rfc3339(),
         "updatedAt": memory.updated_at.to_rfc3339(),
@@ -401,6 +402,8 @@ pub fn pull_memories(
         related_ids: Vec<String>,
         #[serde(default)]
         source: Option<serde_json::Value>,
+        #[serde(default)]
+        author: Option<String>,
         created_at: Option<String>,
         updated_at: Option<String>,
         last_accessed: Option<String>,
@@ -432,7 +435,7 @@ pub fn pull_memories(
             let source = cm
                 .source
                 .and_then(|v| serde_json::from_value::<icm_core::MemorySource>(v).ok())
-                .unwrap_or(icm_core::MemorySource::Manual);
+                .unwrap_or_default();
             let now = chrono::Utc::now();
 
             Memory {
@@ -440,9 +443,11 @@ pub fn pull_memories(
                 topic: cm.topic,
                 summary: cm.summary,
                 raw_excerpt: cm.raw_excerpt,
+                attachment: None,
                 keywords: cm.keywords,
                 importance,
                 scope,
+                author: cm.author,
                 source,
                 weight: cm.weight,
                 access_count: cm.access_count,
@@ -460,6 +465,8 @@ pub fn pull_memories(
                     .last_accessed
                     .and_then(|s| s.parse::<chrono::DateTime<chrono::Utc>>().ok())
                     .unwrap_or(now),
+                metadata: Default::default(),
+                kind: Default::default(),
             }
         })
         .collect();
@@ -0,0 +1,199 @@
+//! `icm diff` — compare the live memories against a snapshot.
+//!
+//! The snapshot is either a copy of the SQLite database (a scheduled
+//! `cp memories.db`, a pack) or a JSON array of memories such as the
+//! backup `icm reset` writes. Memories are matched by id; a memory present
+//! on both sides counts as changed only when its content moved (topic,
+//! summary, excerpt, keywords, importance). Access counts, weights and
+//! timestamps shift on every recall and decay pass and are ignored.
+
+use std::collections::HashMap;
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use icm_core::Memory;
+use icm_store::Store;
+use serde::Serialize;
+
+/// A memory present on both sides whose content differs.
+#[derive(Debug, Serialize)]
+pub struct Changed {
+    pub before: Memory,
+    pub after: Memory,
+    /// Names of the fields that differ, in declaration order.
+    pub fields: Vec<&'static str>,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct MemoryDiff {
+    /// In the live store only.
+    pub added: Vec<Memory>,
+    /// In the snapshot only.
+    pub deleted: Vec<Memory>,
+    pub changed: Vec<Changed>,
+    pub unchanged: usize,
+}
+
+impl MemoryDiff {
+    /// Diff `before` (the snapshot) against `after` (the live store).
+    /// Each list is sorted by topic, then id.
+    pub fn between(before: Vec<Memory>, after: Vec<Memory>) -> Self {
+        let mut diff = Self::default();
+        let mut before: HashMap<String, Memory> =
+            before.into_iter().map(|m| (m.id.clone(), m)).collect();
+        for after in after {
+            let Some(before) = before.remove(&after.id) else {
+                diff.added.push(after);
+                continue;
+            };
+            let fields = changed_fields(&before, &after);
+            if fields.is_empty() {
+                diff.unchanged += 1;
+            } else {
+                diff.changed.push(Changed {
+                    before,
+                    after,
+                    fields,
+                });
+            }
+        }
+        diff.deleted = before.into_values().collect();
+
+        let key = |m: &Memory| (m.topic.clone(), m.id.clone());
+        diff.added.sort_by_key(key);
+        diff.deleted.sort_by_key(key);
+        diff.changed.sort_by_key(|c| key(&c.after));
+        diff
+    }
+
+    /// Keep only entries touching `topic` on either side.
+    pub fn retain_topic(&mut self, topic: &str) {
+        self.added.retain(|m| m.topic == topic);
+        self.deleted.retain(|m| m.topic == topic);
+        self.changed
+            .retain(|c| c.before.topic == topic || c.after.topic == topic);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.deleted.is_empty() && self.changed.is_empty()
+    }
+}
+
+fn changed_fields(before: &Memory, after: &Memory) -> Vec<&'static str> {
+    let mut fields = Vec::new();
+    if before.topic != after.topic {
+        fields.push("topic");
+    }
+    if before.summary != after.summary {
+        fields.push("summary");
+    }
+    if before.raw_excerpt != after.raw_excerpt {
+        fields.push("raw_excerpt");
+    }
+    if before.keywords != after.keywords {
+        fields.push("keywords");
+    }
+    if before.importance != after.importance {
+        fields.push("importance");
+    }
+    fields
+}
+
+/// Load every memory from a snapshot: a `.json` array of memories, or
+/// anything else opened read-only as a database.
+pub fn load_snapshot(path: &Path) -> Result<Vec<Memory>> {
+    if !path.exists() {
+        anyhow::bail!("snapshot not found: {}", path.display());
+    }
+    if path
+        .extension()
+        .is_some_and(|e| e.eq_ignore_ascii_case("json"))
+    {
+        let text = std::fs::read_to_string(path)
+            .with_context(|| format!("cannot read {}", path.display()))?;
+        return serde_json::from_str(&text)
+            .with_context(|| format!("{} is not a JSON array of memories", path.display()));
+    }
+    let store = Store::open_readonly(path)
+        .with_context(|| format!("cannot open {} as a database", path.display()))?;
+    all_memories(&store)
+}
+
+/// Every memory in `store`, for either side of a diff. Not `list_all`:
+/// it stops at the heaviest 10k, and a capped side would report the
+/// memories it missed as added or deleted.
+pub fn all_memories(store: &Store) -> Result<Vec<Memory>> {
+    Ok(store.iter_memories().collect::<Result<_, _>>()?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use icm_core::{Importance, MemoryStore};
+
+    #[test]
+    fn classifies_by_id_and_ignores_access_noise() {
+        let kept = Memory::new("a".into(), "same".into(), Importance::Low);
+        let edited = Memory::new("b".into(), "old text".into(), Importance::Low);
+        let gone = Memory::new("b".into(), "gone".into(), Importance::Low);
+        let new = Memory::new("a".into(), "new".into(), Importance::Low);
+
+        let mut kept_after = kept.clone();
+        kept_after.access_count += 3;
+        kept_after.weight = 0.5;
+        let mut edited_after = edited.clone();
+        edited_after.summary = "new text".into();
+        edited_after.importance = Importance::High;
+
+        let mut diff = MemoryDiff::between(
+            vec![kept, edited.clone(), gone.clone()],
+            vec![kept_after, edited_after, new.clone()],
+        );
+        assert_eq!(diff.unchanged, 1);
+        assert_eq!(diff.added[0].id, new.id);
+        assert_eq!(diff.deleted[0].id, gone.id);
+        assert_eq!(diff.changed[0].after.id, edited.id);
+        assert_eq!(diff.changed[0].fields, ["summary", "importance"]);
+
+        diff.retain_topic("a");
+        assert_eq!(diff.added.len(), 1);
+        assert!(diff.deleted.is_empty() && diff.changed.is_empty());
+    }
+
+    #[test]
+    fn loads_json_and_database_snapshots() {
+        let dir = tempfile::tempdir().unwrap();
+        let mem = Memory::new("t".into(), "hello".into(), Importance::Medium);
+
+        let json = dir.path().join("backup.json");
+        std::fs::write(&json, serde_json::to_string(&[&mem]).unwrap()).unwrap();
+        assert_eq!(load_snapshot(&json).unwrap()[0].id, mem.id);
+
+        let db = dir.path().join("backup.db");
+        let id = Store::new(&db).unwrap().store(mem).unwrap();
+        assert_eq!(load_snapshot(&db).unwrap()[0].id, id);
+
+        assert!(load_snapshot(&dir.path().join("missing.db")).is_err());
+    }
+
+    #[test]
+    fn reads_past_the_list_all_cap() {
+        let store = Store::in_memory().unwrap();
+        for i in 0..10_000 {
+            store
+                .store(Memory::new(
+                    "t".into(),
+                    format!("note {i}"),
+                    Importance::Medium,
+                ))
+                .unwrap();
+        }
+        let mut light = Memory::new("t".into(), "lightest".into(), Importance::Low);
+        light.weight = 0.01;
+        let light_id = store.store(light).unwrap();
+
+        let all = all_memories(&store).unwrap();
+        assert_eq!(all.len(), 10_001);
+        assert!(all.iter().any(|m| m.id == light_id));
+    }
+}
@@ -0,0 +1,101 @@
+//! Process exit codes (0 on success), so scripts and hook wrappers can
+//! branch on the outcome of a command. Documented in the README; don't
+//! renumber.
+//!
+//! Store errors map through their [`IcmError`] variant. Failures decided
+//! in the CLI itself (a memoir that doesn't exist, embeddings switched
+//! off) are raised as an [`ExitError`] carrying the code.
+
+use std::fmt;
+
+use icm_core::IcmError;
+
+/// Any failure without a more specific code.
+pub const FAILURE: i32 = 1;
+/// The memory, memoir, concept or fact asked for does not exist.
+pub const NOT_FOUND: i32 = 2;
+/// The input was rejected (bad arguments, over a `[memory.limits]`
+/// limit).
+pub const VALIDATION: i32 = 3;
+/// The database is locked by another process; retrying may succeed.
+pub const DB_LOCKED: i32 = 4;
+/// The config file, `[…]` settings or backend environment is invalid.
+pub const CONFIG: i32 = 5;
+/// The command needs embeddings and none are available.
+pub const EMBEDDING_UNAVAILABLE: i32 = 6;
+
+/// A CLI-level failure with a specific exit code.
+#[derive(Debug)]
+pub struct ExitError {
+    pub code: i32,
+    pub message: String,
+}
+
+impl fmt::Display for ExitError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for ExitError {}
+
+/// `what` does not exist (exit [`NOT_FOUND`]).
+pub fn not_found(what: impl Into<String>) -> anyhow::Error {
+    ExitError {
+        code: NOT_FOUND,
+        message: what.into(),
+    }
+    .into()
+}
+
+/// The command needs embeddings (exit [`EMBEDDING_UNAVAILABLE`]).
+pub fn embeddings_unavailable(why: impl Into<String>) -> anyhow::Error {
+    ExitError {
+        code: EMBEDDING_UNAVAILABLE,
+        message: why.into(),
+    }
+    .into()
+}
+
+/// Exit code for a failed command.
+pub fn for_error(err: &anyhow::Error) -> i32 {
+    if let Some(e) = err.downcast_ref::<ExitError>() {
+        return e.code;
+    }
+    match err.downcast_ref::<IcmError>() {
+        Some(IcmError::NotFound(_)) => NOT_FOUND,
+        Some(IcmError::InvalidInput(_) | IcmError::Validation(_)) => VALIDATION,
+        Some(IcmError::Busy(_)) => DB_LOCKED,
+        Some(IcmError::Config(_)) => CONFIG,
+        Some(IcmError::Embedding(_) | IcmError::DimensionMismatch(_)) => EMBEDDING_UNAVAILABLE,
+        _ => FAILURE,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Context;
+
+    #[test]
+    fn maps_store_errors_through_context() {
+        let err = Err::<(), _>(IcmError::Busy("locked".into()))
+            .context("failed to store")
+            .unwrap_err();
+        assert_eq!(for_error(&err), DB_LOCKED);
+        let err = anyhow::Error::new(IcmError::Validation("too long".into()));
+        assert_eq!(for_error(&err), VALIDATION);
+        let err = anyhow::Error::new(IcmError::Database("disk I/O".into()));
+        assert_eq!(for_error(&err), FAILURE);
+    }
+
+    #[test]
+    fn cli_errors_carry_their_code() {
+        assert_eq!(for_error(&not_found("memoir not found: x")), NOT_FOUND);
+        assert_eq!(
+            for_error(&embeddings_unavailable("no model").context("embed")),
+            EMBEDDING_UNAVAILABLE
+        );
+        assert_eq!(for_error(&anyhow::anyhow!("boom")), FAILURE);
+    }
+}
@@ -261,7 +261,7 @@ impl SemanticScorer {
     }
 }
 
-fn cosine(a: &[f32], b: &[f32]) -> f32 {
+pub(crate) fn cosine(a: &[f32], b: &[f32]) -> f32 {
     let n = a.len().min(b.len());
     let mut dot = 0.0f32;
     let mut na = 0.0f32;
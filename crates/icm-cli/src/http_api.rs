@@ -38,8 +38,8 @@ use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
 
 use icm_core::{
-    is_preference_topic, keyword_matches, project_matches, topic_matches, Embedder, Importance,
-    Memory, MemoryStore, MSG_NO_MEMORIES,
+    keyword_matches, project_matches, topic_matches, Embedder, Importance, Memory, MemoryStore,
+    MSG_NO_MEMORIES,
 };
 use icm_store::Store;
 
@@ -61,6 +61,9 @@ pub struct AppState {
     embedder: Option<Arc<dyn Embedder + Send + Sync>>,
     /// When set, every request must carry `Authorization: Bearer <token>`.
     token: Option<String>,
+    /// Decay factor for the daily pass `/recall` may run first
+    /// (`[memory] auto_decay = true`). `None` leaves weights alone.
+    decay_on_recall: Option<f32>,
 }
 
 impl AppState {
@@ -169,11 +172,13 @@ pub async fn run_http_server(
     embedder: Option<Box<dyn Embedder + Send + Sync>>,
     addr: SocketAddr,
     token: Option<String>,
+    decay_on_recall: Option<f32>,
 ) -> Result<()> {
     let state = AppState {
         store: Arc::new(Mutex::new(store)),
         embedder: embedder.map(Arc::from),
         token,
+        decay_on_recall,
     };
 
     let app = Router::new()
@@ -261,8 +266,12 @@ fn run_recall(state: &AppState, req: &RecallReq) -> Result<Vec<(Memory, Option<f
         .store
         .lock()
         .map_err(|_| anyhow::anyhow!("store poisoned"))?;
-    if let Err(e) = store.maybe_auto_decay() {
-        tracing::warn!(error = %e, "auto-decay failed during /recall");
+    if let Some(factor) = state.decay_on_recall {
+        match store.maybe_auto_decay(factor) {
+            Ok(Some(n)) => eprintln!("[icm http] auto-decay: {n} memories decayed by {factor}"),
+            Ok(None) => {}
+            Err(e) => tracing::warn!(error = %e, "auto-decay failed during /recall"),
+        }
     }
 
     let limit = req.limit.unwrap_or(5).clamp(1, 100);
@@ -270,7 +279,7 @@ fn run_recall(state: &AppState, req: &RecallReq) -> Result<Vec<(Memory, Option<f
     let project_filter = |m: &Memory| -> bool {
         match req.project.as_deref() {
             None | Some("") => true,
-            Some(p) => is_preference_topic(&m.topic) || project_matches(&m.topic, Some(p)),
+            Some(p) => m.is_preference() || project_matches(&m.topic, Some(p)),
         }
     };
 
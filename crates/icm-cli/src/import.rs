@@ -7,6 +7,10 @@
 //! - Slack JSON export
 //! - Plain text files
 //!
+//! `icm import --from chatgpt-export|claude-export` reads a full data
+//! export instead: each conversation is extracted on its own, topics
+//! follow its title and memories keep its dates ([`cmd_import_export`]).
+//!
 //! Zero dependencies beyond serde_json (already in icm-cli).
 
 use std::path::{Path, PathBuf};
@@ -25,7 +29,6 @@ use crate::extract;
 pub struct Exchange {
     pub role: Role,
     pub content: String,
-    #[allow(dead_code)]
     pub timestamp: Option<DateTime<Utc>>,
 }
 
@@ -83,36 +86,86 @@ pub fn detect_format(path: &Path) -> Result<ImportFormat> {
 
 // ── Parsers ──────────────────────────────────────────────────────────────
 
-/// Parse Claude.ai JSON export.
-/// Format: {"uuid":"...", "chat_messages":[{"sender":"human"|"assistant","text":"..."}]}
-/// Or array of such objects.
-pub fn parse_claude_ai(content: &str) -> Result<(Vec<Exchange>, String)> {
-    let val: serde_json::Value = serde_json::from_str(content).context("invalid Claude.ai JSON")?;
+/// One conversation of a Claude.ai or ChatGPT data export.
+#[derive(Debug, Clone)]
+pub struct Conversation {
+    pub id: Option<String>,
+    pub title: Option<String>,
+    pub created_at: Option<DateTime<Utc>>,
+    pub updated_at: Option<DateTime<Utc>>,
+    pub exchanges: Vec<Exchange>,
+}
 
-    // Could be a single conversation or an array
-    let conversations = if val.is_array() {
-        val.as_array().unwrap().clone()
-    } else {
-        vec![val]
-    };
+impl Conversation {
+    /// Conversation start: the export's own date, else the first
+    /// timestamped message.
+    fn started(&self) -> Option<DateTime<Utc>> {
+        self.created_at
+            .or_else(|| self.exchanges.iter().find_map(|e| e.timestamp))
+    }
 
-    let mut exchanges = Vec::new();
-    let mut thread_id = String::from("claude-ai");
+    /// Last activity: the export's own date, else the last timestamped
+    /// message.
+    fn last_active(&self) -> Option<DateTime<Utc>> {
+        self.updated_at
+            .or_else(|| self.exchanges.iter().rev().find_map(|e| e.timestamp))
+    }
+}
 
-    for convo in &conversations {
-        if let Some(uuid) = convo.get("uuid").and_then(|v| v.as_str()) {
-            thread_id = uuid.to_string();
-        }
+/// A single conversation or an array of them.
+fn conversation_values(val: serde_json::Value) -> Vec<serde_json::Value> {
+    match val {
+        serde_json::Value::Array(items) => items,
+        other => vec![other],
+    }
+}
+
+fn rfc3339(v: Option<&serde_json::Value>) -> Option<DateTime<Utc>> {
+    let s = v?.as_str()?;
+    DateTime::parse_from_rfc3339(s)
+        .ok()
+        .map(|dt| dt.with_timezone(&Utc))
+}
+
+/// ChatGPT stores times as fractional Unix seconds.
+fn unix_seconds(v: Option<&serde_json::Value>) -> Option<DateTime<Utc>> {
+    let secs = v?.as_f64()?;
+    DateTime::from_timestamp_millis((secs * 1000.0) as i64)
+}
+
+/// Split a Claude.ai export into its conversations.
+/// Format: {"uuid":"...", "name":"...", "created_at":"...", "chat_messages":[{"sender":"human"|"assistant","text":"...","created_at":"..."}]}
+/// Or array of such objects. Messages with an empty `text` fall back to
+/// their `content` text blocks.
+pub fn claude_ai_conversations(content: &str) -> Result<Vec<Conversation>> {
+    let val: serde_json::Value = serde_json::from_str(content).context("invalid Claude.ai JSON")?;
 
+    let mut conversations = Vec::new();
+    for convo in conversation_values(val) {
         let messages = convo
             .get("chat_messages")
             .and_then(|v| v.as_array())
             .cloned()
             .unwrap_or_default();
 
+        let mut exchanges = Vec::new();
         for msg in &messages {
             let sender = msg.get("sender").and_then(|v| v.as_str()).unwrap_or("");
-            let text = msg.get("text").and_then(|v| v.as_str()).unwrap_or("");
+            let mut text = msg
+                .get("text")
+                .and_then(|v| v.as_str())
+                .unwrap_or("")
+                .to_string();
+            if text.is_empty() {
+                text = msg
+                    .get("content")
+                    .and_then(|v| v.as_array())
+                    .into_iter()
+                    .flatten()
+                    .filter_map(|block| block.get("text").and_then(|t| t.as_str()))
+                    .collect::<Vec<_>>()
+                    .join("\n");
+            }
             if text.is_empty() {
                 continue;
             }
@@ -123,35 +176,36 @@ pub fn parse_claude_ai(content: &str) -> Result<(Vec<Exchange>, String)> {
             };
             exchanges.push(Exchange {
                 role,
-                content: text.to_string(),
-                timestamp: None,
+                content: text,
+                timestamp: rfc3339(msg.get("created_at")),
             });
         }
+
+        conversations.push(Conversation {
+            id: convo.get("uuid").and_then(|v| v.as_str()).map(String::from),
+            title: convo.get("name").and_then(|v| v.as_str()).map(String::from),
+            created_at: rfc3339(convo.get("created_at")),
+            updated_at: rfc3339(convo.get("updated_at")),
+            exchanges,
+        });
     }
+    Ok(conversations)
+}
 
-    Ok((exchanges, thread_id))
+/// Parse Claude.ai JSON export, every conversation as one thread.
+pub fn parse_claude_ai(content: &str) -> Result<(Vec<Exchange>, String)> {
+    let conversations = claude_ai_conversations(content)?;
+    Ok(flatten(conversations, "claude-ai"))
 }
 
-/// Parse ChatGPT conversations.json export.
-/// Format: {"conversation_id":"...", "mapping":{"node_id":{"message":{"author":{"role":"..."},"content":{"parts":["..."]}}}}}
-pub fn parse_chatgpt(content: &str) -> Result<(Vec<Exchange>, String)> {
+/// Split a ChatGPT conversations.json export into its conversations.
+/// Format: {"conversation_id":"...", "title":"...", "create_time":1.0, "mapping":{"node_id":{"message":{"author":{"role":"..."},"content":{"parts":["..."]},"create_time":1.0}}}}
+/// Or array of such objects.
+pub fn chatgpt_conversations(content: &str) -> Result<Vec<Conversation>> {
     let val: serde_json::Value = serde_json::from_str(content).context("invalid ChatGPT JSON")?;
 
-    // Could be array of conversations or single
-    let conversations = if val.is_array() {
-        val.as_array().unwrap().clone()
-    } else {
-        vec![val]
-    };
-
-    let mut exchanges = Vec::new();
-    let mut thread_id = String::from("chatgpt");
-
-    for convo in &conversations {
-        if let Some(cid) = convo.get("conversation_id").and_then(|v| v.as_str()) {
-            thread_id = cid.to_string();
-        }
-
+    let mut conversations = Vec::new();
+    for convo in conversation_values(val) {
         let mapping = match convo.get("mapping").and_then(|v| v.as_object()) {
             Some(m) => m,
             None => continue,
@@ -172,6 +226,7 @@ pub fn parse_chatgpt(content: &str) -> Result<(Vec<Exchange>, String)> {
         // Guard against NaN create_time (would otherwise panic the import).
         nodes.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(std::cmp::Ordering::Equal));
 
+        let mut exchanges = Vec::new();
         for (_, msg) in &nodes {
             let role_str = msg
                 .pointer("/author/role")
@@ -198,12 +253,46 @@ pub fn parse_chatgpt(content: &str) -> Result<(Vec<Exchange>, String)> {
             exchanges.push(Exchange {
                 role,
                 content: text,
-                timestamp: None,
+                timestamp: unix_seconds(msg.get("create_time")),
             });
         }
+
+        conversations.push(Conversation {
+            id: convo
+                .get("conversation_id")
+                .or_else(|| convo.get("id"))
+                .and_then(|v| v.as_str())
+                .map(String::from),
+            title: convo
+                .get("title")
+                .and_then(|v| v.as_str())
+                .map(String::from),
+            created_at: unix_seconds(convo.get("create_time")),
+            updated_at: unix_seconds(convo.get("update_time")),
+            exchanges,
+        });
     }
+    Ok(conversations)
+}
 
-    Ok((exchanges, thread_id))
+/// Parse ChatGPT conversations.json export, every conversation as one
+/// thread.
+pub fn parse_chatgpt(content: &str) -> Result<(Vec<Exchange>, String)> {
+    let conversations = chatgpt_conversations(content)?;
+    Ok(flatten(conversations, "chatgpt"))
+}
+
+/// All exchanges in order, under the last conversation's id.
+fn flatten(conversations: Vec<Conversation>, default_id: &str) -> (Vec<Exchange>, String) {
+    let mut thread_id = default_id.to_string();
+    let mut exchanges = Vec::new();
+    for convo in conversations {
+        if let Some(id) = convo.id {
+            thread_id = id;
+        }
+        exchanges.extend(convo.exchanges);
+    }
+    (exchanges, thread_id)
 }
 
 /// Parse Claude Code JSONL session.
@@ -539,6 +628,124 @@ pub fn cmd_import(
     Ok(())
 }
 
+// ── Data exports (one conversation at a time) ────────────────────────────
+
+/// Full account data exports read by [`cmd_import_export`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ChatExport {
+    ChatGpt,
+    ClaudeAi,
+}
+
+impl std::fmt::Display for ChatExport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::ChatGpt => "ChatGPT",
+            Self::ClaudeAi => "Claude.ai",
+        })
+    }
+}
+
+/// Longest title slug used as a topic project, in characters.
+const TITLE_SLUG_MAX: usize = 48;
+
+/// Topic namespace for a conversation: its title in lowercase, words
+/// joined by `-`; `conversation-<id prefix>` when untitled.
+fn conversation_slug(convo: &Conversation) -> String {
+    let title = convo.title.as_deref().unwrap_or("");
+    let mut slug = String::new();
+    for c in title.chars().flat_map(char::to_lowercase) {
+        if c.is_alphanumeric() {
+            slug.push(c);
+        } else if !slug.is_empty() && !slug.ends_with('-') {
+            slug.push('-');
+        }
+        if slug.chars().count() >= TITLE_SLUG_MAX {
+            break;
+        }
+    }
+    let slug = slug.trim_end_matches('-');
+    if !slug.is_empty() {
+        return slug.to_string();
+    }
+    match convo.id.as_deref() {
+        Some(id) => format!("conversation-{}", id.chars().take(8).collect::<String>()),
+        None => "conversation".to_string(),
+    }
+}
+
+/// `icm import --from chatgpt-export|claude-export`: run extraction on
+/// each conversation of a data export (`conversations.json`, or the
+/// export directory holding it). Facts are namespaced by the
+/// conversation title, sourced to the conversation, and dated from it.
+pub fn cmd_import_export(
+    store: &Store,
+    path: &Path,
+    export: ChatExport,
+    dry_run: bool,
+) -> Result<()> {
+    let file = if path.is_dir() {
+        path.join("conversations.json")
+    } else {
+        path.to_path_buf()
+    };
+    let content =
+        std::fs::read_to_string(&file).with_context(|| format!("reading {}", file.display()))?;
+    let conversations = match export {
+        ChatExport::ChatGpt => chatgpt_conversations(&content)?,
+        ChatExport::ClaudeAi => claude_ai_conversations(&content)?,
+    };
+
+    let total = conversations.len();
+    let mut total_facts = 0;
+    let mut with_facts = 0;
+    for convo in &conversations {
+        if convo.exchanges.is_empty() {
+            continue;
+        }
+        let text = exchanges_to_text(&convo.exchanges);
+        let slug = conversation_slug(convo);
+        let facts = extract::extract_and_classify(&text, &slug);
+        if facts.is_empty() {
+            continue;
+        }
+        with_facts += 1;
+        total_facts += facts.len();
+
+        let title = convo.title.as_deref().unwrap_or("(untitled)");
+        if dry_run {
+            println!("  -- {title}: {} facts", facts.len());
+            for (topic, content, importance, _) in &facts {
+                println!("  [{importance}] ({topic}) {content}");
+            }
+            continue;
+        }
+
+        let thread_id = convo.id.clone().unwrap_or_else(|| slug.clone());
+        let raw = crate::truncate_at_char_boundary(&text, 500);
+        for (topic, content, importance, extra_kw) in facts {
+            let mut mem = Memory::new(topic, content, importance);
+            mem.source = MemorySource::Conversation {
+                thread_id: thread_id.clone(),
+            };
+            mem.keywords = extra_kw;
+            mem.raw_excerpt = Some(raw.to_string());
+            if let Some(started) = convo.started() {
+                mem.created_at = started;
+                mem.updated_at = convo.last_active().unwrap_or(started).max(started);
+            }
+            store.store(mem)?;
+        }
+    }
+
+    let verb = if dry_run { "Would import" } else { "Imported" };
+    let suffix = if dry_run { " (dry run)" } else { "" };
+    println!(
+        "{verb} {total_facts} facts from {with_facts} of {total} {export} conversations{suffix}."
+    );
+    Ok(())
+}
+
 // ── Tests ────────────────────────────────────────────────────────────────
 
 #[cfg(test)]
@@ -593,6 +800,69 @@ mod tests {
         assert!(exchanges[0].content.contains("Rust"));
     }
 
+    #[test]
+    fn test_chatgpt_conversations_keep_titles_and_times() {
+        let sample = r#"[
+            {"conversation_id": "c1", "title": "Postgres migration plan!",
+             "create_time": 1733040000.5, "update_time": 1733043600.0,
+             "mapping": {
+                "root": {"message": null},
+                "n1": {"message": {"author": {"role": "user"}, "content": {"parts": ["Plan it"]}, "create_time": 1733040001.0}}
+             }},
+            {"id": "c2", "mapping": {}}
+        ]"#;
+        let convos = chatgpt_conversations(sample).unwrap();
+        assert_eq!(convos.len(), 2);
+        assert_eq!(convos[0].id.as_deref(), Some("c1"));
+        assert_eq!(convos[0].exchanges.len(), 1);
+        assert_eq!(
+            convos[0].started().unwrap().to_rfc3339(),
+            "2024-12-01T08:00:00.500+00:00"
+        );
+        assert_eq!(conversation_slug(&convos[0]), "postgres-migration-plan");
+        assert_eq!(convos[1].id.as_deref(), Some("c2"));
+        assert_eq!(conversation_slug(&convos[1]), "conversation-c2");
+    }
+
+    #[test]
+    fn test_claude_ai_conversations_fall_back_to_content_blocks() {
+        let sample = r#"[{"uuid": "u1", "name": "Auth design", "chat_messages": [
+            {"sender": "human", "text": "", "content": [{"type": "text", "text": "Use OAuth"}],
+             "created_at": "2024-11-02T09:00:00Z"},
+            {"sender": "assistant", "text": "Agreed", "created_at": "2024-11-02T09:05:00Z"}
+        ]}]"#;
+        let convos = claude_ai_conversations(sample).unwrap();
+        let c = &convos[0];
+        assert_eq!(c.exchanges[0].content, "Use OAuth");
+        assert_eq!(
+            c.started().unwrap().to_rfc3339(),
+            "2024-11-02T09:00:00+00:00"
+        );
+        assert_eq!(
+            c.last_active().unwrap().to_rfc3339(),
+            "2024-11-02T09:05:00+00:00"
+        );
+        assert_eq!(conversation_slug(c), "auth-design");
+    }
+
+    #[test]
+    fn test_import_export_namespaces_and_dates_by_conversation() {
+        let sample = r#"[{"uuid": "u-42", "name": "Storage layer", "created_at": "2024-10-01T12:00:00Z",
+            "chat_messages": [{"sender": "human", "text": "We decided to use SQLite instead of Postgres because we need zero external dependencies."}]}]"#;
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("conversations.json"), sample).unwrap();
+
+        let store = Store::in_memory().unwrap();
+        cmd_import_export(&store, dir.path(), ChatExport::ClaudeAi, false).unwrap();
+        let mems = store.search_fts("SQLite", 5).unwrap();
+        assert!(!mems.is_empty(), "should import the decision");
+        for m in &mems {
+            assert!(m.topic.ends_with("storage-layer"), "{}", m.topic);
+            assert_eq!(m.source.session_id(), Some("u-42"));
+            assert_eq!(m.created_at.to_rfc3339(), "2024-10-01T12:00:00+00:00");
+        }
+    }
+
     #[test]
     fn test_parse_claude_code_jsonl() {
         let sample = concat!(
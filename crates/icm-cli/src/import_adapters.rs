@@ -0,0 +1,541 @@
+//! Import memories from other memory systems (`icm import --from`).
+//!
+//! Unlike [`crate::import`], which extracts facts from conversations,
+//! these adapters map stored memories one to one:
+//! - mem0: `get_all()` output (`{"results": [...]}` or a bare array)
+//! - Letta / MemGPT: agent files (`.af`) and API dumps — core memory
+//!   blocks and archival passages
+//! - Zep: facts / graph edges (superseded ones are skipped) and entity
+//!   node summaries
+//!
+//! Fields with no memory counterpart are counted and reported, so a
+//! migration shows what was left behind.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, NaiveDateTime, Utc};
+use serde_json::{Map, Value};
+
+use icm_core::{Importance, Memory, MemoryStore};
+use icm_store::Store;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MemorySystem {
+    Mem0,
+    Letta,
+    Zep,
+}
+
+impl std::fmt::Display for MemorySystem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Mem0 => "mem0",
+            Self::Letta => "letta",
+            Self::Zep => "zep",
+        })
+    }
+}
+
+/// Memories mapped from an export, with what could not be mapped.
+#[derive(Debug, Default)]
+pub struct Adapted {
+    pub memories: Vec<Memory>,
+    /// Records left out, by reason (`empty`, `invalidated`).
+    pub skipped: BTreeMap<&'static str, usize>,
+    /// Fields with no memory counterpart, by name, with the number of
+    /// records carrying them. Nested fields read `parent.field`.
+    pub unmapped: BTreeMap<String, usize>,
+}
+
+impl Adapted {
+    fn skip(&mut self, reason: &'static str) {
+        *self.skipped.entry(reason).or_default() += 1;
+    }
+
+    /// Count every key of `obj` not in `mapped` as unmapped.
+    fn note_unmapped(&mut self, prefix: &str, obj: &Map<String, Value>, mapped: &[&str]) {
+        for key in obj.keys().filter(|k| !mapped.contains(&k.as_str())) {
+            let name = if prefix.is_empty() {
+                key.clone()
+            } else {
+                format!("{prefix}.{key}")
+            };
+            *self.unmapped.entry(name).or_default() += 1;
+        }
+    }
+}
+
+/// Map the JSON export `content` of `system` onto memories.
+pub fn adapt(system: MemorySystem, content: &str) -> Result<Adapted> {
+    let val: Value =
+        serde_json::from_str(content).with_context(|| format!("invalid {system} JSON export"))?;
+    let mut out = Adapted::default();
+    match system {
+        MemorySystem::Mem0 => adapt_mem0(&val, &mut out),
+        MemorySystem::Letta => adapt_letta(&val, &mut out),
+        MemorySystem::Zep => adapt_zep(&val, &mut out),
+    }
+    Ok(out)
+}
+
+// ── mem0 ─────────────────────────────────────────────────────────────────
+
+const MEM0_TOPIC: &str = "mem0";
+
+/// `[{"memory", "categories", "created_at", "updated_at", ...}]`, bare
+/// or under `results` / `memories`. The first category becomes the
+/// topic; all of them become keywords.
+fn adapt_mem0(val: &Value, out: &mut Adapted) {
+    let items = match val {
+        Value::Object(obj) => {
+            out.note_unmapped("", obj, &["results", "memories"]);
+            obj.get("results").or_else(|| obj.get("memories"))
+        }
+        other => Some(other),
+    };
+    for item in items.and_then(Value::as_array).into_iter().flatten() {
+        let Some(obj) = item.as_object() else {
+            continue;
+        };
+        out.note_unmapped(
+            "",
+            obj,
+            &["memory", "categories", "created_at", "updated_at"],
+        );
+        let Some(text) = non_empty_str(obj.get("memory")) else {
+            out.skip("empty");
+            continue;
+        };
+        let categories = str_list(obj.get("categories"));
+        let topic = categories
+            .first()
+            .cloned()
+            .unwrap_or_else(|| MEM0_TOPIC.into());
+        let mut mem = Memory::new(topic, text.into(), Importance::Medium);
+        mem.keywords = categories;
+        set_times(&mut mem, obj.get("created_at"), obj.get("updated_at"));
+        tag(&mut mem, MemorySystem::Mem0);
+        out.memories.push(mem);
+    }
+}
+
+// ── Letta / MemGPT ───────────────────────────────────────────────────────
+
+/// Keys holding core memory blocks, across `.af` versions and API dumps.
+const LETTA_BLOCK_KEYS: &[&str] = &["core_memory", "memory_blocks", "blocks"];
+/// Keys holding archival memory passages.
+const LETTA_PASSAGE_KEYS: &[&str] = &["archival_memory", "passages"];
+/// Every agent key the adapter reads (`memory` holds `memory.blocks`).
+const LETTA_AGENT_KEYS: &[&str] = &[
+    "core_memory",
+    "memory_blocks",
+    "blocks",
+    "archival_memory",
+    "passages",
+    "memory",
+];
+
+/// Core memory blocks (`{"label", "value"}`) become `letta-<label>`
+/// memories, high importance since Letta keeps them in every prompt;
+/// archival passages (`{"text", "tags"}`) go to `letta-archival`.
+/// Accepts an agent, `{"agents": [...]}`, or a bare array of passages.
+fn adapt_letta(val: &Value, out: &mut Adapted) {
+    match val {
+        Value::Array(passages) => letta_passages(passages, out),
+        Value::Object(obj) => {
+            let mut mapped = LETTA_AGENT_KEYS.to_vec();
+            mapped.push("agents");
+            out.note_unmapped("", obj, &mapped);
+            letta_agent(obj, out);
+            for agent in obj
+                .get("agents")
+                .and_then(Value::as_array)
+                .into_iter()
+                .flatten()
+            {
+                if let Some(agent) = agent.as_object() {
+                    out.note_unmapped("agent", agent, LETTA_AGENT_KEYS);
+                    letta_agent(agent, out);
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+fn letta_agent(agent: &Map<String, Value>, out: &mut Adapted) {
+    let nested = agent
+        .get("memory")
+        .and_then(|m| m.get("blocks"))
+        .and_then(Value::as_array);
+    let blocks = LETTA_BLOCK_KEYS
+        .iter()
+        .filter_map(|k| agent.get(*k).and_then(Value::as_array))
+        .chain(nested)
+        .flatten();
+    for block in blocks {
+        let Some(obj) = block.as_object() else {
+            continue;
+        };
+        out.note_unmapped(
+            "block",
+            obj,
+            &["label", "value", "created_at", "updated_at"],
+        );
+        let Some(value) = non_empty_str(obj.get("value")) else {
+            out.skip("empty");
+            continue;
+        };
+        let label = non_empty_str(obj.get("label")).unwrap_or("core");
+        let mut mem = Memory::new(format!("letta-{label}"), value.into(), Importance::High);
+        set_times(&mut mem, obj.get("created_at"), obj.get("updated_at"));
+        tag(&mut mem, MemorySystem::Letta);
+        out.memories.push(mem);
+    }
+    for key in LETTA_PASSAGE_KEYS {
+        if let Some(passages) = agent.get(*key).and_then(Value::as_array) {
+            letta_passages(passages, out);
+        }
+    }
+}
+
+fn letta_passages(passages: &[Value], out: &mut Adapted) {
+    for passage in passages {
+        let Some(obj) = passage.as_object() else {
+            continue;
+        };
+        out.note_unmapped(
+            "passage",
+            obj,
+            &["text", "tags", "created_at", "updated_at"],
+        );
+        let Some(text) = non_empty_str(obj.get("text")) else {
+            out.skip("empty");
+            continue;
+        };
+        let mut mem = Memory::new("letta-archival".into(), text.into(), Importance::Medium);
+        mem.keywords = str_list(obj.get("tags"));
+        set_times(&mut mem, obj.get("created_at"), obj.get("updated_at"));
+        tag(&mut mem, MemorySystem::Letta);
+        out.memories.push(mem);
+    }
+}
+
+// ── Zep ──────────────────────────────────────────────────────────────────
+
+/// Keys holding facts / graph edges.
+const ZEP_FACT_KEYS: &[&str] = &["facts", "relevant_facts", "edges"];
+
+/// Facts and edges (`{"fact", "name", "rating", "valid_at", ...}`, or
+/// plain strings) go to `zep-facts`, skipping those Zep marked invalid
+/// or expired; entity nodes (`{"name", "summary", "labels"}`) go to
+/// `zep-entities`. Accepts the object or a bare array of facts.
+fn adapt_zep(val: &Value, out: &mut Adapted) {
+    match val {
+        Value::Array(facts) => zep_facts(facts, out),
+        Value::Object(obj) => {
+            let mut mapped: Vec<&str> = ZEP_FACT_KEYS.to_vec();
+            mapped.push("nodes");
+            out.note_unmapped("", obj, &mapped);
+            for key in ZEP_FACT_KEYS {
+                if let Some(facts) = obj.get(*key).and_then(Value::as_array) {
+                    zep_facts(facts, out);
+                }
+            }
+            for node in obj
+                .get("nodes")
+                .and_then(Value::as_array)
+                .into_iter()
+                .flatten()
+            {
+                if let Some(node) = node.as_object() {
+                    zep_node(node, out);
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+fn zep_facts(facts: &[Value], out: &mut Adapted) {
+    for fact in facts {
+        let (text, obj) = match fact {
+            Value::String(s) => (Some(s.trim()).filter(|s| !s.is_empty()), None),
+            Value::Object(obj) => (non_empty_str(obj.get("fact")), Some(obj)),
+            _ => continue,
+        };
+        if let Some(obj) = obj {
+            out.note_unmapped(
+                "fact",
+                obj,
+                &[
+                    "fact",
+                    "name",
+                    "rating",
+                    "created_at",
+                    "valid_at",
+                    "invalid_at",
+                    "expired_at",
+                ],
+            );
+            let superseded = ["invalid_at", "expired_at"]
+                .iter()
+                .any(|k| obj.get(*k).is_some_and(|v| !v.is_null()));
+            if superseded {
+                out.skip("invalidated");
+                continue;
+            }
+        }
+        let Some(text) = text else {
+            out.skip("empty");
+            continue;
+        };
+        let rating = obj.and_then(|o| o.get("rating")).and_then(Value::as_f64);
+        let importance = match rating {
+            Some(r) if r >= 0.8 => Importance::High,
+            Some(r) if r < 0.3 => Importance::Low,
+            _ => Importance::Medium,
+        };
+        let mut mem = Memory::new("zep-facts".into(), text.into(), importance);
+        if let Some(obj) = obj {
+            if let Some(name) = non_empty_str(obj.get("name")) {
+                mem.keywords.push(name.to_lowercase());
+            }
+            let valid_at = obj.get("valid_at").filter(|v| !v.is_null());
+            set_times(&mut mem, valid_at.or(obj.get("created_at")), None);
+        }
+        tag(&mut mem, MemorySystem::Zep);
+        out.memories.push(mem);
+    }
+}
+
+fn zep_node(node: &Map<String, Value>, out: &mut Adapted) {
+    out.note_unmapped("node", node, &["name", "summary", "labels", "created_at"]);
+    let Some(summary) = non_empty_str(node.get("summary")) else {
+        out.skip("empty");
+        return;
+    };
+    let text = match non_empty_str(node.get("name")) {
+        Some(name) => format!("{name}: {summary}"),
+        None => summary.to_string(),
+    };
+    let mut mem = Memory::new("zep-entities".into(), text, Importance::Medium);
+    mem.keywords = str_list(node.get("labels"))
+        .into_iter()
+        .map(|l| l.to_lowercase())
+        .filter(|l| l != "entity")
+        .collect();
+    set_times(&mut mem, node.get("created_at"), None);
+    tag(&mut mem, MemorySystem::Zep);
+    out.memories.push(mem);
+}
+
+// ── Helpers ──────────────────────────────────────────────────────────────
+
+fn non_empty_str(v: Option<&Value>) -> Option<&str> {
+    v.and_then(Value::as_str)
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+}
+
+fn str_list(v: Option<&Value>) -> Vec<String> {
+    v.and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter_map(|s| non_empty_str(Some(s)))
+        .map(String::from)
+        .collect()
+}
+
+/// RFC 3339, or a naive ISO timestamp read as UTC (Letta omits the zone).
+fn parse_time(v: Option<&Value>) -> Option<DateTime<Utc>> {
+    let s = non_empty_str(v)?;
+    DateTime::parse_from_rfc3339(s)
+        .map(|dt| dt.with_timezone(&Utc))
+        .or_else(|_| NaiveDateTime::parse_from_str(s, "%Y-%m-%dT%H:%M:%S%.f").map(|n| n.and_utc()))
+        .ok()
+}
+
+/// Keep the source system's timestamps, so age-based recency, decay
+/// and `recall --as-of` see the memory's real history.
+fn set_times(mem: &mut Memory, created: Option<&Value>, updated: Option<&Value>) {
+    if let Some(created) = parse_time(created) {
+        mem.created_at = created;
+        mem.updated_at = created;
+    }
+    if let Some(updated) = parse_time(updated).filter(|u| *u >= mem.created_at) {
+        mem.updated_at = updated;
+    }
+}
+
+/// Keyword naming the source system, for `recall --keyword mem0` etc.
+fn tag(mem: &mut Memory, system: MemorySystem) {
+    let name = system.to_string();
+    if !mem.keywords.contains(&name) {
+        mem.keywords.push(name);
+    }
+}
+
+// ── Command ──────────────────────────────────────────────────────────────
+
+pub fn cmd_import_from(
+    store: &Store,
+    path: &Path,
+    system: MemorySystem,
+    dry_run: bool,
+) -> Result<()> {
+    let content =
+        std::fs::read_to_string(path).with_context(|| format!("reading {}", path.display()))?;
+    let adapted = adapt(system, &content)?;
+
+    let count = adapted.memories.len();
+    for mem in adapted.memories {
+        if dry_run {
+            println!("  [{}] ({}) {}", mem.importance, mem.topic, mem.summary);
+        } else {
+            store.store(mem)?;
+        }
+    }
+
+    let skipped = if adapted.skipped.is_empty() {
+        String::new()
+    } else {
+        let parts: Vec<String> = adapted
+            .skipped
+            .iter()
+            .map(|(reason, n)| format!("{n} {reason}"))
+            .collect();
+        format!(" (skipped: {})", parts.join(", "))
+    };
+    if dry_run {
+        println!("Would import {count} memories from {system}{skipped} (dry run).");
+    } else {
+        println!("Imported {count} memories from {system}{skipped}.");
+    }
+
+    if !adapted.unmapped.is_empty() {
+        let width = adapted.unmapped.keys().map(String::len).max().unwrap_or(0);
+        println!("Unmapped fields (not imported):");
+        for (field, n) in &adapted.unmapped {
+            println!("  {field:<width$}  {n}");
+        }
+    }
+    Ok(())
+}
+
+// ── Tests ────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mem0_maps_categories_times_and_reports_extras() {
+        let sample = r#"{"results": [
+            {"id": "m1", "memory": "Prefers dark mode", "hash": "h1",
+             "categories": ["preferences", "ui"],
+             "created_at": "2024-07-20T01:30:11.244557-07:00",
+             "updated_at": "2024-08-01T10:00:00Z", "user_id": "alice"},
+            {"id": "m2", "memory": "Allergic to nuts", "hash": "h2"},
+            {"id": "m3", "memory": "  "}
+        ]}"#;
+        let a = adapt(MemorySystem::Mem0, sample).unwrap();
+        assert_eq!(a.memories.len(), 2);
+        let m = &a.memories[0];
+        assert_eq!(m.topic, "preferences");
+        assert_eq!(m.keywords, ["preferences", "ui", "mem0"]);
+        assert_eq!(
+            m.created_at.to_rfc3339(),
+            "2024-07-20T08:30:11.244557+00:00"
+        );
+        assert_eq!(m.updated_at.to_rfc3339(), "2024-08-01T10:00:00+00:00");
+        assert_eq!(a.memories[1].topic, "mem0");
+        assert_eq!(a.skipped["empty"], 1);
+        assert_eq!(a.unmapped["id"], 3);
+        assert_eq!(a.unmapped["hash"], 2);
+        assert_eq!(a.unmapped["user_id"], 1);
+    }
+
+    #[test]
+    fn letta_maps_blocks_and_passages() {
+        let sample = r#"{
+            "name": "helper", "messages": [],
+            "core_memory": [
+                {"label": "persona", "value": "I am a coding assistant", "limit": 5000},
+                {"label": "human", "value": ""}
+            ],
+            "agents": [{"memory": {"blocks": [{"label": "human", "value": "Name: Sam"}]}}],
+            "archival_memory": [
+                {"text": "Deployed v2 on Friday", "tags": ["deploy"],
+                 "created_at": "2025-01-10T18:33:48.123456"}
+            ]
+        }"#;
+        let a = adapt(MemorySystem::Letta, sample).unwrap();
+        let got: Vec<(&str, &str, Importance)> = a
+            .memories
+            .iter()
+            .map(|m| (m.topic.as_str(), m.summary.as_str(), m.importance))
+            .collect();
+        assert_eq!(
+            got,
+            [
+                ("letta-persona", "I am a coding assistant", Importance::High),
+                (
+                    "letta-archival",
+                    "Deployed v2 on Friday",
+                    Importance::Medium
+                ),
+                ("letta-human", "Name: Sam", Importance::High),
+            ]
+        );
+        assert_eq!(a.memories[1].keywords, ["deploy", "letta"]);
+        assert_eq!(
+            a.memories[1].created_at.to_rfc3339(),
+            "2025-01-10T18:33:48.123456+00:00"
+        );
+        assert_eq!(a.skipped["empty"], 1);
+        assert_eq!(a.unmapped["block.limit"], 1);
+        assert_eq!(a.unmapped["messages"], 1);
+        assert!(!a.unmapped.contains_key("core_memory"));
+    }
+
+    #[test]
+    fn zep_skips_invalidated_facts_and_maps_nodes() {
+        let sample = r#"{
+            "edges": [
+                {"uuid": "e1", "fact": "Sam works at Acme", "name": "WORKS_AT",
+                 "valid_at": "2024-03-01T00:00:00Z", "invalid_at": null, "rating": 0.9},
+                {"uuid": "e2", "fact": "Sam works at Initech", "name": "WORKS_AT",
+                 "invalid_at": "2024-03-01T00:00:00Z"}
+            ],
+            "facts": ["Sam likes Rust"],
+            "nodes": [{"uuid": "n1", "name": "Sam", "summary": "Backend engineer",
+                       "labels": ["Entity", "Person"]}]
+        }"#;
+        let a = adapt(MemorySystem::Zep, sample).unwrap();
+        assert_eq!(a.memories.len(), 3);
+        assert_eq!(a.memories[0].summary, "Sam likes Rust");
+        let acme = &a.memories[1];
+        assert_eq!(acme.summary, "Sam works at Acme");
+        assert_eq!(acme.importance, Importance::High);
+        assert_eq!(acme.keywords, ["works_at", "zep"]);
+        assert_eq!(acme.created_at.to_rfc3339(), "2024-03-01T00:00:00+00:00");
+        let node = &a.memories[2];
+        assert_eq!(
+            (node.topic.as_str(), node.summary.as_str()),
+            ("zep-entities", "Sam: Backend engineer")
+        );
+        assert_eq!(node.keywords, ["person", "zep"]);
+        assert_eq!(a.skipped["invalidated"], 1);
+        assert_eq!(a.unmapped["fact.uuid"], 2);
+        assert_eq!(a.unmapped["node.uuid"], 1);
+    }
+
+    #[test]
+    fn invalid_json_names_the_system() {
+        let err = adapt(MemorySystem::Zep, "not json").unwrap_err();
+        assert!(err.to_string().contains("zep"), "{err}");
+    }
+}
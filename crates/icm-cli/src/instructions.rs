@@ -0,0 +1,234 @@
+//! The `<!-- icm:start --> ... <!-- icm:end -->` block that `icm init`
+//! writes to CLAUDE.md / AGENTS.md / GEMINI.md, and that
+//! `icm sync-instructions` fills with the `kind = instruction` memories,
+//! so agent guidance and memory share one source of truth. The block is
+//! regenerated whole; text outside the markers is left alone.
+
+use std::path::Path;
+
+use icm_core::Memory;
+
+pub const START_MARKER: &str = "<!-- icm:start -->";
+pub const END_MARKER: &str = "<!-- icm:end -->";
+
+/// Which agent reads the file, and so how the block addresses it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Flavor {
+    /// CLAUDE.md, and the tool-specific files of Copilot, Windsurf, Aider.
+    Claude,
+    /// AGENTS.md, read by Codex, Pi and the other AGENTS.md-aware agents.
+    Agents,
+    /// GEMINI.md, read by Gemini CLI.
+    Gemini,
+}
+
+impl Flavor {
+    /// Pick the phrasing from the file name, case-insensitively.
+    pub fn for_path(path: &Path) -> Self {
+        let name = path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_ascii_lowercase())
+            .unwrap_or_default();
+        match name.as_str() {
+            "agents.md" => Flavor::Agents,
+            "gemini.md" => Flavor::Gemini,
+            _ => Flavor::Claude,
+        }
+    }
+
+    fn intro(self) -> &'static str {
+        match self {
+            Flavor::Claude => {
+                "This project uses [ICM](https://github.com/rtk-ai/icm) for persistent memory across sessions."
+            }
+            Flavor::Agents => {
+                "Agents working here use [ICM](https://github.com/rtk-ai/icm) for persistent memory across sessions.\n\
+                 Run the `icm` commands below from the shell, or call the matching `icm_memory_*` MCP tools when the `icm` server is configured."
+            }
+            Flavor::Gemini => {
+                "Gemini CLI uses [ICM](https://github.com/rtk-ai/icm) for persistent memory across sessions.\n\
+                 Run the `icm` commands below with the shell tool, or call the `icm_memory_*` MCP tools when `icm` is listed in settings.json."
+            }
+        }
+    }
+}
+
+const HEADING: &str = "## Persistent memory (ICM) — MANDATORY";
+
+/// How to use ICM, the fixed part of every block after the intro.
+const GUIDE: &str = r#"You MUST use it actively. Not optional.
+
+### Recall (before starting work)
+```bash
+icm recall "query"                        # search memories
+icm recall "query" -t "topic-name"        # filter by topic
+icm recall-context "query" --limit 5      # formatted for prompt injection
+```
+
+### Store — MANDATORY triggers
+You MUST call `icm store` when ANY of the following happens:
+1. **Error resolved** → `icm store -t errors-resolved -c "description" -i high -k "keyword1,keyword2"`
+2. **Architecture/design decision** → `icm store -t decisions-{project} -c "description" -i high`
+3. **User preference discovered** → `icm store -t preferences -c "description" -i critical`
+4. **Significant task completed** → `icm store -t context-{project} -c "summary of work done" -i high`
+5. **Conversation exceeds ~20 tool calls without a store** → store a progress summary
+
+Do this BEFORE responding to the user. Not after. Not later. Immediately.
+
+Do NOT store: trivial details, info already in this file, ephemeral state (build logs, git status).
+
+### Other commands
+```bash
+icm forget <id>                          # remove a memory by ID
+icm list --all                           # list all memories
+icm list --topic <name>                  # list memories in a topic
+icm update <id> -c "updated content"     # edit memory in-place
+icm health                                # topic hygiene audit
+icm topics                                # list all topics
+```"#;
+
+const INSTRUCTIONS_HEADING: &str = "\n### Instructions\n";
+
+/// The full block: the usage guide, then one bullet per instruction,
+/// oldest first so the file does not churn as weights decay.
+pub fn render_block(flavor: Flavor, instructions: &[Memory]) -> String {
+    let mut section = String::new();
+    if !instructions.is_empty() {
+        let mut sorted: Vec<&Memory> = instructions.iter().collect();
+        sorted.sort_by(|a, b| (a.created_at, &a.id).cmp(&(b.created_at, &b.id)));
+        section.push_str(INSTRUCTIONS_HEADING);
+        for m in sorted {
+            let line: Vec<&str> = m.summary.split_whitespace().collect();
+            section.push_str(&format!("- {}\n", line.join(" ")));
+        }
+    }
+    assemble(flavor, &section)
+}
+
+fn assemble(flavor: Flavor, section: &str) -> String {
+    format!(
+        "{START_MARKER}\n{HEADING}\n\n{}\n{GUIDE}\n{section}{END_MARKER}",
+        flavor.intro()
+    )
+}
+
+/// Byte range of the block in `content`, end marker included.
+fn block_span(content: &str) -> Option<(usize, usize)> {
+    let start = content.find(START_MARKER)?;
+    let end = content[start..].find(END_MARKER)? + start + END_MARKER.len();
+    Some((start, end))
+}
+
+/// `content` with its block replaced by `block`, or with `block` appended
+/// when it has none.
+pub fn splice_block(content: &str, block: &str) -> String {
+    match block_span(content) {
+        Some((s, e)) => format!("{}{block}{}", &content[..s], &content[e..]),
+        None if content.trim().is_empty() => format!("{block}\n"),
+        None => format!("{}\n\n{block}\n", content.trim_end()),
+    }
+}
+
+/// `content` with its block re-rendered for `flavor` (what `icm init`
+/// does). Instructions written by `icm sync-instructions` are kept, since
+/// init has no store to re-read them from.
+pub fn refresh_block(content: &str, flavor: Flavor) -> String {
+    let section = block_span(content)
+        .map(|(s, e)| &content[s..e - END_MARKER.len()])
+        .and_then(|block| block.find(INSTRUCTIONS_HEADING).map(|i| &block[i..]))
+        .unwrap_or("");
+    splice_block(content, &assemble(flavor, section))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use icm_core::Importance;
+
+    #[test]
+    fn block_without_instructions_is_the_init_block() {
+        let block = render_block(Flavor::Claude, &[]);
+        assert!(block.starts_with(
+            "<!-- icm:start -->\n## Persistent memory (ICM) — MANDATORY\n\n\
+             This project uses [ICM](https://github.com/rtk-ai/icm) for persistent memory across sessions.\n\
+             You MUST use it actively. Not optional.\n"
+        ));
+        assert!(block.ends_with(
+            "icm topics                                # list all topics\n```\n<!-- icm:end -->"
+        ));
+        assert!(!block.contains("### Instructions"));
+    }
+
+    #[test]
+    fn instructions_render_oldest_first_on_one_line() {
+        let mut first = Memory::new(
+            "icm".into(),
+            "Run cargo fmt\nbefore committing".into(),
+            Importance::High,
+        );
+        first.created_at -= chrono::Duration::days(1);
+        let second = Memory::new(
+            "icm".into(),
+            "Never push to main".into(),
+            Importance::Critical,
+        );
+        let block = render_block(Flavor::Claude, &[second, first]);
+        assert!(block.ends_with(
+            "### Instructions\n- Run cargo fmt before committing\n- Never push to main\n<!-- icm:end -->"
+        ));
+    }
+
+    #[test]
+    fn splice_replaces_or_appends_the_block() {
+        let block = "<!-- icm:start -->\nnew\n<!-- icm:end -->";
+        let existing = "# Project\n\n<!-- icm:start -->\nold\n<!-- icm:end -->\n\n## Notes\n";
+        assert_eq!(
+            splice_block(existing, block),
+            "# Project\n\n<!-- icm:start -->\nnew\n<!-- icm:end -->\n\n## Notes\n"
+        );
+        assert_eq!(
+            splice_block("# Project\n", block),
+            "# Project\n\n<!-- icm:start -->\nnew\n<!-- icm:end -->\n"
+        );
+        assert_eq!(splice_block("", block), format!("{block}\n"));
+        // A start marker without its end is not a block: append.
+        let broken = "<!-- icm:start -->\ndangling\n";
+        assert!(splice_block(broken, block).ends_with(&format!("dangling\n\n{block}\n")));
+    }
+
+    #[test]
+    fn flavor_follows_the_file_name() {
+        assert_eq!(Flavor::for_path(Path::new("/p/AGENTS.md")), Flavor::Agents);
+        assert_eq!(Flavor::for_path(Path::new("gemini.md")), Flavor::Gemini);
+        assert_eq!(Flavor::for_path(Path::new("CLAUDE.md")), Flavor::Claude);
+        assert_eq!(
+            Flavor::for_path(Path::new(".windsurfrules")),
+            Flavor::Claude
+        );
+        assert!(render_block(Flavor::Agents, &[]).contains("Agents working here use [ICM]"));
+        assert!(render_block(Flavor::Gemini, &[]).contains("with the shell tool"));
+    }
+
+    #[test]
+    fn refresh_rephrases_but_keeps_synced_instructions() {
+        let synced = render_block(
+            Flavor::Claude,
+            &[Memory::new(
+                "icm".into(),
+                "Never push to main".into(),
+                Importance::High,
+            )],
+        );
+        let content = format!("# Notes\n\n{synced}\n");
+        let refreshed = refresh_block(&content, Flavor::Agents);
+        assert!(refreshed.starts_with("# Notes\n\n<!-- icm:start -->"));
+        assert!(refreshed.contains("Agents working here use [ICM]"));
+        assert!(!refreshed.contains("This project uses [ICM]"));
+        assert!(refreshed.ends_with("### Instructions\n- Never push to main\n<!-- icm:end -->\n"));
+        assert_eq!(refresh_block(&refreshed, Flavor::Agents), refreshed);
+        assert_eq!(
+            refresh_block("", Flavor::Gemini),
+            format!("{}\n", render_block(Flavor::Gemini, &[]))
+        );
+    }
+}
@@ -0,0 +1,500 @@
+//! Memory packs: signed, compressed exports of selected topics and
+//! memoirs that a teammate (or an organization) can mount read-only.
+//!
+//! A pack file is a gzip'd JSON envelope holding the serialized contents
+//! plus an ed25519 signature over exactly those bytes and the signer's
+//! public key. Mounting verifies the signature and unpacks the contents
+//! into a standalone SQLite DB under `packs/` next to the primary
+//! database; `icm recall` then searches it alongside the primary without
+//! ever merging it in.
+
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, bail, Context, Result};
+use chrono::{DateTime, Utc};
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use icm_core::{Concept, ConceptLink, Memoir, MemoirStore, Memory, MemoryStore};
+use icm_store::Store;
+use ring::rand::SystemRandom;
+use ring::signature::{Ed25519KeyPair, KeyPair, UnparsedPublicKey, ED25519};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+/// Envelope format tag; bumped on incompatible layout changes.
+pub const PACK_FORMAT: &str = "icm-pack/1";
+
+/// Directory (next to the primary DB) holding mounted packs.
+const PACKS_DIR: &str = "packs";
+
+/// Largest decompressed envelope `read_pack` accepts, so a gzip bomb
+/// fails before it exhausts memory.
+pub const MAX_PACK_BYTES: u64 = 256 * 1024 * 1024;
+
+/// What a pack carries.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PackContents {
+    pub name: String,
+    pub created_at: DateTime<Utc>,
+    /// Model that produced the memories' embeddings, if any were kept.
+    pub embedding_model: Option<String>,
+    pub memories: Vec<Memory>,
+    pub memoirs: Vec<PackMemoir>,
+}
+
+/// A memoir with its whole concept graph.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PackMemoir {
+    pub memoir: Memoir,
+    pub concepts: Vec<Concept>,
+    pub links: Vec<ConceptLink>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Envelope {
+    format: String,
+    /// `PackContents` as JSON; the signature covers these bytes.
+    contents: String,
+    public_key: String,
+    signature: String,
+}
+
+/// A verified pack and who signed it.
+pub struct OpenedPack {
+    pub contents: PackContents,
+    /// Short fingerprint of the signer's public key.
+    pub signer: String,
+}
+
+/// A mounted pack, as listed by `icm pack list`.
+pub struct MountedPack {
+    pub name: String,
+    pub db_path: PathBuf,
+}
+
+/// Collect the memories of the given topics and the named memoirs.
+pub fn collect(
+    store: &Store,
+    name: &str,
+    topics: &[String],
+    memoirs: &[String],
+    embedding_model: Option<&str>,
+) -> Result<PackContents> {
+    let mut memories = Vec::new();
+    for topic in topics {
+        let found = store.get_by_topic(topic)?;
+        if found.is_empty() {
+            bail!("no memories in topic '{topic}'");
+        }
+        memories.extend(found);
+    }
+    // The pack must stand alone: inline large excerpts instead of
+    // shipping a hash the mounting machine cannot resolve.
+    for m in &mut memories {
+        store.load_attachment(m)?;
+        m.attachment = None;
+        if embedding_model.is_none() {
+            m.embedding = None;
+        }
+    }
+
+    let mut packed = Vec::new();
+    for name in memoirs {
+        let memoir = store
+            .get_memoir_by_name(name)?
+            .ok_or_else(|| crate::exit_code::not_found(format!("memoir not found: {name}")))?;
+        let concepts = store.list_concepts(&memoir.id)?;
+        let links = store.get_links_for_memoir(&memoir.id)?;
+        packed.push(PackMemoir {
+            memoir,
+            concepts,
+            links,
+        });
+    }
+
+    if memories.is_empty() && packed.is_empty() {
+        bail!("nothing to pack: pass at least one --topic or --memoir");
+    }
+    Ok(PackContents {
+        name: name.to_string(),
+        created_at: Utc::now(),
+        embedding_model: embedding_model
+            .filter(|_| memories.iter().any(|m| m.embedding.is_some()))
+            .map(str::to_string),
+        memories,
+        memoirs: packed,
+    })
+}
+
+/// Sign `contents` with `key` and write the compressed pack to `path`.
+pub fn write_pack(path: &Path, contents: &PackContents, key: &Ed25519KeyPair) -> Result<()> {
+    let contents = serde_json::to_string(contents)?;
+    let envelope = Envelope {
+        format: PACK_FORMAT.into(),
+        signature: hex(key.sign(contents.as_bytes()).as_ref()),
+        public_key: hex(key.public_key().as_ref()),
+        contents,
+    };
+    let file =
+        std::fs::File::create(path).with_context(|| format!("cannot create {}", path.display()))?;
+    let mut gz = GzEncoder::new(file, flate2::Compression::default());
+    serde_json::to_writer(&mut gz, &envelope)?;
+    gz.finish()?.flush()?;
+    Ok(())
+}
+
+/// Read a pack and verify its signature against the public key it
+/// carries. Whether that key is trusted is the caller's decision.
+pub fn read_pack(path: &Path) -> Result<OpenedPack> {
+    read_pack_limited(path, MAX_PACK_BYTES)
+}
+
+fn read_pack_limited(path: &Path, max_bytes: u64) -> Result<OpenedPack> {
+    let file =
+        std::fs::File::open(path).with_context(|| format!("cannot open {}", path.display()))?;
+    let mut json = String::new();
+    GzDecoder::new(file)
+        .take(max_bytes + 1)
+        .read_to_string(&mut json)
+        .context("not a valid icm pack (gzip)")?;
+    if json.len() as u64 > max_bytes {
+        bail!("pack expands beyond {max_bytes} bytes; refusing to read it");
+    }
+    let envelope: Envelope =
+        serde_json::from_str(&json).context("not a valid icm pack (envelope)")?;
+    if envelope.format != PACK_FORMAT {
+        bail!(
+            "unsupported pack format '{}' (expected {PACK_FORMAT})",
+            envelope.format
+        );
+    }
+    let public_key = unhex(&envelope.public_key).context("malformed public key")?;
+    let signature = unhex(&envelope.signature).context("malformed signature")?;
+    UnparsedPublicKey::new(&ED25519, &public_key)
+        .verify(envelope.contents.as_bytes(), &signature)
+        .map_err(|_| anyhow!("pack signature does not match its contents"))?;
+    let contents: PackContents =
+        serde_json::from_str(&envelope.contents).context("malformed pack contents")?;
+    Ok(OpenedPack {
+        contents,
+        signer: fingerprint(&public_key),
+    })
+}
+
+/// Decide whether a pack signed by `signer` may be mounted. The
+/// signature only proves the pack matches the key it ships with, so the
+/// key itself must be listed in `trusted_keys` or passed with `--trust`;
+/// `allow_untrusted` overrides. Returns whether the signer is trusted.
+pub fn check_trust(
+    signer: &str,
+    trusted_keys: &[String],
+    trust: &[String],
+    allow_untrusted: bool,
+) -> Result<bool> {
+    let trusted = trusted_keys
+        .iter()
+        .chain(trust)
+        .any(|k| k.eq_ignore_ascii_case(signer));
+    if trusted || allow_untrusted {
+        return Ok(trusted);
+    }
+    if trusted_keys.is_empty() && trust.is_empty() {
+        bail!(
+            "pack is signed by {signer}, and no [pack] trusted_keys are configured; \
+             pass --trust {signer} if you expect this signer, or --allow-untrusted"
+        );
+    }
+    bail!("pack is signed by {signer}, which is not in [pack] trusted_keys or --trust")
+}
+
+/// Load the local signing key, generating one on first use.
+pub fn signing_key(path: &Path) -> Result<Ed25519KeyPair> {
+    if !path.exists() {
+        let pkcs8 = Ed25519KeyPair::generate_pkcs8(&SystemRandom::new())
+            .map_err(|_| anyhow!("failed to generate a signing key"))?;
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let mut opts = std::fs::OpenOptions::new();
+        opts.write(true).create_new(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            opts.mode(0o600);
+        }
+        opts.open(path)
+            .and_then(|mut f| f.write_all(pkcs8.as_ref()))
+            .with_context(|| format!("cannot write signing key {}", path.display()))?;
+    }
+    let pkcs8 = std::fs::read(path)
+        .with_context(|| format!("cannot read signing key {}", path.display()))?;
+    Ed25519KeyPair::from_pkcs8(&pkcs8).map_err(|e| anyhow!("invalid signing key: {e}"))
+}
+
+/// Short, shareable identifier for a public key (first 16 hex digits of
+/// its SHA-256), used in `[pack] trusted_keys`.
+pub fn fingerprint(public_key: &[u8]) -> String {
+    hex(&Sha256::digest(public_key)[..8])
+}
+
+/// Mount directory for the database at `db_path`.
+pub fn packs_dir(db_path: &Path) -> PathBuf {
+    db_path
+        .parent()
+        .unwrap_or_else(|| Path::new("."))
+        .join(PACKS_DIR)
+}
+
+/// Restrict pack names to characters that are safe as a file name.
+pub fn sanitize_name(name: &str) -> String {
+    let cleaned: String = name
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.') {
+                c
+            } else {
+                '-'
+            }
+        })
+        .collect();
+    cleaned.trim_matches(|c| c == '-' || c == '.').to_string()
+}
+
+/// Unpack `contents` into a fresh DB at `db_path`, replacing any
+/// previous mount. Embeddings are kept only when `keep_embeddings` (they
+/// are useless if the pack was embedded with a different model).
+pub fn materialize(contents: &PackContents, db_path: &Path, keep_embeddings: bool) -> Result<()> {
+    if let Some(dir) = db_path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    for suffix in ["", "-wal", "-shm"] {
+        let mut p = db_path.as_os_str().to_owned();
+        p.push(suffix);
+        let _ = std::fs::remove_file(PathBuf::from(p));
+    }
+    let dims = contents
+        .memories
+        .iter()
+        .find_map(|m| m.embedding.as_ref().map(Vec::len))
+        .filter(|_| keep_embeddings)
+        .unwrap_or(icm_core::DEFAULT_EMBEDDING_DIMS);
+    let store = Store::with_dims(db_path, dims)?;
+    for m in &contents.memories {
+        let mut m = m.clone();
+        if !keep_embeddings {
+            m.embedding = None;
+        }
+        store.store(m)?;
+    }
+    for pm in &contents.memoirs {
+        store.create_memoir(pm.memoir.clone())?;
+        for c in &pm.concepts {
+            store.add_concept(c.clone())?;
+        }
+        for l in &pm.links {
+            store.add_link(l.clone())?;
+        }
+    }
+    Ok(())
+}
+
+/// Packs mounted next to the database at `db_path`, sorted by name.
+pub fn mounted(db_path: &Path) -> Vec<MountedPack> {
+    let Ok(entries) = std::fs::read_dir(packs_dir(db_path)) else {
+        return Vec::new();
+    };
+    let mut packs: Vec<MountedPack> = entries
+        .flatten()
+        .map(|e| e.path())
+        .filter(|p| p.extension().is_some_and(|e| e == "db"))
+        .filter_map(|p| {
+            let name = p.file_stem()?.to_string_lossy().to_string();
+            Some(MountedPack { name, db_path: p })
+        })
+        .collect();
+    packs.sort_by(|a, b| a.name.cmp(&b.name));
+    packs
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+fn unhex(s: &str) -> Option<Vec<u8>> {
+    if !s.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..s.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(s.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use icm_core::Importance;
+
+    fn sample_store() -> Store {
+        let store = Store::in_memory().unwrap();
+        store
+            .store(Memory::new(
+                "decisions-api".into(),
+                "use sqlite".into(),
+                Importance::High,
+            ))
+            .unwrap();
+        store
+            .store(Memory::new(
+                "scratch".into(),
+                "not shared".into(),
+                Importance::Low,
+            ))
+            .unwrap();
+        let memoir = Memoir::new("arch".into(), "architecture".into());
+        let memoir_id = store.create_memoir(memoir).unwrap();
+        store
+            .add_concept(Concept::new(
+                memoir_id,
+                "store".into(),
+                "sqlite backed".into(),
+            ))
+            .unwrap();
+        store
+    }
+
+    fn test_key() -> Ed25519KeyPair {
+        let pkcs8 = Ed25519KeyPair::generate_pkcs8(&SystemRandom::new()).unwrap();
+        Ed25519KeyPair::from_pkcs8(pkcs8.as_ref()).unwrap()
+    }
+
+    #[test]
+    fn pack_round_trips_and_mounts() {
+        let dir = tempfile::tempdir().unwrap();
+        let contents = collect(
+            &sample_store(),
+            "team",
+            &["decisions-api".into()],
+            &["arch".into()],
+            None,
+        )
+        .unwrap();
+        assert_eq!(contents.memories.len(), 1);
+
+        let key = test_key();
+        let file = dir.path().join("team.icmpack");
+        write_pack(&file, &contents, &key).unwrap();
+        let opened = read_pack(&file).unwrap();
+        assert_eq!(opened.signer, fingerprint(key.public_key().as_ref()));
+        assert_eq!(opened.contents.memories[0].summary, "use sqlite");
+
+        let db = packs_dir(&dir.path().join("memories.db")).join("team.db");
+        materialize(&opened.contents, &db, false).unwrap();
+        let pack_db = Store::open_readonly(&db).unwrap();
+        assert_eq!(pack_db.list_all().unwrap().len(), 1);
+        let memoir = pack_db.get_memoir_by_name("arch").unwrap().unwrap();
+        assert_eq!(pack_db.list_concepts(&memoir.id).unwrap().len(), 1);
+        assert_eq!(mounted(&dir.path().join("memories.db"))[0].name, "team");
+    }
+
+    #[test]
+    fn pack_carries_attachment_excerpts() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = Store::in_memory().unwrap();
+        let excerpt = "log line\n".repeat(icm_store::ATTACHMENT_MIN_BYTES / 8);
+        let mut memory = Memory::new("incident".into(), "build log".into(), Importance::High);
+        memory.raw_excerpt = Some(excerpt.clone());
+        let id = store.store(memory).unwrap();
+        assert!(store.get(&id).unwrap().unwrap().attachment.is_some());
+
+        let contents = collect(&store, "logs", &["incident".into()], &[], None).unwrap();
+        assert_eq!(contents.memories[0].raw_excerpt.as_deref(), Some(&*excerpt));
+        assert!(contents.memories[0].attachment.is_none());
+
+        let file = dir.path().join("logs.icmpack");
+        write_pack(&file, &contents, &test_key()).unwrap();
+        let opened = read_pack(&file).unwrap();
+        let db = packs_dir(&dir.path().join("memories.db")).join("logs.db");
+        materialize(&opened.contents, &db, false).unwrap();
+
+        let pack_db = Store::open_readonly(&db).unwrap();
+        let mut mounted = pack_db.get(&id).unwrap().unwrap();
+        pack_db.load_attachment(&mut mounted).unwrap();
+        assert_eq!(mounted.raw_excerpt.as_deref(), Some(&*excerpt));
+    }
+
+    #[test]
+    fn tampered_pack_is_rejected() {
+        let dir = tempfile::tempdir().unwrap();
+        let contents = collect(&sample_store(), "t", &["scratch".into()], &[], None).unwrap();
+        let file = dir.path().join("t.icmpack");
+        write_pack(&file, &contents, &test_key()).unwrap();
+
+        let mut json = String::new();
+        GzDecoder::new(std::fs::File::open(&file).unwrap())
+            .read_to_string(&mut json)
+            .unwrap();
+        let tampered = json.replace("not shared", "rm -rf /");
+        let mut gz = GzEncoder::new(
+            std::fs::File::create(&file).unwrap(),
+            flate2::Compression::default(),
+        );
+        gz.write_all(tampered.as_bytes()).unwrap();
+        gz.finish().unwrap();
+
+        let err = read_pack(&file).err().unwrap();
+        assert!(err.to_string().contains("signature"), "{err}");
+    }
+
+    #[test]
+    fn untrusted_signers_are_refused() {
+        let keys = ["ABCDEF0123456789".to_string()];
+        assert!(check_trust("abcdef0123456789", &keys, &[], false).unwrap());
+        assert!(check_trust("abcdef0123456789", &[], &keys, false).unwrap());
+
+        // No trusted keys at all: a self-signed pack proves nothing.
+        let err = check_trust("1111222233334444", &[], &[], false).unwrap_err();
+        assert!(
+            err.to_string().contains("--trust 1111222233334444"),
+            "{err}"
+        );
+        let err = check_trust("1111222233334444", &keys, &[], false).unwrap_err();
+        assert!(
+            err.to_string().contains("not in [pack] trusted_keys"),
+            "{err}"
+        );
+
+        assert!(!check_trust("1111222233334444", &[], &[], true).unwrap());
+    }
+
+    #[test]
+    fn oversized_pack_is_rejected() {
+        let dir = tempfile::tempdir().unwrap();
+        let contents = collect(&sample_store(), "t", &["scratch".into()], &[], None).unwrap();
+        let file = dir.path().join("t.icmpack");
+        write_pack(&file, &contents, &test_key()).unwrap();
+
+        assert!(read_pack_limited(&file, MAX_PACK_BYTES).is_ok());
+        let err = read_pack_limited(&file, 64).err().unwrap();
+        assert!(err.to_string().contains("beyond 64 bytes"), "{err}");
+    }
+
+    #[test]
+    fn signing_key_is_created_once() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("pack-signing.key");
+        let a = signing_key(&path).unwrap();
+        let b = signing_key(&path).unwrap();
+        assert_eq!(a.public_key().as_ref(), b.public_key().as_ref());
+    }
+
+    #[test]
+    fn names_and_hex_are_safe() {
+        assert_eq!(sanitize_name("Team Pack/v2"), "Team-Pack-v2");
+        assert_eq!(sanitize_name("../x"), "x");
+        assert_eq!(unhex(&hex(&[0, 15, 255])).unwrap(), [0, 15, 255]);
+        assert!(unhex("abc").is_none());
+        assert!(unhex("zz").is_none());
+    }
+}
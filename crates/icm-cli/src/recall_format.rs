@@ -1,7 +1,7 @@
 //! Output formats for `icm recall` (and `icm list`, which reuses the
 //! same renderers).
 //!
-//! Four modes:
+//! Output modes:
 //! - `Toon` (default) — TOON one-row-per-memory, header declared once.
 //!   Smallest token cost when stdout gets piped into an LLM context.
 //! - `Detail` — the legacy multi-line labelled view, for humans reading
@@ -10,12 +10,23 @@
 //! - `Toml` — TOML `[[memories]]` array for config-friendly consumption
 //!   (issue #269 — used by TakoIA-style agents reading their memory map
 //!   straight from a TOML file).
+//! - `Ids` / `Tsv` — one memory ID per line, or `id<TAB>topic<TAB>score`
+//!   rows, for `xargs icm forget` and shell pipelines (`--ids-only`,
+//!   `--tsv`).
+//!
+//! `icm recall --include-concepts` appends matching memoir concepts via
+//! [`with_concepts`]; `Detail` output ends with the weight gain each
+//! recalled memory got, via [`reinforcement_note`].
+
+use std::collections::BTreeMap;
 
 use anyhow::Result;
 use clap::ValueEnum;
-use icm_core::{format_local, Memory};
+use icm_core::{format_local, Memory, MemoryKind, Reinforcement};
 use serde::Serialize;
 
+use crate::extract::RecalledConcept;
+
 #[derive(Clone, Copy, Debug, ValueEnum)]
 pub enum RecallFormat {
     /// Compact tabular form (header + CSV rows). Default — best token cost.
@@ -28,6 +39,10 @@ pub enum RecallFormat {
     /// `~/.icm/config.toml`-style files or consume from Rust/Python TOML
     /// parsers.
     Toml,
+    /// One memory ID per line.
+    Ids,
+    /// Tab-separated `id`, `topic`, `score` (`-` when unscored), no header.
+    Tsv,
 }
 
 /// Render a list of `(memory, score)` pairs into the chosen format.
@@ -37,21 +52,238 @@ pub enum RecallFormat {
 /// header reflects whether any score is present so we don't emit a
 /// useless empty column.
 pub fn render(results: &[(Memory, Option<f32>)], format: RecallFormat) -> Result<String> {
+    render_rows(results, None, format)
+}
+
+/// [`render`] for a recall spanning several databases: `dbs[i]` labels the
+/// database `results[i]` came from, shown as an extra `db` field.
+pub fn render_with_db(
+    results: &[(Memory, Option<f32>)],
+    dbs: &[&str],
+    format: RecallFormat,
+) -> Result<String> {
+    debug_assert_eq!(results.len(), dbs.len());
+    render_rows(results, Some(dbs), format)
+}
+
+fn render_rows(
+    results: &[(Memory, Option<f32>)],
+    dbs: Option<&[&str]>,
+    format: RecallFormat,
+) -> Result<String> {
+    Ok(match format {
+        RecallFormat::Toon => render_toon(results, dbs),
+        RecallFormat::Detail => render_detail(results, dbs),
+        RecallFormat::Json => render_json(results, dbs)?,
+        RecallFormat::Toml => render_toml(results, dbs)?,
+        RecallFormat::Ids => render_ids(results.iter().map(|(m, _)| m)),
+        RecallFormat::Tsv => render_tsv(results.iter().map(|(m, s)| (m, *s))),
+    })
+}
+
+/// Render raw-vector search hits (`icm recall --embedding-file`) with
+/// their cosine distance (`1 - similarity`, lower is closer) instead of
+/// a relevance score, so alternative embedders can be compared against
+/// the stored vectors directly.
+pub fn render_distances(results: &[(Memory, f32)], format: RecallFormat) -> Result<String> {
+    #[derive(Serialize)]
+    struct Row<'a> {
+        distance: f32,
+        id: &'a str,
+        topic: &'a str,
+        summary: &'a str,
+    }
+    #[derive(Serialize)]
+    struct Doc<'a> {
+        memories: Vec<Row<'a>>,
+    }
+    let rows: Vec<Row> = results
+        .iter()
+        .map(|(m, d)| Row {
+            distance: *d,
+            id: &m.id,
+            topic: &m.topic,
+            summary: &m.summary,
+        })
+        .collect();
     Ok(match format {
-        RecallFormat::Toon => render_toon(results),
-        RecallFormat::Detail => render_detail(results),
-        RecallFormat::Json => render_json(results)?,
-        RecallFormat::Toml => render_toml(results)?,
+        RecallFormat::Toon => {
+            let mut out = format!("memories[{}]{{distance,id,topic,summary}}:\n", rows.len());
+            for r in &rows {
+                let fields = [
+                    format!("{:.4}", r.distance),
+                    r.id.to_string(),
+                    r.topic.to_string(),
+                    r.summary.to_string(),
+                ];
+                let escaped: Vec<String> = fields.iter().map(|f| toon_escape(f)).collect();
+                out.push_str("  ");
+                out.push_str(&escaped.join(","));
+                out.push('\n');
+            }
+            out
+        }
+        RecallFormat::Detail => rows
+            .iter()
+            .map(|r| {
+                format!(
+                    "--- {} [distance: {:.4}] ---\n  topic:      {}\n  summary:    {}\n\n",
+                    r.id, r.distance, r.topic, r.summary
+                )
+            })
+            .collect(),
+        RecallFormat::Json => serde_json::to_string_pretty(&rows)?,
+        RecallFormat::Toml => toml::to_string(&Doc { memories: rows })?,
+        RecallFormat::Ids => render_ids(results.iter().map(|(m, _)| m)),
+        RecallFormat::Tsv => render_tsv(results.iter().map(|(m, d)| (m, Some(*d)))),
     })
 }
 
-fn render_toon(results: &[(Memory, Option<f32>)]) -> String {
+/// Append recalled memoir concepts to already-rendered memories.
+///
+/// TOON, detail and TOML get a trailing `concepts` block; JSON becomes
+/// an object `{"memories": [...], "concepts": [...]}` so it stays one
+/// document. `Ids` and `Tsv` list memories only, since their rows feed
+/// memory commands such as `icm forget`.
+pub fn with_concepts(
+    memories: String,
+    concepts: &[RecalledConcept],
+    format: RecallFormat,
+) -> Result<String> {
+    #[derive(Serialize)]
+    struct Row<'a> {
+        id: &'a str,
+        memoir: &'a str,
+        name: &'a str,
+        confidence: f32,
+        definition: &'a str,
+        #[serde(skip_serializing_if = "Vec::is_empty")]
+        labels: Vec<String>,
+    }
+    #[derive(Serialize)]
+    struct Doc<'a> {
+        concepts: Vec<Row<'a>>,
+    }
+    let rows: Vec<Row> = concepts
+        .iter()
+        .map(|rc| Row {
+            id: &rc.concept.id,
+            memoir: &rc.memoir,
+            name: &rc.concept.name,
+            confidence: rc.concept.confidence,
+            definition: &rc.concept.definition,
+            labels: rc.concept.labels.iter().map(|l| l.to_string()).collect(),
+        })
+        .collect();
+    let mut out = memories;
+    match format {
+        RecallFormat::Toon => {
+            out.push_str(&format!(
+                "concepts[{}]{{id,memoir,name,confidence,definition}}:\n",
+                rows.len()
+            ));
+            for r in &rows {
+                let fields = [
+                    r.id.to_string(),
+                    r.memoir.to_string(),
+                    r.name.to_string(),
+                    format!("{:.2}", r.confidence),
+                    r.definition.to_string(),
+                ];
+                let escaped: Vec<String> = fields.iter().map(|f| toon_escape(f)).collect();
+                out.push_str("  ");
+                out.push_str(&escaped.join(","));
+                out.push('\n');
+            }
+        }
+        RecallFormat::Detail => {
+            for r in &rows {
+                out.push_str(&format!(
+                    "--- {}/{} [concept, confidence: {:.2}] ---\n  definition: {}\n",
+                    r.memoir, r.name, r.confidence, r.definition
+                ));
+                if !r.labels.is_empty() {
+                    out.push_str(&format!("  labels:     {}\n", r.labels.join(", ")));
+                }
+                out.push('\n');
+            }
+        }
+        RecallFormat::Json => {
+            let memories: serde_json::Value = serde_json::from_str(&out)?;
+            out = serde_json::to_string_pretty(&serde_json::json!({
+                "memories": memories,
+                "concepts": rows,
+            }))?;
+        }
+        RecallFormat::Toml => {
+            if !rows.is_empty() {
+                out.push_str(&toml::to_string(&Doc { concepts: rows })?);
+            }
+        }
+        RecallFormat::Ids | RecallFormat::Tsv => {}
+    }
+    Ok(out)
+}
+
+/// Trailing block for `Detail` output: the weight each memory in
+/// `reinforced` went from and to on this recall (`results` hold the
+/// weights read before it). Empty when reinforcement is off or changed
+/// nothing.
+pub fn reinforcement_note(
+    results: &[(Memory, Option<f32>)],
+    reinforced: &[&str],
+    reinforcement: Reinforcement,
+) -> String {
+    if reinforcement.is_off() {
+        return String::new();
+    }
+    let mut rows = String::new();
+    for (m, _) in results
+        .iter()
+        .filter(|(m, _)| reinforced.contains(&m.id.as_str()))
+    {
+        let after = reinforcement.apply(m.weight);
+        if after > m.weight {
+            rows.push_str(&format!("  {}  {:.3} -> {:.3}\n", m.id, m.weight, after));
+        }
+    }
+    if rows.is_empty() {
+        return rows;
+    }
+    format!(
+        "reinforced on recall (rate {}, cap {}):\n{rows}",
+        reinforcement.rate, reinforcement.cap
+    )
+}
+
+fn render_ids<'a>(memories: impl Iterator<Item = &'a Memory>) -> String {
+    memories.map(|m| format!("{}\n", m.id)).collect()
+}
+
+fn render_tsv<'a>(rows: impl Iterator<Item = (&'a Memory, Option<f32>)>) -> String {
+    rows.map(|(m, score)| {
+        let score = score.map_or_else(|| "-".into(), |s| format!("{s:.3}"));
+        format!("{}\t{}\t{score}\n", m.id, m.topic)
+    })
+    .collect()
+}
+
+fn render_toon(results: &[(Memory, Option<f32>)], dbs: Option<&[&str]>) -> String {
     let has_score = results.iter().any(|(_, s)| s.is_some());
-    let cols: &[&str] = if has_score {
-        &["score", "id", "topic", "importance", "weight", "summary"]
-    } else {
-        &["id", "topic", "importance", "weight", "summary"]
-    };
+    let has_author = results.iter().any(|(m, _)| m.author.is_some());
+    let mut cols: Vec<&str> = Vec::with_capacity(8);
+    if has_score {
+        cols.push("score");
+    }
+    cols.push("id");
+    if dbs.is_some() {
+        cols.push("db");
+    }
+    cols.extend(["topic", "importance", "weight"]);
+    if has_author {
+        cols.push("author");
+    }
+    cols.push("summary");
 
     let mut out = String::new();
     out.push_str(&format!(
@@ -59,7 +291,7 @@ fn render_toon(results: &[(Memory, Option<f32>)]) -> String {
         results.len(),
         cols.join(",")
     ));
-    for (m, score) in results {
+    for (i, (m, score)) in results.iter().enumerate() {
         let weight = format!("{:.3}", m.weight);
         let importance = m.importance.to_string();
         let mut row: Vec<String> = Vec::with_capacity(cols.len());
@@ -71,9 +303,15 @@ fn render_toon(results: &[(Memory, Option<f32>)]) -> String {
             );
         }
         row.push(m.id.clone());
+        if let Some(dbs) = dbs {
+            row.push(dbs[i].to_string());
+        }
         row.push(m.topic.clone());
         row.push(importance);
         row.push(weight);
+        if has_author {
+            row.push(m.author.clone().unwrap_or_else(|| "-".into()));
+        }
         row.push(m.summary.clone());
 
         let escaped: Vec<String> = row.iter().map(|f| toon_escape(f)).collect();
@@ -93,18 +331,27 @@ fn toon_escape(field: &str) -> String {
     }
 }
 
-fn render_detail(results: &[(Memory, Option<f32>)]) -> String {
+fn render_detail(results: &[(Memory, Option<f32>)], dbs: Option<&[&str]>) -> String {
     use std::fmt::Write as _;
 
     let mut out = String::new();
-    for (m, score) in results {
+    for (i, (m, score)) in results.iter().enumerate() {
         match score {
             Some(s) => writeln!(&mut out, "--- {} [score: {:.3}] ---", m.id, s).ok(),
             None => writeln!(&mut out, "--- {} ---", m.id).ok(),
         };
+        if let Some(dbs) = dbs {
+            let _ = writeln!(&mut out, "  db:         {}", dbs[i]);
+        }
         let _ = writeln!(&mut out, "  topic:      {}", m.topic);
+        if !m.kind.is_fact() {
+            let _ = writeln!(&mut out, "  kind:       {}", m.kind);
+        }
         let _ = writeln!(&mut out, "  importance: {}", m.importance);
         let _ = writeln!(&mut out, "  weight:     {:.3}", m.weight);
+        if let Some(ref author) = m.author {
+            let _ = writeln!(&mut out, "  author:     {author}");
+        }
         let _ = writeln!(
             &mut out,
             "  created:    {}",
@@ -117,9 +364,27 @@ fn render_detail(results: &[(Memory, Option<f32>)]) -> String {
             m.access_count
         );
         let _ = writeln!(&mut out, "  summary:    {}", m.summary);
+        match m.source.path() {
+            Some(path) => writeln!(&mut out, "  source:     {} ({path})", m.source).ok(),
+            None if !matches!(m.source, icm_core::MemorySource::Manual { .. }) => {
+                writeln!(&mut out, "  source:     {}", m.source).ok()
+            }
+            None => None,
+        };
+        if let Some(p) = m.source.provenance().filter(|p| p.repo.is_some()) {
+            let repo = p.repo.as_deref().unwrap_or_default();
+            match &p.branch {
+                Some(branch) => writeln!(&mut out, "  repo:       {repo} ({branch})").ok(),
+                None => writeln!(&mut out, "  repo:       {repo}").ok(),
+            };
+        }
         if !m.keywords.is_empty() {
             let _ = writeln!(&mut out, "  keywords:   {}", m.keywords.join(", "));
         }
+        if !m.metadata.is_empty() {
+            let pairs: Vec<String> = m.metadata.iter().map(|(k, v)| format!("{k}={v}")).collect();
+            let _ = writeln!(&mut out, "  meta:       {}", pairs.join(", "));
+        }
         if let Some(ref raw) = m.raw_excerpt {
             let _ = writeln!(&mut out, "  raw:        {raw}");
         }
@@ -131,25 +396,29 @@ fn render_detail(results: &[(Memory, Option<f32>)]) -> String {
     out
 }
 
-fn render_json(results: &[(Memory, Option<f32>)]) -> Result<String> {
+fn render_json(results: &[(Memory, Option<f32>)], dbs: Option<&[&str]>) -> Result<String> {
     #[derive(Serialize)]
     struct Row<'a> {
         #[serde(skip_serializing_if = "Option::is_none")]
         score: Option<f32>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        db: Option<&'a str>,
         #[serde(flatten)]
         memory: &'a Memory,
     }
     let rows: Vec<Row> = results
         .iter()
-        .map(|(m, s)| Row {
+        .enumerate()
+        .map(|(i, (m, s))| Row {
             score: *s,
+            db: dbs.map(|d| d[i]),
             memory: m,
         })
         .collect();
     Ok(serde_json::to_string_pretty(&rows)?)
 }
 
-fn render_toml(results: &[(Memory, Option<f32>)]) -> Result<String> {
+fn render_toml(results: &[(Memory, Option<f32>)], dbs: Option<&[&str]>) -> Result<String> {
     /// Mirror of `Memory`'s public shape but `Serialize`-friendly for
     /// `toml::to_string`. The base `Memory` impls already derive
     /// `Serialize`, but we cherry-pick fields so the TOML stays compact
@@ -160,17 +429,29 @@ fn render_toml(results: &[(Memory, Option<f32>)]) -> Result<String> {
         #[serde(skip_serializing_if = "Option::is_none")]
         score: Option<f32>,
         id: &'a str,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        db: Option<&'a str>,
         topic: &'a str,
+        #[serde(skip_serializing_if = "MemoryKind::is_fact")]
+        kind: MemoryKind,
         importance: String,
         weight: f32,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        author: Option<&'a str>,
         access_count: u32,
         created_at: String,
         last_accessed: String,
         summary: &'a str,
+        source: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        source_path: Option<&'a str>,
         #[serde(skip_serializing_if = "Vec::is_empty")]
         keywords: Vec<&'a str>,
         #[serde(skip_serializing_if = "Option::is_none")]
         raw_excerpt: Option<&'a str>,
+        /// Last: TOML writes it as a sub-table.
+        #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+        metadata: &'a BTreeMap<String, String>,
     }
     #[derive(Serialize)]
     struct Doc<'a> {
@@ -178,18 +459,25 @@ fn render_toml(results: &[(Memory, Option<f32>)]) -> Result<String> {
     }
     let rows: Vec<Row> = results
         .iter()
-        .map(|(m, s)| Row {
+        .enumerate()
+        .map(|(i, (m, s))| Row {
             score: *s,
             id: &m.id,
+            db: dbs.map(|d| d[i]),
             topic: &m.topic,
+            kind: m.kind,
             importance: m.importance.to_string(),
             weight: m.weight,
+            author: m.author.as_deref(),
             access_count: m.access_count,
             created_at: m.created_at.to_rfc3339(),
             last_accessed: m.last_accessed.to_rfc3339(),
             summary: &m.summary,
+            source: m.source.to_string(),
+            source_path: m.source.path(),
             keywords: m.keywords.iter().map(String::as_str).collect(),
             raw_excerpt: m.raw_excerpt.as_deref(),
+            metadata: &m.metadata,
         })
         .collect();
     Ok(toml::to_string(&Doc { memories: rows })?)
@@ -216,9 +504,29 @@ mod tests {
         vec![(a, Some(0.91)), (b, Some(0.64))]
     }
 
+    #[test]
+    fn reinforcement_note_lists_raised_weights_only() {
+        let data = fixture();
+        let r = Reinforcement {
+            rate: 0.1,
+            cap: 0.8,
+        };
+        // 01HZZ0 (0.85) is already above the cap; 01HZZ1 is not recalled
+        // from this store.
+        assert_eq!(reinforcement_note(&data, &["01HZZ0"], r), "");
+        assert_eq!(
+            reinforcement_note(&data, &["01HZZ0", "01HZZ1"], r),
+            "reinforced on recall (rate 0.1, cap 0.8):\n  01HZZ1  0.720 -> 0.748\n"
+        );
+        assert_eq!(
+            reinforcement_note(&data, &["01HZZ1"], Reinforcement::OFF),
+            ""
+        );
+    }
+
     #[test]
     fn toon_includes_score_column_when_scored() {
-        let s = render_toon(&fixture());
+        let s = render_toon(&fixture(), None);
         let header = s.lines().next().unwrap();
         assert!(header.contains("score,"), "expected score column: {header}");
         assert_eq!(s.lines().count(), 3, "header + 2 rows");
@@ -230,7 +538,7 @@ mod tests {
         for (_, s) in &mut data {
             *s = None;
         }
-        let s = render_toon(&data);
+        let s = render_toon(&data, None);
         let header = s.lines().next().unwrap();
         assert!(
             !header.contains("score,"),
@@ -240,7 +548,7 @@ mod tests {
 
     #[test]
     fn toon_escapes_commas_in_summary() {
-        let s = render_toon(&fixture());
+        let s = render_toon(&fixture(), None);
         assert!(
             s.contains("\"second, with a comma\""),
             "comma must be CSV-quoted in toon output:\n{s}"
@@ -249,15 +557,52 @@ mod tests {
 
     #[test]
     fn detail_renders_label_lines_per_memory() {
-        let s = render_detail(&fixture());
+        let s = render_detail(&fixture(), None);
         assert!(s.contains("topic:"));
         assert!(s.contains("importance:"));
         assert!(s.contains("--- 01HZZ0 [score: 0.910] ---"));
     }
 
+    #[test]
+    fn detail_shows_hook_provenance() {
+        let mut m = Memory::new("ctx".into(), "hook fact".into(), Importance::Medium);
+        m.source = icm_core::MemorySource::ClaudeCode {
+            session_id: "sess-42".into(),
+            file_path: Some("/work/app".into()),
+            provenance: icm_core::Provenance {
+                repo: Some("0123456789abcdef".into()),
+                branch: Some("main".into()),
+                ..Default::default()
+            },
+        };
+        let s = render_detail(&[(m, None)], None);
+        assert!(
+            s.contains("source:     claude-code:sess-42 (/work/app)"),
+            "{s}"
+        );
+        assert!(s.contains("repo:       0123456789abcdef (main)"), "{s}");
+        // Manual memories stay quiet.
+        assert!(!render_detail(&fixture(), None).contains("source:"));
+    }
+
+    #[test]
+    fn distances_render_in_every_format() {
+        let m = Memory::new("t".into(), "near, far".into(), Importance::Low);
+        let hits = vec![(m, 0.125f32)];
+        let toon = render_distances(&hits, RecallFormat::Toon).unwrap();
+        assert!(toon.starts_with("memories[1]{distance,id,topic,summary}:"));
+        assert!(toon.contains("0.1250"));
+        assert!(toon.contains("\"near, far\""));
+        let json = render_distances(&hits, RecallFormat::Json).unwrap();
+        assert!(json.contains("\"distance\": 0.125"));
+        assert!(render_distances(&hits, RecallFormat::Toml)
+            .unwrap()
+            .contains("distance = 0.125"));
+    }
+
     #[test]
     fn json_includes_score_field() {
-        let s = render_json(&fixture()).unwrap();
+        let s = render_json(&fixture(), None).unwrap();
         assert!(s.contains("\"score\""));
         assert!(s.contains("\"id\": \"01HZZ0\""));
     }
@@ -275,7 +620,7 @@ mod tests {
         m.created_at = Utc.with_ymd_and_hms(2026, 5, 10, 12, 34, 0).unwrap();
         m.last_accessed = m.created_at;
 
-        let s = render_detail(&[(m.clone(), None)]);
+        let s = render_detail(&[(m.clone(), None)], None);
 
         let expected_created = m
             .created_at
@@ -302,18 +647,18 @@ mod tests {
     fn empty_list_renders_clean() {
         let empty: Vec<(Memory, Option<f32>)> = Vec::new();
         assert_eq!(
-            render_toon(&empty),
+            render_toon(&empty, None),
             "memories[0]{id,topic,importance,weight,summary}:\n"
         );
-        assert_eq!(render_detail(&empty), "");
-        assert_eq!(render_json(&empty).unwrap(), "[]");
+        assert_eq!(render_detail(&empty, None), "");
+        assert_eq!(render_json(&empty, None).unwrap(), "[]");
     }
 
     #[test]
     fn toml_round_trips_through_a_parser() {
         // Take the fixture, render it as TOML, parse it back, and check
         // we still see the field shape from issue #269.
-        let s = render_toml(&fixture()).unwrap();
+        let s = render_toml(&fixture(), None).unwrap();
         let parsed: toml::Value = toml::from_str(&s).unwrap();
         let memories = parsed
             .get("memories")
@@ -341,11 +686,119 @@ mod tests {
     #[test]
     fn toml_empty_list_renders_clean() {
         let empty: Vec<(Memory, Option<f32>)> = Vec::new();
-        let s = render_toml(&empty).unwrap();
+        let s = render_toml(&empty, None).unwrap();
         // toml crate emits no header line when the array is empty.
         assert!(
             s.trim().is_empty() || s == "memories = []\n",
             "unexpected empty TOML: {s:?}"
         );
     }
+
+    #[test]
+    fn multi_db_results_carry_their_db() {
+        let rows = fixture();
+        let dbs = ["primary.db", "team.db"];
+        let toon = render_with_db(&rows, &dbs, RecallFormat::Toon).unwrap();
+        assert!(toon.starts_with("memories[2]{score,id,db,topic,"), "{toon}");
+        assert!(toon.contains(",team.db,topic-b,"), "{toon}");
+        let json: serde_json::Value =
+            serde_json::from_str(&render_with_db(&rows, &dbs, RecallFormat::Json).unwrap())
+                .unwrap();
+        assert_eq!(json[0]["db"], "primary.db");
+        assert!(render_with_db(&rows, &dbs, RecallFormat::Detail)
+            .unwrap()
+            .contains("  db:         team.db\n"));
+        assert!(render_with_db(&rows, &dbs, RecallFormat::Toml)
+            .unwrap()
+            .contains("db = \"team.db\""));
+        assert!(!render(&rows, RecallFormat::Json)
+            .unwrap()
+            .contains("\"db\""));
+    }
+
+    #[test]
+    fn author_is_shown_when_attributed() {
+        let mut rows = fixture();
+        assert!(!render_toon(&rows, None).contains("author"));
+        rows[0].0.author = Some("alice".into());
+        let toon = render_toon(&rows, None);
+        assert!(toon.contains("weight,author,summary}"), "{toon}");
+        assert!(toon.contains(",alice,"), "{toon}");
+        assert!(toon.contains(",-,"), "{toon}");
+        assert!(render_detail(&rows, None).contains("  author:     alice\n"));
+        assert!(render_toml(&rows, None)
+            .unwrap()
+            .contains("author = \"alice\""));
+        let json: serde_json::Value =
+            serde_json::from_str(&render(&rows, RecallFormat::Json).unwrap()).unwrap();
+        assert_eq!(json[0]["author"], "alice");
+    }
+
+    #[test]
+    fn metadata_is_shown_when_tagged() {
+        let mut rows = fixture();
+        rows[0].0.metadata.insert("ticket".into(), "PROJ-1".into());
+        assert!(render_detail(&rows, None).contains("  meta:       ticket=PROJ-1\n"));
+        let toml = render_toml(&rows, None).unwrap();
+        assert!(toml.contains("ticket = \"PROJ-1\""), "{toml}");
+        let json: serde_json::Value =
+            serde_json::from_str(&render(&rows, RecallFormat::Json).unwrap()).unwrap();
+        assert_eq!(json[0]["metadata"]["ticket"], "PROJ-1");
+        assert!(json[1].get("metadata").is_none());
+    }
+
+    #[test]
+    fn ids_and_tsv_are_plain_rows() {
+        let results = fixture();
+        let ids = render(&results, RecallFormat::Ids).unwrap();
+        let expected: String = results.iter().map(|(m, _)| format!("{}\n", m.id)).collect();
+        assert_eq!(ids, expected);
+
+        let tsv = render(&results, RecallFormat::Tsv).unwrap();
+        let lines: Vec<Vec<&str>> = tsv.lines().map(|l| l.split('\t').collect()).collect();
+        assert_eq!(lines.len(), results.len());
+        assert_eq!(lines[0][0], results[0].0.id);
+        assert_eq!(lines[0][1], "topic-a");
+        assert_eq!(lines[0].len(), 3);
+
+        assert_eq!(render(&[], RecallFormat::Ids).unwrap(), "");
+        assert_eq!(render(&[], RecallFormat::Tsv).unwrap(), "");
+    }
+
+    #[test]
+    fn concepts_follow_memories_in_each_format() {
+        let results = fixture();
+        let mut concept = icm_core::Concept::new(
+            "m1".into(),
+            "release-train".into(),
+            "Ships every Tuesday, no exceptions".into(),
+        );
+        concept.confidence = 0.8;
+        let concepts = [RecalledConcept {
+            memoir: "ops".into(),
+            concept,
+        }];
+        let with =
+            |format| with_concepts(render(&results, format).unwrap(), &concepts, format).unwrap();
+
+        let toon = with(RecallFormat::Toon);
+        assert!(toon.starts_with("memories[2]"), "{toon}");
+        assert!(toon.contains("concepts[1]{id,memoir,name,confidence,definition}:"));
+        assert!(toon.contains(",ops,release-train,0.80,\"Ships every Tuesday, no exceptions\""));
+
+        let json: serde_json::Value = serde_json::from_str(&with(RecallFormat::Json)).unwrap();
+        assert_eq!(json["memories"].as_array().unwrap().len(), 2);
+        assert_eq!(json["concepts"][0]["memoir"], "ops");
+        assert_eq!(json["concepts"][0]["name"], "release-train");
+
+        let doc: toml::Value = toml::from_str(&with(RecallFormat::Toml)).unwrap();
+        assert_eq!(doc["memories"].as_array().unwrap().len(), 2);
+        assert_eq!(doc["concepts"][0]["name"].as_str(), Some("release-train"));
+
+        assert!(with(RecallFormat::Detail).contains("--- ops/release-train [concept"));
+        assert_eq!(
+            with(RecallFormat::Ids),
+            render(&results, RecallFormat::Ids).unwrap()
+        );
+    }
 }
@@ -0,0 +1,368 @@
+//! The slash-command pack `icm init --mode skill` installs, and the tools
+//! it installs it for. Every command is one prompt written once with
+//! `$ARGUMENTS` for the text typed after the command; each target renders
+//! it in the file format its tool reads custom commands from.
+
+use std::path::{Path, PathBuf};
+
+/// One slash command of the pack.
+pub struct SkillCommand {
+    /// Command name, without the per-tool prefix (`recall`, `forget`…).
+    pub name: &'static str,
+    pub description: &'static str,
+    /// What the command takes after its name, for tools that show a hint.
+    pub argument_hint: &'static str,
+    pub prompt: &'static str,
+}
+
+pub const COMMANDS: &[SkillCommand] = &[
+    SkillCommand {
+        name: "recall",
+        description: "Search ICM memory",
+        argument_hint: "[query]",
+        prompt: r#"Search ICM memory for: $ARGUMENTS
+
+Run:
+```bash
+if [ -z "$ARGUMENTS" ]; then
+  icm wake-up --max-tokens 800
+else
+  icm recall "$ARGUMENTS" --limit 10
+fi
+```
+"#,
+    },
+    SkillCommand {
+        name: "remember",
+        description: "Store a fact in ICM memory",
+        argument_hint: "<fact>",
+        prompt: r#"Store the following in ICM memory: $ARGUMENTS
+
+Run:
+```bash
+icm remember "$ARGUMENTS"
+```
+"#,
+    },
+    SkillCommand {
+        name: "remember-session",
+        description: "Checkpoint this session's lessons into ICM",
+        argument_hint: "",
+        prompt: r#"Checkpoint this session: store non-obvious, reusable lessons in ICM long-term memory.
+
+Target 3-10 pertinent stores total. Store the lesson, not the play-by-play. One fact per call, one sentence each, covering *what*, *why*, and *outcome*. Always pair a problem with its resolution if both happened this session; never store a gap alone. Anchor in VCS: prefer PR numbers and branch names. Feature-branch SHAs drift on amend; if you cite one, include the commit title so it stays grep-able.
+
+| Kind                         | Topic                  | Importance |
+| ---------------------------- | ---------------------- | ---------- |
+| Decision + reason            | `decisions-<project>`  | high       |
+| Error + root cause + fix     | `errors-resolved`      | high       |
+| User preference / correction | `preferences`          | critical   |
+| Pattern or invariant found   | `review-patterns`      | high       |
+| Significant work completed   | `context-<project>`    | high       |
+
+`<project>` = current project name (e.g. `decisions-icm`).
+
+Skip: facts derivable from code or `git log`, transient build state, anything already stored this session (on re-run, capture only the delta).
+
+Run:
+
+    icm remember "<fact>" --topic <topic> --importance <level> [--keywords "k1,k2"]
+
+Example:
+
+    icm remember "Fixed flaky test by using fake timers; race condition only appeared under CI load" --topic errors-resolved --importance high --keywords "tests,flaky"
+
+End with a one-line recap.
+"#,
+    },
+    SkillCommand {
+        name: "forget",
+        description: "Delete ICM memories matching a description",
+        argument_hint: "<description>",
+        prompt: r#"Forget the ICM memories matching: $ARGUMENTS
+
+If no description was given, ask what should be forgotten and stop there.
+
+Otherwise list the candidates:
+```bash
+icm recall "$ARGUMENTS" --limit 5
+```
+
+Show the matches with their IDs and ask which ones to delete (none, some, all). Never delete without an explicit confirmation. Then, for each confirmed ID:
+```bash
+icm forget <id>
+```
+
+End with a one-line recap of what was removed.
+"#,
+    },
+    SkillCommand {
+        name: "consolidate",
+        description: "Merge the memories of an ICM topic into one summary",
+        argument_hint: "[topic]",
+        prompt: r#"Consolidate the ICM topic: $ARGUMENTS
+
+If no topic was given, find one worth consolidating:
+```bash
+icm health
+```
+Propose the topic with the most memories or the most stale ones, and ask before going on.
+
+Then review what will be merged:
+```bash
+icm list --topic "<topic>"
+```
+
+And consolidate (add `--keep-originals` if the user wants the originals kept):
+```bash
+icm consolidate --topic "<topic>"
+```
+
+End with a one-line recap: the topic and how many memories were merged.
+"#,
+    },
+    SkillCommand {
+        name: "memoir",
+        description: "Search ICM memoirs (knowledge graphs)",
+        argument_hint: "[query]",
+        prompt: r#"Search ICM memoirs for: $ARGUMENTS
+
+Run:
+```bash
+if [ -z "$ARGUMENTS" ]; then
+  icm memoir list
+else
+  icm memoir search-all "$ARGUMENTS" --limit 10
+fi
+```
+
+To follow a concept's links:
+```bash
+icm memoir inspect -m <memoir> "<concept>"
+```
+"#,
+    },
+    SkillCommand {
+        name: "session-summary",
+        description: "Store a summary of this session in ICM",
+        argument_hint: "[focus]",
+        prompt: r#"Summarize this session into ICM memory. Focus, if given: $ARGUMENTS
+
+Write one summary of 3-6 sentences: the goal, what was done, what was decided and why, and what is left to do. Name files, PRs and branches rather than describing them. Skip anything derivable from `git log`.
+
+Run:
+```bash
+icm store -t context-<project> -c "<summary>" -i high
+```
+
+`<project>` = current project name (e.g. `context-icm`). Reply with the stored summary.
+"#,
+    },
+];
+
+/// Always-on rule for the tools without custom commands (Cursor's
+/// `.mdc`, Roo Code's `.md`).
+pub const CURSOR_RULE: &str = r#"---
+description: ICM persistent memory for AI agents
+globs:
+alwaysApply: true
+---
+
+This project uses ICM (Infinite Context Memory) for persistent memory. Usage is MANDATORY.
+
+**Recall** — at the start of each task, search for relevant past context:
+```bash
+icm recall "query"
+```
+
+**Store** — you MUST store when any of these happens:
+1. Error resolved → `icm store -t errors-resolved -c "description" -i high`
+2. Architecture decision → `icm store -t decisions-{project} -c "description" -i high`
+3. User preference discovered → `icm store -t preferences -c "description" -i critical`
+4. Significant task completed → `icm store -t context-{project} -c "summary" -i high`
+5. Conversation exceeds ~20 tool calls without a store → store progress summary
+
+Do this BEFORE responding to the user. Not optional.
+"#;
+
+/// File format a tool reads its custom commands in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    /// The prompt as-is.
+    Markdown,
+    /// The prompt under a `description` / `argument-hint` front matter.
+    FrontMatter,
+    /// Gemini CLI's TOML, which spells the arguments `{{args}}`.
+    GeminiToml,
+}
+
+/// A tool that supports custom slash commands.
+pub struct SkillTarget {
+    /// Name `icm init` detects and filters the tool by.
+    pub tool: &'static str,
+    pub dir: PathBuf,
+    /// Prepended to the command name in the file name.
+    pub prefix: &'static str,
+    /// How the tool spells a command whose file is `<prefix><name>`.
+    pub invocation: &'static str,
+    pub format: Format,
+}
+
+impl SkillTarget {
+    pub fn file_name(&self, cmd: &SkillCommand) -> String {
+        let ext = match self.format {
+            Format::GeminiToml => "toml",
+            Format::Markdown | Format::FrontMatter => "md",
+        };
+        format!("{}{}.{ext}", self.prefix, cmd.name)
+    }
+
+    /// The tool and the command as typed in it, e.g. `Gemini /icm:recall`.
+    pub fn label(&self, cmd: &SkillCommand) -> String {
+        format!(
+            "{} {}{}{}",
+            self.tool, self.invocation, self.prefix, cmd.name
+        )
+    }
+
+    pub fn render(&self, cmd: &SkillCommand) -> String {
+        match self.format {
+            Format::Markdown => cmd.prompt.to_string(),
+            Format::FrontMatter => {
+                let mut out = format!("---\ndescription: {}\n", cmd.description);
+                if !cmd.argument_hint.is_empty() {
+                    // Quoted: a bare `[query]` is a YAML list.
+                    out.push_str(&format!("argument-hint: \"{}\"\n", cmd.argument_hint));
+                }
+                out.push_str("---\n\n");
+                out.push_str(cmd.prompt);
+                out
+            }
+            // A literal multi-line string: prompts hold quotes and
+            // backslashes but never `'''`.
+            Format::GeminiToml => format!(
+                "description = \"{}\"\nprompt = '''\n{}'''\n",
+                cmd.description,
+                cmd.prompt.replace("$ARGUMENTS", "{{args}}")
+            ),
+        }
+    }
+}
+
+/// Every tool the pack is installed for. Claude Code keeps its historical
+/// unprefixed `/recall` / `/remember`; the others namespace the pack.
+pub fn targets(
+    home: &Path,
+    claude_dir: &Path,
+    codex_dir: &Path,
+    gemini_dir: &Path,
+) -> Vec<SkillTarget> {
+    vec![
+        SkillTarget {
+            tool: "Claude Code",
+            dir: claude_dir.join("commands"),
+            prefix: "",
+            invocation: "/",
+            format: Format::FrontMatter,
+        },
+        SkillTarget {
+            tool: "Codex CLI",
+            dir: codex_dir.join("prompts"),
+            prefix: "icm-",
+            invocation: "/prompts:",
+            format: Format::FrontMatter,
+        },
+        SkillTarget {
+            tool: "Gemini",
+            dir: gemini_dir.join("commands/icm"),
+            prefix: "",
+            invocation: "/icm:",
+            format: Format::GeminiToml,
+        },
+        SkillTarget {
+            tool: "OpenCode",
+            dir: home.join(".config/opencode/command"),
+            prefix: "icm-",
+            invocation: "/",
+            format: Format::FrontMatter,
+        },
+        SkillTarget {
+            tool: "Amp",
+            dir: home.join(".config/amp/skills"),
+            prefix: "icm-",
+            invocation: "/",
+            format: Format::Markdown,
+        },
+        // Same shape as Amp (see issue #259).
+        SkillTarget {
+            tool: "Pi",
+            dir: home.join(".pi/agent/skills"),
+            prefix: "icm-",
+            invocation: "/",
+            format: Format::Markdown,
+        },
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn target(format: Format) -> SkillTarget {
+        SkillTarget {
+            tool: "Test",
+            dir: PathBuf::from("/tmp"),
+            prefix: "icm-",
+            invocation: "/",
+            format,
+        }
+    }
+
+    fn command(name: &str) -> &'static SkillCommand {
+        COMMANDS.iter().find(|c| c.name == name).unwrap()
+    }
+
+    #[test]
+    fn every_prompt_handles_its_arguments() {
+        for cmd in COMMANDS {
+            assert!(!cmd.prompt.contains("'''"), "{} breaks the TOML", cmd.name);
+            assert_eq!(
+                cmd.prompt.contains("$ARGUMENTS"),
+                !cmd.argument_hint.is_empty(),
+                "{}",
+                cmd.name
+            );
+        }
+    }
+
+    #[test]
+    fn front_matter_carries_description_and_hint() {
+        let t = target(Format::FrontMatter);
+        let forget = command("forget");
+        assert_eq!(t.file_name(forget), "icm-forget.md");
+        assert_eq!(t.label(forget), "Test /icm-forget");
+        assert!(t.render(forget).starts_with(
+            "---\ndescription: Delete ICM memories matching a description\n\
+             argument-hint: \"<description>\"\n---\n\nForget the ICM memories matching: $ARGUMENTS\n"
+        ));
+        let session = t.render(command("remember-session"));
+        assert!(!session.contains("argument-hint"));
+    }
+
+    #[test]
+    fn gemini_toml_uses_args_placeholder() {
+        let t = target(Format::GeminiToml);
+        let memoir = command("memoir");
+        assert_eq!(t.file_name(memoir), "icm-memoir.toml");
+        let toml_text = t.render(memoir);
+        assert!(!toml_text.contains("$ARGUMENTS"));
+        let parsed: toml::Value = toml::from_str(&toml_text).unwrap();
+        assert_eq!(
+            parsed["description"].as_str(),
+            Some("Search ICM memoirs (knowledge graphs)")
+        );
+        let prompt = parsed["prompt"].as_str().unwrap();
+        assert!(prompt.starts_with("Search ICM memoirs for: {{args}}\n"));
+        assert!(prompt.contains(r#"icm memoir search-all "{{args}}" --limit 10"#));
+    }
+}
@@ -375,6 +375,24 @@ pub fn build_consolidate_prompt(topic: &str, summaries: &[&str], max_tokens: usi
     p
 }
 
+/// Build the prompt used to condense one over-long memory into a
+/// summary of at most `max_chars` characters. The full text is kept in
+/// `raw_excerpt`, so the summary only needs to be findable and accurate.
+pub fn build_condense_prompt(content: &str, max_chars: usize) -> String {
+    let mut p = String::new();
+    p.push_str("Task: condense the text below into a memory summary of at most ");
+    p.push_str(&max_chars.to_string());
+    p.push_str(" characters.\n\n");
+    p.push_str("Rules:\n");
+    p.push_str("- Keep the durable facts and decisions; drop logs, listings and filler.\n");
+    p.push_str("- Preserve identifiers, file paths, versions and error codes EXACTLY.\n");
+    p.push_str("- Output PLAIN TEXT ONLY — no preamble, no markdown headers.\n\n");
+    p.push_str("Text:\n");
+    p.push_str(content);
+    p.push_str("\n\nSummary (plain text, no preamble):\n");
+    p
+}
+
 // ─────────────────────────────────────────────────────────────────────────────
 // Tests
 // ─────────────────────────────────────────────────────────────────────────────
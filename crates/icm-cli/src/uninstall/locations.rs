@@ -446,6 +446,31 @@ pub(crate) fn build_locations(d: &DirContext) -> Vec<LocationSpec> {
         purge_data_only: false,
     });
 
+    // --- Slash-command pack (`crate::skills`), minus the files listed above ---
+    for target in crate::skills::targets(&d.home, &d.claude_dir, &d.codex_dir, &d.gemini_dir) {
+        let label = match target.tool {
+            "Claude Code" => "Claude Code command",
+            "Codex CLI" => "Codex CLI prompt",
+            "Gemini" => "Gemini CLI command",
+            "OpenCode" => "OpenCode command",
+            "Amp" => "Amp skill",
+            "Pi" => "Pi skill",
+            _ => "ICM slash command",
+        };
+        for cmd in crate::skills::COMMANDS {
+            let path = target.dir.join(target.file_name(cmd));
+            if specs.iter().any(|s| s.path == path) {
+                continue;
+            }
+            specs.push(LocationSpec {
+                label,
+                path,
+                kind: K::OwnedFile,
+                purge_data_only: false,
+            });
+        }
+    }
+
     // --- Cwd instruction files (written by `icm init` at the cwd it ran in) ---
     let cwd_md_files: &[(&str, &str)] = &[
         ("CLAUDE.md (cwd)", "CLAUDE.md"),
@@ -556,6 +581,10 @@ mod tests {
             "Pi AGENTS.md",
             "Pi /icm-recall",
             "Pi /icm-remember",
+            "Claude Code command",
+            "Codex CLI prompt",
+            "Gemini CLI command",
+            "OpenCode command",
             "Amazon Q MCP",
             "Continue.dev",
             "CLAUDE.md (cwd)",
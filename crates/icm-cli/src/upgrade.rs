@@ -5,6 +5,7 @@
 
 use std::io::{Read, Write};
 use std::path::PathBuf;
+use std::time::Duration;
 
 use anyhow::{anyhow, bail, Context, Result};
 use sha2::{Digest, Sha256};
@@ -36,13 +37,15 @@ fn detect_target() -> Result<(&'static str, &'static str)> {
 }
 
 /// Fetch the latest release tag from the GitHub API.
-fn fetch_latest_version() -> Result<String> {
+fn fetch_latest_version(timeout: Option<Duration>) -> Result<String> {
     let url = format!("https://api.github.com/repos/{REPO}/releases/latest");
-    let resp = ureq::get(&url)
+    let mut req = ureq::get(&url)
         .set("User-Agent", "icm-upgrader")
-        .set("Accept", "application/vnd.github+json")
-        .call()
-        .context("failed to fetch latest release")?;
+        .set("Accept", "application/vnd.github+json");
+    if let Some(t) = timeout {
+        req = req.timeout(t);
+    }
+    let resp = req.call().context("failed to fetch latest release")?;
 
     let json: serde_json::Value = resp.into_json().context("invalid API response")?;
     let tag = json
@@ -52,6 +55,40 @@ fn fetch_latest_version() -> Result<String> {
     Ok(tag.to_string())
 }
 
+/// Version number of a release tag (`icm-v0.10.57` / `v0.10.57`).
+fn tag_version(tag: &str) -> &str {
+    tag.strip_prefix("icm-v")
+        .or_else(|| tag.strip_prefix('v'))
+        .unwrap_or(tag)
+}
+
+/// True when `latest` is a strictly newer `x.y.z` than `current`.
+/// Pre-release suffixes (`-rc.1`) are ignored; unparsable parts count
+/// as 0, so a dev build never "upgrades" to an older release.
+pub fn is_newer(latest: &str, current: &str) -> bool {
+    fn parts(v: &str) -> Vec<u64> {
+        v.split('-')
+            .next()
+            .unwrap_or("")
+            .split('.')
+            .map(|p| p.parse().unwrap_or(0))
+            .collect()
+    }
+    let (mut a, mut b) = (parts(latest), parts(current));
+    let len = a.len().max(b.len());
+    a.resize(len, 0);
+    b.resize(len, 0);
+    a > b
+}
+
+/// The latest release version if it is newer than this binary. Used for
+/// the passive notice in `icm doctor`, so the request is short-lived.
+pub fn newer_release(timeout: Duration) -> Result<Option<String>> {
+    let latest = fetch_latest_version(Some(timeout))?;
+    let latest = tag_version(&latest);
+    Ok(is_newer(latest, env!("CARGO_PKG_VERSION")).then(|| latest.to_string()))
+}
+
 /// Download a URL to a byte vector with size tracking.
 fn download_bytes(url: &str) -> Result<Vec<u8>> {
     let resp = ureq::get(url)
@@ -88,8 +125,7 @@ fn parse_expected_sha(checksums: &str, filename: &str) -> Result<String> {
 /// Extract a binary from a tar.gz or zip archive. Returns the binary bytes.
 fn extract_binary(archive: &[u8], is_zip: bool) -> Result<Vec<u8>> {
     if is_zip {
-        // Windows: zip containing icm.exe
-        bail!("zip extraction not supported — use the standalone installer on Windows");
+        return extract_zip_binary(archive);
     }
 
     // Unix: tar.gz containing icm
@@ -109,6 +145,32 @@ fn extract_binary(archive: &[u8], is_zip: bool) -> Result<Vec<u8>> {
     bail!("binary {BINARY_NAME} not found in archive")
 }
 
+/// Windows release archives are zips holding `icm.exe`. Windows 10+ ships
+/// bsdtar as `tar.exe`, which reads zips, so we unpack with it rather
+/// than pulling in a zip crate for this one path.
+fn extract_zip_binary(archive: &[u8]) -> Result<Vec<u8>> {
+    let dir = std::env::temp_dir().join(format!("icm-upgrade-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).with_context(|| format!("cannot create {}", dir.display()))?;
+    let zip_path = dir.join("icm.zip");
+    let result = (|| {
+        std::fs::write(&zip_path, archive).context("cannot write archive")?;
+        let status = std::process::Command::new("tar")
+            .arg("-xf")
+            .arg(&zip_path)
+            .arg("-C")
+            .arg(&dir)
+            .status()
+            .context("cannot run tar to unpack the zip archive")?;
+        if !status.success() {
+            bail!("tar failed to unpack the zip archive ({status})");
+        }
+        std::fs::read(dir.join(format!("{BINARY_NAME}.exe")))
+            .with_context(|| format!("binary {BINARY_NAME}.exe not found in archive"))
+    })();
+    std::fs::remove_dir_all(&dir).ok();
+    result
+}
+
 /// Run the upgrade flow: fetch latest, verify checksum, replace binary.
 pub fn cmd_upgrade(apply: bool, check_only: bool) -> Result<()> {
     let current_version = env!("CARGO_PKG_VERSION");
@@ -116,11 +178,11 @@ pub fn cmd_upgrade(apply: bool, check_only: bool) -> Result<()> {
 
     // 1. Fetch latest release
     eprintln!("Checking for updates...");
-    let latest_tag = fetch_latest_version()?;
-    let latest_version = latest_tag.strip_prefix("icm-v").unwrap_or(&latest_tag);
+    let latest_tag = fetch_latest_version(None)?;
+    let latest_version = tag_version(&latest_tag);
     eprintln!("Latest version:  {latest_version}");
 
-    if latest_version == current_version {
+    if !is_newer(latest_version, current_version) {
         eprintln!("Already up to date.");
         return Ok(());
     }
@@ -226,3 +288,36 @@ pub fn cmd_upgrade(apply: bool, check_only: bool) -> Result<()> {
     eprintln!("Successfully upgraded to {latest_version}");
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn newer_compares_numerically() {
+        assert!(is_newer("0.10.58", "0.10.57"));
+        assert!(is_newer("0.11.0", "0.10.99"));
+        assert!(is_newer("1.0", "0.99.9"));
+        assert!(!is_newer("0.10.57", "0.10.57"));
+        assert!(!is_newer("0.10.9", "0.10.57"));
+        assert!(!is_newer("0.10.57-rc.1", "0.10.57"));
+    }
+
+    #[test]
+    fn tag_prefixes_are_stripped() {
+        assert_eq!(tag_version("icm-v0.10.57"), "0.10.57");
+        assert_eq!(tag_version("v1.2.3"), "1.2.3");
+        assert_eq!(tag_version("1.2.3"), "1.2.3");
+    }
+
+    #[test]
+    fn expected_sha_matches_exact_filename() {
+        let sums =
+            "aaa  icm-x86_64-unknown-linux-gnu.tar.gz\nbbb  icm-x86_64-pc-windows-msvc.zip\n";
+        assert_eq!(
+            parse_expected_sha(sums, "icm-x86_64-pc-windows-msvc.zip").unwrap(),
+            "bbb"
+        );
+        assert!(parse_expected_sha(sums, "icm.zip").is_err());
+    }
+}
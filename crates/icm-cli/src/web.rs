@@ -36,6 +36,10 @@ struct WebAssets;
 #[derive(Clone)]
 pub struct AppState {
     store: Arc<Mutex<Store>>,
+    /// Read-only connection behind the GET routes, so browsing never
+    /// contends with the actions (or the hooks) for a write lock. The
+    /// same store as `store` when no read-only open was possible.
+    reader: Arc<Mutex<Store>>,
     username: String,
     password: String,
 }
@@ -223,13 +227,16 @@ fn spa_router() -> Router<AppState> {
 #[tokio::main]
 pub async fn run_web_server(
     store: Store,
+    reader: Option<Store>,
     host: &str,
     port: u16,
     username: String,
     password: String,
 ) -> Result<()> {
+    let store = Arc::new(Mutex::new(store));
     let state = AppState {
-        store: Arc::new(Mutex::new(store)),
+        reader: reader.map_or_else(|| store.clone(), |r| Arc::new(Mutex::new(r))),
+        store,
         username,
         password,
     };
@@ -361,7 +368,7 @@ async fn api_health_check() -> impl IntoResponse {
 }
 
 async fn api_stats(State(state): State<AppState>) -> impl IntoResponse {
-    let store = state.store.lock().unwrap();
+    let store = state.reader.lock().unwrap();
     let stats = match store.stats() {
         Ok(s) => s,
         Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
@@ -394,7 +401,7 @@ async fn api_stats(State(state): State<AppState>) -> impl IntoResponse {
 }
 
 async fn api_topics(State(state): State<AppState>) -> impl IntoResponse {
-    let store = state.store.lock().unwrap();
+    let store = state.reader.lock().unwrap();
     match store.list_topics() {
         Ok(topics) => Json(
             topics
@@ -411,7 +418,7 @@ async fn api_topic_detail(
     State(state): State<AppState>,
     Path(name): Path<String>,
 ) -> impl IntoResponse {
-    let store = state.store.lock().unwrap();
+    let store = state.reader.lock().unwrap();
     match store.get_by_topic(&name) {
         Ok(memories) => Json(memories).into_response(),
         Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
@@ -422,7 +429,7 @@ async fn api_topic_health(
     State(state): State<AppState>,
     Path(name): Path<String>,
 ) -> impl IntoResponse {
-    let store = state.store.lock().unwrap();
+    let store = state.reader.lock().unwrap();
     match store.topic_health(&name) {
         Ok(health) => Json(health).into_response(),
         Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
@@ -495,21 +502,9 @@ async fn api_memories(
     State(state): State<AppState>,
     Query(params): Query<PaginationParams>,
 ) -> impl IntoResponse {
-    let store = state.store.lock().unwrap();
-    match store.list_all() {
-        Ok(mut memories) => {
-            memories.sort_by(|a, b| {
-                b.weight
-                    .partial_cmp(&a.weight)
-                    .unwrap_or(std::cmp::Ordering::Equal)
-            });
-            let page: Vec<_> = memories
-                .into_iter()
-                .skip(params.offset)
-                .take(params.limit)
-                .collect();
-            Json(page).into_response()
-        }
+    let store = state.reader.lock().unwrap();
+    match store.get_all(params.limit, params.offset) {
+        Ok(page) => Json(page).into_response(),
         Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
     }
 }
@@ -518,7 +513,7 @@ async fn api_memories_search(
     State(state): State<AppState>,
     Query(params): Query<SearchParams>,
 ) -> impl IntoResponse {
-    let store = state.store.lock().unwrap();
+    let store = state.reader.lock().unwrap();
     match store.search_fts(&params.q, params.limit) {
         Ok(memories) => Json(memories).into_response(),
         Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
@@ -545,7 +540,7 @@ async fn api_memory_delete(
 }
 
 async fn api_health_all(State(state): State<AppState>) -> impl IntoResponse {
-    let store = state.store.lock().unwrap();
+    let store = state.reader.lock().unwrap();
     let topics = match store.list_topics() {
         Ok(t) => t,
         Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
@@ -590,7 +585,7 @@ async fn api_prune(State(state): State<AppState>) -> impl IntoResponse {
 }
 
 async fn api_memoirs(State(state): State<AppState>) -> impl IntoResponse {
-    let store = state.store.lock().unwrap();
+    let store = state.reader.lock().unwrap();
     let memoirs = match store.list_memoirs() {
         Ok(m) => m,
         Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
@@ -620,7 +615,7 @@ async fn api_memoir_detail(
     State(state): State<AppState>,
     Path(id): Path<String>,
 ) -> impl IntoResponse {
-    let store = state.store.lock().unwrap();
+    let store = state.reader.lock().unwrap();
     let memoir = match store.get_memoir(&id) {
         Ok(Some(m)) => m,
         Ok(None) => return (StatusCode::NOT_FOUND, "Memoir not found").into_response(),
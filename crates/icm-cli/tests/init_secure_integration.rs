@@ -452,3 +452,95 @@ fn pi_per_project_drops_cwd_agents_md() {
          {starts}\ncontent:\n{content}"
     );
 }
+
+#[test]
+fn skill_pack_installs_for_every_command_tool_and_uninstalls() {
+    let (tmp, cwd) = make_home();
+    let out = icm_in(
+        tmp.path(),
+        &cwd,
+        "/dev/null",
+        &["init", "--mode", "skill", "--force"],
+    );
+    assert_eq!(
+        out.status.code(),
+        Some(0),
+        "stdout: {}\nstderr: {}",
+        String::from_utf8_lossy(&out.stdout),
+        String::from_utf8_lossy(&out.stderr),
+    );
+
+    let files = [
+        ".claude/commands/forget.md",
+        ".claude/commands/session-summary.md",
+        ".codex/prompts/icm-consolidate.md",
+        ".gemini/commands/icm/memoir.toml",
+        ".config/opencode/command/icm-recall.md",
+        ".config/amp/skills/icm-forget.md",
+        ".pi/agent/skills/icm-remember-session.md",
+    ];
+    for rel in files {
+        assert!(tmp.path().join(rel).exists(), "{rel} missing");
+    }
+    let gemini =
+        std::fs::read_to_string(tmp.path().join(".gemini/commands/icm/memoir.toml")).unwrap();
+    assert!(gemini.contains("{{args}}") && !gemini.contains("$ARGUMENTS"));
+
+    let out = icm_in(tmp.path(), &cwd, "/dev/null", &["uninstall", "-y"]);
+    assert_eq!(out.status.code(), Some(0));
+    for rel in files {
+        assert!(!tmp.path().join(rel).exists(), "{rel} left behind");
+    }
+}
+
+#[test]
+fn init_print_emits_fragments_without_writing_configs() {
+    let (tmp, cwd) = make_home();
+    let out = icm_in(
+        tmp.path(),
+        &cwd,
+        "/dev/null",
+        &["init", "--mode", "all", "--print"],
+    );
+    assert_eq!(
+        out.status.code(),
+        Some(0),
+        "stderr: {}",
+        String::from_utf8_lossy(&out.stderr)
+    );
+    let touched = list_user_writes(tmp.path());
+    assert!(touched.is_empty(), "--print wrote files: {touched:?}");
+
+    let stdout = String::from_utf8_lossy(&out.stdout);
+    let home = tmp.path().display();
+    assert!(stdout.contains(&format!(
+        "# Codex CLI: {home}/.codex/config.toml\n[mcp_servers.icm]"
+    )));
+    assert!(stdout.contains(&format!("# Claude Code: {home}/.claude/settings.json\n{{")));
+    assert!(stdout.contains("\"PreToolUse\""));
+    assert!(stdout.contains(&format!(
+        "# Pi: {home}/.pi/agent/AGENTS.md\n<!-- icm:start -->"
+    )));
+
+    // With a directory: the same files, laid out as under $HOME.
+    let out_dir = tempfile::tempdir().unwrap();
+    let out = icm_in(
+        tmp.path(),
+        &cwd,
+        "/dev/null",
+        &[
+            "init",
+            "--mode",
+            "mcp",
+            "--only",
+            "gemini",
+            "--print",
+            out_dir.path().to_str().unwrap(),
+        ],
+    );
+    assert_eq!(out.status.code(), Some(0));
+    let settings = std::fs::read_to_string(out_dir.path().join(".gemini/settings.json")).unwrap();
+    let json: serde_json::Value = serde_json::from_str(&settings).unwrap();
+    assert_eq!(json["mcpServers"]["icm"]["args"][0], "serve");
+    assert!(list_user_writes(tmp.path()).is_empty());
+}
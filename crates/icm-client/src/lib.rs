@@ -0,0 +1,355 @@
+//! High-level ICM facade for other Rust programs.
+//!
+//! The CLI and MCP server each carry their own glue around the store:
+//! picking the database path and embedding dimension, embedding on write,
+//! deduplicating near-identical memories, falling back from hybrid search
+//! to FTS and keywords, recording access. [`Icm`] bundles that glue so a
+//! third-party tool can share the user's memory without re-implementing it:
+//!
+//! ```no_run
+//! use icm_client::Icm;
+//!
+//! # fn main() -> icm_core::IcmResult<()> {
+//! let icm = Icm::open_default()?;
+//! icm.store("decisions-myapp", "Release builds are signed in CI")?;
+//! for memory in icm.recall("release signing", 5)? {
+//!     println!("{}: {}", memory.topic, memory.summary);
+//! }
+//! let prompt_context = icm.context(Some("myapp"), "how do we ship?", 800)?;
+//! # let _ = prompt_context;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Anything the facade does not cover is one [`Icm::store_handle`] away.
+
+use std::path::{Path, PathBuf};
+
+use icm_core::{
+    build_context_snapshot, find_similar_memory, resolve_importance, ContextSnapshotOptions,
+    Embedder, IcmResult, Importance, Memory, MemoryStore, SnapshotFormat,
+    DEDUP_SIMILARITY_THRESHOLD,
+};
+use icm_store::Store;
+
+/// Memories pulled into [`Icm::context`] by the query, at most.
+const CONTEXT_RECALL_LIMIT: usize = 5;
+
+/// The database the `icm` CLI uses when no `--db` is given.
+pub fn default_db_path() -> PathBuf {
+    directories::ProjectDirs::from("dev", "icm", "icm")
+        .map(|dirs| dirs.data_dir().join("memories.db"))
+        .unwrap_or_else(|| PathBuf::from("memories.db"))
+}
+
+/// An open memory store plus the embedder used on store and recall.
+pub struct Icm {
+    store: Store,
+    embedder: Option<Box<dyn Embedder>>,
+}
+
+impl Icm {
+    /// Open the CLI's default database. With the `embeddings` feature the
+    /// default local model is attached (loaded lazily on first use).
+    pub fn open_default() -> IcmResult<Self> {
+        Self::open(&default_db_path())
+    }
+
+    /// Open (or create) the database at `path`, with the default embedder
+    /// when the `embeddings` feature is enabled.
+    pub fn open(path: &Path) -> IcmResult<Self> {
+        Self::open_with(path, default_embedder())
+    }
+
+    /// Open the database at `path` with an explicit embedder, or none for
+    /// keyword-only search.
+    ///
+    /// The store is opened at the embedder's dimension. Without one, an
+    /// existing database keeps its stored dimension, so opening it never
+    /// drops embeddings written by an embedding-enabled build.
+    pub fn open_with(path: &Path, embedder: Option<Box<dyn Embedder>>) -> IcmResult<Self> {
+        let dims = match &embedder {
+            Some(e) => e.dimensions(),
+            None => Store::read_stored_embedding_dims(path)
+                .ok()
+                .flatten()
+                .unwrap_or(icm_core::DEFAULT_EMBEDDING_DIMS),
+        };
+        Ok(Self {
+            store: Store::with_dims(path, dims)?,
+            embedder,
+        })
+    }
+
+    /// A throwaway in-memory store without an embedder (tests, scratch
+    /// sessions).
+    pub fn in_memory() -> IcmResult<Self> {
+        Ok(Self {
+            store: Store::in_memory()?,
+            embedder: None,
+        })
+    }
+
+    /// Wrap an already-open store.
+    pub fn from_store(store: Store, embedder: Option<Box<dyn Embedder>>) -> Self {
+        Self { store, embedder }
+    }
+
+    /// The underlying store, for everything the facade does not cover
+    /// (memoirs, facts, decay, ...).
+    pub fn store_handle(&self) -> &Store {
+        &self.store
+    }
+
+    /// The attached embedder, if any.
+    pub fn embedder(&self) -> Option<&dyn Embedder> {
+        self.embedder.as_deref()
+    }
+
+    /// Store `content` under `topic` and return the memory's id. The
+    /// importance is inferred the way `icm store` infers it.
+    pub fn store(&self, topic: &str, content: &str) -> IcmResult<String> {
+        let memory = Memory::new(topic.to_string(), content.to_string(), Importance::Medium);
+        self.insert(memory, None)
+    }
+
+    /// Store a prepared memory, keeping its importance, and return its id.
+    ///
+    /// The memory is embedded when it has no embedding yet. If a very
+    /// similar memory already exists in the same topic it is updated in
+    /// place instead, and its id is returned.
+    pub fn store_memory(&self, memory: Memory) -> IcmResult<String> {
+        let importance = memory.importance;
+        self.insert(memory, Some(importance))
+    }
+
+    fn insert(&self, mut memory: Memory, importance: Option<Importance>) -> IcmResult<String> {
+        if memory.embedding.is_none() {
+            if let Some(emb) = self.embedder() {
+                memory.embedding = emb.embed(&memory.embed_text()).ok();
+            }
+        }
+        let centroids = match memory.embedding {
+            Some(_) => self.store.importance_centroids().unwrap_or_default(),
+            None => Vec::new(),
+        };
+        memory.importance = resolve_importance(
+            &memory.summary,
+            memory.embedding.as_deref(),
+            &centroids,
+            importance,
+        );
+
+        if let Some(emb) = &memory.embedding {
+            if let Some((mut existing, _)) = find_similar_memory(
+                &self.store,
+                &memory.embed_text(),
+                emb,
+                &memory.topic,
+                DEDUP_SIMILARITY_THRESHOLD,
+            )? {
+                existing.summary = memory.summary;
+                existing.raw_excerpt = memory.raw_excerpt.or(existing.raw_excerpt);
+                if !memory.keywords.is_empty() {
+                    existing.keywords = memory.keywords;
+                }
+                existing.embedding = memory.embedding;
+                existing.importance = memory.importance;
+                existing.updated_at = chrono::Utc::now();
+                existing.weight = 1.0;
+                self.store.update(&existing)?;
+                return Ok(existing.id);
+            }
+        }
+        self.store.store(memory)
+    }
+
+    /// Up to `limit` memories relevant to `query`, best first.
+    ///
+    /// Uses hybrid search when an embedder is attached and falls back to
+    /// full-text, then keyword search. Returned memories count as
+    /// accessed, which feeds decay and reinforcement like a CLI recall.
+    pub fn recall(&self, query: &str, limit: usize) -> IcmResult<Vec<Memory>> {
+        let memories = self.search(query, limit)?;
+        let ids: Vec<&str> = memories.iter().map(|m| m.id.as_str()).collect();
+        if !ids.is_empty() {
+            self.store.batch_update_access(&ids)?;
+        }
+        Ok(memories)
+    }
+
+    /// Prompt-ready context: the always-on baseline (identity,
+    /// preferences, `project` context) followed by the memories most
+    /// relevant to `query`, kept within roughly `max_tokens`.
+    pub fn context(
+        &self,
+        project: Option<&str>,
+        query: &str,
+        max_tokens: usize,
+    ) -> IcmResult<String> {
+        let snapshot = build_context_snapshot(
+            &self.store,
+            &ContextSnapshotOptions {
+                project,
+                max_tokens,
+                format: SnapshotFormat::Markdown,
+            },
+        )?;
+        let mut out = if snapshot.sections.is_empty() {
+            String::new()
+        } else {
+            snapshot.render(SnapshotFormat::Markdown)
+        };
+
+        let query = query.trim();
+        if query.is_empty() {
+            return Ok(out);
+        }
+        let max_chars = max_tokens.saturating_mul(4);
+        let mut lines = Vec::new();
+        let mut used = out.len();
+        for memory in self.recall(query, CONTEXT_RECALL_LIMIT)? {
+            if out.contains(&memory.summary) {
+                continue;
+            }
+            let line = format!("- [{}] {}\n", memory.topic, memory.summary);
+            if used + line.len() > max_chars {
+                break;
+            }
+            used += line.len();
+            lines.push(line);
+        }
+        if !lines.is_empty() {
+            if !out.is_empty() && !out.ends_with("\n\n") {
+                out.push('\n');
+            }
+            out.push_str("## Relevant memories\n");
+            out.extend(lines);
+        }
+        Ok(out)
+    }
+
+    /// Ranked search without recording access.
+    fn search(&self, query: &str, limit: usize) -> IcmResult<Vec<Memory>> {
+        if let Some(emb) = self.embedder() {
+            if let Ok(query_emb) = emb.embed_query(query) {
+                if let Ok(scored) = self.store.search_hybrid(query, &query_emb, limit) {
+                    return Ok(scored.into_iter().map(|(m, _)| m).collect());
+                }
+            }
+        }
+        let results = self.store.search_fts(query, limit)?;
+        if !results.is_empty() {
+            return Ok(results);
+        }
+        let keywords: Vec<&str> = query.split_whitespace().collect();
+        self.store.search_by_keywords(&keywords, limit)
+    }
+}
+
+#[cfg(feature = "embeddings")]
+fn default_embedder() -> Option<Box<dyn Embedder>> {
+    Some(Box::new(icm_core::FastEmbedder::new()))
+}
+
+#[cfg(not(feature = "embeddings"))]
+fn default_embedder() -> Option<Box<dyn Embedder>> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Maps text onto two axes, so "release" and "database" memories
+    /// are far apart and near-duplicates are identical.
+    struct AxisEmbedder;
+
+    impl Embedder for AxisEmbedder {
+        fn embed(&self, text: &str) -> IcmResult<Vec<f32>> {
+            let mut v = vec![0.0; icm_core::DEFAULT_EMBEDDING_DIMS];
+            if text.contains("release") {
+                v[0] = 1.0;
+            } else {
+                v[1] = 1.0;
+            }
+            Ok(v)
+        }
+        fn embed_batch(&self, texts: &[&str]) -> IcmResult<Vec<Vec<f32>>> {
+            texts.iter().map(|t| self.embed(t)).collect()
+        }
+        fn dimensions(&self) -> usize {
+            icm_core::DEFAULT_EMBEDDING_DIMS
+        }
+    }
+
+    #[test]
+    fn store_then_recall_records_access() {
+        let icm = Icm::in_memory().unwrap();
+        let id = icm
+            .store("decisions-app", "Release builds are signed in CI")
+            .unwrap();
+        icm.store("decisions-app", "Postgres is the production database")
+            .unwrap();
+
+        let hits = icm.recall("signed", 5).unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].id, id);
+        let stored = icm.store_handle().get(&id).unwrap().unwrap();
+        assert_eq!(stored.access_count, 1);
+
+        assert!(icm.recall("nothing matches this", 5).unwrap().is_empty());
+    }
+
+    #[test]
+    fn store_embeds_and_dedups_within_topic() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("nested").join("memories.db");
+        let icm = Icm::open_with(&path, Some(Box::new(AxisEmbedder))).unwrap();
+
+        let first = icm
+            .store("decisions-app", "release needs sign-off")
+            .unwrap();
+        let mut again = Memory::new(
+            "decisions-app".into(),
+            "release needs sign-off".into(),
+            Importance::High,
+        );
+        again.keywords = vec!["release".into()];
+        assert_eq!(icm.store_memory(again).unwrap(), first);
+        let memory = icm.store_handle().get(&first).unwrap().unwrap();
+        assert_eq!(memory.keywords, ["release"]);
+        assert_eq!(memory.importance, Importance::High);
+        assert!(memory.embedding.is_some());
+
+        // Same text in another topic is its own memory.
+        let other = icm.store("notes", "release needs sign-off").unwrap();
+        assert_ne!(other, first);
+        let hits = icm.recall("release", 5).unwrap();
+        assert_eq!(hits.len(), 2);
+    }
+
+    #[test]
+    fn context_adds_relevant_memories_after_baseline() {
+        let icm = Icm::in_memory().unwrap();
+        icm.store("preferences", "Answer in short bullet points")
+            .unwrap();
+        icm.store("decisions-app", "Release builds are signed in CI")
+            .unwrap();
+
+        let ctx = icm.context(Some("app"), "signed release", 800).unwrap();
+        assert!(ctx.contains("Answer in short bullet points"), "{ctx}");
+        assert!(
+            ctx.contains(
+                "## Relevant memories\n- [decisions-app] Release builds are signed in CI\n"
+            ),
+            "{ctx}"
+        );
+
+        // No query: baseline only. Tiny budget: no room for recall.
+        let baseline = icm.context(Some("app"), "  ", 800).unwrap();
+        assert!(!baseline.contains("Relevant memories"), "{baseline}");
+        let tiny = icm.context(None, "signed release", 1).unwrap();
+        assert!(!tiny.contains("Release builds"), "{tiny}");
+    }
+}
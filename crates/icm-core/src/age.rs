@@ -0,0 +1,44 @@
+//! Human-written ages such as `36h` or `90d`, as taken by `icm reset
+//! --older-than`, `icm recent --since` and the `icm_list_recent` tool.
+
+use chrono::Duration;
+
+use crate::error::{IcmError, IcmResult};
+
+/// Parse an age like `90d`, `12w`, `36h` or `1y`. `m` is rejected as
+/// ambiguous between minutes and months.
+pub fn parse_age(s: &str) -> IcmResult<Duration> {
+    let s = s.trim();
+    let split = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+    let (n, unit) = s.split_at(split);
+    let n: i64 = n.parse().map_err(|_| {
+        IcmError::InvalidInput(format!("invalid age `{s}` (expected e.g. 90d, 12w, 36h)"))
+    })?;
+    Ok(match unit {
+        "h" => Duration::hours(n),
+        "d" => Duration::days(n),
+        "w" => Duration::weeks(n),
+        "y" => Duration::days(n * 365),
+        _ => {
+            return Err(IcmError::InvalidInput(format!(
+                "invalid age `{s}`: unit must be h, d, w or y"
+            )))
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_age_units() {
+        assert_eq!(parse_age("90d").unwrap(), Duration::days(90));
+        assert_eq!(parse_age("2w").unwrap(), Duration::days(14));
+        assert_eq!(parse_age(" 36h ").unwrap(), Duration::hours(36));
+        assert_eq!(parse_age("1y").unwrap(), Duration::days(365));
+        assert!(parse_age("90").is_err());
+        assert!(parse_age("d").is_err());
+        assert!(parse_age("3m").is_err());
+    }
+}
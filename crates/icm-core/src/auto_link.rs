@@ -121,6 +121,7 @@ mod tests {
     use super::*;
     use crate::error::IcmResult;
     use crate::memory::{Importance, Memory, StoreStats, TopicHealth};
+    use crate::ranking::HybridScore;
     use std::cell::RefCell;
     use std::collections::HashMap;
 
@@ -208,6 +209,19 @@ mod tests {
             self.search_by_embedding(embedding, limit)
         }
 
+        fn search_hybrid_scored(
+            &self,
+            _query: &str,
+            embedding: &[f32],
+            limit: usize,
+        ) -> IcmResult<Vec<(Memory, HybridScore)>> {
+            Ok(self
+                .search_by_embedding(embedding, limit)?
+                .into_iter()
+                .map(|(m, s)| (m, HybridScore::new(None, 0.0, Some(s), s)))
+                .collect())
+        }
+
         fn update_access(&self, _id: &str) -> IcmResult<()> {
             Ok(())
         }
@@ -223,6 +237,10 @@ mod tests {
         fn list_all(&self) -> IcmResult<Vec<Memory>> {
             Ok(self.memories.borrow().values().cloned().collect())
         }
+        fn get_all(&self, limit: usize, offset: usize) -> IcmResult<Vec<Memory>> {
+            let all = self.list_all()?;
+            Ok(all.into_iter().skip(offset).take(limit).collect())
+        }
         fn get_by_topic(&self, topic: &str) -> IcmResult<Vec<Memory>> {
             Ok(self
                 .memories
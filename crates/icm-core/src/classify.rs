@@ -0,0 +1,304 @@
+//! Lightweight classification of incoming memories.
+//!
+//! Used when a caller stores content without choosing a topic: the
+//! nearest existing memories vote for theirs, so new facts land next to
+//! related ones instead of under a one-off junk topic. Importance gets
+//! the same treatment: agents pick `medium` for everything, so a rule
+//! pass (plus per-class embedding centroids when available) suggests a
+//! level instead. Inference stops at `high`: `critical` memories never
+//! decay, so only an explicit choice makes one.
+
+use std::collections::HashMap;
+
+use crate::embedder::Embedder;
+use crate::error::IcmResult;
+use crate::memory::{Importance, Memory};
+use crate::ranking::cosine;
+use crate::store::MemoryStore;
+
+/// Topic used when inference finds no neighbours to vote.
+pub const DEFAULT_TOPIC: &str = "note";
+
+/// Number of nearest memories consulted by [`infer_topic`].
+pub const TOPIC_VOTE_NEIGHBORS: usize = 7;
+
+/// Words probed individually when voting without an embedder.
+const FTS_VOTE_TERMS: usize = 8;
+
+/// Pick the topic for `text` by nearest-neighbour vote over existing
+/// memories. Uses hybrid search when an embedder is available (and
+/// succeeds), FTS otherwise. Returns `None` when nothing similar exists
+/// yet; callers fall back to [`DEFAULT_TOPIC`].
+pub fn infer_topic(
+    store: &dyn MemoryStore,
+    embedder: Option<&dyn Embedder>,
+    text: &str,
+) -> IcmResult<Option<String>> {
+    let embedding = embedder.and_then(|e| e.embed_query(text).ok());
+    let neighbors: Vec<(Memory, f32)> = match embedding {
+        Some(emb) => store.search_hybrid(text, &emb, TOPIC_VOTE_NEIGHBORS)?,
+        None => fts_neighbors(store, text)?,
+    };
+    Ok(vote_topic(&neighbors))
+}
+
+/// FTS matches every term, so a whole sentence rarely hits anything.
+/// Query the most distinctive words one at a time instead, weighting
+/// each hit by rank (1, 1/2, 1/3, ...) since FTS carries no score.
+fn fts_neighbors(store: &dyn MemoryStore, text: &str) -> IcmResult<Vec<(Memory, f32)>> {
+    let mut words: Vec<String> = text
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| w.chars().count() >= 4)
+        .map(str::to_lowercase)
+        .collect();
+    words.sort();
+    words.dedup();
+    words.sort_by_key(|w| std::cmp::Reverse(w.len()));
+
+    let mut neighbors = Vec::new();
+    for word in words.iter().take(FTS_VOTE_TERMS) {
+        for (i, m) in store
+            .search_fts(word, TOPIC_VOTE_NEIGHBORS)?
+            .into_iter()
+            .enumerate()
+        {
+            neighbors.push((m, 1.0 / (i as f32 + 1.0)));
+        }
+    }
+    Ok(neighbors)
+}
+
+/// Score-weighted vote: each neighbour adds its score to its topic's
+/// tally and the highest tally wins. Ties go to the topic whose best
+/// neighbour ranked first.
+pub fn vote_topic(neighbors: &[(Memory, f32)]) -> Option<String> {
+    let mut tally: HashMap<&str, (f32, usize)> = HashMap::new();
+    for (rank, (m, score)) in neighbors.iter().enumerate() {
+        let entry = tally.entry(m.topic.as_str()).or_insert((0.0, rank));
+        entry.0 += score.max(0.0);
+    }
+    tally
+        .into_iter()
+        .max_by(|a, b| {
+            a.1 .0
+                .partial_cmp(&b.1 .0)
+                .unwrap_or(std::cmp::Ordering::Equal)
+                .then(b.1 .1.cmp(&a.1 .1))
+        })
+        .map(|(topic, _)| topic.to_string())
+}
+
+/// Minimum embedded memories per importance class before its centroid
+/// is trusted.
+const MIN_CENTROID_SAMPLES: usize = 3;
+
+/// Phrases that mark a standing rule, hazard, decision, convention, or
+/// resolved problem. Rule and hazard wording shows up in plenty of
+/// passing remarks too, so none of these earn more than `High`.
+const HIGH_CUES: &[&str] = &[
+    "never ",
+    "always ",
+    "must not",
+    "do not ",
+    "don't ",
+    "security",
+    "credential",
+    "secret",
+    "password",
+    "data loss",
+    "irreversible",
+    "decided",
+    "decision",
+    "we chose",
+    "switched to",
+    "architecture",
+    "convention",
+    "root cause",
+    "fixed by",
+    "workaround",
+    "prefers",
+    "breaking change",
+];
+
+/// Phrases that mark transient or tentative notes.
+const LOW_CUES: &[&str] = &[
+    "todo",
+    "maybe",
+    "temporary",
+    "for now",
+    "wip",
+    "fyi",
+    "might ",
+    "tried ",
+];
+
+/// Rule pass: the first class (most important first) with a matching
+/// cue wins. `None` when nothing matches; never `Critical`.
+pub fn rule_importance(text: &str) -> Option<Importance> {
+    let lower = format!("{} ", text.to_lowercase());
+    [(Importance::High, HIGH_CUES), (Importance::Low, LOW_CUES)]
+        .into_iter()
+        .find(|(_, cues)| cues.iter().any(|c| lower.contains(c)))
+        .map(|(imp, _)| imp)
+}
+
+/// Mean embedding per importance class over `(importance, embedding)`
+/// samples. Classes with fewer than a handful of samples (or mismatched
+/// dimensions) are left out.
+///
+/// Stores compute this over a bounded sample and cache the result (see
+/// `icm_store::Store::importance_centroids`), so the write path never
+/// scans the whole table.
+pub fn importance_centroids<'a>(
+    samples: impl IntoIterator<Item = (Importance, &'a [f32])>,
+) -> Vec<(Importance, Vec<f32>)> {
+    let mut sums: HashMap<Importance, (Vec<f32>, usize)> = HashMap::new();
+    for (importance, emb) in samples {
+        let entry = sums
+            .entry(importance)
+            .or_insert_with(|| (vec![0.0; emb.len()], 0));
+        if entry.0.len() != emb.len() {
+            continue;
+        }
+        for (acc, x) in entry.0.iter_mut().zip(emb) {
+            *acc += x;
+        }
+        entry.1 += 1;
+    }
+    sums.into_iter()
+        .filter(|(_, (_, n))| *n >= MIN_CENTROID_SAMPLES)
+        .map(|(imp, (sum, n))| (imp, sum.into_iter().map(|x| x / n as f32).collect()))
+        .collect()
+}
+
+/// Suggest an importance for `text`. Rules win when they match;
+/// otherwise the nearest centroid (if `embedding` and centroids are
+/// available); otherwise `Medium`. A nearest `Critical` centroid still
+/// suggests `High`, since a critical memory never decays.
+pub fn suggest_importance(
+    text: &str,
+    embedding: Option<&[f32]>,
+    centroids: &[(Importance, Vec<f32>)],
+) -> Importance {
+    if let Some(imp) = rule_importance(text) {
+        return imp;
+    }
+    embedding
+        .and_then(|emb| {
+            centroids
+                .iter()
+                .filter(|(_, c)| c.len() == emb.len())
+                .map(|(imp, c)| (*imp, cosine(emb, c)))
+                .max_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(std::cmp::Ordering::Equal))
+        })
+        .map(|(imp, _)| match imp {
+            Importance::Critical => Importance::High,
+            imp => imp,
+        })
+        .unwrap_or(Importance::Medium)
+}
+
+/// Resolve the importance for a new memory: the explicit value when
+/// given, the suggestion otherwise. Disagreements between the two are
+/// logged under the `icm::importance` target (`RUST_LOG=icm::importance=info`)
+/// so the rules can be tuned against real usage.
+///
+/// `centroids` are the store's cached class centroids; pass an empty
+/// slice when there is no embedding to compare.
+pub fn resolve_importance(
+    text: &str,
+    embedding: Option<&[f32]>,
+    centroids: &[(Importance, Vec<f32>)],
+    explicit: Option<Importance>,
+) -> Importance {
+    let suggested = suggest_importance(text, embedding, centroids);
+    match explicit {
+        Some(given) => {
+            if given != suggested {
+                tracing::info!(
+                    target: "icm::importance",
+                    %given,
+                    %suggested,
+                    "importance suggestion differs from explicit value"
+                );
+            }
+            given
+        }
+        None => suggested,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::memory::Importance;
+
+    fn mem(topic: &str) -> Memory {
+        Memory::new(topic.into(), "x".into(), Importance::Medium)
+    }
+
+    #[test]
+    fn vote_picks_heaviest_topic() {
+        let neighbors = vec![
+            (mem("decisions-api"), 0.9),
+            (mem("context-web"), 0.6),
+            (mem("context-web"), 0.5),
+        ];
+        assert_eq!(vote_topic(&neighbors).as_deref(), Some("context-web"));
+    }
+
+    #[test]
+    fn vote_tie_goes_to_best_ranked() {
+        let neighbors = vec![(mem("b"), 0.5), (mem("a"), 0.5)];
+        assert_eq!(vote_topic(&neighbors).as_deref(), Some("b"));
+    }
+
+    #[test]
+    fn vote_empty_is_none() {
+        assert_eq!(vote_topic(&[]), None);
+    }
+
+    #[test]
+    fn rules_classify_common_phrasings() {
+        assert_eq!(
+            rule_importance("Never force-push to main"),
+            Some(Importance::High)
+        );
+        assert_eq!(
+            rule_importance("We decided to use Postgres"),
+            Some(Importance::High)
+        );
+        assert_eq!(
+            rule_importance("TODO: look at flaky test"),
+            Some(Importance::Low)
+        );
+        assert_eq!(rule_importance("The API lives under /v2"), None);
+    }
+
+    #[test]
+    fn centroid_breaks_tie_when_rules_are_silent() {
+        let mut samples: Vec<(Importance, &[f32])> = Vec::new();
+        for (imp, emb) in [
+            (Importance::High, &[1.0, 0.0][..]),
+            (Importance::Low, &[0.0, 1.0][..]),
+        ] {
+            samples.extend(std::iter::repeat_n((imp, emb), MIN_CENTROID_SAMPLES));
+        }
+        let centroids = importance_centroids(samples);
+        assert_eq!(centroids.len(), 2);
+        let plain = "The API lives under /v2";
+        assert_eq!(
+            suggest_importance(plain, Some(&[0.1, 0.9]), &centroids),
+            Importance::Low
+        );
+        assert_eq!(
+            suggest_importance(plain, None, &centroids),
+            Importance::Medium
+        );
+    }
+
+    #[test]
+    fn sparse_classes_get_no_centroid() {
+        assert!(importance_centroids([(Importance::High, &[1.0][..])]).is_empty());
+    }
+}
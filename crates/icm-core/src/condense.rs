@@ -0,0 +1,144 @@
+//! Heuristic condensing of over-long memory content.
+//!
+//! Agents sometimes store multi-kilobyte blobs as a memory's summary,
+//! which drowns ranking (one memory matches every query) and blows the
+//! context-injection budget. The store paths keep the full text in
+//! `raw_excerpt` and replace the summary with the first informative
+//! sentences that fit the configured budget.
+
+/// Default cap on summary length, in characters. `0` disables condensing.
+pub const DEFAULT_MAX_SUMMARY_CHARS: usize = 1000;
+
+/// Whether `text` exceeds the summary budget. `max_chars == 0` means
+/// no limit.
+pub fn needs_condensing(text: &str, max_chars: usize) -> bool {
+    max_chars > 0 && text.chars().count() > max_chars
+}
+
+/// Condense `text` to at most `max_chars` characters by keeping the
+/// first informative sentences in order. Fenced code blocks, short
+/// fragments and symbol-heavy lines (logs, tables, stack frames) are
+/// skipped. Falls back to a hard cut when nothing qualifies.
+pub fn condense(text: &str, max_chars: usize) -> String {
+    if !needs_condensing(text, max_chars) {
+        return text.to_string();
+    }
+    let mut out = String::new();
+    let mut out_chars = 0usize;
+    for sentence in informative_sentences(text) {
+        let len = sentence.chars().count();
+        let sep = usize::from(!out.is_empty());
+        if out_chars + sep + len > max_chars {
+            if out.is_empty() {
+                return truncate_chars(&sentence, max_chars);
+            }
+            break;
+        }
+        if sep == 1 {
+            out.push(' ');
+        }
+        out.push_str(&sentence);
+        out_chars += sep + len;
+    }
+    if out.is_empty() {
+        truncate_chars(text.trim(), max_chars)
+    } else {
+        out
+    }
+}
+
+fn informative_sentences(text: &str) -> Vec<String> {
+    let mut sentences = Vec::new();
+    let mut in_fence = false;
+    for line in text.lines() {
+        let line = line.trim();
+        if line.starts_with("```") {
+            in_fence = !in_fence;
+            continue;
+        }
+        if in_fence || line.is_empty() {
+            continue;
+        }
+        let line = line.trim_start_matches(['#', '-', '*', '>', ' ']);
+        let mut start = 0;
+        let bytes = line.as_bytes();
+        for (i, &b) in bytes.iter().enumerate() {
+            let end_of_sentence = matches!(b, b'.' | b'!' | b'?')
+                && bytes.get(i + 1).is_none_or(|n| n.is_ascii_whitespace());
+            if end_of_sentence {
+                push_if_informative(&mut sentences, &line[start..=i]);
+                start = i + 1;
+            }
+        }
+        push_if_informative(&mut sentences, &line[start..]);
+    }
+    sentences
+}
+
+fn push_if_informative(out: &mut Vec<String>, sentence: &str) {
+    let s = sentence.trim();
+    let words = s.split_whitespace().count();
+    let total = s.chars().count();
+    let alpha = s
+        .chars()
+        .filter(|c| c.is_alphabetic() || c.is_whitespace())
+        .count();
+    if words >= 4 && total > 0 && alpha * 10 >= total * 7 {
+        out.push(s.to_string());
+    }
+}
+
+pub(crate) fn truncate_chars(s: &str, max_chars: usize) -> String {
+    if s.chars().count() <= max_chars {
+        return s.to_string();
+    }
+    let mut out: String = s.chars().take(max_chars.saturating_sub(1)).collect();
+    out.push('…');
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn short_text_is_untouched() {
+        assert_eq!(condense("hello world", 100), "hello world");
+        assert!(!needs_condensing("anything at all", 0));
+    }
+
+    #[test]
+    fn keeps_leading_informative_sentences() {
+        let text = "We moved auth to OIDC last sprint. \
+                    ok.\n\
+                    ```\nlet x = 1;\nlet y = 2;\n```\n\
+                    Tokens are refreshed by the gateway every hour. \
+                    The legacy cookie path stays behind a flag until March.";
+        let out = condense(text, 90);
+        assert_eq!(
+            out,
+            "We moved auth to OIDC last sprint. Tokens are refreshed by the gateway every hour."
+        );
+        assert!(out.chars().count() <= 90);
+    }
+
+    #[test]
+    fn skips_log_noise() {
+        let text = format!(
+            "{}\nThe deploy failed because the migration lock was held.",
+            "2024-01-01T00:00:00Z [INFO] 0x7f3a 12/34 ok=1 err=0 ".repeat(20)
+        );
+        assert_eq!(
+            condense(&text, 80),
+            "The deploy failed because the migration lock was held."
+        );
+    }
+
+    #[test]
+    fn falls_back_to_hard_cut() {
+        let text = "x".repeat(50);
+        let out = condense(&text, 10);
+        assert_eq!(out.chars().count(), 10);
+        assert!(out.ends_with('…'));
+    }
+}
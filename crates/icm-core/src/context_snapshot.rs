@@ -19,7 +19,6 @@ use serde::Serialize;
 use crate::error::IcmResult;
 use crate::memory::{Importance, Memory};
 use crate::store::MemoryStore;
-use crate::wake_up::is_preference_topic;
 
 /// Output format for the rendered snapshot.
 #[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
@@ -121,7 +120,7 @@ pub fn build_context_snapshot_from_memories(
     let (mut prefs, mut ctx): (Vec<Memory>, Vec<Memory>) = memories
         .into_iter()
         .filter(|m| is_relevant(m, opts.project))
-        .partition(|m| is_preference_topic(&m.topic));
+        .partition(Memory::is_preference);
 
     let order_key = |m: &Memory| {
         (
@@ -173,7 +172,7 @@ pub fn build_context_snapshot_from_memories(
 /// Eligible iff the memory is a preference/identity OR a project-context
 /// topic for the active project filter.
 fn is_relevant(m: &Memory, project: Option<&str>) -> bool {
-    if is_preference_topic(&m.topic) {
+    if m.is_preference() {
         // Identity/preferences are always eligible (cross-project baseline).
         return true;
     }
@@ -339,6 +338,24 @@ mod tests {
         assert!(!body.contains("SQLite"));
     }
 
+    #[test]
+    fn includes_preference_kind_from_any_topic() {
+        let mut pref = mem(
+            "icm",
+            "Commit messages use the imperative",
+            Importance::Medium,
+        );
+        pref.kind = crate::memory::MemoryKind::Preference;
+        let memories = vec![pref, mem("icm", "ICM uses SQLite", Importance::High)];
+        let snap = build_context_snapshot_from_memories(memories, &Default::default());
+        assert_eq!(snap.sections.len(), 1);
+        assert_eq!(snap.sections[0].title, "Identity & preferences");
+        assert_eq!(
+            snap.sections[0].lines,
+            ["Commit messages use the imperative"]
+        );
+    }
+
     #[test]
     fn includes_project_context_only_with_filter() {
         let memories = vec![
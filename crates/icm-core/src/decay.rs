@@ -0,0 +1,445 @@
+//! Weight dynamics: recall reinforcement, and the decay what-if
+//! simulation (`icm decay --simulate`, `icm prune --simulate`).
+//!
+//! The simulation replays the store's decay pass in memory, pass after
+//! pass, and records when each memory first falls below the prune
+//! threshold. Nothing is written. Auto-decay runs one pass a day, so
+//! passes read as days.
+
+use std::collections::BTreeMap;
+
+use serde::Deserialize;
+
+use crate::memory::{Importance, Memory};
+
+/// Factor of the daily decay pass (`[memory] decay_rate`).
+pub const DEFAULT_DECAY_RATE: f32 = 0.95;
+
+/// Share of the remaining headroom (`1 - weight`) a recall adds to a
+/// memory's weight.
+pub const DEFAULT_REINFORCEMENT_RATE: f32 = 0.05;
+
+/// Weight gain on recall (`[recall] reinforcement`): every recalled
+/// memory gets `weight += rate * (1 - weight)`, up to `cap`, so memories
+/// that keep being useful outpace decay.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Reinforcement {
+    pub rate: f32,
+    pub cap: f32,
+}
+
+impl Default for Reinforcement {
+    fn default() -> Self {
+        Self {
+            rate: DEFAULT_REINFORCEMENT_RATE,
+            cap: 1.0,
+        }
+    }
+}
+
+impl Reinforcement {
+    /// No gain on recall: access is counted, weight left alone.
+    pub const OFF: Self = Self {
+        rate: 0.0,
+        cap: 1.0,
+    };
+
+    /// Weight after one recall. Never lowers a weight already above
+    /// the cap.
+    pub fn apply(&self, weight: f32) -> f32 {
+        weight.max((weight + self.rate * (1.0 - weight)).min(self.cap))
+    }
+
+    pub fn is_off(&self) -> bool {
+        self.rate <= 0.0
+    }
+
+    /// Rate and cap limited to `0.0..=1.0`.
+    pub fn clamped(self) -> Self {
+        Self {
+            rate: self.rate.clamp(0.0, 1.0),
+            cap: self.cap.clamp(0.0, 1.0),
+        }
+    }
+}
+
+/// Where the daily decay pass runs (`[memory] auto_decay`).
+///
+/// `daemon-only` lets the MCP server (`icm serve`) run the pass between
+/// requests and leaves recall results reproducible. `true` also lets the
+/// first recall after 24 hours decay every weight before ranking, the
+/// historical behaviour. `false` never decays on its own; run
+/// `icm decay` instead.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(try_from = "AutoDecaySetting")]
+pub enum AutoDecay {
+    On,
+    #[default]
+    DaemonOnly,
+    Off,
+}
+
+/// `auto_decay` as written in the config: a bool or `"daemon-only"`.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum AutoDecaySetting {
+    Bool(bool),
+    Name(String),
+}
+
+impl TryFrom<AutoDecaySetting> for AutoDecay {
+    type Error = String;
+
+    fn try_from(setting: AutoDecaySetting) -> Result<Self, Self::Error> {
+        match setting {
+            AutoDecaySetting::Bool(true) => Ok(Self::On),
+            AutoDecaySetting::Bool(false) => Ok(Self::Off),
+            AutoDecaySetting::Name(name) => name.parse(),
+        }
+    }
+}
+
+impl std::str::FromStr for AutoDecay {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "true" | "on" => Ok(Self::On),
+            "false" | "off" => Ok(Self::Off),
+            "daemon-only" | "daemon" => Ok(Self::DaemonOnly),
+            other => Err(format!(
+                "invalid auto_decay `{other}` (expected true, false or \"daemon-only\")"
+            )),
+        }
+    }
+}
+
+impl std::fmt::Display for AutoDecay {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::On => "true",
+            Self::DaemonOnly => "daemon-only",
+            Self::Off => "false",
+        })
+    }
+}
+
+impl AutoDecay {
+    /// Recall may run the pass before ranking.
+    pub fn on_recall(self) -> bool {
+        self == Self::On
+    }
+
+    /// A long-running server may run the pass between requests.
+    pub fn in_daemon(self) -> bool {
+        self != Self::Off
+    }
+}
+
+/// Weight after one decay pass with `factor`: the formula `apply_decay`
+/// runs, slowed by importance and by up to five recorded accesses.
+pub fn decay_once(weight: f32, importance: Importance, access_count: u32, factor: f32) -> f32 {
+    let slowdown = 1.0 + access_count.min(5) as f32 * 0.1;
+    weight * (1.0 - (1.0 - factor) * importance.decay_multiplier() / slowdown)
+}
+
+/// Outcome of simulating `iterations` decay passes over a set of
+/// memories.
+#[derive(Debug, Clone)]
+pub struct DecaySimulation {
+    pub factor: f32,
+    pub threshold: f32,
+    pub iterations: u32,
+    rows: Vec<SimulatedMemory>,
+}
+
+#[derive(Debug, Clone)]
+struct SimulatedMemory {
+    topic: String,
+    importance: Importance,
+    /// Pass after which the weight is first below the threshold (0 when
+    /// it already is); `None` when it stays above for every pass.
+    crosses_at: Option<u32>,
+}
+
+/// Memory counts for one bucket of the report.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct DecayBucket {
+    pub total: usize,
+    /// Below the threshold after the last pass.
+    pub below: usize,
+    /// Below the threshold and not protected from pruning.
+    pub prunable: usize,
+}
+
+impl DecaySimulation {
+    pub fn new(factor: f32, threshold: f32, iterations: u32) -> Self {
+        Self {
+            factor,
+            threshold,
+            iterations,
+            rows: Vec::new(),
+        }
+    }
+
+    /// Simulate one memory's weight over every pass.
+    pub fn add(&mut self, memory: &Memory) {
+        let mut weight = memory.weight;
+        let mut crosses_at = (weight < self.threshold).then_some(0);
+        let mut pass = 0;
+        while crosses_at.is_none() && pass < self.iterations {
+            pass += 1;
+            weight = decay_once(weight, memory.importance, memory.access_count, self.factor);
+            if weight < self.threshold {
+                crosses_at = Some(pass);
+            }
+        }
+        self.rows.push(SimulatedMemory {
+            topic: memory.topic.clone(),
+            importance: memory.importance,
+            crosses_at,
+        });
+    }
+
+    /// Counts over every memory after `pass` passes.
+    pub fn after(&self, pass: u32) -> DecayBucket {
+        self.bucket(self.rows.iter(), pass)
+    }
+
+    /// Counts per importance level after the last pass.
+    pub fn by_importance(&self) -> Vec<(Importance, DecayBucket)> {
+        [
+            Importance::Critical,
+            Importance::High,
+            Importance::Medium,
+            Importance::Low,
+        ]
+        .into_iter()
+        .map(|imp| {
+            let rows = self.rows.iter().filter(|r| r.importance == imp);
+            (imp, self.bucket(rows, self.iterations))
+        })
+        .collect()
+    }
+
+    /// Counts per topic after the last pass, by topic name.
+    pub fn by_topic(&self) -> BTreeMap<String, DecayBucket> {
+        let mut topics: BTreeMap<String, DecayBucket> = BTreeMap::new();
+        for row in &self.rows {
+            let b = topics.entry(row.topic.clone()).or_default();
+            b.total += 1;
+            if row.is_below(self.iterations) {
+                b.below += 1;
+                if !row.importance.is_prune_protected() {
+                    b.prunable += 1;
+                }
+            }
+        }
+        topics
+    }
+
+    fn bucket<'a>(
+        &self,
+        rows: impl Iterator<Item = &'a SimulatedMemory>,
+        pass: u32,
+    ) -> DecayBucket {
+        let mut b = DecayBucket::default();
+        for row in rows {
+            b.total += 1;
+            if row.is_below(pass) {
+                b.below += 1;
+                if !row.importance.is_prune_protected() {
+                    b.prunable += 1;
+                }
+            }
+        }
+        b
+    }
+
+    /// Passes shown in the timeline: now, then each quarter of the run.
+    fn checkpoints(&self) -> Vec<u32> {
+        let mut passes = vec![0];
+        for q in 1..=4 {
+            let pass = (self.iterations * q).div_ceil(4);
+            if passes.last() != Some(&pass) {
+                passes.push(pass);
+            }
+        }
+        passes
+    }
+
+    /// Timeline, then the state after the last pass by importance and by
+    /// topic (topics with nothing below the threshold are left out).
+    pub fn render(&self) -> String {
+        let mut out = format!(
+            "Decay simulation: factor {}, prune threshold {}, {} passes (auto-decay runs one pass a day)\n",
+            self.factor, self.threshold, self.iterations
+        );
+        if self.rows.is_empty() {
+            out.push_str("No memories to simulate.\n");
+            return out;
+        }
+
+        out.push_str("\n  pass  below  prunable\n");
+        for pass in self.checkpoints() {
+            let b = self.after(pass);
+            out.push_str(&format!("  {pass:>4}  {:>5}  {:>8}\n", b.below, b.prunable));
+        }
+
+        let last = self.after(self.iterations);
+        if last.below == 0 {
+            out.push_str(&format!(
+                "\nNo memory falls below {} within {} passes.\n",
+                self.threshold, self.iterations
+            ));
+            return out;
+        }
+
+        out.push_str(&format!(
+            "\nBy importance, after {} passes:\n  importance  total  below  prunable\n",
+            self.iterations
+        ));
+        for (imp, b) in self.by_importance() {
+            if b.total > 0 {
+                out.push_str(&format!(
+                    "  {:<10}  {:>5}  {:>5}  {:>8}\n",
+                    imp.to_string(),
+                    b.total,
+                    b.below,
+                    b.prunable
+                ));
+            }
+        }
+
+        let mut topics: Vec<(String, DecayBucket)> = self
+            .by_topic()
+            .into_iter()
+            .filter(|(_, b)| b.below > 0)
+            .collect();
+        topics.sort_by(|a, b| b.1.below.cmp(&a.1.below).then_with(|| a.0.cmp(&b.0)));
+        let width = topics
+            .iter()
+            .map(|(t, _)| t.len())
+            .max()
+            .unwrap_or(0)
+            .max(5);
+        out.push_str(&format!(
+            "\nBy topic, after {} passes:\n  {:<width$}  total  below  prunable\n",
+            self.iterations, "topic"
+        ));
+        for (topic, b) in topics {
+            out.push_str(&format!(
+                "  {topic:<width$}  {:>5}  {:>5}  {:>8}\n",
+                b.total, b.below, b.prunable
+            ));
+        }
+        out.push_str("\nCritical and high memories are never pruned.\n");
+        out
+    }
+}
+
+impl SimulatedMemory {
+    fn is_below(&self, pass: u32) -> bool {
+        self.crosses_at.is_some_and(|p| p <= pass)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn memory(topic: &str, importance: Importance, weight: f32) -> Memory {
+        let mut m = Memory::new(topic.into(), "s".into(), importance);
+        m.weight = weight;
+        m
+    }
+
+    #[test]
+    fn reinforcement_closes_headroom_up_to_cap() {
+        let r = Reinforcement {
+            rate: 0.1,
+            cap: 0.9,
+        };
+        assert!((r.apply(0.5) - 0.55).abs() < 1e-6);
+        assert!((r.apply(0.89) - 0.9).abs() < 1e-6);
+        // Above the cap already: left as is, not pulled down.
+        assert_eq!(r.apply(0.95), 0.95);
+        assert_eq!(Reinforcement::OFF.apply(0.3), 0.3);
+        assert!(Reinforcement::OFF.is_off());
+    }
+
+    #[test]
+    fn auto_decay_accepts_bool_or_daemon_only() {
+        let parse = |json: &str| serde_json::from_str::<AutoDecay>(json);
+        assert_eq!(parse("true").unwrap(), AutoDecay::On);
+        assert_eq!(parse("false").unwrap(), AutoDecay::Off);
+        assert_eq!(parse("\"daemon-only\"").unwrap(), AutoDecay::DaemonOnly);
+        assert!(parse("\"weekly\"").is_err());
+        assert!(AutoDecay::On.on_recall() && AutoDecay::On.in_daemon());
+        assert!(!AutoDecay::DaemonOnly.on_recall() && AutoDecay::DaemonOnly.in_daemon());
+        assert!(!AutoDecay::Off.on_recall() && !AutoDecay::Off.in_daemon());
+    }
+
+    #[test]
+    fn decay_once_follows_importance_and_access() {
+        assert!((decay_once(1.0, Importance::Medium, 0, 0.9) - 0.9).abs() < 1e-6);
+        assert!((decay_once(1.0, Importance::High, 0, 0.9) - 0.95).abs() < 1e-6);
+        assert!((decay_once(1.0, Importance::Low, 0, 0.9) - 0.8).abs() < 1e-6);
+        assert_eq!(decay_once(1.0, Importance::Critical, 0, 0.5), 1.0);
+        // Access slows decay, capped at five accesses.
+        let capped = decay_once(1.0, Importance::Medium, 5, 0.9);
+        assert_eq!(capped, decay_once(1.0, Importance::Medium, 50, 0.9));
+        assert!(capped > 0.9);
+    }
+
+    #[test]
+    fn simulation_buckets_by_pass_topic_and_importance() {
+        let mut sim = DecaySimulation::new(0.5, 0.1, 4);
+        sim.add(&memory("api", Importance::Low, 0.05)); // already below
+        sim.add(&memory("api", Importance::Medium, 1.0)); // 0.0625 after 4
+        sim.add(&memory("ops", Importance::High, 0.3)); // 0.3*0.75^n: below at 4
+        sim.add(&memory("ops", Importance::Critical, 1.0)); // never decays
+
+        assert_eq!(
+            sim.after(0),
+            DecayBucket {
+                total: 4,
+                below: 1,
+                prunable: 1
+            }
+        );
+        assert_eq!(sim.after(3).below, 1);
+        assert_eq!(
+            sim.after(4),
+            DecayBucket {
+                total: 4,
+                below: 3,
+                prunable: 2
+            }
+        );
+
+        let topics = sim.by_topic();
+        assert_eq!(topics["api"].below, 2);
+        assert_eq!((topics["ops"].below, topics["ops"].prunable), (1, 0));
+        let high = sim.by_importance()[1];
+        assert_eq!(high.0, Importance::High);
+        assert_eq!((high.1.below, high.1.prunable), (1, 0));
+
+        let text = sim.render();
+        assert!(text.contains("     0      1         1\n"), "{text}");
+        assert!(text.contains("     4      3         2\n"), "{text}");
+        assert!(text.contains("  api        2      2         2\n"), "{text}");
+    }
+
+    #[test]
+    fn simulation_reports_when_nothing_crosses() {
+        let mut sim = DecaySimulation::new(0.99, 0.1, 3);
+        sim.add(&memory("api", Importance::Medium, 1.0));
+        assert!(sim
+            .render()
+            .contains("No memory falls below 0.1 within 3 passes."));
+        assert!(DecaySimulation::new(0.9, 0.1, 3)
+            .render()
+            .contains("No memories to simulate."));
+    }
+}
@@ -21,4 +21,82 @@ pub trait Embedder: Send + Sync {
 
     fn embed_batch(&self, texts: &[&str]) -> IcmResult<Vec<Vec<f32>>>;
     fn dimensions(&self) -> usize;
+
+    /// Whether the embedder can currently produce embeddings.
+    ///
+    /// Lazily-loaded models report `false` after a failed load so callers
+    /// (e.g. the MCP server's tool list) stop advertising embedding-only
+    /// features. Defaults to `true`.
+    fn is_available(&self) -> bool {
+        true
+    }
+}
+
+/// Outcome of [`check_embedder`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EmbedderHealth {
+    /// A probe embedding came back at the advertised dimension.
+    Ok { dims: usize },
+    /// Semantic search is unavailable; recall falls back to keywords.
+    Unavailable(String),
+}
+
+/// Embed a short probe, so a missing model or an unreachable provider is
+/// reported up front instead of silently degrading the next recall.
+pub fn check_embedder(embedder: &dyn Embedder) -> EmbedderHealth {
+    match embedder.embed_query("icm health check") {
+        Ok(v) if v.len() == embedder.dimensions() => EmbedderHealth::Ok { dims: v.len() },
+        Ok(v) => EmbedderHealth::Unavailable(format!(
+            "embedder returned {} dims, expected {}",
+            v.len(),
+            embedder.dimensions()
+        )),
+        Err(e) => EmbedderHealth::Unavailable(e.to_string()),
+    }
+}
+
+/// Marker line for results served without semantic search.
+pub fn degraded_notice(reason: &str) -> String {
+    format!("degraded: true (semantic search unavailable, keyword results only: {reason})")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::IcmError;
+
+    struct Fixed(IcmResult<Vec<f32>>);
+
+    impl Embedder for Fixed {
+        fn embed(&self, _text: &str) -> IcmResult<Vec<f32>> {
+            match &self.0 {
+                Ok(v) => Ok(v.clone()),
+                Err(e) => Err(IcmError::Embedding(e.to_string())),
+            }
+        }
+        fn embed_batch(&self, texts: &[&str]) -> IcmResult<Vec<Vec<f32>>> {
+            texts.iter().map(|t| self.embed(t)).collect()
+        }
+        fn dimensions(&self) -> usize {
+            3
+        }
+    }
+
+    #[test]
+    fn check_embedder_reports_failures_and_wrong_dims() {
+        assert_eq!(
+            check_embedder(&Fixed(Ok(vec![0.0; 3]))),
+            EmbedderHealth::Ok { dims: 3 }
+        );
+        assert_eq!(
+            check_embedder(&Fixed(Ok(vec![0.0; 2]))),
+            EmbedderHealth::Unavailable("embedder returned 2 dims, expected 3".into())
+        );
+        let down = Fixed(Err(IcmError::Embedding("model files missing".into())));
+        let EmbedderHealth::Unavailable(reason) = check_embedder(&down) else {
+            panic!("failing embedder reported healthy");
+        };
+        assert!(reason.contains("model files missing"), "{reason}");
+        assert!(degraded_notice(&reason).starts_with("degraded: true ("));
+    }
 }
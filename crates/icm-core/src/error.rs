@@ -8,6 +8,24 @@ pub enum IcmError {
     #[error("database error: {0}")]
     Database(String),
 
+    /// A write broke a database constraint (unique key, foreign key, …).
+    #[error("constraint violation: {0}")]
+    Constraint(String),
+
+    /// The database is locked by another process or transaction. Worth
+    /// retrying.
+    #[error("database is busy: {0}")]
+    Busy(String),
+
+    /// The database file or an index is damaged.
+    #[error("database is corrupt: {0}")]
+    Corrupt(String),
+
+    /// An embedding's length disagrees with the store's vector dimension,
+    /// usually after switching embedding models (`icm embed --force`).
+    #[error("embedding dimension mismatch: {0}")]
+    DimensionMismatch(String),
+
     #[error("serialization error: {0}")]
     Serialization(#[from] serde_json::Error),
 
@@ -22,6 +40,12 @@ pub enum IcmError {
     #[error("invalid input: {0}")]
     InvalidInput(String),
 
+    /// Content breaks a configured policy such as the `[memory.limits]`
+    /// size limits. Unlike `InvalidInput`, the same write may be fine
+    /// under a different configuration.
+    #[error("validation failed: {0}")]
+    Validation(String),
+
     /// Attempted to mutate a store that was opened in read-only mode
     /// (issue #263). Carries the operation name so the caller can tell
     /// the user what they tried to do.
@@ -39,4 +63,42 @@ pub enum IcmError {
     Unsupported(String),
 }
 
+impl IcmError {
+    /// Stable machine-readable code for the error kind. Surfaced in MCP
+    /// error results; never changes once published.
+    pub fn code(&self) -> &'static str {
+        match self {
+            IcmError::NotFound(_) => "not_found",
+            IcmError::Database(_) => "database",
+            IcmError::Constraint(_) => "constraint",
+            IcmError::Busy(_) => "busy",
+            IcmError::Corrupt(_) => "corrupt",
+            IcmError::DimensionMismatch(_) => "dimension_mismatch",
+            IcmError::Serialization(_) => "serialization",
+            IcmError::Config(_) => "config",
+            IcmError::Embedding(_) => "embedding",
+            IcmError::InvalidInput(_) => "invalid_input",
+            IcmError::Validation(_) => "validation",
+            IcmError::ReadOnly(_) => "read_only",
+            IcmError::Unsupported(_) => "unsupported",
+        }
+    }
+}
+
 pub type IcmResult<T> = Result<T, IcmError>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn codes_are_stable() {
+        assert_eq!(IcmError::NotFound("x".into()).code(), "not_found");
+        assert_eq!(IcmError::Busy("x".into()).code(), "busy");
+        assert_eq!(
+            IcmError::DimensionMismatch("x".into()).code(),
+            "dimension_mismatch"
+        );
+        assert_eq!(IcmError::Validation("x".into()).code(), "validation");
+    }
+}
@@ -0,0 +1,217 @@
+//! Retrieval explanations (`icm why`): why a memory ranks where it does
+//! for a query, signal by signal.
+//!
+//! Candidates come from [`MemoryStore::search_hybrid_scored`], the search
+//! recall runs, and get recall's recency boost, so the rank reported is
+//! the one recall gives before graph expansion and filters. Without a
+//! query embedding recall falls back to FTS ordering, which carries no
+//! scores; the explanation then holds the rank only.
+
+use chrono::{DateTime, Utc};
+
+use crate::error::{IcmError, IcmResult};
+use crate::memory::{Importance, Memory};
+use crate::ranking::{
+    apply_recency_boost, recency_factor, HybridScore, HYBRID_FTS_WEIGHT, HYBRID_VECTOR_WEIGHT,
+};
+use crate::store::MemoryStore;
+
+/// How one memory ranks for one query.
+#[derive(Debug, Clone)]
+pub struct RankExplanation {
+    pub memory: Memory,
+    /// 1-based position among the candidates; `None` when the memory is
+    /// not among them.
+    pub rank: Option<usize>,
+    /// Number of candidates ranked.
+    pub candidates: usize,
+    /// Whether candidates came from hybrid search; `false` means the
+    /// unscored FTS / keyword fallback.
+    pub hybrid_search: bool,
+    /// Hybrid-search breakdown; `None` without a query embedding or when
+    /// the memory was not retrieved.
+    pub hybrid: Option<HybridScore>,
+    /// Days since the memory was last updated.
+    pub age_days: f32,
+    /// Recency time constant used; `<= 0` means the boost is off.
+    pub tau_days: f32,
+    /// Recall's recency multiplier `exp(-age / τ)`.
+    pub recency: f32,
+    /// `hybrid.combined * recency`, the score recall sorts by.
+    pub score: Option<f32>,
+}
+
+/// Rank the top `pool` candidates for `query` the way recall does and
+/// report where memory `id` lands and why.
+pub fn explain_rank<S: MemoryStore + ?Sized>(
+    store: &S,
+    query: &str,
+    query_emb: Option<&[f32]>,
+    id: &str,
+    pool: usize,
+    now: DateTime<Utc>,
+    tau_days: f32,
+) -> IcmResult<RankExplanation> {
+    let memory = store
+        .get(id)?
+        .ok_or_else(|| IcmError::NotFound(format!("memory {id}")))?;
+
+    let (ranked, hybrid): (Vec<Memory>, Option<HybridScore>) = match query_emb {
+        Some(emb) => {
+            let scored = store.search_hybrid_scored(query, emb, pool)?;
+            let hybrid = scored.iter().find(|(m, _)| m.id == id).map(|(_, s)| *s);
+            let mut results: Vec<(Memory, Option<f32>)> = scored
+                .into_iter()
+                .map(|(m, s)| (m, Some(s.combined)))
+                .collect();
+            apply_recency_boost(&mut results, now, tau_days);
+            (results.into_iter().map(|(m, _)| m).collect(), hybrid)
+        }
+        None => {
+            let mut fts = store.search_fts(query, pool)?;
+            if fts.is_empty() {
+                let kws: Vec<&str> = query.split_whitespace().collect();
+                fts = store.search_by_keywords(&kws, pool)?;
+            }
+            (fts, None)
+        }
+    };
+
+    let recency = recency_factor(&memory, now, tau_days);
+    Ok(RankExplanation {
+        rank: ranked.iter().position(|m| m.id == id).map(|i| i + 1),
+        candidates: ranked.len(),
+        hybrid_search: query_emb.is_some(),
+        hybrid,
+        age_days: (now - memory.updated_at).num_seconds().max(0) as f32 / 86_400.0,
+        tau_days,
+        recency,
+        score: hybrid.map(|h| h.combined * recency),
+        memory,
+    })
+}
+
+/// Human-readable breakdown, one signal per line.
+pub fn render_explanation(e: &RankExplanation) -> String {
+    let m = &e.memory;
+    let mut out = format!("{} [{}] {}\n", m.id, m.topic, m.summary);
+    let search = if e.hybrid_search {
+        "hybrid search"
+    } else {
+        "FTS fallback, unscored"
+    };
+    match e.rank {
+        Some(rank) => out.push_str(&format!(
+            "rank         {rank} of {} ({search})\n",
+            e.candidates
+        )),
+        None if e.candidates == 0 => {
+            out.push_str(&format!("rank         no candidates matched ({search})\n"))
+        }
+        None => out.push_str(&format!(
+            "rank         not among the {} candidates ({search})\n",
+            e.candidates
+        )),
+    }
+
+    if let Some(h) = &e.hybrid {
+        let fts = h.fts * HYBRID_FTS_WEIGHT;
+        match h.bm25 {
+            Some(bm25) => out.push_str(&format!(
+                "fts bm25     {bm25:.3} -> {:.3} x {HYBRID_FTS_WEIGHT} = {fts:.3}\n",
+                h.fts
+            )),
+            None => out.push_str("fts bm25     no text match -> 0.000\n"),
+        }
+        let vector = h.vector * HYBRID_VECTOR_WEIGHT;
+        match h.similarity {
+            Some(sim) => out.push_str(&format!(
+                "vector       distance {:.3} -> {:.3} x {HYBRID_VECTOR_WEIGHT} = {vector:.3}\n",
+                1.0 - sim,
+                h.vector
+            )),
+            None => out.push_str("vector       not a nearest neighbour -> 0.000\n"),
+        }
+        out.push_str(&format!("combined     {:.3}\n", h.combined));
+    }
+
+    if e.tau_days > 0.0 {
+        out.push_str(&format!(
+            "recency      x{:.3} (updated {:.1}d ago, tau {}d)\n",
+            e.recency, e.age_days, e.tau_days
+        ));
+    } else {
+        out.push_str("recency      x1.000 (boost off)\n");
+    }
+    if let Some(score) = e.score {
+        out.push_str(&format!("final score  {score:.3}\n"));
+    }
+
+    // Weight and importance drive decay and pruning, not the score.
+    out.push_str(&format!(
+        "weight       {:.3} (decays over time; not part of the score)\n",
+        m.weight
+    ));
+    let decay = match m.importance {
+        Importance::Critical => "never decays".to_string(),
+        other => format!("decay rate x{:.1}", other.decay_multiplier()),
+    };
+    out.push_str(&format!(
+        "importance   {} ({decay}; not part of the score)\n",
+        m.importance
+    ));
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn explanation(hybrid: Option<HybridScore>, tau_days: f32) -> RankExplanation {
+        let memory = Memory::new("ops".into(), "deploy via helm".into(), Importance::High);
+        RankExplanation {
+            rank: Some(2),
+            candidates: 14,
+            hybrid_search: hybrid.is_some(),
+            hybrid,
+            age_days: 3.0,
+            tau_days,
+            recency: 0.4,
+            score: hybrid.map(|h| h.combined * 0.4),
+            memory,
+        }
+    }
+
+    #[test]
+    fn render_shows_every_signal() {
+        let h = HybridScore::new(Some(-3.0), 0.25, Some(0.8), 0.8);
+        let text = render_explanation(&explanation(Some(h), 30.0));
+        assert!(
+            text.contains("rank         2 of 14 (hybrid search)\n"),
+            "{text}"
+        );
+        assert!(
+            text.contains("fts bm25     -3.000 -> 0.250 x 0.3 = 0.075\n"),
+            "{text}"
+        );
+        assert!(
+            text.contains("vector       distance 0.200 -> 0.800 x 0.7 = 0.560\n"),
+            "{text}"
+        );
+        assert!(text.contains("combined     0.635\n"), "{text}");
+        assert!(text.contains("recency      x0.400 (updated 3.0d ago, tau 30d)\n"));
+        assert!(text.contains("final score  0.254\n"), "{text}");
+        assert!(
+            text.contains("importance   high (decay rate x0.5;"),
+            "{text}"
+        );
+    }
+
+    #[test]
+    fn render_without_embeddings_has_rank_only() {
+        let text = render_explanation(&explanation(None, 0.0));
+        assert!(text.contains("(FTS fallback, unscored)"), "{text}");
+        assert!(text.contains("recency      x1.000 (boost off)"), "{text}");
+        assert!(!text.contains("fts bm25") && !text.contains("final score"));
+    }
+}
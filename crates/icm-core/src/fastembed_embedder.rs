@@ -1,8 +1,15 @@
-use std::path::PathBuf;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Mutex, OnceLock};
 
 use directories::ProjectDirs;
-use fastembed::{EmbeddingModel, InitOptions, TextEmbedding};
+use fastembed::{EmbeddingModel, InitOptions, ModelInfo, TextEmbedding};
+use ort::environment::GlobalThreadPoolOptions;
+use ort::execution_providers::{
+    CUDAExecutionProvider, CoreMLExecutionProvider, ExecutionProvider as _,
+};
+use sha2::{Digest, Sha256};
 
 use crate::embedder::Embedder;
 use crate::error::{IcmError, IcmResult};
@@ -25,61 +32,264 @@ fn cache_dir() -> PathBuf {
         })
 }
 
+/// Tokenizer files fastembed loads next to every model's weights.
+const TOKENIZER_FILES: [&str; 4] = [
+    "tokenizer.json",
+    "config.json",
+    "special_tokens_map.json",
+    "tokenizer_config.json",
+];
+
+/// A model's files in the local cache.
+#[derive(Debug, Clone)]
+pub struct ModelCacheEntry {
+    /// The model's repository directory (`models--<org>--<name>`).
+    pub dir: PathBuf,
+    /// Total size of the files under `dir`.
+    pub bytes: u64,
+    /// Whether every file fastembed loads was found, so the model can be
+    /// loaded without network access.
+    pub complete: bool,
+}
+
+/// Every model fastembed can load, as `(model_code, dims, description)`.
+/// Variants sharing a repository (quantized builds) are listed once.
+pub fn supported_models() -> Vec<(String, usize, String)> {
+    let mut models: Vec<_> = TextEmbedding::list_supported_models()
+        .into_iter()
+        .map(|info| (info.model_code, info.dim, info.description))
+        .collect();
+    models.sort();
+    models.dedup_by(|a, b| a.0 == b.0);
+    models
+}
+
+/// Models cached under `root`, by model code. Directories of models
+/// this build cannot load are listed too, without completeness.
+pub fn cached_models(root: &Path) -> Vec<(String, ModelCacheEntry)> {
+    let Ok(entries) = std::fs::read_dir(root) else {
+        return Vec::new();
+    };
+    let mut models: Vec<(String, ModelCacheEntry)> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let name = entry.file_name().into_string().ok()?;
+            let code = name.strip_prefix("models--")?.replacen("--", "/", 1);
+            let cached = FastEmbedder::with_model(&code)
+                .cached_model_in(root)
+                .ok()
+                .flatten()
+                .unwrap_or_else(|| ModelCacheEntry {
+                    bytes: dir_size(&entry.path()),
+                    dir: entry.path(),
+                    complete: false,
+                });
+            Some((code, cached))
+        })
+        .collect();
+    models.sort_by(|a, b| a.0.cmp(&b.0));
+    models
+}
+
+/// Check each downloaded blob of a cached model against the SHA-256 it
+/// is named after (hf-hub names large-file blobs by their hash) and
+/// return how many were verified. Small files are named by git object
+/// id and are skipped.
+pub fn verify_model_cache(entry: &ModelCacheEntry) -> IcmResult<usize> {
+    let Ok(blobs) = std::fs::read_dir(entry.dir.join("blobs")) else {
+        return Ok(0);
+    };
+    let mut verified = 0;
+    for blob in blobs.flatten() {
+        let name = blob.file_name().to_string_lossy().to_lowercase();
+        if name.len() != 64 || !name.bytes().all(|b| b.is_ascii_hexdigit()) {
+            continue;
+        }
+        let actual = sha256_file(&blob.path()).map_err(|e| {
+            IcmError::Embedding(format!("cannot read {}: {e}", blob.path().display()))
+        })?;
+        if actual != name {
+            return Err(IcmError::Embedding(format!(
+                "checksum mismatch for {} (got {actual}); remove the model and pull it again",
+                blob.path().display()
+            )));
+        }
+        verified += 1;
+    }
+    Ok(verified)
+}
+
+fn sha256_file(path: &Path) -> std::io::Result<String> {
+    let mut file = std::fs::File::open(path)?;
+    let mut hasher = Sha256::new();
+    let mut buf = vec![0u8; 1 << 16];
+    loop {
+        let n = file.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+    }
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
 pub struct FastEmbedder {
     model: OnceLock<TextEmbedding>,
     init_lock: Mutex<()>,
+    /// Set when the last model load failed; cleared once a load succeeds.
+    load_failed: AtomicBool,
     model_name: String,
     dims: usize,
+    /// Model cache root, when not the per-OS default.
+    cache_dir: Option<PathBuf>,
+    /// Never download: loading fails unless the model is cached.
+    offline: bool,
+    provider: ExecutionProvider,
+    /// Intra-op threads for inference. `None`: one per CPU.
+    threads: Option<usize>,
+}
+
+/// ONNX Runtime execution provider the model runs on.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ExecutionProvider {
+    #[default]
+    Cpu,
+    Cuda,
+    CoreMl,
+}
+
+impl ExecutionProvider {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ExecutionProvider::Cpu => "cpu",
+            ExecutionProvider::Cuda => "cuda",
+            ExecutionProvider::CoreMl => "coreml",
+        }
+    }
+
+    /// Whether the linked onnxruntime was built with this provider. A
+    /// provider that is compiled in can still fail to start (no GPU,
+    /// missing driver); ort then logs a warning and runs on the CPU.
+    pub fn is_available(self) -> bool {
+        let available = match self {
+            ExecutionProvider::Cpu => return true,
+            ExecutionProvider::Cuda => CUDAExecutionProvider::default().is_available(),
+            ExecutionProvider::CoreMl => CoreMLExecutionProvider::default().is_available(),
+        };
+        available.unwrap_or(false)
+    }
+}
+
+impl std::fmt::Display for ExecutionProvider {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl std::str::FromStr for ExecutionProvider {
+    type Err = IcmError;
+
+    fn from_str(s: &str) -> IcmResult<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "cpu" => Ok(ExecutionProvider::Cpu),
+            "cuda" => Ok(ExecutionProvider::Cuda),
+            "coreml" => Ok(ExecutionProvider::CoreMl),
+            _ => Err(IcmError::Config(format!(
+                "unknown execution provider '{s}': expected cpu, cuda or coreml"
+            ))),
+        }
+    }
 }
 
 /// Default model: multilingual-e5-small (384d, supports 100+ languages)
 const DEFAULT_MODEL: &str = "intfloat/multilingual-e5-base";
 
-/// Resolve a model string to (EmbeddingModel, dimensions).
+/// Short names accepted wherever a model code is (`[embeddings] model`,
+/// `icm model pull`). Aliases name one exact model, where a shared code
+/// can stand for a full and a quantized build.
+pub const MODEL_ALIASES: &[(&str, EmbeddingModel)] = &[
+    ("multilingual-e5-small", EmbeddingModel::MultilingualE5Small),
+    ("multilingual-e5-base", EmbeddingModel::MultilingualE5Base),
+    ("multilingual-e5-large", EmbeddingModel::MultilingualE5Large),
+    // The e5-large weights are published under Qdrant's name; accept the
+    // original model id too.
+    (
+        "intfloat/multilingual-e5-large",
+        EmbeddingModel::MultilingualE5Large,
+    ),
+    ("bge-small-en-v1.5", EmbeddingModel::BGESmallENV15),
+    ("bge-base-en-v1.5", EmbeddingModel::BGEBaseENV15),
+    ("bge-large-en-v1.5", EmbeddingModel::BGELargeENV15),
+    ("bge-small-zh-v1.5", EmbeddingModel::BGESmallZHV15),
+    ("bge-large-zh-v1.5", EmbeddingModel::BGELargeZHV15),
+    ("all-minilm-l6-v2", EmbeddingModel::AllMiniLML6V2),
+    ("all-minilm-l12-v2", EmbeddingModel::AllMiniLML12V2),
+    (
+        "paraphrase-multilingual-minilm-l12-v2",
+        EmbeddingModel::ParaphraseMLMiniLML12V2,
+    ),
+    (
+        "paraphrase-multilingual-mpnet-base-v2",
+        EmbeddingModel::ParaphraseMLMpnetBaseV2,
+    ),
+    ("nomic-embed-text-v1", EmbeddingModel::NomicEmbedTextV1),
+    ("nomic-embed-text-v1.5", EmbeddingModel::NomicEmbedTextV15),
+    ("mxbai-embed-large-v1", EmbeddingModel::MxbaiEmbedLargeV1),
+    ("gte-base-en-v1.5", EmbeddingModel::GTEBaseENV15),
+    ("gte-large-en-v1.5", EmbeddingModel::GTELargeENV15),
+    (
+        "modernbert-embed-large",
+        EmbeddingModel::ModernBertEmbedLarge,
+    ),
+    (
+        "jina-embeddings-v2-base-code",
+        EmbeddingModel::JinaEmbeddingsV2BaseCode,
+    ),
+];
+
+/// Resolve a model alias or code (case-insensitive) to
+/// (EmbeddingModel, dimensions).
 fn resolve_model(name: &str) -> IcmResult<(EmbeddingModel, usize)> {
-    let model: EmbeddingModel = name.parse().map_err(|e: String| IcmError::Embedding(e))?;
-    let dims = model_dimensions(&model);
+    let model = match MODEL_ALIASES
+        .iter()
+        .find(|(alias, _)| alias.eq_ignore_ascii_case(name))
+    {
+        Some((_, model)) => model.clone(),
+        None => name.parse().map_err(|_| {
+            IcmError::Config(format!(
+                "unknown embedding model '{name}': use a fastembed model code or an alias \
+                 such as multilingual-e5-base or bge-small-en-v1.5 (see `icm model list --all`)"
+            ))
+        })?,
+    };
+    let dims = TextEmbedding::get_model_info(&model)
+        .map_err(|e| IcmError::Embedding(e.to_string()))?
+        .dim;
     Ok((model, dims))
 }
 
-/// Known dimensions for fastembed models.
-fn model_dimensions(model: &EmbeddingModel) -> usize {
-    match model {
-        EmbeddingModel::AllMiniLML6V2
-        | EmbeddingModel::AllMiniLML6V2Q
-        | EmbeddingModel::AllMiniLML12V2
-        | EmbeddingModel::AllMiniLML12V2Q
-        | EmbeddingModel::BGESmallENV15
-        | EmbeddingModel::BGESmallENV15Q
-        | EmbeddingModel::MultilingualE5Small
-        | EmbeddingModel::ParaphraseMLMiniLML12V2
-        | EmbeddingModel::ParaphraseMLMiniLML12V2Q => 384,
-
-        EmbeddingModel::BGEBaseENV15
-        | EmbeddingModel::BGEBaseENV15Q
-        | EmbeddingModel::MultilingualE5Base
-        | EmbeddingModel::ParaphraseMLMpnetBaseV2
-        | EmbeddingModel::BGESmallZHV15
-        | EmbeddingModel::GTEBaseENV15
-        | EmbeddingModel::GTEBaseENV15Q
-        | EmbeddingModel::JinaEmbeddingsV2BaseCode => 768,
-
-        EmbeddingModel::BGELargeENV15
-        | EmbeddingModel::BGELargeENV15Q
-        | EmbeddingModel::MultilingualE5Large
-        | EmbeddingModel::MxbaiEmbedLargeV1
-        | EmbeddingModel::MxbaiEmbedLargeV1Q
-        | EmbeddingModel::BGELargeZHV15
-        | EmbeddingModel::GTELargeENV15
-        | EmbeddingModel::GTELargeENV15Q
-        | EmbeddingModel::ModernBertEmbedLarge => 1024,
-
-        EmbeddingModel::NomicEmbedTextV1
-        | EmbeddingModel::NomicEmbedTextV15
-        | EmbeddingModel::NomicEmbedTextV15Q => 768,
-
-        EmbeddingModel::ClipVitB32 => 512,
-    }
+/// Model code and embedding dimension for a model alias or code.
+pub fn resolve_model_name(name: &str) -> IcmResult<(String, usize)> {
+    let (model, dims) = resolve_model(name)?;
+    let info =
+        TextEmbedding::get_model_info(&model).map_err(|e| IcmError::Embedding(e.to_string()))?;
+    Ok((info.model_code.clone(), dims))
+}
+
+/// Commit a process-wide ONNX Runtime environment whose shared thread
+/// pool has `threads` intra-op threads. fastembed pins every session to
+/// one thread per CPU, but sessions created after this use the shared
+/// pool instead. Only the first call takes effect.
+fn init_thread_pool(threads: usize) {
+    static INIT: std::sync::Once = std::sync::Once::new();
+    INIT.call_once(|| {
+        let committed = GlobalThreadPoolOptions::default()
+            .with_intra_threads(threads)
+            .and_then(|pool| ort::init().with_global_thread_pool(pool).commit());
+        if let Err(e) = committed {
+            tracing::warn!("could not limit inference to {threads} threads: {e}");
+        }
+    });
 }
 
 impl FastEmbedder {
@@ -94,9 +304,133 @@ impl FastEmbedder {
         Self {
             model: OnceLock::new(),
             init_lock: Mutex::new(()),
+            load_failed: AtomicBool::new(false),
             model_name: model_name.to_string(),
             dims,
+            cache_dir: None,
+            offline: false,
+            provider: ExecutionProvider::Cpu,
+            threads: None,
+        }
+    }
+
+    /// Keep models under `dir` instead of the per-OS cache directory.
+    pub fn with_cache_dir(mut self, dir: PathBuf) -> Self {
+        self.cache_dir = Some(dir);
+        self
+    }
+
+    /// Never download the model: loading fails unless it is already
+    /// cached (see [`FastEmbedder::pull`]).
+    pub fn offline(mut self, offline: bool) -> Self {
+        self.offline = offline;
+        self
+    }
+
+    /// Run the model on `provider` instead of the CPU.
+    pub fn with_execution_provider(mut self, provider: ExecutionProvider) -> Self {
+        self.provider = provider;
+        self
+    }
+
+    /// Cap inference at `threads` CPU threads instead of one per CPU.
+    /// The ONNX Runtime thread pool is process-wide, so the first model
+    /// loaded decides it.
+    pub fn with_threads(mut self, threads: Option<usize>) -> Self {
+        self.threads = threads.filter(|&n| n > 0);
+        self
+    }
+
+    /// The provider the model runs on: the configured one, or the CPU
+    /// when this onnxruntime build lacks it.
+    pub fn active_provider(&self) -> ExecutionProvider {
+        if self.provider.is_available() {
+            self.provider
+        } else {
+            ExecutionProvider::Cpu
+        }
+    }
+
+    pub fn model_name(&self) -> &str {
+        &self.model_name
+    }
+
+    /// Root of the model cache: `$HF_HOME` when set (fastembed reads it
+    /// directly, so it wins), then the configured directory, then the
+    /// per-OS default.
+    pub fn cache_root(&self) -> PathBuf {
+        std::env::var_os("HF_HOME")
+            .map(PathBuf::from)
+            .or_else(|| self.cache_dir.clone())
+            .unwrap_or_else(cache_dir)
+    }
+
+    /// This model's files in [`FastEmbedder::cache_root`], or `None` when
+    /// nothing has been downloaded yet. Never touches the network.
+    pub fn cached_model(&self) -> IcmResult<Option<ModelCacheEntry>> {
+        self.cached_model_in(&self.cache_root())
+    }
+
+    fn cached_model_in(&self, root: &Path) -> IcmResult<Option<ModelCacheEntry>> {
+        let info = self.model_info()?;
+        let dir = root.join(format!("models--{}", info.model_code.replace('/', "--")));
+        if !dir.is_dir() {
+            return Ok(None);
         }
+        // hf-hub layout: snapshots/<revision>/<file>, one dir per revision.
+        let required: Vec<&str> = std::iter::once(info.model_file.as_str())
+            .chain(info.additional_files.iter().map(String::as_str))
+            .chain(TOKENIZER_FILES)
+            .collect();
+        let complete = std::fs::read_dir(dir.join("snapshots"))
+            .map(|revs| {
+                revs.flatten()
+                    .any(|rev| required.iter().all(|f| rev.path().join(f).is_file()))
+            })
+            .unwrap_or(false);
+        Ok(Some(ModelCacheEntry {
+            bytes: dir_size(&dir),
+            dir,
+            complete,
+        }))
+    }
+
+    fn model_info(&self) -> IcmResult<ModelInfo<EmbeddingModel>> {
+        let (emb_model, _) = resolve_model(&self.model_name)?;
+        TextEmbedding::get_model_info(&emb_model)
+            .cloned()
+            .map_err(|e| IcmError::Embedding(e.to_string()))
+    }
+
+    /// Download the model when it is not cached (progress on stderr),
+    /// load it once, and verify the downloaded files' checksums. Returns
+    /// the cache entry and how many files were verified. Ignores
+    /// [`FastEmbedder::offline`].
+    pub fn pull(&self) -> IcmResult<(ModelCacheEntry, usize)> {
+        let model = self.download_and_load()?;
+        let _ = self.model.set(model);
+        self.load_failed.store(false, Ordering::Relaxed);
+        let entry = self.cached_model()?.ok_or_else(|| {
+            IcmError::Embedding(format!(
+                "{} loaded but is not in {}",
+                self.model_name,
+                self.cache_root().display()
+            ))
+        })?;
+        let verified = verify_model_cache(&entry)?;
+        Ok((entry, verified))
+    }
+
+    /// Delete the model from the cache. Returns the bytes freed, or
+    /// `None` when it was not cached.
+    pub fn remove_cached(&self) -> IcmResult<Option<u64>> {
+        let Some(entry) = self.cached_model()? else {
+            return Ok(None);
+        };
+        std::fs::remove_dir_all(&entry.dir).map_err(|e| {
+            IcmError::Embedding(format!("cannot remove {}: {e}", entry.dir.display()))
+        })?;
+        Ok(Some(entry.bytes))
     }
 
     fn get_model(&self) -> IcmResult<&TextEmbedding> {
@@ -107,19 +441,51 @@ impl FastEmbedder {
         if let Some(m) = self.model.get() {
             return Ok(m);
         }
+        let model = self.load_model();
+        self.load_failed.store(model.is_err(), Ordering::Relaxed);
+        let _ = self.model.set(model?);
+        Ok(self.model.get().unwrap())
+    }
+
+    fn load_model(&self) -> IcmResult<TextEmbedding> {
+        if self.offline && !self.cached_model()?.is_some_and(|m| m.complete) {
+            return Err(IcmError::Embedding(format!(
+                "{} is not downloaded and offline mode is on; run `icm model pull`",
+                self.model_name
+            )));
+        }
+        self.download_and_load()
+    }
+
+    fn download_and_load(&self) -> IcmResult<TextEmbedding> {
         let (emb_model, _) = resolve_model(&self.model_name)?;
-        let cache = cache_dir();
+        let cache = self.cache_root();
         std::fs::create_dir_all(&cache)
             .and_then(|()| cachedir::ensure_tag(&cache))
             .unwrap_or_else(|e| tracing::warn!("could not tag cache dir: {e}"));
-        let model = TextEmbedding::try_new(
-            InitOptions::new(emb_model)
-                .with_show_download_progress(true)
-                .with_cache_dir(cache),
-        )
-        .map_err(|e| IcmError::Embedding(format!("failed to init model: {e}")))?;
-        let _ = self.model.set(model);
-        Ok(self.model.get().unwrap())
+        if let Some(threads) = self.threads {
+            init_thread_pool(threads);
+        }
+        let mut options = InitOptions::new(emb_model)
+            .with_show_download_progress(true)
+            .with_cache_dir(cache);
+        match self.active_provider() {
+            ExecutionProvider::Cpu if self.provider != ExecutionProvider::Cpu => tracing::warn!(
+                "{} execution provider is not in this onnxruntime build; using the CPU",
+                self.provider
+            ),
+            ExecutionProvider::Cpu => {}
+            ExecutionProvider::Cuda => {
+                options =
+                    options.with_execution_providers(vec![CUDAExecutionProvider::default().build()])
+            }
+            ExecutionProvider::CoreMl => {
+                options = options
+                    .with_execution_providers(vec![CoreMLExecutionProvider::default().build()])
+            }
+        }
+        TextEmbedding::try_new(options)
+            .map_err(|e| IcmError::Embedding(format!("failed to init model: {e}")))
     }
 
     /// e5-family instruction prefixes as `(query_prefix, passage_prefix)`.
@@ -160,6 +526,22 @@ impl FastEmbedder {
     }
 }
 
+/// Total size of the regular files under `dir`. Snapshot entries are
+/// symlinks into `blobs/`, so they are not counted twice.
+fn dir_size(dir: &std::path::Path) -> u64 {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return 0;
+    };
+    entries
+        .flatten()
+        .map(|entry| match entry.file_type() {
+            Ok(t) if t.is_dir() => dir_size(&entry.path()),
+            Ok(t) if t.is_file() => entry.metadata().map_or(0, |m| m.len()),
+            _ => 0,
+        })
+        .sum()
+}
+
 impl Default for FastEmbedder {
     fn default() -> Self {
         Self::new()
@@ -200,6 +582,11 @@ impl Embedder for FastEmbedder {
     fn dimensions(&self) -> usize {
         self.dims
     }
+
+    /// `false` once a model load has failed, until a later load succeeds.
+    fn is_available(&self) -> bool {
+        !self.load_failed.load(Ordering::Relaxed)
+    }
 }
 
 #[cfg(test)]
@@ -222,6 +609,129 @@ mod tests {
         }
     }
 
+    #[test]
+    fn execution_provider_parses_case_insensitively() {
+        assert_eq!(
+            "CUDA".parse::<ExecutionProvider>().unwrap(),
+            ExecutionProvider::Cuda
+        );
+        assert_eq!(
+            "coreml".parse::<ExecutionProvider>().unwrap(),
+            ExecutionProvider::CoreMl
+        );
+        assert!(ExecutionProvider::Cpu.is_available());
+        let err = "tpu".parse::<ExecutionProvider>().unwrap_err().to_string();
+        assert!(err.contains("expected cpu, cuda or coreml"), "{err}");
+    }
+
+    #[test]
+    fn aliases_and_codes_resolve_to_model_dims() {
+        assert_eq!(
+            resolve_model_name("bge-small-en-v1.5").unwrap(),
+            ("Xenova/bge-small-en-v1.5".to_string(), 384)
+        );
+        assert_eq!(
+            resolve_model_name("Multilingual-E5-Large").unwrap(),
+            ("Qdrant/multilingual-e5-large-onnx".to_string(), 1024)
+        );
+        assert_eq!(
+            resolve_model_name("intfloat/multilingual-e5-base")
+                .unwrap()
+                .1,
+            768
+        );
+        assert_eq!(resolve_model_name("bge-small-zh-v1.5").unwrap().1, 512);
+        for (alias, _) in MODEL_ALIASES {
+            assert!(resolve_model_name(alias).is_ok(), "{alias}");
+        }
+        let err = resolve_model_name("bge-tiny").unwrap_err().to_string();
+        assert!(err.contains("unknown embedding model 'bge-tiny'"), "{err}");
+        assert_eq!(
+            FastEmbedder::with_model("all-minilm-l6-v2").dimensions(),
+            384
+        );
+    }
+
+    #[test]
+    fn failed_model_load_marks_embedder_unavailable() {
+        let embedder = FastEmbedder::with_model("not-a-real/model");
+        assert!(embedder.is_available());
+        assert!(embedder.embed("hello").is_err());
+        assert!(!embedder.is_available());
+    }
+
+    #[test]
+    fn cached_model_finds_weights_in_hf_layout() {
+        let dir = tempfile::tempdir().unwrap();
+        let embedder = FastEmbedder::with_model("Xenova/bge-small-en-v1.5");
+        assert!(embedder.cached_model_in(dir.path()).unwrap().is_none());
+
+        let repo = dir.path().join("models--Xenova--bge-small-en-v1.5");
+        let snapshot = repo.join("snapshots").join("abc123");
+        std::fs::create_dir_all(&snapshot).unwrap();
+        for file in TOKENIZER_FILES {
+            std::fs::write(snapshot.join(file), "{}").unwrap();
+        }
+        let partial = embedder.cached_model_in(dir.path()).unwrap().unwrap();
+        assert!(!partial.complete);
+        assert_eq!(partial.bytes, 8);
+
+        std::fs::create_dir_all(snapshot.join("onnx")).unwrap();
+        std::fs::write(snapshot.join("onnx").join("model.onnx"), "weights").unwrap();
+        let full = embedder.cached_model_in(dir.path()).unwrap().unwrap();
+        assert!(full.complete);
+        assert_eq!(full.dir, repo);
+        assert!(FastEmbedder::with_model("nope/nope")
+            .cached_model_in(dir.path())
+            .is_err());
+
+        std::fs::create_dir_all(dir.path().join("models--someone--custom")).unwrap();
+        let listed: Vec<(String, bool)> = cached_models(dir.path())
+            .into_iter()
+            .map(|(code, entry)| (code, entry.complete))
+            .collect();
+        assert_eq!(
+            listed,
+            [
+                ("Xenova/bge-small-en-v1.5".to_string(), true),
+                ("someone/custom".to_string(), false)
+            ]
+        );
+    }
+
+    #[test]
+    fn verify_model_cache_checks_hash_named_blobs() {
+        let dir = tempfile::tempdir().unwrap();
+        let blobs = dir.path().join("blobs");
+        std::fs::create_dir_all(&blobs).unwrap();
+        // sha256("weights")
+        let blob = blobs.join("9a129038d9a00aed0cf6a7ea059ca50a813449061ab87848cf1a13eafdf33b2c");
+        std::fs::write(&blob, "weights").unwrap();
+        // Git object ids (40 hex) are not checked.
+        std::fs::write(blobs.join("a".repeat(40)), "anything").unwrap();
+        let entry = ModelCacheEntry {
+            dir: dir.path().to_path_buf(),
+            bytes: 0,
+            complete: true,
+        };
+        assert_eq!(verify_model_cache(&entry).unwrap(), 1);
+
+        std::fs::write(&blob, "tampered").unwrap();
+        let err = verify_model_cache(&entry).unwrap_err().to_string();
+        assert!(err.contains("checksum mismatch"), "{err}");
+    }
+
+    #[test]
+    fn offline_embedder_fails_fast_without_cached_model() {
+        let dir = tempfile::tempdir().unwrap();
+        let embedder = FastEmbedder::with_model("Xenova/bge-small-en-v1.5")
+            .with_cache_dir(dir.path().to_path_buf())
+            .offline(true);
+        let err = embedder.embed("hello").unwrap_err().to_string();
+        assert!(err.contains("icm model pull"), "{err}");
+        assert!(!embedder.is_available());
+    }
+
     #[test]
     fn non_e5_models_are_left_unprefixed() {
         for model in [
@@ -42,4 +42,102 @@ pub struct FeedbackStats {
     pub total: usize,
     pub by_topic: Vec<(String, usize)>,
     pub most_applied: Vec<(String, u32)>,
+    /// Recall signals recorded as useful.
+    #[serde(default)]
+    pub useful: usize,
+    /// Recall signals recorded as noise.
+    #[serde(default)]
+    pub noise: usize,
+}
+
+/// Weight added to a memory marked useful (capped at 1.0).
+pub const USEFUL_WEIGHT_BOOST: f32 = 0.1;
+/// Factor applied to a memory's weight when it is marked noise. Each
+/// mark compounds, so a memory that keeps coming back irrelevant sinks
+/// toward the prune threshold faster than decay alone would take it.
+pub const NOISE_WEIGHT_FACTOR: f32 = 0.7;
+
+/// Whether a recalled memory helped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RecallSignal {
+    Useful,
+    Noise,
+}
+
+impl RecallSignal {
+    /// The memory weight after this signal.
+    pub fn adjust_weight(self, weight: f32) -> f32 {
+        match self {
+            Self::Useful => (weight + USEFUL_WEIGHT_BOOST).min(1.0),
+            Self::Noise => weight * NOISE_WEIGHT_FACTOR,
+        }
+    }
+}
+
+impl std::fmt::Display for RecallSignal {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Useful => write!(f, "useful"),
+            Self::Noise => write!(f, "noise"),
+        }
+    }
+}
+
+impl std::str::FromStr for RecallSignal {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "useful" => Ok(Self::Useful),
+            "noise" => Ok(Self::Noise),
+            _ => Err(format!("invalid signal: {s} (expected: useful, noise)")),
+        }
+    }
+}
+
+/// A useful / noise mark on a recalled memory, kept for ranking tuning.
+/// Signals outlive the memory they rate.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecallFeedback {
+    pub id: String,
+    pub memory_id: String,
+    pub signal: RecallSignal,
+    /// Query the memory was recalled for, when known.
+    pub query: Option<String>,
+    /// Who gave the signal (`cli`, `mcp`, ...).
+    pub source: String,
+    pub created_at: DateTime<Utc>,
+}
+
+impl RecallFeedback {
+    pub fn new(
+        memory_id: String,
+        signal: RecallSignal,
+        query: Option<String>,
+        source: String,
+    ) -> Self {
+        Self {
+            id: ulid::Ulid::new().to_string(),
+            memory_id,
+            signal,
+            query,
+            source,
+            created_at: Utc::now(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn signals_adjust_weight() {
+        assert!((RecallSignal::Useful.adjust_weight(0.5) - 0.6).abs() < 1e-6);
+        assert_eq!(RecallSignal::Useful.adjust_weight(0.95), 1.0);
+        assert!((RecallSignal::Noise.adjust_weight(0.5) - 0.35).abs() < 1e-6);
+        assert_eq!("Noise".parse::<RecallSignal>(), Ok(RecallSignal::Noise));
+        assert!("meh".parse::<RecallSignal>().is_err());
+    }
 }
@@ -1,5 +1,5 @@
 use crate::error::IcmResult;
-use crate::feedback::{Feedback, FeedbackStats};
+use crate::feedback::{Feedback, FeedbackStats, RecallFeedback};
 
 pub trait FeedbackStore {
     fn store_feedback(&self, feedback: Feedback) -> IcmResult<String>;
@@ -13,4 +13,15 @@ pub trait FeedbackStore {
     fn increment_applied(&self, id: &str) -> IcmResult<()>;
     fn delete_feedback(&self, id: &str) -> IcmResult<()>;
     fn feedback_stats(&self) -> IcmResult<FeedbackStats>;
+
+    /// Record a useful / noise signal and apply it to the memory's
+    /// weight in one step. Returns the new weight; `NotFound` when the
+    /// memory doesn't exist.
+    fn record_recall_feedback(&self, feedback: RecallFeedback) -> IcmResult<f32>;
+    /// Recall signals, newest first, optionally for one memory.
+    fn list_recall_feedback(
+        &self,
+        memory_id: Option<&str>,
+        limit: usize,
+    ) -> IcmResult<Vec<RecallFeedback>>;
 }
@@ -0,0 +1,161 @@
+//! Parsing for the keyword fallback search (`search_by_keywords`).
+//!
+//! The fallback runs when FTS finds nothing, with the raw query split on
+//! whitespace. Tokens are normalized here before any backend builds its
+//! query:
+//!
+//! - `+term` must match, `-term` must not; both override the mode.
+//! - Plain terms are required in [`KeywordMode::All`]; in
+//!   [`KeywordMode::Any`] at least `min_match` of them must match.
+//! - Common English stop words are dropped from plain terms, so
+//!   "the connection pool" doesn't match everything containing "the".
+
+use serde::Deserialize;
+
+/// How plain (unprefixed) terms combine.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum KeywordMode {
+    /// A memory matches when it contains `min_match` of the terms.
+    #[default]
+    Any,
+    /// A memory matches only when it contains every term.
+    All,
+}
+
+/// Keyword fallback settings (`[search.keywords]`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(default)]
+pub struct KeywordOptions {
+    pub mode: KeywordMode,
+    /// Plain terms that must match in `any` mode. Capped at the number
+    /// of terms in the query.
+    pub min_match: usize,
+    /// Drop stop words from plain terms.
+    pub stop_words: bool,
+}
+
+impl Default for KeywordOptions {
+    fn default() -> Self {
+        Self {
+            mode: KeywordMode::Any,
+            min_match: 1,
+            stop_words: true,
+        }
+    }
+}
+
+/// Terms too common to discriminate between memories.
+const STOP_WORDS: &[&str] = &[
+    "a", "about", "an", "and", "are", "as", "at", "be", "but", "by", "can", "do", "does", "for",
+    "from", "has", "have", "how", "i", "if", "in", "into", "is", "it", "its", "me", "my", "not",
+    "of", "on", "or", "our", "so", "that", "the", "their", "then", "there", "these", "this", "to",
+    "was", "we", "were", "what", "when", "where", "which", "who", "why", "will", "with", "you",
+    "your",
+];
+
+/// Most terms considered per query, bounding the generated SQL.
+const MAX_TERMS: usize = 50;
+
+/// A parsed keyword query. Terms are lowercased and deduplicated.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct KeywordQuery {
+    /// Terms every match must contain.
+    pub must: Vec<String>,
+    /// Terms of which at least [`Self::min_should`] must match.
+    pub should: Vec<String>,
+    /// Terms no match may contain.
+    pub exclude: Vec<String>,
+    /// How many `should` terms must match (0 when `should` is empty).
+    pub min_should: usize,
+}
+
+impl KeywordQuery {
+    /// Parse whitespace-split `tokens` under `options`.
+    pub fn parse(tokens: &[&str], options: &KeywordOptions) -> Self {
+        let mut query = Self::default();
+        for token in tokens.iter().take(MAX_TERMS) {
+            let (bucket, term) = if let Some(t) = token.strip_prefix('+') {
+                (&mut query.must, t)
+            } else if let Some(t) = token.strip_prefix('-') {
+                (&mut query.exclude, t)
+            } else {
+                (&mut query.should, *token)
+            };
+            let term = term
+                .trim_matches(|c: char| !c.is_alphanumeric() && c != '_')
+                .to_lowercase();
+            if !term.is_empty() && !bucket.contains(&term) {
+                bucket.push(term);
+            }
+        }
+        if options.stop_words {
+            query.should.retain(|t| !STOP_WORDS.contains(&t.as_str()));
+        }
+        match options.mode {
+            KeywordMode::All => {
+                for term in std::mem::take(&mut query.should) {
+                    if !query.must.contains(&term) {
+                        query.must.push(term);
+                    }
+                }
+            }
+            KeywordMode::Any => {
+                query.should.retain(|t| !query.must.contains(t));
+                query.min_should = options.min_match.clamp(1, query.should.len().max(1));
+                if query.should.is_empty() {
+                    query.min_should = 0;
+                }
+            }
+        }
+        query.exclude.retain(|t| !query.must.contains(t));
+        query
+    }
+
+    /// True when no positive term survived parsing, so nothing can match.
+    pub fn is_empty(&self) -> bool {
+        self.must.is_empty() && self.should.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(q: &str, options: KeywordOptions) -> KeywordQuery {
+        let tokens: Vec<&str> = q.split_whitespace().collect();
+        KeywordQuery::parse(&tokens, &options)
+    }
+
+    #[test]
+    fn any_mode_drops_stop_words_and_caps_min_match() {
+        let q = parse(
+            "The connection POOL, the timeout",
+            KeywordOptions {
+                min_match: 5,
+                ..Default::default()
+            },
+        );
+        assert_eq!(q.should, ["connection", "pool", "timeout"]);
+        assert!(q.must.is_empty());
+        assert_eq!(q.min_should, 3);
+    }
+
+    #[test]
+    fn all_mode_and_prefix_syntax() {
+        let options = KeywordOptions {
+            mode: KeywordMode::All,
+            ..Default::default()
+        };
+        let q = parse("+sqlite pool -postgres timeout", options);
+        assert_eq!(q.must, ["sqlite", "pool", "timeout"]);
+        assert_eq!(q.exclude, ["postgres"]);
+        assert_eq!(q.min_should, 0);
+
+        let q = parse("+pool pool -pool", KeywordOptions::default());
+        assert_eq!(q.must, ["pool"]);
+        assert!(q.should.is_empty() && q.exclude.is_empty());
+
+        assert!(parse("the -pool", KeywordOptions::default()).is_empty());
+    }
+}
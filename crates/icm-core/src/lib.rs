@@ -1,46 +1,92 @@
+pub mod age;
 pub mod auto_link;
+pub mod classify;
+pub mod condense;
 pub mod context_snapshot;
+pub mod decay;
 pub mod embedder;
 pub mod error;
+pub mod explain;
 pub mod facts;
 pub mod facts_store;
 #[cfg(feature = "embeddings")]
 pub mod fastembed_embedder;
 pub mod feedback;
 pub mod feedback_store;
+pub mod injection;
+pub mod keyword_query;
 pub mod learn;
+pub mod limits;
 pub mod memoir;
 pub mod memoir_store;
 pub mod memory;
+pub mod memory_index;
+pub mod provenance;
+pub mod ranking;
+pub mod scrub;
+pub mod search;
 pub mod store;
 pub mod transcript;
 pub mod transcript_store;
+pub mod usage;
 pub mod wake_up;
 
 /// Default embedding vector dimensions (used when no embedder is configured).
 pub const DEFAULT_EMBEDDING_DIMS: usize = 384;
 
+pub use age::parse_age;
 pub use auto_link::{add_backrefs, auto_link_memory, AutoLinkOptions};
+pub use classify::{
+    importance_centroids, infer_topic, resolve_importance, rule_importance, suggest_importance,
+    vote_topic, DEFAULT_TOPIC, TOPIC_VOTE_NEIGHBORS,
+};
+pub use condense::{condense, needs_condensing, DEFAULT_MAX_SUMMARY_CHARS};
 pub use context_snapshot::{
     build_context_snapshot, build_context_snapshot_from_memories, ContextSnapshot,
     ContextSnapshotOptions, SnapshotFormat, SnapshotSection, SNAPSHOT_HEADER,
 };
-pub use embedder::Embedder;
+pub use decay::{
+    decay_once, AutoDecay, DecayBucket, DecaySimulation, Reinforcement, DEFAULT_DECAY_RATE,
+    DEFAULT_REINFORCEMENT_RATE,
+};
+pub use embedder::{check_embedder, degraded_notice, Embedder, EmbedderHealth};
 pub use error::{IcmError, IcmResult};
+pub use explain::{explain_rank, render_explanation, RankExplanation};
 pub use facts::{Fact, FactsStats};
 pub use facts_store::FactsStore;
 #[cfg(feature = "embeddings")]
-pub use fastembed_embedder::FastEmbedder;
-pub use feedback::{Feedback, FeedbackStats};
+pub use fastembed_embedder::{
+    cached_models, resolve_model_name, supported_models, verify_model_cache, ExecutionProvider,
+    FastEmbedder, ModelCacheEntry, MODEL_ALIASES,
+};
+pub use feedback::{
+    Feedback, FeedbackStats, RecallFeedback, RecallSignal, NOISE_WEIGHT_FACTOR, USEFUL_WEIGHT_BOOST,
+};
 pub use feedback_store::FeedbackStore;
-pub use memoir::{Concept, ConceptLink, Label, Memoir, MemoirStats, Relation};
+pub use injection::{InjectionAction, InjectionGuard};
+pub use keyword_query::{KeywordMode, KeywordOptions, KeywordQuery};
+pub use limits::MemoryLimits;
+pub use memoir::{
+    label_vocabulary, validate_label_namespaces, Concept, ConceptLink, ConceptRevision, Label,
+    Memoir, MemoirStats, Relation, LINK_REINFORCEMENT, MAX_LINK_WEIGHT,
+};
 pub use memoir_store::MemoirStore;
 pub use memory::{
-    Importance, Memory, MemorySource, PatternCluster, Scope, StoreStats, TopicHealth,
+    content_idempotency_key, idempotent_id, parse_metadata_pair, Importance, Memory, MemoryKind,
+    MemoryLink, MemorySource, PatternCluster, Scope, StoreStats, TopicHealth,
+};
+pub use memory_index::MemoryIndex;
+pub use provenance::{repo_hash, resolve_repo, Provenance};
+pub use ranking::{
+    apply_recency_boost, fts_score, memory_similarity, mmr_select, recency_factor, sort_results,
+    HybridScore, RecallSort, DEFAULT_MMR_LAMBDA, HYBRID_FTS_WEIGHT, HYBRID_VECTOR_WEIGHT,
 };
+pub use scrub::{ScrubReport, Scrubber};
+pub use search::{render_hits, unified_search, SearchHit, SearchItem};
 pub use store::{find_similar_memory, MemoryStore, DEDUP_SIMILARITY_THRESHOLD};
 pub use transcript::{Message, Role, Session, TranscriptHit, TranscriptStats};
 pub use transcript_store::TranscriptStore;
+pub use usage::{TopicUsage, UsageEntry, UsageReport};
 pub use wake_up::{
     build_wake_up, build_wake_up_from_memories, is_preference_topic, project_matches, WakeUpFormat,
     WakeUpOptions, EMPTY_PACK_HEADER,
@@ -68,3 +114,60 @@ pub fn topic_matches(memory_topic: &str, filter: &str) -> bool {
 pub fn keyword_matches(keywords: &[String], filter: &str) -> bool {
     keywords.iter().any(|k| k.contains(filter))
 }
+
+/// Prefix of the topics auto-consolidation moves a topic's originals to
+/// when asked to keep them (`archive-<topic>`).
+pub const ARCHIVE_TOPIC_PREFIX: &str = "archive-";
+
+/// True for a topic holding originals archived by auto-consolidation.
+pub fn is_archived_topic(topic: &str) -> bool {
+    topic.starts_with(ARCHIVE_TOPIC_PREFIX)
+}
+
+/// Topics and keywords kept out of recall and context injection
+/// (`--exclude-topic`, `--exclude-keyword`, `[recall] exclude_topics`).
+///
+/// Unlike [`topic_matches`], topic exclusion is one-directional: excluding
+/// `scratch` drops `scratch` and `scratch-notes` but never a broader topic
+/// such as `s`. Both checks are case-insensitive.
+///
+/// Archived topics ([`is_archived_topic`]) are always excluded unless
+/// `include_archived` is set: their memories were rolled up, and the
+/// rollup stands in for them.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RecallExclusions {
+    pub topics: Vec<String>,
+    pub keywords: Vec<String>,
+    pub include_archived: bool,
+}
+
+impl RecallExclusions {
+    /// True when no topic or keyword is excluded.
+    pub fn is_empty(&self) -> bool {
+        self.topics.is_empty() && self.keywords.is_empty()
+    }
+
+    /// True when `memory` should be dropped from results.
+    pub fn excludes(&self, memory: &Memory) -> bool {
+        self.excludes_topic(&memory.topic)
+            || self.keywords.iter().any(|kw| {
+                let kw = kw.to_lowercase();
+                !kw.is_empty()
+                    && memory
+                        .keywords
+                        .iter()
+                        .any(|k| k.to_lowercase().contains(&kw))
+            })
+    }
+
+    /// True when everything under `topic` is excluded.
+    pub fn excludes_topic(&self, topic: &str) -> bool {
+        if !self.include_archived && is_archived_topic(topic) {
+            return true;
+        }
+        let topic = topic.to_lowercase();
+        self.topics
+            .iter()
+            .any(|t| !t.is_empty() && topic.contains(&t.to_lowercase()))
+    }
+}
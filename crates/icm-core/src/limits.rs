@@ -0,0 +1,157 @@
+//! Size limits and normalization applied to memories at the store
+//! boundary.
+//!
+//! Every write path (CLI, MCP, hook extraction) ends in the store, so
+//! checking there keeps the policy uniform: an over-sized memory is
+//! rejected with [`IcmError::Validation`] naming the field and the limit
+//! instead of landing as a bloated row.
+
+use serde::Deserialize;
+
+use crate::error::{IcmError, IcmResult};
+use crate::memory::Memory;
+
+/// Per-memory size limits. `0` disables a limit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(default)]
+pub struct MemoryLimits {
+    /// Longest summary, in characters.
+    pub max_summary_chars: usize,
+    /// Longest raw excerpt, in characters.
+    pub max_raw_excerpt_chars: usize,
+    /// Most keywords per memory (after normalization).
+    pub max_keywords: usize,
+}
+
+impl Default for MemoryLimits {
+    fn default() -> Self {
+        Self {
+            max_summary_chars: 16 * 1024,
+            max_raw_excerpt_chars: 128 * 1024,
+            max_keywords: 50,
+        }
+    }
+}
+
+impl MemoryLimits {
+    /// No limits; normalization still applies.
+    pub const UNLIMITED: Self = Self {
+        max_summary_chars: 0,
+        max_raw_excerpt_chars: 0,
+        max_keywords: 0,
+    };
+
+    /// Normalize `memory` in place, then check it against the limits.
+    ///
+    /// Normalization trims the summary and raw excerpt (dropping an
+    /// excerpt that is left empty) and trims keywords, dropping empty and
+    /// duplicate ones while keeping their order.
+    pub fn apply(&self, memory: &mut Memory) -> IcmResult<()> {
+        normalize(memory);
+        check(
+            "summary",
+            memory.summary.chars().count(),
+            self.max_summary_chars,
+            "characters",
+        )?;
+        if let Some(raw) = &memory.raw_excerpt {
+            check(
+                "raw excerpt",
+                raw.chars().count(),
+                self.max_raw_excerpt_chars,
+                "characters",
+            )?;
+        }
+        check(
+            "keywords",
+            memory.keywords.len(),
+            self.max_keywords,
+            "entries",
+        )
+    }
+}
+
+fn normalize(memory: &mut Memory) {
+    let summary = memory.summary.trim();
+    if summary.len() != memory.summary.len() {
+        memory.summary = summary.to_string();
+    }
+    memory.raw_excerpt = memory
+        .raw_excerpt
+        .take()
+        .map(|r| r.trim().to_string())
+        .filter(|r| !r.is_empty());
+    let mut keywords: Vec<String> = Vec::with_capacity(memory.keywords.len());
+    for kw in memory.keywords.drain(..) {
+        let kw = kw.trim();
+        if !kw.is_empty() && !keywords.iter().any(|k| k == kw) {
+            keywords.push(kw.to_string());
+        }
+    }
+    memory.keywords = keywords;
+}
+
+fn check(field: &str, len: usize, max: usize, unit: &str) -> IcmResult<()> {
+    if max > 0 && len > max {
+        return Err(IcmError::Validation(format!(
+            "{field} has {len} {unit}, over the limit of {max} ([memory.limits])"
+        )));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::memory::Importance;
+
+    fn memory(summary: &str) -> Memory {
+        Memory::new("t".into(), summary.into(), Importance::Medium)
+    }
+
+    #[test]
+    fn normalizes_whitespace_and_keywords() {
+        let mut m = memory("  padded summary \n");
+        m.raw_excerpt = Some("  \n ".into());
+        m.keywords = vec![" a ".into(), "b".into(), "".into(), "a".into()];
+        MemoryLimits::default().apply(&mut m).unwrap();
+        assert_eq!(m.summary, "padded summary");
+        assert_eq!(m.raw_excerpt, None);
+        assert_eq!(m.keywords, ["a", "b"]);
+    }
+
+    #[test]
+    fn rejects_each_oversized_field() {
+        let limits = MemoryLimits {
+            max_summary_chars: 5,
+            max_raw_excerpt_chars: 5,
+            max_keywords: 2,
+        };
+        let err = limits.apply(&mut memory("éééééé")).unwrap_err();
+        assert!(matches!(err, IcmError::Validation(_)));
+        assert!(
+            err.to_string().contains("summary has 6 characters"),
+            "{err}"
+        );
+
+        let mut m = memory("ok");
+        m.raw_excerpt = Some("too long".into());
+        assert!(limits
+            .apply(&mut m)
+            .unwrap_err()
+            .to_string()
+            .contains("raw excerpt"));
+
+        let mut m = memory("ok");
+        m.keywords = vec!["a".into(), "b".into(), "c".into()];
+        assert!(limits
+            .apply(&mut m)
+            .unwrap_err()
+            .to_string()
+            .contains("keywords has 3 entries"));
+
+        let mut m = memory("éééééé");
+        m.keywords = vec!["a".into(), "b".into(), "c".into()];
+        MemoryLimits::UNLIMITED.apply(&mut m).unwrap();
+    }
+}
@@ -1,7 +1,10 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::fmt;
 
+use crate::error::{IcmError, IcmResult};
+
 // ---------------------------------------------------------------------------
 // Memoir
 // ---------------------------------------------------------------------------
@@ -73,6 +76,39 @@ impl std::str::FromStr for Label {
     }
 }
 
+/// Reject labels whose namespace is not in `allowed` (`[memoir]
+/// allowed_namespaces`). An empty list allows every namespace.
+pub fn validate_label_namespaces(labels: &[Label], allowed: &[String]) -> IcmResult<()> {
+    if allowed.is_empty() {
+        return Ok(());
+    }
+    let rejected: Vec<String> = labels
+        .iter()
+        .filter(|l| !allowed.contains(&l.namespace))
+        .map(|l| l.to_string())
+        .collect();
+    if rejected.is_empty() {
+        return Ok(());
+    }
+    Err(IcmError::InvalidInput(format!(
+        "label namespace not allowed: {} (allowed: {})",
+        rejected.join(", "),
+        allowed.join(", ")
+    )))
+}
+
+/// Every label carried by `concepts` with the number of concepts
+/// carrying it, sorted by label.
+pub fn label_vocabulary(concepts: &[Concept]) -> Vec<(String, usize)> {
+    let mut counts: BTreeMap<String, usize> = BTreeMap::new();
+    for concept in concepts {
+        for label in &concept.labels {
+            *counts.entry(label.to_string()).or_default() += 1;
+        }
+    }
+    counts.into_iter().collect()
+}
+
 // ---------------------------------------------------------------------------
 // Concept
 // ---------------------------------------------------------------------------
@@ -193,12 +229,34 @@ impl std::str::FromStr for Relation {
 // ConceptLink
 // ---------------------------------------------------------------------------
 
+/// Added to a link's weight each time its two endpoints are retrieved
+/// together.
+pub const LINK_REINFORCEMENT: f32 = 0.1;
+
+/// Ceiling for reinforced link weights, so a pair that is searched for
+/// constantly can't drown out every other link.
+pub const MAX_LINK_WEIGHT: f32 = 3.0;
+
+/// A superseded definition of a concept, kept when the concept is
+/// refined or rolled back.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConceptRevision {
+    pub concept_id: String,
+    pub revision: u32,
+    pub definition: String,
+    pub confidence: f32,
+    /// Last write to the concept while this revision was current.
+    pub updated_at: DateTime<Utc>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ConceptLink {
     pub id: String,
     pub source_id: String,
     pub target_id: String,
     pub relation: Relation,
+    /// Starts at 1.0; grows as both endpoints are retrieved together
+    /// and shrinks with `icm decay`. Strongest links are expanded first.
     pub weight: f32,
     pub created_at: DateTime<Utc>,
 }
@@ -1,5 +1,7 @@
 use crate::error::IcmResult;
-use crate::memoir::{Concept, ConceptLink, Label, Memoir, MemoirStats, Relation};
+use crate::memoir::{
+    Concept, ConceptLink, ConceptRevision, Label, Memoir, MemoirStats, Relation, LINK_REINFORCEMENT,
+};
 
 pub trait MemoirStore {
     // --- Memoir CRUD ---
@@ -25,10 +27,13 @@ pub trait MemoirStore {
         query: &str,
         limit: usize,
     ) -> IcmResult<Vec<Concept>>;
-    fn search_concepts_by_label(
+    /// Concepts carrying any of `labels`, or all of them when
+    /// `match_all`, most confident first.
+    fn search_concepts_by_labels(
         &self,
         memoir_id: &str,
-        label: &Label,
+        labels: &[Label],
+        match_all: bool,
         limit: usize,
     ) -> IcmResult<Vec<Concept>>;
 
@@ -42,17 +47,47 @@ pub trait MemoirStore {
         new_definition: &str,
         new_source_ids: &[String],
     ) -> IcmResult<()>;
+    /// Superseded revisions of a concept, oldest first. The current
+    /// definition lives on the concept itself.
+    fn concept_revisions(&self, concept_id: &str) -> IcmResult<Vec<ConceptRevision>>;
+    /// Restore the definition of an earlier `revision` as a new revision,
+    /// so the rollback itself stays in the history.
+    fn rollback_concept(&self, concept_id: &str, revision: u32) -> IcmResult<()>;
 
     // --- Graph ---
     fn add_link(&self, link: ConceptLink) -> IcmResult<String>;
     fn get_links_from(&self, concept_id: &str) -> IcmResult<Vec<ConceptLink>>;
     fn get_links_to(&self, concept_id: &str) -> IcmResult<Vec<ConceptLink>>;
     fn delete_link(&self, id: &str) -> IcmResult<()>;
+    /// Set a link's weight, clamped to `0.0..=MAX_LINK_WEIGHT`.
+    fn update_link_weight(&self, id: &str, weight: f32) -> IcmResult<()>;
+    /// Multiply every link's weight by `factor`, the link counterpart of
+    /// `MemoryStore::apply_decay`. Returns the number of links updated.
+    fn decay_links(&self, factor: f32) -> IcmResult<usize>;
+
+    /// Strengthen every link whose two endpoints are both in
+    /// `concept_ids`, i.e. concepts that came back from the same search.
+    /// Returns the number of links updated.
+    fn reinforce_links(&self, concept_ids: &[&str]) -> IcmResult<usize> {
+        let mut updated = 0;
+        for id in concept_ids {
+            for link in self.get_links_from(id)? {
+                if concept_ids.contains(&link.target_id.as_str()) {
+                    self.update_link_weight(&link.id, link.weight + LINK_REINFORCEMENT)?;
+                    updated += 1;
+                }
+            }
+        }
+        Ok(updated)
+    }
+
     fn get_neighbors(
         &self,
         concept_id: &str,
         relation: Option<Relation>,
     ) -> IcmResult<Vec<Concept>>;
+    /// The concept and everything within `depth` links of it. Each
+    /// concept's links are followed strongest first.
     fn get_neighborhood(
         &self,
         concept_id: &str,
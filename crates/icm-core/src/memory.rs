@@ -1,7 +1,11 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::fmt;
 
+use crate::memoir::Relation;
+use crate::provenance::Provenance;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Memory {
     pub id: String,
@@ -14,6 +18,11 @@ pub struct Memory {
     pub topic: String,
     pub summary: String,
     pub raw_excerpt: Option<String>,
+    /// Content hash of a large raw excerpt kept in the attachment store
+    /// instead of inline. When set, `raw_excerpt` is `None` on read until
+    /// the attachment is explicitly fetched.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub attachment: Option<String>,
     pub keywords: Vec<String>,
 
     pub importance: Importance,
@@ -27,6 +36,21 @@ pub struct Memory {
     /// Cloud scope: user (local default), project, or org.
     #[serde(default)]
     pub scope: Scope,
+
+    /// Who stored the memory (`[memory] author` / `ICM_AUTHOR`), for
+    /// provenance in shared team DBs. `None` for unattributed memories.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub author: Option<String>,
+
+    /// Structured key/value tags for integrations (`{"pr": "1234",
+    /// "file": "src/auth.rs"}`), filterable with `--meta key=value`.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub metadata: BTreeMap<String, String>,
+
+    /// What the memory is: a fact recalled by relevance, or a preference
+    /// injected into every session regardless of the query.
+    #[serde(default, skip_serializing_if = "MemoryKind::is_fact")]
+    pub kind: MemoryKind,
 }
 
 impl Memory {
@@ -47,14 +71,75 @@ impl Memory {
             topic,
             summary,
             raw_excerpt: None,
+            attachment: None,
             keywords: Vec::new(),
             importance,
-            source: MemorySource::Manual,
+            source: MemorySource::default(),
             related_ids: Vec::new(),
             embedding: None,
             scope: Scope::User,
+            author: None,
+            metadata: BTreeMap::new(),
+            kind: MemoryKind::Fact,
         }
     }
+
+    /// True for memories injected whatever the query: `kind =
+    /// preference`, or a preference/identity topic (the convention that
+    /// predates the kind).
+    pub fn is_preference(&self) -> bool {
+        self.kind == MemoryKind::Preference || crate::wake_up::is_preference_topic(&self.topic)
+    }
+
+    /// True when the memory, as stored now, is what was known at `at`:
+    /// created by then and not rewritten since. Later edits may
+    /// contradict what was known, and the earlier text is not kept.
+    pub fn known_at(&self, at: DateTime<Utc>) -> bool {
+        self.created_at <= at && self.updated_at <= at
+    }
+
+    /// True when every `key=value` pair of `filter` is in the metadata.
+    /// Keys match exactly, values ignoring case.
+    pub fn matches_metadata(&self, filter: &[(String, String)]) -> bool {
+        filter.iter().all(|(key, value)| {
+            self.metadata
+                .get(key)
+                .is_some_and(|v| v.eq_ignore_ascii_case(value))
+        })
+    }
+}
+
+/// Parse one `key=value` metadata pair (`--meta pr=1234`).
+pub fn parse_metadata_pair(s: &str) -> Result<(String, String), String> {
+    match s.split_once('=') {
+        Some((key, value)) if !key.trim().is_empty() => {
+            Ok((key.trim().to_string(), value.trim().to_string()))
+        }
+        _ => Err(format!("invalid metadata `{s}` (expected key=value)")),
+    }
+}
+
+/// Memory id for an idempotency key. The same key always names the same
+/// memory, so a retried store matches the first write instead of adding
+/// a second one.
+pub fn idempotent_id(key: &str) -> String {
+    use sha2::{Digest, Sha256};
+    let digest = Sha256::digest(key.as_bytes());
+    let hex: String = digest[..13].iter().map(|b| format!("{b:02x}")).collect();
+    format!("idem-{hex}")
+}
+
+/// Idempotency key derived from the write itself: topic, content and UTC
+/// day, case and whitespace folded. Retries of a store on the same day
+/// share it; storing the fact again on a later day does not.
+pub fn content_idempotency_key(topic: &str, content: &str, at: DateTime<Utc>) -> String {
+    let content: Vec<&str> = content.split_whitespace().collect();
+    format!(
+        "{}\0{}\0{}",
+        topic.trim().to_lowercase(),
+        content.join(" ").to_lowercase(),
+        at.date_naive()
+    )
 }
 
 /// Memory scope for cloud sync.
@@ -93,7 +178,7 @@ impl std::str::FromStr for Scope {
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum Importance {
     Critical,
@@ -113,6 +198,26 @@ impl fmt::Display for Importance {
     }
 }
 
+impl Importance {
+    /// How fast decay wears this importance's weight down, relative to
+    /// medium, as applied by `apply_decay`. Critical memories never
+    /// decay.
+    pub fn decay_multiplier(&self) -> f32 {
+        match self {
+            Self::Critical => 0.0,
+            Self::High => 0.5,
+            Self::Medium => 1.0,
+            Self::Low => 2.0,
+        }
+    }
+
+    /// Prune never deletes critical or high memories, whatever their
+    /// weight.
+    pub fn is_prune_protected(&self) -> bool {
+        matches!(self, Self::Critical | Self::High)
+    }
+}
+
 impl std::str::FromStr for Importance {
     type Err = String;
 
@@ -127,17 +232,142 @@ impl std::str::FromStr for Importance {
     }
 }
 
+/// How a memory is surfaced. Facts are recalled when relevant to the
+/// query; preferences (formatting style, commit conventions) are always
+/// injected, within the context budget.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MemoryKind {
+    #[default]
+    Fact,
+    Preference,
+    Instruction,
+}
+
+impl MemoryKind {
+    pub fn is_fact(&self) -> bool {
+        *self == Self::Fact
+    }
+}
+
+impl fmt::Display for MemoryKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Fact => write!(f, "fact"),
+            Self::Preference => write!(f, "preference"),
+            Self::Instruction => write!(f, "instruction"),
+        }
+    }
+}
+
+impl std::str::FromStr for MemoryKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "fact" => Ok(Self::Fact),
+            "preference" | "pref" => Ok(Self::Preference),
+            "instruction" => Ok(Self::Instruction),
+            _ => Err(format!("invalid memory kind: {s}")),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "type", rename_all = "snake_case")]
 pub enum MemorySource {
     ClaudeCode {
         session_id: String,
         file_path: Option<String>,
+        #[serde(default, skip_serializing_if = "Provenance::is_empty")]
+        provenance: Provenance,
     },
     Conversation {
         thread_id: String,
     },
-    Manual,
+    Manual {
+        #[serde(default, skip_serializing_if = "Provenance::is_empty")]
+        provenance: Provenance,
+    },
+}
+
+impl Default for MemorySource {
+    fn default() -> Self {
+        Self::Manual {
+            provenance: Provenance::default(),
+        }
+    }
+}
+
+impl MemorySource {
+    /// Stable source kind, as stored in the `source_type` column:
+    /// `claude_code`, `conversation`, or `manual`.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::ClaudeCode { .. } => "claude_code",
+            Self::Conversation { .. } => "conversation",
+            Self::Manual { .. } => "manual",
+        }
+    }
+
+    /// Session / thread identifier the memory came from, if any.
+    pub fn session_id(&self) -> Option<&str> {
+        match self {
+            Self::ClaudeCode { session_id, .. } => Some(session_id),
+            Self::Conversation { thread_id } => Some(thread_id),
+            Self::Manual { .. } => None,
+        }
+    }
+
+    /// Filesystem path recorded at capture time (the edited file or the
+    /// agent's working directory), if any.
+    pub fn path(&self) -> Option<&str> {
+        match self {
+            Self::ClaudeCode { file_path, .. } => file_path
+                .as_deref()
+                .or(self.provenance().and_then(|p| p.cwd.as_deref())),
+            _ => self.provenance().and_then(|p| p.cwd.as_deref()),
+        }
+    }
+
+    /// Working directory, repository and branch captured at store time.
+    /// Conversation imports carry none.
+    pub fn provenance(&self) -> Option<&Provenance> {
+        match self {
+            Self::ClaudeCode { provenance, .. } | Self::Manual { provenance } => Some(provenance),
+            Self::Conversation { .. } => None,
+        }
+    }
+
+    /// Repo id ([`crate::repo_hash`]) the memory was captured in, if any.
+    pub fn repo(&self) -> Option<&str> {
+        self.provenance().and_then(|p| p.repo.as_deref())
+    }
+
+    /// Attach `provenance`; a no-op for conversation imports.
+    pub fn with_provenance(mut self, provenance: Provenance) -> Self {
+        match &mut self {
+            Self::ClaudeCode { provenance: p, .. } | Self::Manual { provenance: p } => {
+                *p = provenance
+            }
+            Self::Conversation { .. } => {}
+        }
+        self
+    }
+
+    /// True when the recorded path lies inside `dir` (component-wise, so
+    /// `/work/app` does not match `/work/app-old`).
+    pub fn originates_under(&self, dir: &str) -> bool {
+        let dir = dir.trim_end_matches(['/', '\\']);
+        if dir.is_empty() {
+            return false;
+        }
+        self.path().is_some_and(|p| {
+            p == dir
+                || p.strip_prefix(dir)
+                    .is_some_and(|rest| rest.starts_with('/') || rest.starts_with('\\'))
+        })
+    }
 }
 
 impl fmt::Display for MemorySource {
@@ -145,7 +375,40 @@ impl fmt::Display for MemorySource {
         match self {
             Self::ClaudeCode { session_id, .. } => write!(f, "claude-code:{session_id}"),
             Self::Conversation { thread_id } => write!(f, "conversation:{thread_id}"),
-            Self::Manual => write!(f, "manual"),
+            Self::Manual { .. } => write!(f, "manual"),
+        }
+    }
+}
+
+/// A typed, directed edge between two memories (`icm link`), e.g. an
+/// error `caused_by` another, or a decision `superseded_by` its revision.
+/// Uses the same [`Relation`] vocabulary as concept links.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MemoryLink {
+    pub id: String,
+    pub source_id: String,
+    pub target_id: String,
+    pub relation: Relation,
+    pub created_at: DateTime<Utc>,
+}
+
+impl MemoryLink {
+    pub fn new(source_id: String, target_id: String, relation: Relation) -> Self {
+        Self {
+            id: ulid::Ulid::new().to_string(),
+            source_id,
+            target_id,
+            relation,
+            created_at: Utc::now(),
+        }
+    }
+
+    /// The end of the link that is not `memory_id`.
+    pub fn other_end(&self, memory_id: &str) -> &str {
+        if self.source_id == memory_id {
+            &self.target_id
+        } else {
+            &self.source_id
         }
     }
 }
@@ -198,3 +461,152 @@ impl TopicHealth {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn metadata_pairs_parse_and_filter() {
+        assert_eq!(
+            parse_metadata_pair("pr = 1234").unwrap(),
+            ("pr".to_string(), "1234".to_string())
+        );
+        assert_eq!(
+            parse_metadata_pair("url=https://x.example/?a=b").unwrap().1,
+            "https://x.example/?a=b"
+        );
+        assert!(parse_metadata_pair("pr").is_err());
+        assert!(parse_metadata_pair("=1234").is_err());
+
+        let mut m = Memory::new("t".into(), "s".into(), Importance::Medium);
+        m.metadata.insert("pr".into(), "1234".into());
+        m.metadata.insert("file".into(), "src/auth.rs".into());
+        let pair = |k: &str, v: &str| (k.to_string(), v.to_string());
+        assert!(m.matches_metadata(&[]));
+        assert!(m.matches_metadata(&[pair("pr", "1234"), pair("file", "src/auth.rs")]));
+        assert!(!m.matches_metadata(&[pair("pr", "999")]));
+        assert!(!m.matches_metadata(&[pair("issue", "1234")]));
+    }
+
+    #[test]
+    fn kind_round_trips_and_marks_preferences() {
+        for kind in [
+            MemoryKind::Fact,
+            MemoryKind::Preference,
+            MemoryKind::Instruction,
+        ] {
+            assert_eq!(kind.to_string().parse::<MemoryKind>().unwrap(), kind);
+        }
+        assert!("rule".parse::<MemoryKind>().is_err());
+
+        let mut m = Memory::new(
+            "icm".into(),
+            "Use conventional commits".into(),
+            Importance::Medium,
+        );
+        assert!(!m.is_preference());
+        let json = serde_json::to_value(&m).unwrap();
+        assert!(json.get("kind").is_none());
+        m.kind = MemoryKind::Preference;
+        assert!(m.is_preference());
+        let back: Memory = serde_json::from_value(serde_json::to_value(&m).unwrap()).unwrap();
+        assert_eq!(back.kind, MemoryKind::Preference);
+
+        let legacy = Memory::new("preferences".into(), "Tabs".into(), Importance::Medium);
+        assert!(legacy.is_preference());
+    }
+
+    #[test]
+    fn idempotent_ids_follow_the_key() {
+        let day = "2026-03-01T09:00:00Z".parse::<DateTime<Utc>>().unwrap();
+        let later = "2026-03-01T18:00:00Z".parse::<DateTime<Utc>>().unwrap();
+        let next_day = "2026-03-02T09:00:00Z".parse::<DateTime<Utc>>().unwrap();
+
+        let key = content_idempotency_key("Deploy", "uses  blue/green", day);
+        assert_eq!(
+            key,
+            content_idempotency_key("deploy ", "Uses blue/green", later)
+        );
+        assert_ne!(
+            key,
+            content_idempotency_key("deploy", "uses blue/green", next_day)
+        );
+
+        let id = idempotent_id(&key);
+        assert!(id.starts_with("idem-") && id.len() == 31, "{id}");
+        assert_eq!(id, idempotent_id(&key));
+        assert_ne!(id, idempotent_id("other"));
+    }
+
+    fn claude_code(path: Option<&str>) -> MemorySource {
+        MemorySource::ClaudeCode {
+            session_id: "s1".into(),
+            file_path: path.map(String::from),
+            provenance: Provenance::default(),
+        }
+    }
+
+    #[test]
+    fn known_at_excludes_later_and_rewritten_memories() {
+        let t = |s: &str| DateTime::parse_from_rfc3339(s).unwrap().with_timezone(&Utc);
+        let mut m = Memory::new("t".into(), "s".into(), Importance::Medium);
+        m.created_at = t("2024-11-20T10:00:00Z");
+        m.updated_at = m.created_at;
+        assert!(m.known_at(t("2024-12-01T00:00:00Z")));
+        assert!(m.known_at(m.created_at));
+        assert!(!m.known_at(t("2024-11-19T00:00:00Z")));
+
+        m.updated_at = t("2024-12-05T09:00:00Z");
+        assert!(!m.known_at(t("2024-12-01T00:00:00Z")));
+        assert!(m.known_at(t("2024-12-06T00:00:00Z")));
+    }
+
+    #[test]
+    fn source_kind_matches_stored_source_type() {
+        assert_eq!(claude_code(None).kind(), "claude_code");
+        assert_eq!(
+            MemorySource::Conversation {
+                thread_id: "t".into()
+            }
+            .kind(),
+            "conversation"
+        );
+        assert_eq!(MemorySource::default().kind(), "manual");
+    }
+
+    #[test]
+    fn originates_under_is_component_aware() {
+        let src = claude_code(Some("/work/app/src/main.rs"));
+        assert!(src.originates_under("/work/app"));
+        assert!(src.originates_under("/work/app/"));
+        assert!(!src.originates_under("/work/ap"));
+        assert!(claude_code(Some("/work/app")).originates_under("/work/app"));
+        assert!(!claude_code(None).originates_under("/work/app"));
+        assert!(!MemorySource::default().originates_under("/work/app"));
+    }
+
+    #[test]
+    fn provenance_round_trips_and_old_rows_still_parse() {
+        let src = MemorySource::default().with_provenance(Provenance {
+            cwd: Some("/work/app".into()),
+            repo: Some("0123456789abcdef".into()),
+            branch: Some("main".into()),
+        });
+        assert_eq!(src.repo(), Some("0123456789abcdef"));
+        assert!(src.originates_under("/work/app"));
+        let json = serde_json::to_string(&src).unwrap();
+        let back: MemorySource = serde_json::from_str(&json).unwrap();
+        assert_eq!(back.provenance(), src.provenance());
+
+        // Rows written before provenance existed.
+        let old: MemorySource =
+            serde_json::from_str(r#"{"type":"claude_code","session_id":"s1","file_path":null}"#)
+                .unwrap();
+        assert_eq!(old.provenance(), Some(&Provenance::default()));
+        assert_eq!(
+            serde_json::to_string(&MemorySource::default()).unwrap(),
+            r#"{"type":"manual"}"#
+        );
+    }
+}
@@ -0,0 +1,246 @@
+//! Store-free recall over memories held in memory.
+//!
+//! Where there is no database — a memory pack loaded in a browser
+//! (`icm-wasm`), a test fixture — [`MemoryIndex`] ranks memories the way
+//! the SQLite backend does: every query term must match (FTS5's implicit
+//! AND) and is scored with BM25 over topic, summary and keywords; with a
+//! query embedding, the text score is blended with cosine similarity into
+//! the same [`HybridScore`] the stores return. BM25 here treats the three
+//! fields as one document, so ranks track FTS5's closely rather than
+//! exactly.
+
+use std::collections::{HashMap, HashSet};
+
+use serde::Deserialize;
+
+use crate::error::IcmResult;
+use crate::memory::Memory;
+use crate::ranking::{cosine, fts_score, HybridScore};
+
+/// FTS5's default BM25 parameters.
+const BM25_K1: f32 = 1.2;
+const BM25_B: f32 = 0.75;
+
+/// Memories plus the term statistics BM25 needs.
+#[derive(Debug, Clone, Default)]
+pub struct MemoryIndex {
+    memories: Vec<Memory>,
+    /// Term frequencies, parallel to `memories`.
+    terms: Vec<HashMap<String, u32>>,
+    /// Token count, parallel to `memories`.
+    lengths: Vec<usize>,
+    /// How many memories contain each term.
+    doc_freq: HashMap<String, usize>,
+    avg_len: f32,
+}
+
+/// Accepted JSON shapes: pack contents (`{"memories": [...], ...}`) or
+/// a bare array of memories.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum MemoriesJson {
+    Pack { memories: Vec<Memory> },
+    List(Vec<Memory>),
+}
+
+impl MemoryIndex {
+    pub fn new(memories: Vec<Memory>) -> Self {
+        let mut terms = Vec::with_capacity(memories.len());
+        let mut lengths = Vec::with_capacity(memories.len());
+        let mut doc_freq: HashMap<String, usize> = HashMap::new();
+        for memory in &memories {
+            let mut tf: HashMap<String, u32> = HashMap::new();
+            let mut len = 0;
+            let text = [
+                memory.topic.as_str(),
+                memory.summary.as_str(),
+                &memory.keywords.join(" "),
+            ]
+            .join(" ");
+            for token in tokenize(&text) {
+                *tf.entry(token).or_default() += 1;
+                len += 1;
+            }
+            for term in tf.keys() {
+                *doc_freq.entry(term.clone()).or_default() += 1;
+            }
+            terms.push(tf);
+            lengths.push(len);
+        }
+        let avg_len = if lengths.is_empty() {
+            0.0
+        } else {
+            lengths.iter().sum::<usize>() as f32 / lengths.len() as f32
+        };
+        Self {
+            memories,
+            terms,
+            lengths,
+            doc_freq,
+            avg_len,
+        }
+    }
+
+    /// Index the memories in `json`: the contents of a memory pack (the
+    /// signed envelope's `contents` string) or a JSON array of memories.
+    pub fn from_json(json: &str) -> IcmResult<Self> {
+        let memories = match serde_json::from_str(json)? {
+            MemoriesJson::Pack { memories } | MemoriesJson::List(memories) => memories,
+        };
+        Ok(Self::new(memories))
+    }
+
+    pub fn len(&self) -> usize {
+        self.memories.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.memories.is_empty()
+    }
+
+    pub fn memories(&self) -> &[Memory] {
+        &self.memories
+    }
+
+    /// Up to `limit` memories for `query`, best first.
+    ///
+    /// Without `embedding` this is full-text search, ordered by BM25.
+    /// With one, memories whose embedding has the same dimension are
+    /// candidates too, and everything is ordered by the blended score.
+    pub fn search(
+        &self,
+        query: &str,
+        embedding: Option<&[f32]>,
+        limit: usize,
+    ) -> Vec<(&Memory, HybridScore)> {
+        let query_terms: Vec<String> = {
+            let mut seen = HashSet::new();
+            tokenize(query).filter(|t| seen.insert(t.clone())).collect()
+        };
+
+        let mut hits: Vec<(&Memory, HybridScore)> = Vec::new();
+        for (i, memory) in self.memories.iter().enumerate() {
+            let bm25 = self.bm25(i, &query_terms);
+            let similarity = match (embedding, memory.embedding.as_deref()) {
+                (Some(q), Some(m)) if q.len() == m.len() && !q.is_empty() => Some(cosine(q, m)),
+                _ => None,
+            };
+            if bm25.is_none() && similarity.is_none() {
+                continue;
+            }
+            // FTS5 reports bm25 negated, lower is better.
+            let rank = bm25.map(|s| -s);
+            hits.push((
+                memory,
+                HybridScore::new(
+                    rank,
+                    rank.map_or(0.0, fts_score),
+                    similarity,
+                    similarity.unwrap_or(0.0),
+                ),
+            ));
+        }
+
+        if embedding.is_some() {
+            hits.sort_by(|a, b| b.1.combined.total_cmp(&a.1.combined));
+        } else {
+            hits.sort_by(|a, b| {
+                let (a, b) = (a.1.bm25.unwrap_or(0.0), b.1.bm25.unwrap_or(0.0));
+                a.total_cmp(&b)
+            });
+        }
+        hits.truncate(limit);
+        hits
+    }
+
+    /// BM25 of memory `i`, or `None` unless it contains every term.
+    fn bm25(&self, i: usize, query_terms: &[String]) -> Option<f32> {
+        if query_terms.is_empty() {
+            return None;
+        }
+        let n = self.memories.len() as f32;
+        let len_norm = 1.0 - BM25_B + BM25_B * self.lengths[i] as f32 / self.avg_len.max(1.0);
+        let mut score = 0.0;
+        for term in query_terms {
+            let tf = *self.terms[i].get(term)? as f32;
+            let df = self.doc_freq.get(term).copied().unwrap_or(0) as f32;
+            // FTS5 floors idf so terms in most documents still count.
+            let idf = ((n - df + 0.5) / (df + 0.5)).ln().max(1e-6);
+            score += idf * tf * (BM25_K1 + 1.0) / (tf + BM25_K1 * len_norm);
+        }
+        Some(score)
+    }
+}
+
+/// Lowercased alphanumeric runs, like FTS5's `unicode61` tokenizer.
+fn tokenize(text: &str) -> impl Iterator<Item = String> + '_ {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|t| !t.is_empty())
+        .map(str::to_lowercase)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::memory::Importance;
+
+    fn memory(topic: &str, summary: &str, embedding: Option<Vec<f32>>) -> Memory {
+        let mut m = Memory::new(topic.into(), summary.into(), Importance::Medium);
+        m.embedding = embedding;
+        m
+    }
+
+    #[test]
+    fn text_search_requires_every_term_and_ranks_by_bm25() {
+        let index = MemoryIndex::new(vec![
+            memory("notes", "release checklist lives in the wiki", None),
+            memory("decisions-app", "release release needs sign-off", None),
+            memory("notes", "sign-off happens on Fridays", None),
+        ]);
+        let hits = index.search("Release", None, 10);
+        let summaries: Vec<&str> = hits.iter().map(|(m, _)| m.summary.as_str()).collect();
+        assert_eq!(
+            summaries,
+            [
+                "release release needs sign-off",
+                "release checklist lives in the wiki"
+            ]
+        );
+        assert!(hits[0].1.bm25.unwrap() < hits[1].1.bm25.unwrap());
+
+        let hits = index.search("sign-off release", None, 10);
+        assert_eq!(hits.len(), 1);
+        assert!(index.search("", None, 10).is_empty());
+        assert!(index.search("deploy", None, 10).is_empty());
+    }
+
+    #[test]
+    fn embedding_adds_vector_candidates_and_blends_scores() {
+        let index = MemoryIndex::new(vec![
+            memory("a", "deploy on tuesdays", Some(vec![1.0, 0.0])),
+            memory("b", "unrelated words", Some(vec![0.0, 1.0])),
+            memory("c", "deploy runbook", None),
+        ]);
+        let hits = index.search("deploy", Some(&[1.0, 0.0]), 10);
+        let topics: Vec<&str> = hits.iter().map(|(m, _)| m.topic.as_str()).collect();
+        assert_eq!(topics, ["a", "c", "b"]);
+        assert_eq!(hits[0].1.similarity, Some(1.0));
+        assert!(hits[1].1.similarity.is_none());
+        assert_eq!(hits[2].1.combined, 0.0);
+
+        // Embeddings of another dimension are ignored.
+        assert_eq!(index.search("tuesdays", Some(&[1.0]), 10).len(), 1);
+    }
+
+    #[test]
+    fn from_json_accepts_pack_contents_and_plain_lists() {
+        let list = vec![memory("notes", "release needs sign-off", None)];
+        let json = serde_json::to_string(&list).unwrap();
+        assert_eq!(MemoryIndex::from_json(&json).unwrap().len(), 1);
+
+        let pack = format!(r#"{{"name":"team","memories":{json},"memoirs":[]}}"#);
+        let index = MemoryIndex::from_json(&pack).unwrap();
+        assert_eq!(index.memories()[0].summary, "release needs sign-off");
+        assert!(MemoryIndex::from_json("{}").is_err());
+    }
+}
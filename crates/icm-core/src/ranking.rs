@@ -0,0 +1,306 @@
+//! Post-retrieval ranking for recall results.
+//!
+//! Hybrid search scores relevance only, so an old, heavily reinforced
+//! memory can outrank a fresh one that answers the query better. These
+//! helpers let callers blend a recency boost into the score
+//! (`score * exp(-age / τ)`) or re-order results by recency / weight,
+//! and provide maximal-marginal-relevance (MMR) selection so injected
+//! context covers distinct facts instead of five paraphrases of one.
+
+use std::collections::HashSet;
+
+use chrono::{DateTime, Utc};
+
+use crate::memory::Memory;
+
+/// Ordering applied to recall results after retrieval.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum RecallSort {
+    /// Relevance score (optionally recency-boosted). Default.
+    #[default]
+    Relevance,
+    /// Most recently updated first.
+    Recent,
+    /// Highest decay weight first.
+    Weight,
+}
+
+impl std::str::FromStr for RecallSort {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "relevance" => Ok(Self::Relevance),
+            "recent" => Ok(Self::Recent),
+            "weight" => Ok(Self::Weight),
+            _ => Err(format!(
+                "invalid sort: {s} (expected: relevance, recent, weight)"
+            )),
+        }
+    }
+}
+
+/// Share of the normalized full-text score in a hybrid-search score.
+pub const HYBRID_FTS_WEIGHT: f32 = 0.3;
+/// Share of the vector score in a hybrid-search score.
+pub const HYBRID_VECTOR_WEIGHT: f32 = 0.7;
+
+/// The signals behind one hybrid-search score. Backends fill in what
+/// they retrieved and [`HybridScore::new`] blends it, so every backend
+/// combines FTS and vector scores the same way.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct HybridScore {
+    /// Full-text rank as the backend reports it: SQLite FTS5 `bm25`
+    /// (negative, lower is better), Postgres `ts_rank_cd`, OpenSearch
+    /// BM25. `None` when the memory did not match the text query.
+    pub bm25: Option<f32>,
+    /// Full-text score normalized to `0..=1`.
+    pub fts: f32,
+    /// Cosine similarity to the query embedding (`1 - distance`).
+    /// `None` when the memory was not among the nearest neighbours.
+    pub similarity: Option<f32>,
+    /// Vector score as blended: the similarity itself, or its min-max
+    /// normalized value on backends that rescale it.
+    pub vector: f32,
+    /// `HYBRID_FTS_WEIGHT * fts + HYBRID_VECTOR_WEIGHT * vector`.
+    pub combined: f32,
+}
+
+impl HybridScore {
+    pub fn new(bm25: Option<f32>, fts: f32, similarity: Option<f32>, vector: f32) -> Self {
+        Self {
+            bm25,
+            fts,
+            similarity,
+            vector,
+            combined: HYBRID_FTS_WEIGHT * fts + HYBRID_VECTOR_WEIGHT * vector,
+        }
+    }
+}
+
+/// Full-text score in `0..=1` for an FTS5-style `rank` (negative bm25),
+/// as blended by the SQLite backend and [`crate::MemoryIndex`].
+pub fn fts_score(rank: f32) -> f32 {
+    1.0 / (1.0 + rank.abs())
+}
+
+/// Recency multiplier `exp(-age / τ)` for a memory, with age measured in
+/// days since `updated_at`. `tau_days <= 0` disables the boost (returns 1).
+pub fn recency_factor(memory: &Memory, now: DateTime<Utc>, tau_days: f32) -> f32 {
+    if tau_days <= 0.0 {
+        return 1.0;
+    }
+    let age_days = (now - memory.updated_at).num_seconds().max(0) as f32 / 86_400.0;
+    (-age_days / tau_days).exp()
+}
+
+/// Multiply each score by its recency factor and re-sort descending.
+/// Unscored entries (FTS / keyword fallback) keep their relative order
+/// after all scored ones.
+pub fn apply_recency_boost(
+    results: &mut [(Memory, Option<f32>)],
+    now: DateTime<Utc>,
+    tau_days: f32,
+) {
+    if tau_days <= 0.0 {
+        return;
+    }
+    for (m, score) in results.iter_mut() {
+        if let Some(s) = score {
+            *s *= recency_factor(m, now, tau_days);
+        }
+    }
+    results.sort_by(|a, b| match (a.1, b.1) {
+        (Some(x), Some(y)) => y.partial_cmp(&x).unwrap_or(std::cmp::Ordering::Equal),
+        (Some(_), None) => std::cmp::Ordering::Less,
+        (None, Some(_)) => std::cmp::Ordering::Greater,
+        (None, None) => std::cmp::Ordering::Equal,
+    });
+}
+
+/// Re-order results for `sort`. `Relevance` leaves the incoming order
+/// untouched (the caller already ranked by score).
+pub fn sort_results(results: &mut [(Memory, Option<f32>)], sort: RecallSort) {
+    match sort {
+        RecallSort::Relevance => {}
+        RecallSort::Recent => results.sort_by_key(|(m, _)| std::cmp::Reverse(m.updated_at)),
+        RecallSort::Weight => results.sort_by(|a, b| {
+            b.0.weight
+                .partial_cmp(&a.0.weight)
+                .unwrap_or(std::cmp::Ordering::Equal)
+        }),
+    }
+}
+
+/// Default MMR trade-off: 1.0 is pure relevance, 0.0 pure diversity.
+/// 0.5 weighs both equally (the usual MMR default).
+pub const DEFAULT_MMR_LAMBDA: f32 = 0.5;
+
+/// Similarity between two memories in `[0, 1]`: cosine over embeddings
+/// when both have one of the same dimension, word-set Jaccard over the
+/// summaries otherwise.
+pub fn memory_similarity(a: &Memory, b: &Memory) -> f32 {
+    match (&a.embedding, &b.embedding) {
+        (Some(ea), Some(eb)) if ea.len() == eb.len() && !ea.is_empty() => cosine(ea, eb).max(0.0),
+        _ => jaccard(&a.summary, &b.summary),
+    }
+}
+
+pub(crate) fn cosine(a: &[f32], b: &[f32]) -> f32 {
+    let mut dot = 0.0f32;
+    let mut na = 0.0f32;
+    let mut nb = 0.0f32;
+    for (x, y) in a.iter().zip(b) {
+        dot += x * y;
+        na += x * x;
+        nb += y * y;
+    }
+    dot / (na.sqrt() * nb.sqrt()).max(1e-10)
+}
+
+fn jaccard(a: &str, b: &str) -> f32 {
+    let a_words: HashSet<String> = a.split_whitespace().map(str::to_lowercase).collect();
+    let b_words: HashSet<String> = b.split_whitespace().map(str::to_lowercase).collect();
+    let union = a_words.union(&b_words).count();
+    if union == 0 {
+        return 0.0;
+    }
+    a_words.intersection(&b_words).count() as f32 / union as f32
+}
+
+/// Greedy maximal-marginal-relevance selection.
+///
+/// `candidates` carry a relevance score (higher is better). Each step
+/// picks the candidate maximising
+/// `lambda * relevance - (1 - lambda) * max_sim(selected)`, so a memory
+/// that mostly repeats something already picked loses to a slightly
+/// less relevant but novel one. Returns at most `limit` memories in
+/// selection order.
+pub fn mmr_select(candidates: Vec<(Memory, f32)>, limit: usize, lambda: f32) -> Vec<Memory> {
+    let lambda = lambda.clamp(0.0, 1.0);
+    let mut pool = candidates;
+    let mut selected: Vec<Memory> = Vec::with_capacity(limit.min(pool.len()));
+    while selected.len() < limit && !pool.is_empty() {
+        let mut best_idx = 0;
+        let mut best_score = f32::NEG_INFINITY;
+        for (i, (m, rel)) in pool.iter().enumerate() {
+            let redundancy = selected
+                .iter()
+                .map(|s| memory_similarity(m, s))
+                .fold(0.0f32, f32::max);
+            let score = lambda * rel - (1.0 - lambda) * redundancy;
+            if score > best_score {
+                best_score = score;
+                best_idx = i;
+            }
+        }
+        selected.push(pool.remove(best_idx).0);
+    }
+    selected
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::memory::Importance;
+    use chrono::Duration;
+
+    fn mem_aged(days: i64, weight: f32) -> Memory {
+        let mut m = Memory::new("t".into(), format!("aged {days}"), Importance::Medium);
+        m.updated_at = Utc::now() - Duration::days(days);
+        m.weight = weight;
+        m
+    }
+
+    #[test]
+    fn hybrid_score_blends_fts_and_vector() {
+        let s = HybridScore::new(Some(-2.0), 1.0 / 3.0, Some(0.9), 0.9);
+        assert!((s.combined - (0.1 + 0.63)).abs() < 1e-6);
+        assert_eq!(HybridScore::new(None, 0.0, None, 0.0).combined, 0.0);
+    }
+
+    #[test]
+    fn recency_factor_disabled_when_tau_zero() {
+        let m = mem_aged(100, 1.0);
+        assert_eq!(recency_factor(&m, Utc::now(), 0.0), 1.0);
+    }
+
+    #[test]
+    fn recency_factor_decays_with_age() {
+        let now = Utc::now();
+        let fresh = recency_factor(&mem_aged(0, 1.0), now, 30.0);
+        let old = recency_factor(&mem_aged(30, 1.0), now, 30.0);
+        assert!(fresh > 0.99);
+        assert!((old - (-1.0f32).exp()).abs() < 0.01);
+    }
+
+    #[test]
+    fn recency_boost_lets_fresh_memory_overtake_old_one() {
+        let mut results = vec![
+            (mem_aged(365, 1.0), Some(0.9)),
+            (mem_aged(1, 1.0), Some(0.7)),
+        ];
+        apply_recency_boost(&mut results, Utc::now(), 30.0);
+        assert_eq!(results[0].0.summary, "aged 1");
+    }
+
+    #[test]
+    fn sort_recent_and_weight() {
+        let mut results = vec![
+            (mem_aged(10, 0.9), None),
+            (mem_aged(1, 0.2), None),
+            (mem_aged(5, 0.5), None),
+        ];
+        sort_results(&mut results, RecallSort::Recent);
+        assert_eq!(results[0].0.summary, "aged 1");
+        sort_results(&mut results, RecallSort::Weight);
+        assert_eq!(results[0].0.summary, "aged 10");
+    }
+
+    fn mem_text(summary: &str, embedding: Option<Vec<f32>>) -> Memory {
+        let mut m = Memory::new("t".into(), summary.into(), Importance::Medium);
+        m.embedding = embedding;
+        m
+    }
+
+    #[test]
+    fn mmr_prefers_novel_over_near_duplicate() {
+        let candidates = vec![
+            (mem_text("postgres is the main database", None), 1.0),
+            (mem_text("postgres is the main database engine", None), 0.95),
+            (mem_text("deploys run through github actions", None), 0.8),
+        ];
+        let picked = mmr_select(candidates, 2, 0.5);
+        assert_eq!(picked.len(), 2);
+        assert_eq!(picked[0].summary, "postgres is the main database");
+        assert_eq!(picked[1].summary, "deploys run through github actions");
+    }
+
+    #[test]
+    fn mmr_uses_embeddings_when_present() {
+        let candidates = vec![
+            (mem_text("a", Some(vec![1.0, 0.0])), 1.0),
+            (mem_text("b", Some(vec![0.99, 0.01])), 0.9),
+            (mem_text("c", Some(vec![0.0, 1.0])), 0.6),
+        ];
+        let picked = mmr_select(candidates, 2, 0.5);
+        assert_eq!(picked[1].summary, "c");
+    }
+
+    #[test]
+    fn mmr_lambda_one_is_pure_relevance() {
+        let candidates = vec![
+            (mem_text("same words here", None), 1.0),
+            (mem_text("same words here too", None), 0.9),
+            (mem_text("different", None), 0.1),
+        ];
+        let picked = mmr_select(candidates, 2, 1.0);
+        assert_eq!(picked[1].summary, "same words here too");
+    }
+
+    #[test]
+    fn parse_sort() {
+        assert_eq!("Recent".parse::<RecallSort>(), Ok(RecallSort::Recent));
+        assert!("bogus".parse::<RecallSort>().is_err());
+    }
+}
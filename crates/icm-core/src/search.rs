@@ -0,0 +1,254 @@
+//! Unified search over memories and memoir concepts (`icm search`,
+//! `icm_search`).
+//!
+//! Memories come from hybrid search when a query embedding is available
+//! (FTS, then keywords, otherwise); concepts from FTS across every
+//! memoir, re-scored by definition similarity when an embedder is given.
+//! Each list is normalized to `0..=1` on its own (its best hit scores
+//! 1.0; unscored lists fall back to rank position), then weighted by how
+//! much the item is trusted — a memory's decay weight, a concept's
+//! confidence — so the two kinds can be ranked in one list.
+
+use std::collections::HashMap;
+
+use crate::embedder::Embedder;
+use crate::error::{IcmError, IcmResult};
+use crate::memoir::Concept;
+use crate::memoir_store::MemoirStore;
+use crate::memory::Memory;
+use crate::ranking::cosine;
+use crate::store::MemoryStore;
+
+/// What a [`SearchHit`] points at.
+#[derive(Debug, Clone)]
+pub enum SearchItem {
+    Memory(Box<Memory>),
+    Concept {
+        concept: Concept,
+        /// Name of the memoir the concept belongs to.
+        memoir: String,
+    },
+}
+
+/// One entry of a unified search, with its normalized score.
+#[derive(Debug, Clone)]
+pub struct SearchHit {
+    pub score: f32,
+    pub item: SearchItem,
+}
+
+impl SearchItem {
+    /// `"memory"` or `"concept"`, the type tag shown in results.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::Memory(_) => "memory",
+            Self::Concept { .. } => "concept",
+        }
+    }
+}
+
+/// Search memories and concepts for `query` and return one ranked list
+/// of at most `limit` hits. Backends without memoirs return memories
+/// only.
+pub fn unified_search<S: MemoryStore + MemoirStore + ?Sized>(
+    store: &S,
+    embedder: Option<&dyn Embedder>,
+    query: &str,
+    limit: usize,
+) -> IcmResult<Vec<SearchHit>> {
+    let query_emb = embedder.and_then(|e| e.embed_query(query).ok());
+
+    let memories: Vec<(Memory, Option<f32>)> = match query_emb
+        .as_deref()
+        .and_then(|emb| store.search_hybrid(query, emb, limit).ok())
+    {
+        Some(scored) => scored.into_iter().map(|(m, s)| (m, Some(s))).collect(),
+        None => {
+            let mut fts = store.search_fts(query, limit)?;
+            if fts.is_empty() {
+                let kws: Vec<&str> = query.split_whitespace().collect();
+                fts = store.search_by_keywords(&kws, limit)?;
+            }
+            fts.into_iter().map(|m| (m, None)).collect()
+        }
+    };
+
+    let concepts = match store.search_all_concepts_fts(query, limit) {
+        Ok(concepts) => concepts,
+        Err(IcmError::Unsupported(_)) => Vec::new(),
+        Err(e) => return Err(e),
+    };
+    let mut scored_concepts: Vec<(Concept, String, Option<f32>)> = Vec::new();
+    if !concepts.is_empty() {
+        let names: HashMap<String, String> = store
+            .list_memoirs()?
+            .into_iter()
+            .map(|m| (m.id, m.name))
+            .collect();
+        let sims = match (embedder, &query_emb) {
+            (Some(emb), Some(q)) => {
+                let defs: Vec<&str> = concepts.iter().map(|c| c.definition.as_str()).collect();
+                emb.embed_batch(&defs)
+                    .ok()
+                    .map(|vecs| vecs.iter().map(|v| cosine(q, v).max(0.0)).collect())
+            }
+            _ => None,
+        };
+        for (i, concept) in concepts.into_iter().enumerate() {
+            let memoir = names
+                .get(&concept.memoir_id)
+                .cloned()
+                .unwrap_or_else(|| "?".into());
+            let sim = sims.as_ref().map(|s: &Vec<f32>| s[i]);
+            scored_concepts.push((concept, memoir, sim));
+        }
+        if sims.is_some() {
+            scored_concepts.sort_by(|a, b| b.2.unwrap_or(0.0).total_cmp(&a.2.unwrap_or(0.0)));
+        }
+    }
+
+    Ok(merge_ranked(memories, scored_concepts, limit))
+}
+
+/// One line per hit: `[kind score] id topic: summary` for memories,
+/// `[kind score] memoir/name: definition` for concepts.
+pub fn render_hits(hits: &[SearchHit]) -> String {
+    let mut out = String::new();
+    for hit in hits {
+        let line = match &hit.item {
+            SearchItem::Memory(m) => format!("{} {}: {}", m.id, m.topic, m.summary),
+            SearchItem::Concept { concept, memoir } => {
+                format!("{memoir}/{}: {}", concept.name, concept.definition)
+            }
+        };
+        out.push_str(&format!(
+            "[{:<7} {:.2}] {line}\n",
+            hit.item.kind(),
+            hit.score
+        ));
+    }
+    out
+}
+
+/// Normalize each list, weight it by trust, and merge the two into one
+/// ranked list of at most `limit` hits. Lists arrive best first; `None`
+/// scores mean "ranked but unscored".
+fn merge_ranked(
+    memories: Vec<(Memory, Option<f32>)>,
+    concepts: Vec<(Concept, String, Option<f32>)>,
+    limit: usize,
+) -> Vec<SearchHit> {
+    let mem_scores = normalize(memories.iter().map(|(_, s)| *s).collect());
+    let concept_scores = normalize(concepts.iter().map(|(_, _, s)| *s).collect());
+
+    let mut hits: Vec<SearchHit> = memories
+        .into_iter()
+        .zip(mem_scores)
+        .map(|((m, _), s)| SearchHit {
+            score: s * trust(m.weight),
+            item: SearchItem::Memory(Box::new(m)),
+        })
+        .chain(
+            concepts
+                .into_iter()
+                .zip(concept_scores)
+                .map(|((concept, memoir, _), s)| SearchHit {
+                    score: s * trust(concept.confidence),
+                    item: SearchItem::Concept { concept, memoir },
+                }),
+        )
+        .collect();
+    hits.sort_by(|a, b| b.score.total_cmp(&a.score));
+    hits.truncate(limit);
+    hits
+}
+
+/// Scale scores so the best is 1.0. When any score is missing the list
+/// is ranked by position instead: 1.0 for the first, down to 0.5.
+fn normalize(scores: Vec<Option<f32>>) -> Vec<f32> {
+    let n = scores.len().max(1) as f32;
+    if scores.iter().any(Option::is_none) {
+        return (0..scores.len())
+            .map(|i| 1.0 - 0.5 * i as f32 / n)
+            .collect();
+    }
+    let max = scores
+        .iter()
+        .flatten()
+        .fold(0.0f32, |a, &b| a.max(b))
+        .max(f32::EPSILON);
+    scores.into_iter().flatten().map(|s| s / max).collect()
+}
+
+/// Map a weight or confidence onto `0.5..=1.0`: trust nudges the
+/// ranking without letting it override relevance.
+fn trust(value: f32) -> f32 {
+    0.5 + 0.5 * value.clamp(0.0, 1.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::memory::Importance;
+
+    fn memory(summary: &str, weight: f32) -> Memory {
+        let mut m = Memory::new("t".into(), summary.into(), Importance::Medium);
+        m.weight = weight;
+        m
+    }
+
+    fn concept(name: &str, confidence: f32) -> Concept {
+        let mut c = Concept::new("m".into(), name.into(), format!("{name} def"));
+        c.confidence = confidence;
+        c
+    }
+
+    #[test]
+    fn merge_interleaves_by_normalized_trusted_score() {
+        let hits = merge_ranked(
+            vec![
+                (memory("best", 1.0), Some(0.04)),
+                (memory("weak", 1.0), Some(0.01)),
+            ],
+            vec![
+                (concept("solid", 1.0), "ops".into(), None),
+                (concept("shaky", 0.2), "ops".into(), None),
+            ],
+            10,
+        );
+        let order: Vec<(&str, f32)> = hits
+            .iter()
+            .map(|h| match &h.item {
+                SearchItem::Memory(m) => (m.summary.as_str(), h.score),
+                SearchItem::Concept { concept, .. } => (concept.name.as_str(), h.score),
+            })
+            .collect();
+        // Raw memory scores (0.04) and unscored concepts share one scale.
+        assert_eq!(order[0].1, 1.0);
+        assert_eq!(order[1].1, 1.0);
+        let names: Vec<&str> = order.iter().map(|(n, _)| *n).collect();
+        assert_eq!(&names[2..], ["shaky", "weak"]);
+        assert_eq!(hits[2].item.kind(), "concept");
+
+        let text = render_hits(&hits);
+        assert!(
+            text.contains("[concept 0.45] ops/shaky: shaky def\n"),
+            "{text}"
+        );
+        assert!(text.contains("[memory  0.25] "), "{text}");
+    }
+
+    #[test]
+    fn merge_truncates_and_handles_empty_lists() {
+        assert!(merge_ranked(Vec::new(), Vec::new(), 5).is_empty());
+        let hits = merge_ranked(
+            (0..5)
+                .map(|i| (memory(&format!("m{i}"), 1.0), None))
+                .collect(),
+            Vec::new(),
+            3,
+        );
+        assert_eq!(hits.len(), 3);
+        assert!(hits[0].score > hits[2].score);
+    }
+}
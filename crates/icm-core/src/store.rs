@@ -1,5 +1,6 @@
 use crate::error::IcmResult;
 use crate::memory::{Memory, StoreStats, TopicHealth};
+use crate::ranking::HybridScore;
 
 /// Similarity score above which a new memory is considered a duplicate of an existing one.
 pub const DEDUP_SIMILARITY_THRESHOLD: f32 = 0.85;
@@ -33,12 +34,26 @@ pub trait MemoryStore {
     fn search_fts(&self, query: &str, limit: usize) -> IcmResult<Vec<Memory>>;
     fn search_by_embedding(&self, embedding: &[f32], limit: usize)
         -> IcmResult<Vec<(Memory, f32)>>;
+    /// Hybrid FTS + vector search, best first, with each score broken
+    /// down into the signals it was built from.
+    fn search_hybrid_scored(
+        &self,
+        query: &str,
+        embedding: &[f32],
+        limit: usize,
+    ) -> IcmResult<Vec<(Memory, HybridScore)>>;
     fn search_hybrid(
         &self,
         query: &str,
         embedding: &[f32],
         limit: usize,
-    ) -> IcmResult<Vec<(Memory, f32)>>;
+    ) -> IcmResult<Vec<(Memory, f32)>> {
+        Ok(self
+            .search_hybrid_scored(query, embedding, limit)?
+            .into_iter()
+            .map(|(m, s)| (m, s.combined))
+            .collect())
+    }
 
     // Lifecycle
     fn update_access(&self, id: &str) -> IcmResult<()>;
@@ -48,6 +63,9 @@ pub trait MemoryStore {
 
     // Organization
     fn list_all(&self) -> IcmResult<Vec<Memory>>;
+    /// One page of every memory, heaviest first, in a stable order so
+    /// consecutive pages neither overlap nor skip rows.
+    fn get_all(&self, limit: usize, offset: usize) -> IcmResult<Vec<Memory>>;
     fn get_by_topic(&self, topic: &str) -> IcmResult<Vec<Memory>>;
     fn list_topics(&self) -> IcmResult<Vec<(String, usize)>>;
     fn consolidate_topic(&self, topic: &str, consolidated: Memory) -> IcmResult<()>;
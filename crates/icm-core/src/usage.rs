@@ -0,0 +1,281 @@
+//! Access-pattern report (`icm stats --usage`): which memories recall
+//! keeps returning, which it never has, and which topics nobody has
+//! recalled from lately.
+//!
+//! Built from each memory's `access_count` and `last_accessed`, which
+//! recall bumps for every memory it returns. A memory that was never
+//! accessed still carries its creation time in `last_accessed`, so
+//! "last recall" only counts when `access_count > 0`.
+
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, Duration, Utc};
+
+use crate::condense::truncate_chars;
+use crate::memory::Memory;
+use crate::time_fmt::format_local;
+
+/// The fields of a memory the report needs.
+#[derive(Debug, Clone)]
+pub struct UsageEntry {
+    pub id: String,
+    pub topic: String,
+    pub summary: String,
+    pub access_count: u32,
+    pub created_at: DateTime<Utc>,
+    /// Last recall; `None` when the memory was never recalled.
+    pub last_recalled: Option<DateTime<Utc>>,
+}
+
+/// Recall activity of one topic.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TopicUsage {
+    pub topic: String,
+    pub memories: usize,
+    pub last_recalled: Option<DateTime<Utc>>,
+}
+
+/// Accumulates memories, then answers the usage questions.
+#[derive(Debug, Clone)]
+pub struct UsageReport {
+    now: DateTime<Utc>,
+    /// Recalls older than this many days don't count as recent.
+    pub window_days: i64,
+    entries: Vec<UsageEntry>,
+}
+
+impl UsageReport {
+    pub fn new(now: DateTime<Utc>, window_days: i64) -> Self {
+        Self {
+            now,
+            window_days,
+            entries: Vec::new(),
+        }
+    }
+
+    pub fn add(&mut self, memory: &Memory) {
+        self.entries.push(UsageEntry {
+            id: memory.id.clone(),
+            topic: memory.topic.clone(),
+            summary: memory.summary.clone(),
+            access_count: memory.access_count,
+            created_at: memory.created_at,
+            last_recalled: (memory.access_count > 0).then_some(memory.last_accessed),
+        });
+    }
+
+    fn cutoff(&self) -> DateTime<Utc> {
+        self.now - Duration::days(self.window_days)
+    }
+
+    /// The `n` most recalled memories, most recent recall first on ties.
+    pub fn most_recalled(&self, n: usize) -> Vec<&UsageEntry> {
+        let mut hits: Vec<&UsageEntry> =
+            self.entries.iter().filter(|e| e.access_count > 0).collect();
+        hits.sort_by(|a, b| {
+            b.access_count
+                .cmp(&a.access_count)
+                .then(b.last_recalled.cmp(&a.last_recalled))
+        });
+        hits.truncate(n);
+        hits
+    }
+
+    /// Memories never recalled since creation, oldest first.
+    pub fn never_recalled(&self) -> Vec<&UsageEntry> {
+        let mut never: Vec<&UsageEntry> = self
+            .entries
+            .iter()
+            .filter(|e| e.last_recalled.is_none())
+            .collect();
+        never.sort_by_key(|e| e.created_at);
+        never
+    }
+
+    /// Topics with no recall inside the window, least recently recalled
+    /// first (never-recalled topics lead).
+    pub fn idle_topics(&self) -> Vec<TopicUsage> {
+        let mut topics: BTreeMap<&str, TopicUsage> = BTreeMap::new();
+        for e in &self.entries {
+            let t = topics.entry(&e.topic).or_insert_with(|| TopicUsage {
+                topic: e.topic.clone(),
+                memories: 0,
+                last_recalled: None,
+            });
+            t.memories += 1;
+            t.last_recalled = t.last_recalled.max(e.last_recalled);
+        }
+        let cutoff = self.cutoff();
+        let mut idle: Vec<TopicUsage> = topics
+            .into_values()
+            .filter(|t| t.last_recalled.is_none_or(|at| at < cutoff))
+            .collect();
+        idle.sort_by_key(|t| t.last_recalled);
+        idle
+    }
+
+    /// Number of distinct topics.
+    fn topic_count(&self) -> usize {
+        let mut topics: Vec<&str> = self.entries.iter().map(|e| e.topic.as_str()).collect();
+        topics.sort_unstable();
+        topics.dedup();
+        topics.len()
+    }
+
+    /// Text report listing at most `top` memories per section.
+    pub fn render(&self, top: usize) -> String {
+        let mut out = String::new();
+        if self.entries.is_empty() {
+            out.push_str("No memories.\n");
+            return out;
+        }
+
+        out.push_str("Most recalled:\n");
+        let most = self.most_recalled(top);
+        if most.is_empty() {
+            out.push_str("  (nothing recalled yet)\n");
+        }
+        for e in most {
+            let last = e
+                .last_recalled
+                .map(|at| format_local(&at, "%Y-%m-%d"))
+                .unwrap_or_default();
+            out.push_str(&format!(
+                "  {:>5}x  {} [{}] {} (last {last})\n",
+                e.access_count,
+                e.id,
+                e.topic,
+                truncate_chars(&e.summary, SUMMARY_CHARS)
+            ));
+        }
+
+        let never = self.never_recalled();
+        let cutoff = self.cutoff();
+        let old = never.iter().filter(|e| e.created_at < cutoff).count();
+        out.push_str(&format!(
+            "\nNever recalled: {} of {} ({old} older than {} days)\n",
+            never.len(),
+            self.entries.len(),
+            self.window_days
+        ));
+        for e in never.iter().take(top) {
+            out.push_str(&format!(
+                "  {} [{}] {} (created {})\n",
+                e.id,
+                e.topic,
+                truncate_chars(&e.summary, SUMMARY_CHARS),
+                format_local(&e.created_at, "%Y-%m-%d")
+            ));
+        }
+        if never.len() > top {
+            out.push_str(&format!("  ... {} more\n", never.len() - top));
+        }
+
+        let idle = self.idle_topics();
+        out.push_str(&format!(
+            "\nTopics not recalled in the last {} days: {} of {}\n",
+            self.window_days,
+            idle.len(),
+            self.topic_count()
+        ));
+        if !idle.is_empty() {
+            let width = idle.iter().map(|t| t.topic.len()).max().unwrap_or(0).max(5);
+            out.push_str(&format!("  {:<width$}  memories  last recall\n", "topic"));
+            for t in &idle {
+                let last = t
+                    .last_recalled
+                    .map(|at| format_local(&at, "%Y-%m-%d"))
+                    .unwrap_or_else(|| "never".into());
+                out.push_str(&format!(
+                    "  {:<width$}  {:>8}  {last}\n",
+                    t.topic, t.memories
+                ));
+            }
+        }
+        out
+    }
+}
+
+/// Summaries are cut to this many characters in the report.
+const SUMMARY_CHARS: usize = 60;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::memory::Importance;
+
+    fn memory(
+        topic: &str,
+        summary: &str,
+        age_days: i64,
+        recalls: u32,
+        recalled_days_ago: i64,
+    ) -> Memory {
+        let now = Utc::now();
+        let mut m = Memory::new(topic.into(), summary.into(), Importance::Medium);
+        m.created_at = now - Duration::days(age_days);
+        m.access_count = recalls;
+        m.last_accessed = if recalls > 0 {
+            now - Duration::days(recalled_days_ago)
+        } else {
+            m.created_at
+        };
+        m
+    }
+
+    fn report() -> UsageReport {
+        let mut r = UsageReport::new(Utc::now(), 30);
+        r.add(&memory("api", "hot", 100, 12, 1));
+        r.add(&memory("api", "warm", 100, 3, 2));
+        r.add(&memory("api", "cold", 90, 0, 0));
+        r.add(&memory("old", "stale", 200, 5, 60));
+        r.add(&memory("old", "ancient", 300, 0, 0));
+        r.add(&memory("new", "fresh", 1, 0, 0));
+        r
+    }
+
+    #[test]
+    fn ranks_most_and_never_recalled() {
+        let r = report();
+        let most: Vec<&str> = r
+            .most_recalled(2)
+            .iter()
+            .map(|e| e.summary.as_str())
+            .collect();
+        assert_eq!(most, ["hot", "stale"]);
+        let never: Vec<&str> = r
+            .never_recalled()
+            .iter()
+            .map(|e| e.summary.as_str())
+            .collect();
+        assert_eq!(never, ["ancient", "cold", "fresh"]);
+    }
+
+    #[test]
+    fn idle_topics_lead_with_never_recalled() {
+        let r = report();
+        let idle = r.idle_topics();
+        let idle: Vec<(&str, usize, bool)> = idle
+            .iter()
+            .map(|t| (t.topic.as_str(), t.memories, t.last_recalled.is_some()))
+            .collect();
+        assert_eq!(idle, [("new", 1, false), ("old", 2, true)]);
+    }
+
+    #[test]
+    fn render_summarizes_each_section() {
+        let text = report().render(2);
+        assert!(text.contains("   12x  "), "{text}");
+        assert!(
+            text.contains("Never recalled: 3 of 6 (2 older than 30 days)\n"),
+            "{text}"
+        );
+        assert!(text.contains("  ... 1 more\n"), "{text}");
+        assert!(
+            text.contains("Topics not recalled in the last 30 days: 2 of 3\n"),
+            "{text}"
+        );
+        assert!(text.contains("  new           1  never\n"), "{text}");
+        assert_eq!(UsageReport::new(Utc::now(), 30).render(5), "No memories.\n");
+    }
+}
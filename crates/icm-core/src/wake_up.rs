@@ -31,6 +31,9 @@ pub struct WakeUpOptions<'a> {
     pub format: WakeUpFormat,
     /// Include preference/identity memories regardless of project filter.
     pub include_preferences: bool,
+    /// Most memories in the pack, highest-scored first. `0` leaves the
+    /// token budget as the only cap.
+    pub max_items: usize,
 }
 
 impl Default for WakeUpOptions<'_> {
@@ -40,6 +43,7 @@ impl Default for WakeUpOptions<'_> {
             max_tokens: 500,
             format: WakeUpFormat::Markdown,
             include_preferences: true,
+            max_items: 0,
         }
     }
 }
@@ -107,12 +111,12 @@ pub fn build_wake_up_from_memories(memories: Vec<Memory>, opts: &WakeUpOptions<'
     let mut candidates: Vec<ScoredMemory> = memories
         .into_iter()
         .filter(|m| {
-            let is_pref = opts.include_preferences && is_preference_topic(&m.topic);
+            let is_pref = opts.include_preferences && m.is_preference();
             // Critical/high are always eligible; preferences always eligible
             // when the option is set (they may be medium-importance).
             matches!(m.importance, Importance::Critical | Importance::High) || is_pref
         })
-        .filter(|m| project_matches(&m.topic, opts.project))
+        .filter(|m| m.is_preference() || project_matches(&m.topic, opts.project))
         .map(|m| {
             let score = compute_score(&m, now);
             let category = categorize(&m);
@@ -134,6 +138,10 @@ pub fn build_wake_up_from_memories(memories: Vec<Memory>, opts: &WakeUpOptions<'
             .then_with(|| a.memory.id.cmp(&b.memory.id))
     });
 
+    if opts.max_items > 0 {
+        candidates.truncate(opts.max_items);
+    }
+
     // Token budget: 1 token ≈ 4 characters (rough approximation).
     let max_chars = opts.max_tokens.saturating_mul(4);
     let selected = truncate_by_budget(candidates, max_chars);
@@ -216,7 +224,7 @@ fn categorize(m: &Memory) -> Category {
     let t = m.topic.to_lowercase();
     let s = m.summary.to_lowercase();
 
-    if is_preference_topic(&m.topic) {
+    if m.is_preference() {
         return Category::Identity;
     }
     if t.contains("decision") || s.contains("decided ") || s.contains("chose ") {
@@ -444,6 +452,21 @@ mod tests {
         assert!(!pack.contains("OTHER: Postgres"));
     }
 
+    #[test]
+    fn max_items_keeps_highest_scored() {
+        let memories = vec![
+            mem("decisions", "Chose SQLite", Importance::Critical),
+            mem("decisions", "Chose clap", Importance::High),
+        ];
+        let opts = WakeUpOptions {
+            max_items: 1,
+            ..Default::default()
+        };
+        let pack = build_wake_up_from_memories(memories, &opts);
+        assert!(pack.contains("Chose SQLite"));
+        assert!(!pack.contains("Chose clap"));
+    }
+
     #[test]
     fn preferences_are_global_under_project_filter() {
         let memories = vec![
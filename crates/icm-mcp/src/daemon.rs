@@ -0,0 +1,169 @@
+//! One MCP server shared by every client on the machine.
+//!
+//! `icm serve --daemon` listens on a Unix socket and serves each
+//! connection as its own MCP session, against one store and one loaded
+//! model. `icm serve --connect` is the stdio shim a client spawns
+//! instead: it relays its stdin to the daemon and the daemon's replies
+//! to its stdout.
+
+use std::fs;
+use std::io::{self, Read, Write};
+use std::net::Shutdown;
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::Path;
+use std::thread;
+
+use anyhow::Context;
+
+use icm_core::Embedder;
+use icm_store::Store;
+
+use crate::server::{self, ServerOptions};
+
+/// Serve MCP clients connecting to `socket` until idle shutdown, opening
+/// the store with `open` on the first tool call. The socket file is
+/// removed on exit.
+pub fn run_daemon(
+    socket: &Path,
+    open: &dyn Fn() -> anyhow::Result<Store>,
+    embedder: Option<&dyn Embedder>,
+    server_opts: ServerOptions,
+) -> anyhow::Result<()> {
+    let listener = bind(socket)?;
+    let result = server::serve_listener(listener, open, embedder, server_opts);
+    let _ = fs::remove_file(socket);
+    result
+}
+
+/// Bind `socket`, replacing a stale file left by a daemon that did not
+/// exit cleanly. Fails if a live daemon already answers there.
+fn bind(socket: &Path) -> anyhow::Result<UnixListener> {
+    match UnixListener::bind(socket) {
+        Ok(listener) => Ok(listener),
+        Err(e) if e.kind() == io::ErrorKind::AddrInUse => {
+            if UnixStream::connect(socket).is_ok() {
+                anyhow::bail!("an icm daemon is already listening on {}", socket.display());
+            }
+            fs::remove_file(socket)
+                .with_context(|| format!("failed to remove stale socket {}", socket.display()))?;
+            UnixListener::bind(socket)
+                .with_context(|| format!("failed to listen on {}", socket.display()))
+        }
+        Err(e) => Err(e).with_context(|| format!("failed to listen on {}", socket.display())),
+    }
+}
+
+/// Relay stdin to the daemon behind `stream` and its replies to stdout.
+/// Returns once the daemon closes the connection, which it does after
+/// stdin reaches EOF.
+pub fn run_proxy(stream: UnixStream) -> anyhow::Result<()> {
+    proxy(stream, io::stdin(), &mut io::stdout())
+}
+
+fn proxy(
+    stream: UnixStream,
+    mut input: impl Read + Send + 'static,
+    output: &mut impl Write,
+) -> anyhow::Result<()> {
+    let mut upstream = stream.try_clone()?;
+    // Detached: a read blocked on stdin must not keep the shim alive
+    // once the daemon is gone.
+    thread::spawn(move || {
+        let _ = io::copy(&mut input, &mut upstream);
+        let _ = upstream.shutdown(Shutdown::Write);
+    });
+    let mut downstream = stream;
+    let mut buf = [0u8; 8192];
+    loop {
+        let n = downstream.read(&mut buf)?;
+        if n == 0 {
+            return Ok(());
+        }
+        // Flushed per read so each reply reaches the client at once.
+        output.write_all(&buf[..n])?;
+        output.flush()?;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader};
+
+    #[test]
+    fn stale_socket_is_replaced_and_live_one_refused() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("icm.sock");
+        drop(UnixListener::bind(&path).unwrap());
+        assert!(path.exists());
+
+        let live = bind(&path).unwrap();
+        let err = bind(&path).unwrap_err();
+        assert!(err.to_string().contains("already listening"), "{err}");
+        drop(live);
+    }
+
+    #[test]
+    fn daemon_serves_concurrent_clients_from_one_store() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("icm.sock");
+        let socket = path.clone();
+        let daemon = thread::spawn(move || {
+            let opts = ServerOptions {
+                idle_shutdown: Some(std::time::Duration::from_millis(200)),
+                ..Default::default()
+            };
+            run_daemon(&socket, &|| Ok(Store::in_memory()?), None, opts)
+        });
+        while UnixStream::connect(&path).is_err() {
+            thread::sleep(std::time::Duration::from_millis(10));
+        }
+
+        let call = |name: &str, args: &str| {
+            format!(
+                r#"{{"jsonrpc":"2.0","id":1,"method":"tools/call","params":{{"name":"{name}","arguments":{args}}}}}"#
+            )
+        };
+        let mut a = UnixStream::connect(&path).unwrap();
+        let mut b = UnixStream::connect(&path).unwrap();
+        let mut a_lines = BufReader::new(a.try_clone().unwrap()).lines();
+        let mut b_lines = BufReader::new(b.try_clone().unwrap()).lines();
+        let init = r#"{"jsonrpc":"2.0","id":0,"method":"initialize","params":{}}"#;
+        writeln!(b, "{}", call("icm_memory_stats", "{}")).unwrap();
+        assert!(b_lines.next().unwrap().unwrap().contains("not initialized"));
+        for (out, lines) in [(&mut a, &mut a_lines), (&mut b, &mut b_lines)] {
+            writeln!(out, "{init}").unwrap();
+            assert!(lines.next().unwrap().unwrap().contains("protocolVersion"));
+        }
+        let store = call(
+            "icm_memory_store",
+            r#"{"topic":"shared","content":"one daemon for every editor"}"#,
+        );
+        writeln!(a, "{store}").unwrap();
+        assert!(a_lines.next().unwrap().unwrap().contains("\"result\""));
+        writeln!(b, "{}", call("icm_memory_stats", "{}")).unwrap();
+        assert!(b_lines.next().unwrap().unwrap().contains("Memories: 1"));
+
+        drop((a, b, a_lines, b_lines));
+        daemon.join().unwrap().unwrap();
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn proxy_relays_both_ways_until_the_daemon_closes() {
+        let (shim_end, daemon_end) = UnixStream::pair().unwrap();
+        let daemon = thread::spawn(move || {
+            let mut lines = BufReader::new(daemon_end.try_clone().unwrap()).lines();
+            let mut out = daemon_end;
+            let request = lines.next().unwrap().unwrap();
+            writeln!(out, "reply to {request}").unwrap();
+            // EOF from the shim once its stdin is exhausted.
+            assert!(lines.next().is_none());
+        });
+
+        let mut output = Vec::new();
+        proxy(shim_end, io::Cursor::new(b"ping\n".to_vec()), &mut output).unwrap();
+        daemon.join().unwrap();
+        assert_eq!(String::from_utf8(output).unwrap(), "reply to ping\n");
+    }
+}
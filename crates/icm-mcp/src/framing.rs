@@ -0,0 +1,168 @@
+//! Reassembly of JSON-RPC messages from a line-oriented stream.
+//!
+//! Clients are meant to send one message per line, but long messages
+//! sometimes arrive split across lines, and some clients interleave log
+//! output with the protocol. Lines are buffered until they hold a complete
+//! JSON value; text outside any value is dropped as noise, and a broken
+//! frame is abandoned at the next line so the messages after it still
+//! parse.
+
+use serde::de::IgnoredAny;
+use tracing::debug;
+
+use crate::server::MAX_LINE_LEN;
+
+/// One unit of input for the request loop.
+#[derive(Debug, PartialEq)]
+pub enum Frame {
+    /// The text of one complete JSON value.
+    Message(String),
+    /// Input that started like a message but is not valid JSON.
+    Invalid(String),
+    /// A message still incomplete at this many bytes; it is discarded.
+    TooLong(usize),
+}
+
+#[derive(Default)]
+pub struct Framer {
+    /// Start of a message whose end has not arrived yet.
+    partial: String,
+    /// Offsets in `partial` where a new line began. Lines are joined
+    /// without a separator, since a split can fall inside a string; a
+    /// broken frame is abandoned at the next of these.
+    breaks: Vec<usize>,
+    /// Whether `partial` followed log noise on its line.
+    partial_after_noise: bool,
+}
+
+impl Framer {
+    /// Feed one line (without its terminator) and take every frame it
+    /// completes.
+    pub fn push(&mut self, line: &str) -> Vec<Frame> {
+        if !self.partial.is_empty() {
+            self.breaks.push(self.partial.len());
+        }
+        self.partial.push_str(line);
+        if self.partial.len() > MAX_LINE_LEN {
+            let len = self.partial.len();
+            self.partial.clear();
+            self.breaks.clear();
+            return vec![Frame::TooLong(len)];
+        }
+
+        let buf = std::mem::take(&mut self.partial);
+        let breaks = std::mem::take(&mut self.breaks);
+        let mut after_noise = std::mem::take(&mut self.partial_after_noise);
+        let mut frames = Vec::new();
+        let mut pos = 0;
+        loop {
+            let text = buf[pos..].trim_start();
+            // Anything before the first `{` or `[` is log noise.
+            let Some(start) = text.find(['{', '[']) else {
+                skip_noise(text);
+                break;
+            };
+            skip_noise(&text[..start]);
+            let behind_noise = start > 0 || std::mem::take(&mut after_noise);
+            let at = buf.len() - text.len() + start;
+            let next_break = breaks.iter().copied().find(|b| *b > at);
+            let mut values =
+                serde_json::Deserializer::from_str(&buf[at..]).into_iter::<IgnoredAny>();
+            match values.next() {
+                Some(Ok(_)) => {
+                    let end = at + values.byte_offset();
+                    frames.push(Frame::Message(buf[at..end].to_string()));
+                    pos = end;
+                }
+                Some(Err(e)) if e.is_eof() => {
+                    self.partial = buf[at..].to_string();
+                    self.breaks = breaks.iter().filter(|b| **b > at).map(|b| b - at).collect();
+                    self.partial_after_noise = behind_noise;
+                    break;
+                }
+                Some(Err(e)) => {
+                    // Drop the broken frame up to the end of its first line
+                    // and resynchronize on whatever follows. A frame behind
+                    // log noise was most likely noise itself.
+                    if behind_noise {
+                        skip_noise(&buf[at..next_break.unwrap_or(buf.len())]);
+                    } else {
+                        frames.push(Frame::Invalid(e.to_string()));
+                    }
+                    match next_break {
+                        Some(b) => pos = b,
+                        None => break,
+                    }
+                }
+                None => break,
+            }
+        }
+        frames
+    }
+}
+
+fn skip_noise(text: &str) {
+    let text = text.trim();
+    if !text.is_empty() {
+        debug!("ignoring non-JSON input: {text}");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message(text: &str) -> Frame {
+        Frame::Message(text.to_string())
+    }
+
+    #[test]
+    fn split_message_is_reassembled() {
+        let mut framer = Framer::default();
+        assert!(framer.push(r#"{"jsonrpc":"2.0","#).is_empty());
+        assert!(framer.push(r#""id":2,"method":"pi"#).is_empty());
+        // Split inside a string: rejoined without a separator.
+        assert_eq!(
+            framer.push(r#"ng"}"#),
+            vec![message(r#"{"jsonrpc":"2.0","id":2,"method":"ping"}"#)]
+        );
+        assert!(framer.push("").is_empty());
+    }
+
+    #[test]
+    fn log_noise_is_dropped() {
+        let mut framer = Framer::default();
+        assert!(framer.push("INFO starting up").is_empty());
+        assert_eq!(
+            framer.push(r#"debug: {"id":1,"method":"ping"} trailing"#),
+            vec![message(r#"{"id":1,"method":"ping"}"#)]
+        );
+        // Braces inside a log line are not a message, even unclosed.
+        assert!(framer.push("WARN retry {attempt 2}").is_empty());
+        assert!(framer.push("WARN got {").is_empty());
+        assert_eq!(framer.push(r#"{"id":2}"#), vec![message(r#"{"id":2}"#)]);
+    }
+
+    #[test]
+    fn several_messages_on_one_line() {
+        let mut framer = Framer::default();
+        assert_eq!(
+            framer.push(r#"{"id":1} {"id":2}"#),
+            vec![message(r#"{"id":1}"#), message(r#"{"id":2}"#)]
+        );
+    }
+
+    #[test]
+    fn broken_frame_does_not_swallow_the_next_message() {
+        let mut framer = Framer::default();
+        // Truncated, then a fresh message on the next line.
+        assert!(framer.push(r#"{"id":1,"method":"pi"#).is_empty());
+        let frames = framer.push(r#"{"id":2,"method":"ping"}"#);
+        assert!(matches!(frames[0], Frame::Invalid(_)), "{frames:?}");
+        assert_eq!(frames[1], message(r#"{"id":2,"method":"ping"}"#));
+
+        let frames = framer.push("{not json}");
+        assert!(matches!(frames[..], [Frame::Invalid(_)]), "{frames:?}");
+        assert_eq!(framer.push(r#"{"id":3}"#), vec![message(r#"{"id":3}"#)]);
+    }
+}
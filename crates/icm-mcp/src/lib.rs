@@ -1,5 +1,13 @@
+#[cfg(unix)]
+mod daemon;
+mod framing;
 pub mod protocol;
+mod recall_cache;
+mod schema;
 pub mod server;
 pub mod tools;
 
-pub use server::run_server;
+#[cfg(unix)]
+pub use daemon::{run_daemon, run_proxy};
+pub use server::{run_server, run_server_lazy, run_server_with_opts, ServerOptions};
+pub use tools::{CallContext, CancelToken, ToolOptions};
@@ -1,5 +1,6 @@
+use icm_core::IcmError;
 use serde::{Deserialize, Serialize};
-use serde_json::Value;
+use serde_json::{json, Value};
 
 // ---------------------------------------------------------------------------
 // JSON-RPC 2.0 message types
@@ -59,14 +60,18 @@ impl JsonRpcResponse {
 // MCP tool result
 // ---------------------------------------------------------------------------
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Clone, Serialize)]
 pub struct ToolResult {
     pub content: Vec<TextContent>,
     #[serde(rename = "isError", skip_serializing_if = "std::ops::Not::not")]
     pub is_error: bool,
+    /// `{"icm/errorCode": …}` on results built from an [`IcmError`], so
+    /// clients can branch on the kind of failure.
+    #[serde(rename = "_meta", skip_serializing_if = "Option::is_none")]
+    pub meta: Option<Value>,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Clone, Serialize)]
 pub struct TextContent {
     #[serde(rename = "type")]
     pub content_type: String,
@@ -81,6 +86,7 @@ impl ToolResult {
                 text,
             }],
             is_error: false,
+            meta: None,
         }
     }
 
@@ -91,9 +97,28 @@ impl ToolResult {
                 text,
             }],
             is_error: true,
+            meta: None,
         }
     }
 
+    /// Error result for a failed store call: `"{context}: {err}"`, tagged
+    /// with the error's stable code.
+    pub fn icm_error(context: &str, err: &IcmError) -> Self {
+        let mut result = Self::error(format!("{context}: {err}"));
+        result.meta = Some(json!({ "icm/errorCode": err.code() }));
+        result
+    }
+
+    /// Mark a result served without semantic search: `"icm/degraded":
+    /// true` in `_meta`, plus the notice line so the model sees it too.
+    pub fn mark_degraded(&mut self, reason: &str) {
+        let meta = self.meta.get_or_insert_with(|| json!({}));
+        if let Some(obj) = meta.as_object_mut() {
+            obj.insert("icm/degraded".into(), Value::Bool(true));
+        }
+        self.append_hint(&format!("\n({})", icm_core::degraded_notice(reason)));
+    }
+
     /// Append a hint to the last text content block.
     pub fn append_hint(&mut self, hint: &str) {
         if let Some(last) = self.content.last_mut() {
@@ -123,6 +148,19 @@ mod tests {
         assert_eq!(result.content[0].text, "boom");
     }
 
+    #[test]
+    fn test_tool_result_icm_error_carries_code() {
+        let result = ToolResult::icm_error("failed", &IcmError::Busy("locked".into()));
+        assert!(result.is_error);
+        assert_eq!(result.content[0].text, "failed: database is busy: locked");
+        let v = serde_json::to_value(&result).unwrap();
+        assert_eq!(v["_meta"]["icm/errorCode"], "busy");
+        assert!(serde_json::to_value(ToolResult::text("ok".into()))
+            .unwrap()
+            .get("_meta")
+            .is_none());
+    }
+
     #[test]
     fn test_append_hint() {
         let mut result = ToolResult::text("original".into());
@@ -135,6 +173,7 @@ mod tests {
         let mut result = ToolResult {
             content: vec![],
             is_error: false,
+            meta: None,
         };
         result.append_hint("[hint]");
         assert!(result.content.is_empty());
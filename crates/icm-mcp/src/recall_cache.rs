@@ -0,0 +1,212 @@
+//! In-process cache of recall results for the MCP server.
+//!
+//! Agents often repeat the same `icm_memory_recall` within a session.
+//! Results are kept per (tool, arguments) for a short TTL, least recently
+//! used first out, and the whole cache is dropped after any tool call
+//! that may write. Writes from other processes (hooks, the CLI) are only
+//! picked up once an entry expires.
+
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+use serde_json::Value;
+
+use crate::protocol::ToolResult;
+
+/// Tools whose results are cached.
+const CACHED_TOOLS: &[&str] = &["icm_memory_recall", "icm_search"];
+
+/// Tools that never write, so calling them keeps the cache. Any other
+/// tool clears it.
+const READ_ONLY_TOOLS: &[&str] = &[
+    "icm_memory_recall",
+    "icm_search",
+    "icm_memory_recall_vector",
+    "icm_memory_list_topics",
+    "icm_list_recent",
+    "icm_memory_links",
+    "icm_memory_stats",
+    "icm_pending_count",
+    "icm_memory_health",
+    "icm_memoir_list",
+    "icm_memoir_show",
+    "icm_memoir_search",
+    "icm_memoir_search_all",
+    "icm_memoir_inspect",
+    "icm_memoir_export",
+    "icm_feedback_search",
+    "icm_feedback_stats",
+    "icm_transcript_search",
+    "icm_transcript_show",
+    "icm_transcript_stats",
+    "icm_wake_up",
+];
+
+/// Entries kept before the least recently used one is evicted.
+pub const RECALL_CACHE_CAPACITY: usize = 64;
+
+pub struct RecallCache {
+    ttl: Duration,
+    capacity: usize,
+    /// Least recently used first.
+    entries: VecDeque<(String, Instant, ToolResult)>,
+}
+
+impl RecallCache {
+    pub fn new(ttl: Duration, capacity: usize) -> Self {
+        Self {
+            ttl,
+            capacity,
+            entries: VecDeque::new(),
+        }
+    }
+
+    /// The cached result of `tool` called with `args`, if still fresh.
+    /// A hit reruns none of the tool's side effects (access counts,
+    /// auto-decay).
+    pub fn get(&mut self, tool: &str, args: &Value) -> Option<ToolResult> {
+        if !CACHED_TOOLS.contains(&tool) {
+            return None;
+        }
+        let key = cache_key(tool, args);
+        let pos = self.entries.iter().position(|(k, _, _)| *k == key)?;
+        let entry = self.entries.remove(pos)?;
+        if entry.1.elapsed() >= self.ttl {
+            return None;
+        }
+        let result = entry.2.clone();
+        self.entries.push_back(entry);
+        Some(result)
+    }
+
+    /// Drop every entry, after a write the cache cannot see (the
+    /// server's decay pass).
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    /// Record the result of a call: cache it if `tool` is cached and the
+    /// call succeeded, or drop every entry if `tool` may have written.
+    pub fn record(&mut self, tool: &str, args: &Value, result: &ToolResult) {
+        if !READ_ONLY_TOOLS.contains(&tool) {
+            self.entries.clear();
+            return;
+        }
+        if !CACHED_TOOLS.contains(&tool) || result.is_error || self.capacity == 0 {
+            return;
+        }
+        let key = cache_key(tool, args);
+        self.entries.retain(|(k, _, _)| *k != key);
+        if self.entries.len() >= self.capacity {
+            self.entries.pop_front();
+        }
+        self.entries
+            .push_back((key, Instant::now(), result.clone()));
+    }
+}
+
+/// `tool` plus `args` with object keys sorted, so argument order does
+/// not split entries.
+fn cache_key(tool: &str, args: &Value) -> String {
+    fn canonical(v: &Value, out: &mut String) {
+        match v {
+            Value::Object(map) => {
+                let mut keys: Vec<&String> = map.keys().collect();
+                keys.sort();
+                out.push('{');
+                for k in keys {
+                    out.push_str(&Value::String(k.clone()).to_string());
+                    out.push(':');
+                    canonical(&map[k], out);
+                    out.push(',');
+                }
+                out.push('}');
+            }
+            Value::Array(items) => {
+                out.push('[');
+                for item in items {
+                    canonical(item, out);
+                    out.push(',');
+                }
+                out.push(']');
+            }
+            other => out.push_str(&other.to_string()),
+        }
+    }
+    let mut key = format!("{tool}:");
+    canonical(args, &mut key);
+    key
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn text(r: &ToolResult) -> &str {
+        &r.content[0].text
+    }
+
+    #[test]
+    fn hit_ignores_argument_order_and_writes_invalidate() {
+        let mut cache = RecallCache::new(Duration::from_secs(60), 8);
+        let args = json!({"query": "auth", "limit": 5});
+        assert!(cache.get("icm_memory_recall", &args).is_none());
+        cache.record("icm_memory_recall", &args, &ToolResult::text("one".into()));
+
+        let reordered = json!({"limit": 5, "query": "auth"});
+        let hit = cache.get("icm_memory_recall", &reordered).unwrap();
+        assert_eq!(text(&hit), "one");
+        assert!(cache
+            .get("icm_memory_recall", &json!({"query": "auth", "limit": 6}))
+            .is_none());
+
+        // Reads keep the cache; a write clears it.
+        cache.record(
+            "icm_memory_stats",
+            &json!({}),
+            &ToolResult::text("s".into()),
+        );
+        assert!(cache.get("icm_memory_recall", &args).is_some());
+        cache.record(
+            "icm_memory_store",
+            &json!({}),
+            &ToolResult::text("ok".into()),
+        );
+        assert!(cache.get("icm_memory_recall", &args).is_none());
+    }
+
+    #[test]
+    fn errors_are_not_cached_and_entries_expire() {
+        let mut cache = RecallCache::new(Duration::ZERO, 8);
+        let args = json!({"query": "auth"});
+        cache.record("icm_memory_recall", &args, &ToolResult::text("one".into()));
+        assert!(cache.get("icm_memory_recall", &args).is_none());
+
+        let mut cache = RecallCache::new(Duration::from_secs(60), 8);
+        cache.record(
+            "icm_memory_recall",
+            &args,
+            &ToolResult::error("boom".into()),
+        );
+        assert!(cache.get("icm_memory_recall", &args).is_none());
+    }
+
+    #[test]
+    fn evicts_least_recently_used() {
+        let mut cache = RecallCache::new(Duration::from_secs(60), 2);
+        let (a, b, c) = (
+            json!({"query": "a"}),
+            json!({"query": "b"}),
+            json!({"query": "c"}),
+        );
+        cache.record("icm_search", &a, &ToolResult::text("a".into()));
+        cache.record("icm_search", &b, &ToolResult::text("b".into()));
+        // Touch `a`, so `b` is the one evicted.
+        assert!(cache.get("icm_search", &a).is_some());
+        cache.record("icm_search", &c, &ToolResult::text("c".into()));
+        assert!(cache.get("icm_search", &a).is_some());
+        assert!(cache.get("icm_search", &b).is_none());
+        assert!(cache.get("icm_search", &c).is_some());
+    }
+}
@@ -0,0 +1,185 @@
+//! Validation of tool-call arguments against each tool's `inputSchema`.
+//!
+//! Handlers read arguments leniently (a mistyped `limit` silently becomes
+//! the default), so mistakes are caught here instead, before dispatch.
+//! Covered: `type`, `required`, `enum` and array `items`. Numeric bounds
+//! are left to the handlers, which clamp. A `null` value counts as absent.
+
+use serde_json::Value;
+
+/// One argument that does not match the schema.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FieldError {
+    /// Path to the argument, e.g. `keywords[1]`; empty for the whole object.
+    pub field: String,
+    pub message: String,
+}
+
+/// Every mismatch between `args` and `schema`, in schema order.
+pub fn validate(schema: &Value, args: &Value) -> Vec<FieldError> {
+    let mut errors = Vec::new();
+    check(schema, args, "", &mut errors);
+    errors
+}
+
+fn check(schema: &Value, value: &Value, path: &str, errors: &mut Vec<FieldError>) {
+    let mut fail = |message: String| {
+        errors.push(FieldError {
+            field: path.to_string(),
+            message,
+        })
+    };
+    if let Some(expected) = schema.get("type").and_then(Value::as_str) {
+        if !has_type(value, expected) {
+            fail(format!("expected {expected}, got {}", describe(value)));
+            return;
+        }
+    }
+    if let Some(allowed) = schema.get("enum").and_then(Value::as_array) {
+        if !allowed.contains(value) {
+            let allowed: Vec<String> = allowed.iter().map(Value::to_string).collect();
+            fail(format!(
+                "expected one of {}, got {value}",
+                allowed.join(", ")
+            ));
+            return;
+        }
+    }
+    match value {
+        Value::Array(items) => {
+            if let Some(item_schema) = schema.get("items") {
+                for (i, item) in items.iter().enumerate() {
+                    check(item_schema, item, &format!("{path}[{i}]"), errors);
+                }
+            }
+        }
+        Value::Object(map) => {
+            let required = schema.get("required").and_then(Value::as_array);
+            for name in required.into_iter().flatten().filter_map(Value::as_str) {
+                if map.get(name).is_none_or(Value::is_null) {
+                    errors.push(FieldError {
+                        field: join(path, name),
+                        message: "required".into(),
+                    });
+                }
+            }
+            let properties = schema.get("properties").and_then(Value::as_object);
+            for (name, prop_schema) in properties.into_iter().flatten() {
+                match map.get(name) {
+                    None | Some(Value::Null) => {}
+                    Some(prop) => check(prop_schema, prop, &join(path, name), errors),
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+fn has_type(value: &Value, expected: &str) -> bool {
+    match expected {
+        "string" => value.is_string(),
+        "integer" => {
+            value.is_i64() || value.is_u64() || value.as_f64().is_some_and(|f| f.fract() == 0.0)
+        }
+        "number" => value.is_number(),
+        "boolean" => value.is_boolean(),
+        "array" => value.is_array(),
+        "object" => value.is_object(),
+        // Unknown schema types are not ours to enforce.
+        _ => true,
+    }
+}
+
+/// `value`'s JSON type, with the value itself when short.
+fn describe(value: &Value) -> String {
+    let kind = match value {
+        Value::Null => return "null".into(),
+        Value::Bool(_) => "boolean",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => return "array".into(),
+        Value::Object(_) => return "object".into(),
+    };
+    let text = value.to_string();
+    if text.chars().count() <= 40 {
+        format!("{kind} {text}")
+    } else {
+        kind.to_string()
+    }
+}
+
+fn join(path: &str, name: &str) -> String {
+    if path.is_empty() {
+        name.to_string()
+    } else {
+        format!("{path}.{name}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn schema() -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "query": { "type": "string" },
+                "limit": { "type": "integer", "minimum": 1, "maximum": 20 },
+                "importance": { "type": "string", "enum": ["high", "low"] },
+                "keywords": { "type": "array", "items": { "type": "string" } }
+            },
+            "required": ["query"]
+        })
+    }
+
+    fn fields(args: Value) -> Vec<(String, String)> {
+        validate(&schema(), &args)
+            .into_iter()
+            .map(|e| (e.field, e.message))
+            .collect()
+    }
+
+    #[test]
+    fn valid_arguments_pass() {
+        assert!(fields(json!({"query": "auth", "limit": 5, "importance": "high"})).is_empty());
+        // Bounds are the handler's business; nulls count as absent;
+        // unknown arguments are tolerated.
+        assert!(fields(json!({"query": "q", "limit": 500, "keywords": null, "x": 1})).is_empty());
+        assert!(fields(json!({"query": "q", "limit": 5.0})).is_empty());
+    }
+
+    #[test]
+    fn reports_each_field() {
+        assert_eq!(
+            fields(json!({
+                "limit": "5",
+                "importance": "urgent",
+                "keywords": ["ok", 3]
+            })),
+            vec![
+                ("query".to_string(), "required".to_string()),
+                (
+                    "limit".to_string(),
+                    "expected integer, got string \"5\"".to_string()
+                ),
+                (
+                    "importance".to_string(),
+                    "expected one of \"high\", \"low\", got \"urgent\"".to_string()
+                ),
+                (
+                    "keywords[1]".to_string(),
+                    "expected string, got number 3".to_string()
+                ),
+            ]
+        );
+        assert_eq!(
+            fields(json!("query")),
+            vec![(
+                String::new(),
+                "expected object, got string \"query\"".to_string()
+            )]
+        );
+    }
+}
@@ -1,13 +1,23 @@
-use std::io::{self, BufRead, Write};
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashMap};
+use std::io::{self, BufRead, BufReader, Write};
+#[cfg(unix)]
+use std::os::unix::net::UnixListener;
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::thread;
+use std::time::{Duration, Instant};
 
+use serde::Serialize;
 use serde_json::{json, Value};
-use tracing::{debug, error};
+use tracing::{debug, error, info, warn};
 
-use icm_core::Embedder;
+use icm_core::{Embedder, IcmError};
 use icm_store::Store;
 
+use crate::framing::{Frame, Framer};
 use crate::protocol::{JsonRpcMessage, JsonRpcResponse};
-use crate::tools;
+use crate::recall_cache::{RecallCache, RECALL_CACHE_CAPACITY};
+use crate::tools::{self, CallContext, CancelToken, ToolOptions};
 
 const SERVER_NAME: &str = "icm";
 const SERVER_VERSION: &str = env!("CARGO_PKG_VERSION");
@@ -16,9 +26,197 @@ const PROTOCOL_VERSION: &str = "2024-11-05";
 /// Number of non-store tool calls before we nudge the agent to store.
 const STORE_NUDGE_THRESHOLD: u32 = 10;
 
-/// Maximum allowed line length (10 MB). Lines exceeding this are rejected
-/// without parsing to prevent memory exhaustion.
-const MAX_LINE_LEN: usize = 10 * 1024 * 1024;
+/// Maximum allowed message length (10 MB). Messages exceeding this are
+/// rejected without parsing to prevent memory exhaustion.
+pub(crate) const MAX_LINE_LEN: usize = 10 * 1024 * 1024;
+
+/// How often the server loop tries the daily decay pass. The store's
+/// 24-hour claim decides whether a try actually decays anything.
+const DECAY_CHECK_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// Cancellations remembered for requests that have not started yet.
+const MAX_EARLY_CANCELS: usize = 64;
+
+/// JSON-RPC error code for a request cancelled before it ran.
+const REQUEST_CANCELLED: i64 = -32800;
+
+/// JSON-RPC error code for a request sent before `initialize`.
+const SERVER_NOT_INITIALIZED: i64 = -32002;
+
+/// Server-level settings: the tool handler options plus what
+/// `initialize` and `tools/list` advertise to the client.
+#[derive(Debug, Clone, Default)]
+pub struct ServerOptions {
+    pub tools: ToolOptions,
+    /// Appended to the built-in instructions in the `initialize` result.
+    pub instructions: Option<String>,
+    /// Extra guidance appended to a tool's description, by tool name.
+    pub tool_overrides: BTreeMap<String, String>,
+    /// Time limit for any tool call. `None` = unlimited.
+    pub tool_timeout: Option<Duration>,
+    /// Per-tool time limits, overriding `tool_timeout`.
+    pub tool_timeouts: BTreeMap<String, Duration>,
+    /// Rebuild the topic digests behind the fast prompt hook this often,
+    /// between requests. `None` or zero = never.
+    pub digest_refresh: Option<Duration>,
+    /// How long identical recalls are answered from memory. Any tool
+    /// call that may write clears the cache. `None` or zero = off.
+    pub recall_cache_ttl: Option<Duration>,
+    /// Exit once no request has arrived for this long, releasing the
+    /// database and model. `None` or zero = never.
+    pub idle_shutdown: Option<Duration>,
+}
+
+impl ServerOptions {
+    /// The time limit for `tool`; a zero duration means unlimited.
+    fn timeout_for(&self, tool: &str) -> Option<Duration> {
+        self.tool_timeouts
+            .get(tool)
+            .copied()
+            .or(self.tool_timeout)
+            .filter(|t| !t.is_zero())
+    }
+}
+
+/// How long a cancelled or timed-out call may take to wind down and
+/// answer itself (`icm_memory_embed_all` reports its partial progress)
+/// before the server answers `REQUEST_CANCELLED` on its behalf.
+const CANCEL_GRACE: Duration = Duration::from_millis(500);
+
+/// A `tools/call` handed to the tool worker and not answered yet.
+struct PendingCall {
+    id: Value,
+    token: CancelToken,
+    /// When the server stops waiting and answers `REQUEST_CANCELLED`:
+    /// the timeout plus [`CANCEL_GRACE`], or sooner once cancelled.
+    answer_by: Option<Instant>,
+    /// The batch the call arrived in, if any.
+    batch: Option<u64>,
+}
+
+/// A session's tool calls in flight, plus cancellations that arrived
+/// before the call they name.
+#[derive(Default)]
+struct Cancellation {
+    pending: Vec<PendingCall>,
+    early: Vec<Value>,
+}
+
+impl Cancellation {
+    /// Cancel `id`: a pending call gets [`CANCEL_GRACE`] to answer, an
+    /// unknown id is remembered in case its request is still on the way.
+    fn cancel(&mut self, id: Value) {
+        match self.pending.iter_mut().find(|p| p.id == id) {
+            Some(call) => {
+                call.token.cancel();
+                let by = Instant::now() + CANCEL_GRACE;
+                call.answer_by = Some(call.answer_by.map_or(by, |t| t.min(by)));
+            }
+            None => {
+                if self.early.len() >= MAX_EARLY_CANCELS {
+                    self.early.remove(0);
+                }
+                self.early.push(id);
+            }
+        }
+    }
+
+    /// Start tracking `id`; returns `None` if it was cancelled while queued.
+    fn start(
+        &mut self,
+        id: &Value,
+        timeout: Option<Duration>,
+        batch: Option<u64>,
+    ) -> Option<CancelToken> {
+        if let Some(pos) = self.early.iter().position(|e| e == id) {
+            self.early.remove(pos);
+            return None;
+        }
+        let token = CancelToken::new(timeout);
+        self.pending.push(PendingCall {
+            id: id.clone(),
+            token: token.clone(),
+            answer_by: timeout.map(|t| Instant::now() + t + CANCEL_GRACE),
+            batch,
+        });
+        Some(token)
+    }
+
+    /// Stop tracking `id`; `None` when it was already answered.
+    fn finish(&mut self, id: &Value) -> Option<PendingCall> {
+        let pos = self.pending.iter().position(|p| p.id == *id)?;
+        Some(self.pending.remove(pos))
+    }
+
+    /// Stop tracking the calls whose answer is overdue, and return them.
+    fn overdue(&mut self, now: Instant) -> Vec<PendingCall> {
+        let (late, pending) = std::mem::take(&mut self.pending)
+            .into_iter()
+            .partition(|p| p.answer_by.is_some_and(|t| t <= now));
+        self.pending = pending;
+        late
+    }
+
+    fn next_deadline(&self) -> Option<Instant> {
+        self.pending.iter().filter_map(|p| p.answer_by).min()
+    }
+}
+
+/// The request id named by an MCP `notifications/cancelled` (or LSP-style
+/// `$/cancelRequest`) notification, if `method` is one.
+fn cancelled_request_id(method: &str, params: Option<&Value>) -> Option<Value> {
+    let key = match method {
+        "notifications/cancelled" => "requestId",
+        "$/cancelRequest" => "id",
+        _ => return None,
+    };
+    params?.get(key).cloned()
+}
+
+/// The store behind the request loop: borrowed from the caller, or
+/// opened on the first tool call so `initialize` and `tools/list` never
+/// wait on it.
+enum StoreSlot<'a> {
+    Ready(&'a Store),
+    Lazy {
+        open: &'a dyn Fn() -> anyhow::Result<Store>,
+        store: Option<Box<Store>>,
+    },
+}
+
+impl StoreSlot<'_> {
+    /// The store, opening it first if needed. A failed open is retried
+    /// on the next call.
+    fn get(&mut self) -> anyhow::Result<&Store> {
+        match self {
+            StoreSlot::Ready(store) => Ok(*store),
+            StoreSlot::Lazy { open, store } => {
+                if store.is_none() {
+                    *store = Some(Box::new(open()?));
+                }
+                Ok(store.as_deref().expect("opened above"))
+            }
+        }
+    }
+
+    /// The store if it is open, without opening it.
+    fn opened(&self) -> Option<&Store> {
+        match self {
+            StoreSlot::Ready(store) => Some(store),
+            StoreSlot::Lazy { store, .. } => store.as_deref(),
+        }
+    }
+}
+
+/// When the request loop must wake without a request: the earlier of
+/// the next maintenance (digest refresh, decay) and the idle deadline.
+/// `None` = block.
+fn next_wake(maintenance_due: Option<Instant>, idle_deadline: Option<Instant>) -> Option<Instant> {
+    match (maintenance_due, idle_deadline) {
+        (Some(a), Some(b)) => Some(a.min(b)),
+        (a, b) => a.or(b),
+    }
+}
 
 /// Run the MCP server on stdio. Blocks until stdin is closed.
 pub fn run_server(
@@ -26,96 +224,868 @@ pub fn run_server(
     embedder: Option<&dyn Embedder>,
     compact: bool,
 ) -> anyhow::Result<()> {
-    let stdin = io::stdin();
-    let mut stdout = io::stdout();
-    let mut calls_since_store: u32 = 0;
+    let opts = ServerOptions {
+        tools: ToolOptions {
+            compact,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    run_server_with_opts(store, embedder, opts)
+}
 
-    for line in stdin.lock().lines() {
-        let line = match line {
-            Ok(l) => l,
-            Err(e) => {
-                error!("stdin read error: {e}");
+/// [`run_server`] with the full set of server options.
+pub fn run_server_with_opts(
+    store: &Store,
+    embedder: Option<&dyn Embedder>,
+    server_opts: ServerOptions,
+) -> anyhow::Result<()> {
+    serve(StoreSlot::Ready(store), embedder, server_opts)
+}
+
+/// [`run_server_with_opts`], opening the store with `open` on the first
+/// tool call instead of up front. Lets `initialize` answer immediately
+/// and leaves a server that is never used without a database handle.
+pub fn run_server_lazy(
+    open: &dyn Fn() -> anyhow::Result<Store>,
+    embedder: Option<&dyn Embedder>,
+    server_opts: ServerOptions,
+) -> anyhow::Result<()> {
+    serve(StoreSlot::Lazy { open, store: None }, embedder, server_opts)
+}
+
+/// Identifies a connected client within one server process.
+type ClientId = u64;
+
+/// Where a client's responses and notifications are written.
+type Output = RefCell<Box<dyn Write + Send>>;
+
+/// A connected MCP client: where its messages go, plus per-client state.
+/// Over stdio there is exactly one; a daemon has one per socket
+/// connection.
+struct Session {
+    out: Output,
+    calls: Cancellation,
+    /// Batch responses still waiting on tool calls, by batch number.
+    batches: HashMap<u64, PendingBatch>,
+    lifecycle: Lifecycle,
+    /// The client hung up; the session stays until its calls are answered.
+    closed: bool,
+}
+
+/// The responses of a batch gathered so far.
+struct PendingBatch {
+    responses: Vec<Value>,
+    waiting: usize,
+}
+
+impl Session {
+    fn new(out: Box<dyn Write + Send>) -> Self {
+        Self {
+            out: RefCell::new(out),
+            calls: Cancellation::default(),
+            batches: HashMap::new(),
+            lifecycle: Lifecycle::default(),
+            closed: false,
+        }
+    }
+
+    /// Record the answer to `call`: ready to send on its own, or as the
+    /// whole array once the last call of its batch is answered.
+    fn resolve(&mut self, call: PendingCall, resp: Value) -> Option<Value> {
+        let Some(batch) = call.batch else {
+            return Some(resp);
+        };
+        let pending = self.batches.get_mut(&batch)?;
+        pending.responses.push(resp);
+        pending.waiting -= 1;
+        if pending.waiting > 0 {
+            return None;
+        }
+        self.batches
+            .remove(&batch)
+            .map(|b| Value::Array(b.responses))
+    }
+
+    /// Whether the session can be dropped: the client exited, or hung up
+    /// and every call it made has been answered.
+    fn is_done(&self) -> bool {
+        self.lifecycle == Lifecycle::Exited || (self.closed && self.calls.pending.is_empty())
+    }
+}
+
+impl Drop for Session {
+    /// Calls nobody will read the answer to stop wasting the worker.
+    fn drop(&mut self) {
+        for call in &self.calls.pending {
+            call.token.cancel();
+        }
+    }
+}
+
+/// Where a session is in the MCP lifecycle.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+enum Lifecycle {
+    /// Only `initialize` and `ping` are accepted.
+    #[default]
+    Connected,
+    /// `initialize` answered, `notifications/initialized` not yet seen.
+    /// Requests are served anyway, as some clients never send it.
+    Initializing,
+    Running,
+    /// `shutdown` answered; only `ping` and the `exit` notification remain.
+    ShuttingDown,
+    /// `exit` received; the session ends once the current frame is done.
+    Exited,
+}
+
+impl Lifecycle {
+    /// Why `method` is refused in this state, as a JSON-RPC error.
+    fn rejection(self, method: &str) -> Option<(i64, &'static str)> {
+        match (self, method) {
+            (Lifecycle::Exited, _) => Some((-32600, "server is shutting down")),
+            (_, "ping") => None,
+            (Lifecycle::Connected, "initialize") => None,
+            (Lifecycle::Connected, _) => Some((SERVER_NOT_INITIALIZED, "server not initialized")),
+            (Lifecycle::ShuttingDown, _) => Some((-32600, "server is shutting down")),
+            (_, "initialize") => Some((-32600, "already initialized")),
+            _ => None,
+        }
+    }
+
+    fn accepts_notifications(self) -> bool {
+        matches!(self, Lifecycle::Initializing | Lifecycle::Running)
+    }
+}
+
+/// What the reader and accept threads, and the tool worker, feed the
+/// message loop.
+enum Event {
+    Connected(ClientId, Session),
+    Frame(ClientId, Frame),
+    Disconnected(ClientId),
+    /// The answer to a `tools/call`, by request id.
+    ToolDone(ClientId, Value, JsonRpcResponse),
+    /// A `notifications/progress` for a running call, by request id.
+    Progress(ClientId, Value, Value),
+}
+
+/// Work for the tool worker.
+enum Job {
+    Call {
+        client: ClientId,
+        id: Value,
+        params: Option<Value>,
+        cancel: CancelToken,
+    },
+    /// The client is gone; forget its per-session state.
+    Disconnected(ClientId),
+}
+
+/// Read `input` on its own thread, reassembling messages from its lines.
+fn spawn_reader(client: ClientId, mut input: Box<dyn BufRead + Send>, tx: mpsc::Sender<Event>) {
+    thread::spawn(move || {
+        let mut framer = Framer::default();
+        let mut line = Vec::new();
+        loop {
+            line.clear();
+            match input.read_until(b'\n', &mut line) {
+                Ok(0) => break,
+                Ok(_) => {}
+                Err(e) => {
+                    error!("read error: {e}");
+                    break;
+                }
+            }
+            let text = String::from_utf8_lossy(&line);
+            for frame in framer.push(text.trim_end_matches(['\n', '\r'])) {
+                if tx.send(Event::Frame(client, frame)).is_err() {
+                    return;
+                }
+            }
+        }
+        let _ = tx.send(Event::Disconnected(client));
+    });
+}
+
+fn serve(
+    store: StoreSlot,
+    embedder: Option<&dyn Embedder>,
+    server_opts: ServerOptions,
+) -> anyhow::Result<()> {
+    let (tx, rx) = mpsc::channel();
+    let _ = tx.send(Event::Connected(0, Session::new(Box::new(io::stdout()))));
+    spawn_reader(0, Box::new(BufReader::new(io::stdin())), tx.clone());
+    request_loop(store, embedder, server_opts, tx, rx, false)
+}
+
+/// Serve every client that connects to `listener` from this one process,
+/// sharing the store and the embedder. Runs until idle shutdown.
+#[cfg(unix)]
+pub(crate) fn serve_listener(
+    listener: UnixListener,
+    open: &dyn Fn() -> anyhow::Result<Store>,
+    embedder: Option<&dyn Embedder>,
+    server_opts: ServerOptions,
+) -> anyhow::Result<()> {
+    let (tx, rx) = mpsc::channel();
+    let accept_tx = tx.clone();
+    thread::spawn(move || {
+        for (client, stream) in (1..).zip(listener.incoming()) {
+            let (reader, writer) = match stream.and_then(|s| Ok((s.try_clone()?, s))) {
+                Ok(pair) => pair,
+                Err(e) => {
+                    warn!("failed to accept a client: {e}");
+                    continue;
+                }
+            };
+            let session = Session::new(Box::new(writer));
+            if accept_tx.send(Event::Connected(client, session)).is_err() {
                 break;
             }
-        };
+            spawn_reader(client, Box::new(BufReader::new(reader)), accept_tx.clone());
+        }
+    });
+    let store = StoreSlot::Lazy { open, store: None };
+    request_loop(store, embedder, server_opts, tx, rx, true)
+}
 
-        let line = line.trim();
-        if line.is_empty() {
-            continue;
+/// Write `msg` to `client`. A client that can no longer be written to is
+/// dropped; over stdio (`shared` false) that is an error that ends the
+/// server, as it always was.
+fn deliver(
+    sessions: &mut HashMap<ClientId, Session>,
+    client: ClientId,
+    msg: &impl Serialize,
+    shared: bool,
+) -> anyhow::Result<()> {
+    let Some(session) = sessions.get(&client) else {
+        return Ok(());
+    };
+    if let Err(e) = send(&session.out, msg) {
+        if !shared {
+            return Err(e);
         }
+        warn!("dropping client {client}: {e}");
+        sessions.remove(&client);
+    }
+    Ok(())
+}
+
+/// Loop-wide state for answering messages: everything but the session
+/// the message came from.
+struct Dispatcher<'s> {
+    server_opts: &'s ServerOptions,
+    /// Where `tools/call` requests go to run.
+    jobs: mpsc::Sender<Job>,
+    /// Whether the embedding-only tools are advertised. Re-evaluated after
+    /// each event: a lazily-loaded model can fail mid-session, in which
+    /// case clients are told to re-fetch the tool list.
+    embedder_ok: bool,
+    next_batch: u64,
+}
 
-        if line.len() > MAX_LINE_LEN {
-            error!("line too long: {} bytes (max {MAX_LINE_LEN})", line.len());
-            let resp = JsonRpcResponse::err(
+impl Dispatcher<'_> {
+    /// Answer one frame: a single message, or a batch answered with the
+    /// array of its responses. `None` when there is nothing to send back
+    /// yet: tool calls are answered when the worker is done with them.
+    fn handle_frame(
+        &mut self,
+        client: ClientId,
+        session: &mut Session,
+        value: Value,
+    ) -> Option<Value> {
+        match value {
+            Value::Array(batch) if batch.is_empty() => Some(to_value(&JsonRpcResponse::err(
                 Value::Null,
                 -32600,
-                format!("line too long: {} bytes (max {MAX_LINE_LEN})", line.len()),
-            );
-            write_response(&mut stdout, &resp)?;
-            continue;
+                "invalid request: empty batch".into(),
+            ))),
+            Value::Array(batch) => {
+                let number = self.next_batch;
+                self.next_batch += 1;
+                let responses: Vec<Value> = batch
+                    .into_iter()
+                    .filter_map(|msg| self.handle_message(client, session, msg, Some(number)))
+                    .map(|resp| to_value(&resp))
+                    .collect();
+                let waiting = session
+                    .calls
+                    .pending
+                    .iter()
+                    .filter(|p| p.batch == Some(number))
+                    .count();
+                if waiting > 0 {
+                    session
+                        .batches
+                        .insert(number, PendingBatch { responses, waiting });
+                    return None;
+                }
+                (!responses.is_empty()).then(|| Value::Array(responses))
+            }
+            msg => self
+                .handle_message(client, session, msg, None)
+                .map(|resp| to_value(&resp)),
         }
+    }
 
-        let msg: JsonRpcMessage = match serde_json::from_str(line) {
+    /// Answer one message. `None` for notifications, client responses and
+    /// tool calls handed to the worker.
+    fn handle_message(
+        &mut self,
+        client: ClientId,
+        session: &mut Session,
+        value: Value,
+        batch: Option<u64>,
+    ) -> Option<JsonRpcResponse> {
+        let raw_id = value.get("id").cloned();
+        let msg: JsonRpcMessage = match serde_json::from_value(value) {
             Ok(m) => m,
             Err(e) => {
+                // Valid JSON, but not a JSON-RPC message: answered under
+                // its id when it has one.
                 error!("invalid JSON-RPC: {e}");
-                // Send parse error if we can
-                let resp = JsonRpcResponse::err(Value::Null, -32700, format!("parse error: {e}"));
-                write_response(&mut stdout, &resp)?;
-                continue;
+                return Some(JsonRpcResponse::err(
+                    raw_id.unwrap_or(Value::Null),
+                    -32600,
+                    format!("invalid request: {e}"),
+                ));
             }
         };
 
-        let method = msg.method.as_deref().unwrap_or("");
+        // A response to a server-initiated request; the server sends none.
+        let Some(method) = msg.method.as_deref() else {
+            debug!("ignoring message without a method");
+            return None;
+        };
         debug!("MCP request: {method}");
 
         // Notifications have no id — don't respond
-        let id = match msg.id {
-            Some(id) => id,
-            None => continue,
+        let Some(id) = msg.id else {
+            match method {
+                "notifications/initialized" if session.lifecycle == Lifecycle::Initializing => {
+                    session.lifecycle = Lifecycle::Running;
+                }
+                "exit" => session.lifecycle = Lifecycle::Exited,
+                _ => {
+                    if let Some(id) = cancelled_request_id(method, msg.params.as_ref()) {
+                        session.calls.cancel(id);
+                    }
+                }
+            }
+            return None;
         };
+        if let Some((code, message)) = session.lifecycle.rejection(method) {
+            return Some(JsonRpcResponse::err(id, code, message.into()));
+        }
 
-        let response = match method {
-            "initialize" => handle_initialize(id),
+        let server_opts = self.server_opts;
+        Some(match method {
+            "initialize" => {
+                session.lifecycle = Lifecycle::Initializing;
+                handle_initialize(id, server_opts.instructions.as_deref())
+            }
+            "shutdown" => {
+                session.lifecycle = Lifecycle::ShuttingDown;
+                JsonRpcResponse::ok(id, Value::Null)
+            }
             "ping" => JsonRpcResponse::ok(id, json!({})),
-            "tools/list" => handle_tools_list(id, embedder.is_some()),
-            "tools/call" => handle_tools_call(
-                id,
-                &msg.params,
-                store,
-                embedder,
-                compact,
-                &mut calls_since_store,
-            ),
+            "tools/list" => handle_tools_list(id, self.embedder_ok, &server_opts.tool_overrides),
+            "tools/call" => {
+                let tool = msg
+                    .params
+                    .as_ref()
+                    .and_then(|p| p.get("name"))
+                    .and_then(|v| v.as_str())
+                    .unwrap_or("");
+                let timeout = server_opts.timeout_for(tool);
+                let Some(cancel) = session.calls.start(&id, timeout, batch) else {
+                    return Some(JsonRpcResponse::err(
+                        id,
+                        REQUEST_CANCELLED,
+                        "request cancelled".into(),
+                    ));
+                };
+                let job = Job::Call {
+                    client,
+                    id: id.clone(),
+                    params: msg.params,
+                    cancel,
+                };
+                if self.jobs.send(job).is_err() {
+                    session.calls.finish(&id);
+                    return Some(JsonRpcResponse::err(
+                        id,
+                        -32603,
+                        "the tool worker has stopped".into(),
+                    ));
+                }
+                return None;
+            }
             other => JsonRpcResponse::method_not_found(id, other),
+        })
+    }
+}
+
+fn to_value(resp: &impl Serialize) -> Value {
+    serde_json::to_value(resp).unwrap_or(json!(null))
+}
+
+/// The error answering a call that was cancelled or ran out of time.
+fn cancelled_response(id: Value, token: &CancelToken) -> JsonRpcResponse {
+    JsonRpcResponse::err(id, REQUEST_CANCELLED, format!("request {}", token.reason()))
+}
+
+/// Serve every client. Messages are read and answered on a scoped thread,
+/// so `ping`, `tools/list` and cancellations never wait behind a tool
+/// call; tool calls run one at a time on this thread, which owns the
+/// store (it is not shareable across threads). With `shared` (a daemon)
+/// the loop outlives its clients and the idle clock only runs while none
+/// is connected; otherwise it ends with its single client.
+fn request_loop(
+    store: StoreSlot,
+    embedder: Option<&dyn Embedder>,
+    server_opts: ServerOptions,
+    tx: mpsc::Sender<Event>,
+    rx: mpsc::Receiver<Event>,
+    shared: bool,
+) -> anyhow::Result<()> {
+    for name in tools::unknown_tool_names(server_opts.tool_overrides.keys()) {
+        warn!("[mcp.tool_overrides] names unknown tool `{name}`; ignored");
+    }
+    let opts = &server_opts;
+    thread::scope(|scope| {
+        let (jobs_tx, jobs_rx) = mpsc::channel();
+        let messages = scope.spawn(move || message_loop(embedder, opts, rx, jobs_tx, shared));
+        tool_worker(scope, store, embedder, opts, jobs_rx, tx);
+        messages
+            .join()
+            .unwrap_or_else(|panic| std::panic::resume_unwind(panic))
+    })
+}
+
+/// Read events in arrival order and answer everything but tool calls,
+/// which go to the worker; their answers come back as events. Returns
+/// when the server should stop, which also stops the worker.
+fn message_loop(
+    embedder: Option<&dyn Embedder>,
+    server_opts: &ServerOptions,
+    rx: mpsc::Receiver<Event>,
+    jobs: mpsc::Sender<Job>,
+    shared: bool,
+) -> anyhow::Result<()> {
+    let mut sessions: HashMap<ClientId, Session> = HashMap::new();
+    let mut dispatcher = Dispatcher {
+        server_opts,
+        jobs,
+        embedder_ok: embedder_available(embedder),
+        next_batch: 0,
+    };
+    let idle_shutdown = server_opts.idle_shutdown.filter(|d| !d.is_zero());
+    let mut last_activity = Instant::now();
+    // Over stdio the loop ends with its one client, once it has connected.
+    let mut connected = false;
+    loop {
+        // Answer the calls that ran out of time or were cancelled and did
+        // not wind down within the grace period.
+        let now = Instant::now();
+        let mut overdue = Vec::new();
+        for (&client, session) in &mut sessions {
+            for call in session.calls.overdue(now) {
+                let resp = to_value(&cancelled_response(call.id.clone(), &call.token));
+                if let Some(reply) = session.resolve(call, resp) {
+                    overdue.push((client, reply));
+                }
+            }
+        }
+        for (client, reply) in overdue {
+            deliver(&mut sessions, client, &reply, shared)?;
+        }
+        sessions.retain(|client, s| {
+            if s.is_done() {
+                debug!("client {client} is gone");
+            }
+            !s.is_done()
+        });
+        if !shared && connected && sessions.is_empty() {
+            break;
+        }
+
+        let busy = sessions.values().any(|s| !s.calls.pending.is_empty());
+        let idle_shutdown = idle_shutdown.filter(|_| !busy && (!shared || sessions.is_empty()));
+        if let Some(idle) = idle_shutdown.filter(|idle| last_activity.elapsed() >= *idle) {
+            info!("no requests for {}s; shutting down", idle.as_secs());
+            break;
+        }
+        let call_due = sessions
+            .values()
+            .filter_map(|s| s.calls.next_deadline())
+            .min();
+        let wake = next_wake(call_due, idle_shutdown.map(|idle| last_activity + idle));
+        let event = match wake {
+            Some(at) => match rx.recv_timeout(at.saturating_duration_since(Instant::now())) {
+                Ok(event) => event,
+                Err(RecvTimeoutError::Timeout) => continue,
+                Err(RecvTimeoutError::Disconnected) => break,
+            },
+            None => match rx.recv() {
+                Ok(event) => event,
+                Err(_) => break,
+            },
+        };
+        last_activity = Instant::now();
+        let (client, frame) = match event {
+            Event::Connected(client, session) => {
+                debug!("client {client} connected");
+                sessions.insert(client, session);
+                connected = true;
+                continue;
+            }
+            Event::Disconnected(client) => {
+                debug!("client {client} disconnected");
+                let _ = dispatcher.jobs.send(Job::Disconnected(client));
+                if let Some(session) = sessions.get_mut(&client) {
+                    session.closed = true;
+                }
+                continue;
+            }
+            Event::ToolDone(client, id, resp) => {
+                let reply = sessions.get_mut(&client).and_then(|session| {
+                    // `None`: already answered as cancelled.
+                    let call = session.calls.finish(&id)?;
+                    session.resolve(call, to_value(&resp))
+                });
+                if let Some(reply) = reply {
+                    deliver(&mut sessions, client, &reply, shared)?;
+                }
+                notify_embedder_change(&mut sessions, &mut dispatcher, embedder, shared)?;
+                continue;
+            }
+            Event::Progress(client, id, note) => {
+                let running = sessions
+                    .get(&client)
+                    .is_some_and(|s| s.calls.pending.iter().any(|p| p.id == id));
+                if running {
+                    deliver(&mut sessions, client, &note, shared)?;
+                }
+                continue;
+            }
+            Event::Frame(client, frame) => (client, frame),
         };
 
-        write_response(&mut stdout, &response)?;
+        let value = match frame {
+            Frame::Message(text) => match serde_json::from_str::<Value>(&text) {
+                Ok(value) => value,
+                Err(e) => {
+                    error!("invalid JSON: {e}");
+                    let resp =
+                        JsonRpcResponse::err(Value::Null, -32700, format!("parse error: {e}"));
+                    deliver(&mut sessions, client, &resp, shared)?;
+                    continue;
+                }
+            },
+            Frame::TooLong(len) => {
+                error!("message too long: {len} bytes (max {MAX_LINE_LEN})");
+                let resp = JsonRpcResponse::err(
+                    Value::Null,
+                    -32600,
+                    format!("message too long: {len} bytes (max {MAX_LINE_LEN})"),
+                );
+                deliver(&mut sessions, client, &resp, shared)?;
+                continue;
+            }
+            Frame::Invalid(e) => {
+                error!("invalid JSON: {e}");
+                let resp = JsonRpcResponse::err(Value::Null, -32700, format!("parse error: {e}"));
+                deliver(&mut sessions, client, &resp, shared)?;
+                continue;
+            }
+        };
+
+        let Some(session) = sessions.get_mut(&client) else {
+            continue;
+        };
+        if let Some(reply) = dispatcher.handle_frame(client, session, value) {
+            deliver(&mut sessions, client, &reply, shared)?;
+        }
+        notify_embedder_change(&mut sessions, &mut dispatcher, embedder, shared)?;
     }
 
     Ok(())
 }
 
-fn write_response(stdout: &mut io::Stdout, resp: &JsonRpcResponse) -> anyhow::Result<()> {
-    let json = serde_json::to_string(resp)?;
-    writeln!(stdout, "{json}")?;
-    stdout.flush()?;
+/// Tell clients to re-fetch `tools/list` when the embedder came or went.
+fn notify_embedder_change(
+    sessions: &mut HashMap<ClientId, Session>,
+    dispatcher: &mut Dispatcher,
+    embedder: Option<&dyn Embedder>,
+    shared: bool,
+) -> anyhow::Result<()> {
+    let now_ok = embedder_available(embedder);
+    if now_ok == dispatcher.embedder_ok {
+        return Ok(());
+    }
+    warn!(
+        "embedder is now {}; notifying clients of tool list change",
+        if now_ok { "available" } else { "unavailable" }
+    );
+    dispatcher.embedder_ok = now_ok;
+    let clients: Vec<ClientId> = sessions
+        .iter()
+        .filter(|(_, s)| s.lifecycle.accepts_notifications())
+        .map(|(client, _)| *client)
+        .collect();
+    for client in clients {
+        deliver(sessions, client, &tools_list_changed(), shared)?;
+    }
+    Ok(())
+}
+
+/// Run tool calls one at a time, and the periodic maintenance (topic
+/// digests, decay) between them. Returns once the message loop is gone.
+fn tool_worker<'scope, 'env>(
+    scope: &'scope thread::Scope<'scope, 'env>,
+    mut store: StoreSlot,
+    embedder: Option<&'env dyn Embedder>,
+    server_opts: &ServerOptions,
+    jobs: mpsc::Receiver<Job>,
+    events: mpsc::Sender<Event>,
+) {
+    let mut recall_cache = match server_opts.recall_cache_ttl.filter(|t| !t.is_zero()) {
+        Some(ttl) => RecallCache::new(ttl, RECALL_CACHE_CAPACITY),
+        None => RecallCache::new(Duration::ZERO, 0),
+    };
+    // The embedder health check runs on the first tool call, off this
+    // thread since a first model load can take a while.
+    let mut embedder_probed = false;
+    let mut calls_since_store: HashMap<ClientId, u32> = HashMap::new();
+
+    // Topic digests are rebuilt once the store is open, then whenever the
+    // interval has passed: while idle, or right after the call that
+    // crossed it.
+    let mut digest_refresh = server_opts.digest_refresh.filter(|d| !d.is_zero());
+    let mut digests_due = Instant::now();
+    // The daily decay pass (`[memory] auto_decay` other than `false`) is
+    // tried the same way, hourly, so recall itself never has to run it.
+    let decay_factor = server_opts
+        .tools
+        .auto_decay
+        .in_daemon()
+        .then_some(server_opts.tools.decay_rate);
+    let mut decay_due = Instant::now();
+    loop {
+        if let (Some(every), Some(opened)) = (digest_refresh, store.opened()) {
+            if Instant::now() >= digests_due {
+                if !refresh_digests(opened) {
+                    digest_refresh = None;
+                }
+                digests_due = Instant::now() + every;
+            }
+        }
+        if let (Some(factor), Some(opened)) = (decay_factor, store.opened()) {
+            if Instant::now() >= decay_due {
+                if run_auto_decay(opened, factor) {
+                    recall_cache.clear();
+                }
+                decay_due = Instant::now() + DECAY_CHECK_INTERVAL;
+            }
+        }
+        let maintenance_due = [
+            digest_refresh.map(|_| digests_due),
+            decay_factor.map(|_| decay_due),
+        ]
+        .into_iter()
+        .flatten()
+        .min()
+        .filter(|_| store.opened().is_some());
+        let job = match maintenance_due {
+            Some(at) => match jobs.recv_timeout(at.saturating_duration_since(Instant::now())) {
+                Ok(job) => job,
+                Err(RecvTimeoutError::Timeout) => continue,
+                Err(RecvTimeoutError::Disconnected) => break,
+            },
+            None => match jobs.recv() {
+                Ok(job) => job,
+                Err(_) => break,
+            },
+        };
+        let (client, id, params, cancel) = match job {
+            Job::Call {
+                client,
+                id,
+                params,
+                cancel,
+            } => (client, id, params, cancel),
+            Job::Disconnected(client) => {
+                calls_since_store.remove(&client);
+                continue;
+            }
+        };
+        // Cancelled or out of time while it waited in the queue.
+        if cancel.is_cancelled() {
+            let resp = cancelled_response(id.clone(), &cancel);
+            let _ = events.send(Event::ToolDone(client, id, resp));
+            continue;
+        }
+        if let Some(emb) = embedder.filter(|_| !embedder_probed) {
+            embedder_probed = true;
+            scope.spawn(move || probe_embedder(emb));
+        }
+
+        let resp = {
+            let progress_token = progress_token(&params);
+            let report = |done: usize, total: usize, message: &str| {
+                if let Some(token) = &progress_token {
+                    let note = progress_notification(token, done, total, message);
+                    let _ = events.send(Event::Progress(client, id.clone(), note));
+                }
+            };
+            let ctx = CallContext {
+                cancel: cancel.clone(),
+                progress: &report,
+            };
+            match store.get() {
+                Ok(store) => {
+                    // Stops SQLite mid-statement once the call is cancelled
+                    // or out of time.
+                    let token = cancel.clone();
+                    store.set_interrupt_check(Some(Box::new(move || token.is_cancelled())));
+                    let resp = handle_tools_call(
+                        id.clone(),
+                        &params,
+                        store,
+                        embedder,
+                        &server_opts.tools,
+                        &ctx,
+                        calls_since_store.entry(client).or_default(),
+                        &mut recall_cache,
+                    );
+                    store.set_interrupt_check(None);
+                    resp
+                }
+                Err(e) => {
+                    error!("failed to open the store: {e:#}");
+                    JsonRpcResponse::err(
+                        id.clone(),
+                        -32603,
+                        format!("failed to open the memory store: {e:#}"),
+                    )
+                }
+            }
+        };
+        if events.send(Event::ToolDone(client, id, resp)).is_err() {
+            break;
+        }
+    }
+}
+
+/// Run the daily decay pass if one is due. True when it decayed
+/// weights, so cached recall results are stale.
+fn run_auto_decay(store: &Store, factor: f32) -> bool {
+    match store.maybe_auto_decay(factor) {
+        Ok(Some(n)) => {
+            info!("auto-decay: {n} memories decayed by {factor}");
+            true
+        }
+        Ok(None) => false,
+        Err(e) => {
+            warn!("auto-decay failed: {e}");
+            false
+        }
+    }
+}
+
+/// Rebuild the topic digests. False when this store can never hold
+/// them (read-only, or a backend without digests), so the caller stops.
+fn refresh_digests(store: &Store) -> bool {
+    match store.refresh_topic_digests(icm_store::DIGEST_ENTRIES) {
+        Ok(topics) => {
+            debug!("refreshed {topics} topic digests");
+            true
+        }
+        Err(IcmError::ReadOnly(_) | IcmError::Unsupported(_)) => false,
+        Err(e) => {
+            warn!("topic digest refresh failed: {e}");
+            true
+        }
+    }
+}
+
+/// Log a missing model or unreachable provider now instead of letting
+/// it surface as silently keyword-only recall.
+fn probe_embedder(embedder: &dyn Embedder) {
+    if let icm_core::EmbedderHealth::Unavailable(reason) = icm_core::check_embedder(embedder) {
+        warn!(
+            "semantic search unavailable, recall degrades to keywords: \
+             {reason} (see `icm doctor`)"
+        );
+    }
+}
+
+fn embedder_available(embedder: Option<&dyn Embedder>) -> bool {
+    embedder.is_some_and(|e| e.is_available())
+}
+
+/// `notifications/tools/list_changed` — tells the client to re-fetch `tools/list`.
+fn tools_list_changed() -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "method": "notifications/tools/list_changed"
+    })
+}
+
+/// The client's `params._meta.progressToken` for a request, if it asked for progress.
+fn progress_token(params: &Option<Value>) -> Option<Value> {
+    params
+        .as_ref()?
+        .get("_meta")?
+        .get("progressToken")
+        .filter(|t| t.is_string() || t.is_number())
+        .cloned()
+}
+
+fn progress_notification(token: &Value, done: usize, total: usize, message: &str) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "method": "notifications/progress",
+        "params": {
+            "progressToken": token,
+            "progress": done,
+            "total": total,
+            "message": message
+        }
+    })
+}
+
+fn send(out: &Output, msg: &impl Serialize) -> anyhow::Result<()> {
+    let json = serde_json::to_string(msg)?;
+    let mut out = out.borrow_mut();
+    writeln!(out, "{json}")?;
+    out.flush()?;
     Ok(())
 }
 
-fn handle_initialize(id: Value) -> JsonRpcResponse {
+/// Built-in instructions, followed by the user's `[mcp] instructions`.
+fn server_instructions(extra: Option<&str>) -> String {
+    match extra.map(str::trim).filter(|s| !s.is_empty()) {
+        Some(extra) => format!("{ICM_INSTRUCTIONS}\n\n{extra}"),
+        None => ICM_INSTRUCTIONS.to_string(),
+    }
+}
+
+fn handle_initialize(id: Value, extra_instructions: Option<&str>) -> JsonRpcResponse {
     JsonRpcResponse::ok(
         id,
         json!({
             "protocolVersion": PROTOCOL_VERSION,
             "capabilities": {
-                "tools": {}
+                "tools": { "listChanged": true }
             },
             "serverInfo": {
                 "name": SERVER_NAME,
                 "version": SERVER_VERSION
             },
-            "instructions": ICM_INSTRUCTIONS
+            "instructions": server_instructions(extra_instructions)
         }),
     )
 }
@@ -139,17 +1109,26 @@ Do NOT store: trivial details, information already in CLAUDE.md, ephemeral state
 \n\
 Importance levels: critical (never forgotten), high (slow decay), medium (normal), low (fast decay).";
 
-fn handle_tools_list(id: Value, has_embedder: bool) -> JsonRpcResponse {
-    JsonRpcResponse::ok(id, tools::tool_definitions(has_embedder))
+fn handle_tools_list(
+    id: Value,
+    has_embedder: bool,
+    overrides: &BTreeMap<String, String>,
+) -> JsonRpcResponse {
+    let mut defs = tools::tool_definitions(has_embedder);
+    tools::apply_description_overrides(&mut defs, overrides);
+    JsonRpcResponse::ok(id, defs)
 }
 
+#[allow(clippy::too_many_arguments)]
 fn handle_tools_call(
     id: Value,
     params: &Option<Value>,
     store: &Store,
     embedder: Option<&dyn Embedder>,
-    compact: bool,
+    opts: &ToolOptions,
+    ctx: &CallContext,
     calls_since_store: &mut u32,
+    recall_cache: &mut RecallCache,
 ) -> JsonRpcResponse {
     let params = match params {
         Some(p) => p,
@@ -174,7 +1153,20 @@ fn handle_tools_call(
         *calls_since_store += 1;
     }
 
-    let mut result = tools::call_tool(store, embedder, tool_name, &args, compact);
+    let mut result = match recall_cache.get(tool_name, &args) {
+        Some(hit) => hit,
+        None => {
+            let result =
+                tools::call_tool_with_context(store, embedder, tool_name, &args, opts, ctx);
+            if result.is_error && ctx.cancel.is_cancelled() {
+                // Most likely SQLite interrupted mid-query: report the
+                // cancellation rather than the error it caused.
+                return cancelled_response(id, &ctx.cancel);
+            }
+            recall_cache.record(tool_name, &args, &result);
+            result
+        }
+    };
 
     // Nudge: append a store reminder if too many calls without storing
     if *calls_since_store >= STORE_NUDGE_THRESHOLD && tool_name != "icm_memory_store" {
@@ -187,3 +1179,342 @@ fn handle_tools_call(
 
     JsonRpcResponse::ok(id, serde_json::to_value(result).unwrap_or(json!(null)))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn initialize_appends_configured_instructions() {
+        let resp =
+            serde_json::to_value(handle_initialize(json!(1), Some("Store after every task.")))
+                .unwrap();
+        let text = resp["result"]["instructions"].as_str().unwrap();
+        assert!(text.starts_with(ICM_INSTRUCTIONS));
+        assert!(text.ends_with("\n\nStore after every task."));
+
+        let resp = serde_json::to_value(handle_initialize(json!(1), Some("  "))).unwrap();
+        assert_eq!(resp["result"]["instructions"], ICM_INSTRUCTIONS);
+    }
+
+    struct FlakyEmbedder(std::sync::atomic::AtomicBool);
+
+    impl Embedder for FlakyEmbedder {
+        fn embed(&self, _text: &str) -> icm_core::IcmResult<Vec<f32>> {
+            Ok(vec![0.0; 4])
+        }
+        fn embed_batch(&self, texts: &[&str]) -> icm_core::IcmResult<Vec<Vec<f32>>> {
+            Ok(vec![vec![0.0; 4]; texts.len()])
+        }
+        fn dimensions(&self) -> usize {
+            4
+        }
+        fn is_available(&self) -> bool {
+            self.0.load(std::sync::atomic::Ordering::Relaxed)
+        }
+    }
+
+    fn listed_names(has_embedder: bool) -> Vec<String> {
+        let resp =
+            serde_json::to_value(handle_tools_list(json!(1), has_embedder, &BTreeMap::new()))
+                .unwrap();
+        resp["result"]["tools"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|t| t["name"].as_str().unwrap().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn tool_list_follows_embedder_availability() {
+        let flaky = FlakyEmbedder(std::sync::atomic::AtomicBool::new(true));
+        assert!(embedder_available(Some(&flaky)));
+        assert!(!embedder_available(None));
+        assert!(listed_names(true).contains(&"icm_memory_embed_all".to_string()));
+
+        flaky.0.store(false, std::sync::atomic::Ordering::Relaxed);
+        assert!(!embedder_available(Some(&flaky)));
+        assert!(!listed_names(false).contains(&"icm_memory_embed_all".to_string()));
+
+        let note = tools_list_changed();
+        assert_eq!(note["method"], "notifications/tools/list_changed");
+        assert!(note.get("id").is_none());
+
+        let init = serde_json::to_value(handle_initialize(json!(1), None)).unwrap();
+        assert_eq!(init["result"]["capabilities"]["tools"]["listChanged"], true);
+    }
+
+    #[test]
+    fn cancel_notifications_name_the_request() {
+        let mcp = json!({"requestId": 7, "reason": "user"});
+        assert_eq!(
+            cancelled_request_id("notifications/cancelled", Some(&mcp)),
+            Some(json!(7))
+        );
+        let lsp = json!({"id": "abc"});
+        assert_eq!(
+            cancelled_request_id("$/cancelRequest", Some(&lsp)),
+            Some(json!("abc"))
+        );
+        assert_eq!(cancelled_request_id("tools/call", Some(&lsp)), None);
+        assert_eq!(cancelled_request_id("notifications/cancelled", None), None);
+    }
+
+    #[test]
+    fn cancellation_reaches_running_and_queued_calls() {
+        let mut c = Cancellation::default();
+        let token = c.start(&json!(1), None, None).unwrap();
+        assert_eq!(c.next_deadline(), None);
+        c.cancel(json!(1));
+        assert!(token.is_cancelled());
+        // The call gets a grace period to answer before it is overdue.
+        assert!(c.next_deadline().is_some());
+        assert!(c.overdue(Instant::now()).is_empty());
+        assert_eq!(c.overdue(Instant::now() + CANCEL_GRACE).len(), 1);
+        assert!(c.finish(&json!(1)).is_none(), "already answered");
+
+        // Cancelled before it started: never runs.
+        c.cancel(json!(2));
+        assert!(c.start(&json!(2), None, None).is_none());
+        assert!(c
+            .start(&json!(3), None, None)
+            .is_some_and(|t| !t.is_cancelled()));
+        assert!(c.finish(&json!(3)).is_some());
+    }
+
+    #[test]
+    fn per_tool_timeout_overrides_default() {
+        let opts = ServerOptions {
+            tool_timeout: Some(Duration::from_secs(30)),
+            tool_timeouts: [
+                ("icm_memory_embed_all".to_string(), Duration::from_secs(600)),
+                ("icm_memory_recall".to_string(), Duration::ZERO),
+            ]
+            .into(),
+            ..Default::default()
+        };
+        assert_eq!(
+            opts.timeout_for("icm_memory_embed_all"),
+            Some(Duration::from_secs(600))
+        );
+        assert_eq!(
+            opts.timeout_for("icm_memory_store"),
+            Some(Duration::from_secs(30))
+        );
+        assert_eq!(opts.timeout_for("icm_memory_recall"), None);
+        assert_eq!(
+            ServerOptions::default().timeout_for("icm_memory_store"),
+            None
+        );
+    }
+
+    #[test]
+    fn lazy_store_opens_on_first_use_and_retries_failures() {
+        let attempts = std::cell::Cell::new(0);
+        let open = || {
+            attempts.set(attempts.get() + 1);
+            if attempts.get() == 1 {
+                anyhow::bail!("database is locked");
+            }
+            Ok(Store::in_memory()?)
+        };
+        let mut slot = StoreSlot::Lazy {
+            open: &open,
+            store: None,
+        };
+        assert!(slot.opened().is_none());
+        assert_eq!(attempts.get(), 0);
+
+        assert!(slot.get().is_err());
+        assert!(slot.get().is_ok());
+        assert!(slot.get().is_ok());
+        assert!(slot.opened().is_some());
+        assert_eq!(attempts.get(), 2);
+    }
+
+    #[test]
+    fn batches_are_answered_with_an_array() {
+        let opts = ServerOptions::default();
+        let (jobs, queued) = mpsc::channel();
+        let mut dispatcher = Dispatcher {
+            server_opts: &opts,
+            jobs,
+            embedder_ok: false,
+            next_batch: 0,
+        };
+        let mut session = Session::new(Box::new(io::sink()));
+        let reply = dispatcher.handle_frame(
+            0,
+            &mut session,
+            json!([
+                {"jsonrpc": "2.0", "id": 1, "method": "initialize", "params": {}},
+                {"jsonrpc": "2.0", "method": "notifications/initialized"},
+                {"jsonrpc": "2.0", "id": 2, "method": "tools/call",
+                 "params": {"name": "icm_memory_stats", "arguments": {}}},
+                1
+            ]),
+        );
+        // The tool call went to the worker; the batch waits for it.
+        assert!(reply.is_none());
+        let Ok(Job::Call { id, .. }) = queued.try_recv() else {
+            panic!("tool call not queued");
+        };
+        assert_eq!(id, 2);
+        let call = session.calls.finish(&id).unwrap();
+        let done = to_value(&JsonRpcResponse::ok(id, json!({"content": []})));
+        let reply = session.resolve(call, done).unwrap();
+        let replies = reply.as_array().unwrap();
+        assert_eq!(replies.len(), 3, "{reply}");
+        assert_eq!(replies[0]["id"], 1);
+        assert_eq!(replies[1]["id"], Value::Null);
+        assert_eq!(replies[1]["error"]["code"], -32600);
+        assert_eq!(replies[2]["id"], 2);
+        assert_eq!(session.lifecycle, Lifecycle::Running);
+        assert!(session.batches.is_empty());
+
+        // Only notifications: nothing to send back.
+        let notes = json!([{"jsonrpc": "2.0", "method": "notifications/initialized"}]);
+        assert!(dispatcher.handle_frame(0, &mut session, notes).is_none());
+        let empty = dispatcher.handle_frame(0, &mut session, json!([])).unwrap();
+        assert_eq!(empty["error"]["code"], -32600);
+    }
+
+    /// Collects what the server writes, one JSON message per line.
+    #[derive(Clone, Default)]
+    struct Capture(std::sync::Arc<std::sync::Mutex<Vec<u8>>>);
+
+    impl Write for Capture {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl Capture {
+        fn messages(&self) -> Vec<Value> {
+            String::from_utf8(self.0.lock().unwrap().clone())
+                .unwrap()
+                .lines()
+                .map(|l| serde_json::from_str(l).unwrap())
+                .collect()
+        }
+    }
+
+    /// An embedder that takes far longer than the tool timeout.
+    struct SlowEmbedder;
+
+    impl Embedder for SlowEmbedder {
+        fn embed(&self, _text: &str) -> icm_core::IcmResult<Vec<f32>> {
+            thread::sleep(Duration::from_millis(1500));
+            Ok(vec![0.0; 4])
+        }
+        fn embed_batch(&self, texts: &[&str]) -> icm_core::IcmResult<Vec<Vec<f32>>> {
+            thread::sleep(Duration::from_millis(1500));
+            Ok(vec![vec![0.0; 4]; texts.len()])
+        }
+        fn dimensions(&self) -> usize {
+            4
+        }
+    }
+
+    #[test]
+    fn slow_calls_do_not_block_other_requests_and_time_out() {
+        use icm_core::MemoryStore;
+        let store = Store::in_memory().unwrap();
+        store
+            .store(icm_core::Memory::new(
+                "t".into(),
+                "needs a vector".into(),
+                icm_core::Importance::Medium,
+            ))
+            .unwrap();
+        let opts = ServerOptions {
+            tool_timeout: Some(Duration::from_millis(50)),
+            ..Default::default()
+        };
+        let out = Capture::default();
+        let (tx, rx) = mpsc::channel();
+        tx.send(Event::Connected(0, Session::new(Box::new(out.clone()))))
+            .unwrap();
+        for msg in [
+            json!({"jsonrpc": "2.0", "id": 1, "method": "initialize", "params": {}}),
+            json!({"jsonrpc": "2.0", "id": 2, "method": "tools/call",
+                   "params": {"name": "icm_memory_embed_all", "arguments": {}}}),
+            json!({"jsonrpc": "2.0", "id": 3, "method": "ping"}),
+        ] {
+            tx.send(Event::Frame(0, Frame::Message(msg.to_string())))
+                .unwrap();
+        }
+        tx.send(Event::Disconnected(0)).unwrap();
+
+        let started = Instant::now();
+        request_loop(
+            StoreSlot::Ready(&store),
+            Some(&SlowEmbedder),
+            opts,
+            tx,
+            rx,
+            false,
+        )
+        .unwrap();
+
+        let messages = out.messages();
+        let ids: Vec<&Value> = messages.iter().map(|m| &m["id"]).collect();
+        // The ping is answered while the embedding is still running, and
+        // the call is answered at its timeout (plus grace), not when the
+        // embedder returns.
+        assert_eq!(ids, [&json!(1), &json!(3), &json!(2)], "{messages:?}");
+        assert_eq!(messages[2]["error"]["code"], REQUEST_CANCELLED);
+        assert_eq!(messages[2]["error"]["message"], "request timed out");
+        assert!(started.elapsed() >= CANCEL_GRACE);
+    }
+
+    #[test]
+    fn lifecycle_gates_requests() {
+        let code = |state: Lifecycle, method: &str| state.rejection(method).map(|(code, _)| code);
+        assert_eq!(
+            code(Lifecycle::Connected, "tools/list"),
+            Some(SERVER_NOT_INITIALIZED)
+        );
+        assert_eq!(code(Lifecycle::Connected, "ping"), None);
+        assert_eq!(code(Lifecycle::Connected, "initialize"), None);
+        // Served before `notifications/initialized`, for lenient clients.
+        assert_eq!(code(Lifecycle::Initializing, "tools/call"), None);
+        assert_eq!(code(Lifecycle::Running, "initialize"), Some(-32600));
+        assert_eq!(code(Lifecycle::ShuttingDown, "tools/call"), Some(-32600));
+        assert_eq!(code(Lifecycle::ShuttingDown, "ping"), None);
+        assert!(!Lifecycle::Connected.accepts_notifications());
+        assert!(Lifecycle::Initializing.accepts_notifications());
+    }
+
+    #[test]
+    fn wakes_at_the_earlier_deadline() {
+        let now = Instant::now();
+        let later = now + Duration::from_secs(60);
+        assert_eq!(next_wake(Some(now), Some(later)), Some(now));
+        assert_eq!(next_wake(Some(later), Some(now)), Some(now));
+        assert_eq!(next_wake(None, Some(later)), Some(later));
+        assert_eq!(next_wake(Some(later), None), Some(later));
+        assert_eq!(next_wake(None, None), None);
+    }
+
+    #[test]
+    fn progress_uses_the_client_token() {
+        let params =
+            Some(json!({"name": "icm_memory_embed_all", "_meta": {"progressToken": "p1"}}));
+        let token = progress_token(&params).unwrap();
+        let note = progress_notification(&token, 64, 200, "embedding memories");
+        assert_eq!(note["method"], "notifications/progress");
+        assert_eq!(note["params"]["progressToken"], "p1");
+        assert_eq!(note["params"]["progress"], 64);
+        assert_eq!(note["params"]["total"], 200);
+        assert!(note.get("id").is_none());
+
+        assert_eq!(progress_token(&Some(json!({"name": "x"}))), None);
+        assert_eq!(progress_token(&None), None);
+    }
+}
@@ -0,0 +1,145 @@
+//! Criterion benchmarks for the store calls hooks make on every prompt,
+//! where latency adds directly to prompt time.
+//!
+//! ```sh
+//! cargo bench -p icm-store
+//! cargo bench -p icm-store -- search            # only matching benches
+//! ICM_BENCH_COUNT=5000 cargo bench -p icm-store   # larger seeded store
+//! ```
+//!
+//! Runs against a file-backed WAL database, as the hooks do.
+
+use std::hint::black_box;
+
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion};
+use icm_core::{Importance, Memory, MemoryStore};
+use icm_store::SqliteStore;
+use tempfile::TempDir;
+
+const TOPICS: [&str; 5] = [
+    "architecture",
+    "preferences",
+    "errors-resolved",
+    "context-project",
+    "decisions",
+];
+
+const QUERIES: [&str; 5] = [
+    "database architecture",
+    "authentication flow",
+    "error handling",
+    "user preferences",
+    "deployment config",
+];
+
+fn memory(i: usize) -> Memory {
+    let topic = TOPICS[i % TOPICS.len()].to_string();
+    let content =
+        format!("Benchmark memory number {i} about {topic} with some extra words for FTS matching");
+    let mut mem = Memory::new(topic, content, Importance::Medium);
+    mem.keywords = vec![format!("kw{}", i % 50), format!("bench{}", i % 20)];
+    mem
+}
+
+/// Memories seeded before the read benches (`ICM_BENCH_COUNT`).
+fn seed_count() -> usize {
+    std::env::var("ICM_BENCH_COUNT")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(1000)
+}
+
+/// A file-backed store holding `count` memories, with their ids. The
+/// directory must outlive the store.
+fn seeded_store(count: usize) -> (TempDir, SqliteStore, Vec<String>) {
+    let dir = tempfile::tempdir().expect("tempdir");
+    let store = SqliteStore::new(&dir.path().join("bench.db")).expect("open store");
+    let ids = (0..count)
+        .map(|i| store.store(memory(i)).expect("store"))
+        .collect();
+    (dir, store, ids)
+}
+
+fn bench_writes(c: &mut Criterion) {
+    let (_dir, store, ids) = seeded_store(seed_count());
+
+    let mut next = ids.len();
+    c.bench_function("store", |b| {
+        b.iter(|| {
+            next += 1;
+            store.store(memory(next)).expect("store")
+        })
+    });
+
+    // Re-storing the same content takes the dedup-merge path.
+    let mut i = 0;
+    c.bench_function("store (dedup hit)", |b| {
+        b.iter(|| {
+            i += 1;
+            store.store(memory(i % ids.len())).expect("store")
+        })
+    });
+
+    let mut i = 0;
+    c.bench_function("update_access", |b| {
+        b.iter(|| {
+            i += 1;
+            store
+                .update_access(&ids[i % ids.len()])
+                .expect("update_access")
+        })
+    });
+}
+
+fn bench_reads(c: &mut Criterion) {
+    let (_dir, store, ids) = seeded_store(seed_count());
+
+    let mut i = 0;
+    c.bench_function("search_fts", |b| {
+        b.iter(|| {
+            i += 1;
+            store
+                .search_fts(black_box(QUERIES[i % QUERIES.len()]), 10)
+                .expect("search_fts")
+        })
+    });
+
+    let mut i = 0;
+    c.bench_function("search_by_keywords", |b| {
+        b.iter(|| {
+            i += 1;
+            store
+                .search_by_keywords(&["kw7", TOPICS[i % TOPICS.len()]], 10)
+                .expect("search_by_keywords")
+        })
+    });
+
+    // `update_access` invalidates the LRU entry, so each timed `get`
+    // reads from SQLite rather than the in-process cache.
+    let mut i = 0;
+    c.bench_function("get (uncached)", |b| {
+        b.iter_batched(
+            || {
+                i += 1;
+                let id = &ids[i % ids.len()];
+                store.update_access(id).expect("update_access");
+                id
+            },
+            |id| store.get(id).expect("get"),
+            BatchSize::SmallInput,
+        )
+    });
+
+    let mut i = 0;
+    c.bench_function("get_by_topic", |b| {
+        b.iter(|| {
+            i += 1;
+            store
+                .get_by_topic(TOPICS[i % TOPICS.len()])
+                .expect("get_by_topic")
+        })
+    });
+}
+
+criterion_group!(benches, bench_writes, bench_reads);
+criterion_main!(benches);
@@ -6,19 +6,26 @@
 //! single binary can carry every backend and pick one without a rebuild.
 //! Cargo features only decide which variants are available.
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::path::Path;
 
 use chrono::{DateTime, Utc};
 
 use icm_core::{
-    Concept, ConceptLink, Embedder, Fact, FactsStats, FactsStore, Feedback, FeedbackStats,
-    FeedbackStore, IcmError, IcmResult, Label, Memoir, MemoirStats, MemoirStore, Memory,
-    MemoryStore, Message, PatternCluster, Relation, Role, Session, StoreStats, TopicHealth,
-    TranscriptHit, TranscriptStats, TranscriptStore,
+    Concept, ConceptLink, ConceptRevision, Embedder, Fact, FactsStats, FactsStore, Feedback,
+    FeedbackStats, FeedbackStore, HybridScore, IcmError, IcmResult, Importance, KeywordOptions,
+    Label, Memoir, MemoirStats, MemoirStore, Memory, MemoryKind, MemoryLimits, MemoryLink,
+    MemoryStore, Message, PatternCluster, RecallFeedback, Reinforcement, Relation, Role,
+    ScrubReport, Scrubber, Session, StoreStats, TopicHealth, TranscriptHit, TranscriptStats,
+    TranscriptStore,
 };
 
-use crate::common::{CodeArea, HookEvent, HookEventInsert, HookStatsRow, PendingRow};
+use crate::common::{
+    CodeArea, HookEvent, HookEventInsert, HookStatsRow, KeyedOutcome, LinkStep, MemoryIter,
+    PendingRow, ReviewItem, SessionContext, SessionContextInsert, StorageStats, StoreOutcome,
+    TopicDigest,
+};
+use crate::vector_index::IndexKind;
 
 #[cfg(feature = "backend-sqlite")]
 use crate::store::SqliteStore;
@@ -273,15 +280,120 @@ impl Store {
         }
     }
 
+    /// Peek how many memories hold an embedding. Only the SQLite backend
+    /// drops embeddings when the dimension changes, so the remote
+    /// backends report zero.
+    pub fn count_stored_embeddings(path: &Path) -> IcmResult<usize> {
+        match BackendKind::from_env()? {
+            BackendKind::Sqlite => {
+                #[cfg(feature = "backend-sqlite")]
+                {
+                    SqliteStore::count_stored_embeddings(path)
+                }
+                #[cfg(not(feature = "backend-sqlite"))]
+                {
+                    let _ = path;
+                    Ok(0)
+                }
+            }
+            BackendKind::Postgres | BackendKind::OpenSearch => Ok(0),
+        }
+    }
+
+    /// Serve vector search from `kind` (see [`IndexKind`]). Only the
+    /// SQLite backend has a choice; the remote backends search natively
+    /// and reject anything but the default.
+    pub fn with_vector_index(self, kind: IndexKind, path: &Path) -> IcmResult<Self> {
+        match self {
+            #[cfg(feature = "backend-sqlite")]
+            Store::Sqlite(s) => Ok(Store::Sqlite(s.with_vector_index(kind, path)?)),
+            #[allow(unreachable_patterns)]
+            other => {
+                let _ = path;
+                match kind {
+                    IndexKind::SqliteVec => Ok(other),
+                    _ => Err(IcmError::Unsupported(format!("vector index '{kind}'"))),
+                }
+            }
+        }
+    }
+
+    /// Stop in-flight SQLite work once `should_stop` returns true (see
+    /// [`SqliteStore::set_interrupt_check`]); `None` removes the check.
+    /// The remote backends ignore it.
+    pub fn set_interrupt_check(&self, should_stop: Option<Box<dyn FnMut() -> bool + Send>>) {
+        match self {
+            #[cfg(feature = "backend-sqlite")]
+            Store::Sqlite(s) => s.set_interrupt_check(should_stop),
+            #[allow(unreachable_patterns)]
+            _ => drop(should_stop),
+        }
+    }
+
+    /// Scrub secrets out of every memory written through this store
+    /// (`store`, `update`, consolidation, the review queue).
+    pub fn with_scrubber(mut self, scrubber: Scrubber) -> Self {
+        dispatch!(&mut self, write_policy_mut()).scrubber = Some(scrubber);
+        self
+    }
+
+    /// Normalize every memory written through this store and reject
+    /// those over `limits` with [`IcmError::Validation`].
+    pub fn with_limits(mut self, limits: MemoryLimits) -> Self {
+        dispatch!(&mut self, write_policy_mut()).limits = Some(limits);
+        self
+    }
+
+    /// Rank full-text hits by a blend of text relevance and memory
+    /// weight: `1.0` orders by BM25 alone, `0.0` by weight alone.
+    /// Values are clamped to `0.0..=1.0`. OpenSearch always ranks by its
+    /// own BM25 score and ignores this.
+    pub fn with_fts_relevance(mut self, relevance: f32) -> Self {
+        dispatch!(&mut self, set_fts_relevance(relevance.clamp(0.0, 1.0)));
+        self
+    }
+
+    /// How `search_by_keywords` combines terms: `any`/`all` mode,
+    /// minimum matches and stop-word removal. `+term` / `-term` in the
+    /// query always require / exclude a term.
+    pub fn with_keyword_options(mut self, options: KeywordOptions) -> Self {
+        dispatch!(&mut self, set_keyword_options(options));
+        self
+    }
+
+    /// Raise each recalled memory's weight by `rate * (1 - weight)`, up
+    /// to `cap`, whenever `update_access` / `batch_update_access` count
+    /// an access. Both values are clamped to `0.0..=1.0`;
+    /// [`Reinforcement::OFF`] only counts accesses.
+    pub fn with_reinforcement(mut self, reinforcement: Reinforcement) -> Self {
+        dispatch!(&mut self, set_reinforcement(reinforcement.clamped()));
+        self
+    }
+
+    /// Redactions made since the last call, resetting the tally.
+    pub fn take_scrub_report(&self) -> ScrubReport {
+        dispatch!(self, write_policy()).take_report()
+    }
+
+    fn prepare(&self, memory: &mut Memory) -> IcmResult<()> {
+        dispatch!(self, write_policy()).apply(memory)
+    }
+
     /// Whether the active store was opened read-only.
     pub fn is_readonly(&self) -> bool {
         dispatch!(self, is_readonly())
     }
 
+    /// Stream every memory in id order, a page at a time. Prefer this to
+    /// [`MemoryStore::list_all`] for whole-database passes.
+    pub fn iter_memories(&self) -> MemoryIter<'_> {
+        dispatch!(self, iter_memories())
+    }
+
     // --- Inherent store/recall/hook surface (forwarded) ---
 
-    pub fn maybe_auto_decay(&self) -> IcmResult<()> {
-        dispatch!(self, maybe_auto_decay())
+    pub fn maybe_auto_decay(&self, factor: f32) -> IcmResult<Option<usize>> {
+        dispatch!(self, maybe_auto_decay(factor))
     }
     pub fn increment_hook_counter(&self) -> IcmResult<usize> {
         dispatch!(self, increment_hook_counter())
@@ -309,6 +421,20 @@ impl Store {
     pub fn pending_extraction_count(&self) -> IcmResult<usize> {
         dispatch!(self, pending_extraction_count())
     }
+    pub fn queue_for_review(&self, memory: &Memory, origin: &str) -> IcmResult<String> {
+        let mut memory = memory.clone();
+        self.prepare(&mut memory)?;
+        dispatch!(self, queue_for_review(&memory, origin))
+    }
+    pub fn list_review_queue(&self, limit: usize) -> IcmResult<Vec<ReviewItem>> {
+        dispatch!(self, list_review_queue(limit))
+    }
+    pub fn remove_from_review_queue(&self, id: &str) -> IcmResult<bool> {
+        dispatch!(self, remove_from_review_queue(id))
+    }
+    pub fn review_queue_count(&self) -> IcmResult<usize> {
+        dispatch!(self, review_queue_count())
+    }
     pub fn upsert_code_area(
         &self,
         project: &str,
@@ -353,6 +479,50 @@ impl Store {
     pub fn hook_event_count(&self) -> IcmResult<usize> {
         dispatch!(self, hook_event_count())
     }
+    pub fn session_injections(
+        &self,
+        session_id: &str,
+    ) -> IcmResult<HashMap<String, DateTime<Utc>>> {
+        dispatch!(self, session_injections(session_id))
+    }
+    pub fn record_session_injections(
+        &self,
+        session_id: &str,
+        memories: &[Memory],
+    ) -> IcmResult<()> {
+        dispatch!(self, record_session_injections(session_id, memories))
+    }
+    pub fn clear_session_injections(
+        &self,
+        session_id: &str,
+        cutoff_rfc3339: &str,
+    ) -> IcmResult<usize> {
+        dispatch!(self, clear_session_injections(session_id, cutoff_rfc3339))
+    }
+    /// Log what a hook fire injected into or extracted from a session.
+    pub fn record_session_context(&self, ctx: &SessionContextInsert) -> IcmResult<i64> {
+        dispatch!(self, record_session_context(ctx))
+    }
+    /// A session's context log, oldest first.
+    pub fn session_context(&self, session_id: &str) -> IcmResult<Vec<SessionContext>> {
+        dispatch!(self, session_context(session_id))
+    }
+    /// Rebuild every topic's digest from its `per_topic` heaviest memories.
+    pub fn refresh_topic_digests(&self, per_topic: usize) -> IcmResult<usize> {
+        dispatch!(self, refresh_topic_digests(per_topic))
+    }
+    pub fn topic_digests(&self) -> IcmResult<Vec<TopicDigest>> {
+        dispatch!(self, topic_digests())
+    }
+    /// Cached per-importance embedding centroids for
+    /// [`icm_core::resolve_importance`].
+    pub fn importance_centroids(&self) -> IcmResult<Vec<(Importance, Vec<f32>)>> {
+        dispatch!(self, importance_centroids())
+    }
+    /// Recompute and cache the importance centroids.
+    pub fn refresh_importance_centroids(&self) -> IcmResult<Vec<(Importance, Vec<f32>)>> {
+        dispatch!(self, refresh_importance_centroids())
+    }
     pub fn auto_consolidate(&self, topic: &str, threshold: usize) -> IcmResult<bool> {
         dispatch!(self, auto_consolidate(topic, threshold))
     }
@@ -367,6 +537,74 @@ impl Store {
             auto_consolidate_with_embedder(topic, threshold, embedder)
         )
     }
+    pub fn auto_consolidate_with_opts(
+        &self,
+        topic: &str,
+        threshold: usize,
+        embedder: Option<&dyn Embedder>,
+        keep_originals: bool,
+    ) -> IcmResult<bool> {
+        dispatch!(
+            self,
+            auto_consolidate_with_opts(topic, threshold, embedder, keep_originals)
+        )
+    }
+    pub fn consolidation_proposal(
+        &self,
+        topic: &str,
+        threshold: usize,
+    ) -> IcmResult<Option<Memory>> {
+        dispatch!(self, consolidation_proposal(topic, threshold))
+    }
+    pub fn rebuild_vector_index(&self) -> IcmResult<usize> {
+        dispatch!(self, rebuild_vector_index())
+    }
+    pub fn storage_stats(&self) -> IcmResult<StorageStats> {
+        dispatch!(self, storage_stats())
+    }
+    /// Content of the attachment with this hash, if it is still stored.
+    pub fn attachment(&self, hash: &str) -> IcmResult<Option<String>> {
+        dispatch!(self, attachment(hash))
+    }
+    /// Store `memory` under its own id unless that id is already taken,
+    /// in which case nothing is written. With an id from
+    /// [`icm_core::idempotent_id`], retrying a store is a no-op. Atomic
+    /// on SQLite; a check then a write on the remote backends.
+    pub fn store_idempotent(&self, memory: Memory) -> IcmResult<StoreOutcome> {
+        match self {
+            #[cfg(feature = "backend-sqlite")]
+            Store::Sqlite(s) => s.store_idempotent(memory),
+            #[allow(unreachable_patterns)]
+            other => {
+                if other.get(&memory.id)?.is_some() {
+                    return Ok(StoreOutcome {
+                        id: memory.id,
+                        created: false,
+                    });
+                }
+                let wanted = memory.id.clone();
+                let id = other.store(memory)?;
+                Ok(StoreOutcome {
+                    created: id == wanted,
+                    id,
+                })
+            }
+        }
+    }
+    /// Store `memory` as the value of a stable `key`, replacing the memory
+    /// that held it (same id and `created_at`, next revision).
+    pub fn store_keyed(&self, key: &str, memory: Memory) -> IcmResult<KeyedOutcome> {
+        dispatch!(self, store_keyed(key, memory))
+    }
+    /// Fill in `memory.raw_excerpt` from its attachment, if it has one.
+    pub fn load_attachment(&self, memory: &mut Memory) -> IcmResult<()> {
+        if memory.raw_excerpt.is_none() {
+            if let Some(hash) = &memory.attachment {
+                memory.raw_excerpt = self.attachment(hash)?;
+            }
+        }
+        Ok(())
+    }
     pub fn expand_with_neighbors(
         &self,
         initial: &[(Memory, f32)],
@@ -382,9 +620,120 @@ impl Store {
     pub fn get_many(&self, ids: &[&str]) -> IcmResult<HashMap<String, Memory>> {
         dispatch!(self, get_many(ids))
     }
+    /// Point references to the memories `old` (memories' `related_ids`,
+    /// concepts' `source_memory_ids`) at `new`, or drop them when `new`
+    /// is `None`. Returns the rows rewritten.
+    pub fn replace_memory_refs(&self, old: &[&str], new: Option<&str>) -> IcmResult<usize> {
+        dispatch!(self, replace_memory_refs(old, new))
+    }
+    /// Ids that some memory's `related_ids` or some concept's
+    /// `source_memory_ids` still reference but that no longer exist,
+    /// sorted. Deletes clean up after themselves; this finds what older
+    /// versions, bulk deletes and imports left behind.
+    pub fn dangling_memory_refs(&self) -> IcmResult<Vec<String>> {
+        let mut known: HashSet<String> = HashSet::new();
+        let mut referenced: HashSet<String> = HashSet::new();
+        for memory in self.iter_memories() {
+            let memory = memory?;
+            referenced.extend(memory.related_ids);
+            known.insert(memory.id);
+        }
+        let memoirs = match self.list_memoirs() {
+            Ok(memoirs) => memoirs,
+            Err(IcmError::Unsupported(_)) => Vec::new(),
+            Err(e) => return Err(e),
+        };
+        for memoir in memoirs {
+            for concept in self.list_concepts(&memoir.id)? {
+                referenced.extend(concept.source_memory_ids);
+            }
+        }
+        let mut dangling: Vec<String> = referenced.difference(&known).cloned().collect();
+        dangling.sort();
+        Ok(dangling)
+    }
     pub fn get_by_topic_prefix(&self, topic: &str) -> IcmResult<Vec<Memory>> {
         dispatch!(self, get_by_topic_prefix(topic))
     }
+    /// Every memory of `kind` (`icm prefs list`), heaviest first.
+    pub fn list_by_kind(&self, kind: MemoryKind) -> IcmResult<Vec<Memory>> {
+        match self {
+            #[cfg(feature = "backend-sqlite")]
+            Store::Sqlite(s) => s.list_by_kind(kind),
+            #[allow(unreachable_patterns)]
+            other => {
+                let mut all = other.list_all()?;
+                all.retain(|m| m.kind == kind);
+                Ok(all)
+            }
+        }
+    }
+    /// The `limit` most recently created memories, newest first,
+    /// optionally narrowed to a topic (`foo*` for a prefix), a source
+    /// kind and a creation cutoff.
+    pub fn list_recent(
+        &self,
+        topic: Option<&str>,
+        source: Option<&str>,
+        since: Option<DateTime<Utc>>,
+        limit: usize,
+    ) -> IcmResult<Vec<Memory>> {
+        dispatch!(self, list_recent(topic, source, since, limit))
+    }
+    /// Link two memories with a typed relation (idempotent per
+    /// `(source, target, relation)`); returns the link id.
+    pub fn add_memory_link(&self, link: MemoryLink) -> IcmResult<String> {
+        dispatch!(self, add_memory_link(link))
+    }
+    pub fn remove_memory_link(
+        &self,
+        source_id: &str,
+        target_id: &str,
+        relation: Option<Relation>,
+    ) -> IcmResult<usize> {
+        dispatch!(self, remove_memory_link(source_id, target_id, relation))
+    }
+    /// Links starting or ending at `memory_id`.
+    pub fn memory_links(&self, memory_id: &str) -> IcmResult<Vec<MemoryLink>> {
+        dispatch!(self, memory_links(memory_id))
+    }
+    /// Walk the link graph from `memory_id` in both directions, up to
+    /// `max_depth` hops, so chains like error → fix → revision come out
+    /// whole. Depth-first: each step is followed by the steps reached
+    /// through it. Each memory is reached once; links back into one
+    /// already reached are skipped.
+    pub fn memory_link_chain(&self, memory_id: &str, max_depth: usize) -> IcmResult<Vec<LinkStep>> {
+        fn walk(
+            store: &Store,
+            from: &str,
+            depth: usize,
+            max_depth: usize,
+            visited: &mut HashSet<String>,
+            steps: &mut Vec<LinkStep>,
+        ) -> IcmResult<()> {
+            if depth > max_depth {
+                return Ok(());
+            }
+            for link in store.memory_links(from)? {
+                let next = link.other_end(from).to_string();
+                if !visited.insert(next.clone()) {
+                    continue;
+                }
+                steps.push(LinkStep {
+                    depth,
+                    from: from.to_string(),
+                    link,
+                });
+                walk(store, &next, depth + 1, max_depth, visited, steps)?;
+            }
+            Ok(())
+        }
+
+        let mut visited = HashSet::from([memory_id.to_string()]);
+        let mut steps = Vec::new();
+        walk(self, memory_id, 1, max_depth, &mut visited, &mut steps)?;
+        Ok(steps)
+    }
     pub fn list_topics_with_prefix(&self, prefix: Option<&str>) -> IcmResult<Vec<(String, usize)>> {
         dispatch!(self, list_topics_with_prefix(prefix))
     }
@@ -405,14 +754,17 @@ impl Store {
 }
 
 impl MemoryStore for Store {
-    fn store(&self, memory: Memory) -> IcmResult<String> {
+    fn store(&self, mut memory: Memory) -> IcmResult<String> {
+        self.prepare(&mut memory)?;
         dispatch!(self, store(memory))
     }
     fn get(&self, id: &str) -> IcmResult<Option<Memory>> {
         dispatch!(self, get(id))
     }
     fn update(&self, memory: &Memory) -> IcmResult<()> {
-        dispatch!(self, update(memory))
+        let mut memory = memory.clone();
+        self.prepare(&mut memory)?;
+        dispatch!(self, update(&memory))
     }
     fn delete(&self, id: &str) -> IcmResult<()> {
         dispatch!(self, delete(id))
@@ -430,6 +782,14 @@ impl MemoryStore for Store {
     ) -> IcmResult<Vec<(Memory, f32)>> {
         dispatch!(self, search_by_embedding(embedding, limit))
     }
+    fn search_hybrid_scored(
+        &self,
+        query: &str,
+        embedding: &[f32],
+        limit: usize,
+    ) -> IcmResult<Vec<(Memory, HybridScore)>> {
+        dispatch!(self, search_hybrid_scored(query, embedding, limit))
+    }
     fn search_hybrid(
         &self,
         query: &str,
@@ -453,13 +813,18 @@ impl MemoryStore for Store {
     fn list_all(&self) -> IcmResult<Vec<Memory>> {
         dispatch!(self, list_all())
     }
+
+    fn get_all(&self, limit: usize, offset: usize) -> IcmResult<Vec<Memory>> {
+        dispatch!(self, get_all(limit, offset))
+    }
     fn get_by_topic(&self, topic: &str) -> IcmResult<Vec<Memory>> {
         dispatch!(self, get_by_topic(topic))
     }
     fn list_topics(&self) -> IcmResult<Vec<(String, usize)>> {
         dispatch!(self, list_topics())
     }
-    fn consolidate_topic(&self, topic: &str, consolidated: Memory) -> IcmResult<()> {
+    fn consolidate_topic(&self, topic: &str, mut consolidated: Memory) -> IcmResult<()> {
+        self.prepare(&mut consolidated)?;
         dispatch!(self, consolidate_topic(topic, consolidated))
     }
     fn count(&self) -> IcmResult<usize> {
@@ -521,13 +886,17 @@ impl MemoirStore for Store {
     ) -> IcmResult<Vec<Concept>> {
         dispatch!(self, search_concepts_fts(memoir_id, query, limit))
     }
-    fn search_concepts_by_label(
+    fn search_concepts_by_labels(
         &self,
         memoir_id: &str,
-        label: &Label,
+        labels: &[Label],
+        match_all: bool,
         limit: usize,
     ) -> IcmResult<Vec<Concept>> {
-        dispatch!(self, search_concepts_by_label(memoir_id, label, limit))
+        dispatch!(
+            self,
+            search_concepts_by_labels(memoir_id, labels, match_all, limit)
+        )
     }
     fn search_all_concepts_fts(&self, query: &str, limit: usize) -> IcmResult<Vec<Concept>> {
         dispatch!(self, search_all_concepts_fts(query, limit))
@@ -540,6 +909,12 @@ impl MemoirStore for Store {
     ) -> IcmResult<()> {
         dispatch!(self, refine_concept(id, new_definition, new_source_ids))
     }
+    fn concept_revisions(&self, concept_id: &str) -> IcmResult<Vec<ConceptRevision>> {
+        dispatch!(self, concept_revisions(concept_id))
+    }
+    fn rollback_concept(&self, concept_id: &str, revision: u32) -> IcmResult<()> {
+        dispatch!(self, rollback_concept(concept_id, revision))
+    }
     fn add_link(&self, link: ConceptLink) -> IcmResult<String> {
         dispatch!(self, add_link(link))
     }
@@ -552,6 +927,12 @@ impl MemoirStore for Store {
     fn delete_link(&self, id: &str) -> IcmResult<()> {
         dispatch!(self, delete_link(id))
     }
+    fn update_link_weight(&self, id: &str, weight: f32) -> IcmResult<()> {
+        dispatch!(self, update_link_weight(id, weight))
+    }
+    fn decay_links(&self, factor: f32) -> IcmResult<usize> {
+        dispatch!(self, decay_links(factor))
+    }
     fn get_neighbors(
         &self,
         concept_id: &str,
@@ -601,6 +982,16 @@ impl FeedbackStore for Store {
     fn feedback_stats(&self) -> IcmResult<FeedbackStats> {
         dispatch!(self, feedback_stats())
     }
+    fn record_recall_feedback(&self, feedback: RecallFeedback) -> IcmResult<f32> {
+        dispatch!(self, record_recall_feedback(feedback))
+    }
+    fn list_recall_feedback(
+        &self,
+        memory_id: Option<&str>,
+        limit: usize,
+    ) -> IcmResult<Vec<RecallFeedback>> {
+        dispatch!(self, list_recall_feedback(memory_id, limit))
+    }
 }
 
 impl FactsStore for Store {
@@ -5,7 +5,17 @@
 //! a single binary without colliding type definitions. The runtime
 //! [`crate::Store`] enum dispatches across whichever backends are enabled.
 
+use std::collections::HashSet;
+use std::sync::Mutex;
+
 use chrono::{DateTime, Utc};
+use icm_core::{IcmResult, KeywordQuery, Memory, MemoryLimits, MemoryLink, ScrubReport, Scrubber};
+
+/// Topic that auto-consolidation moves a topic's originals to when
+/// asked to keep them.
+pub fn archived_topic(topic: &str) -> String {
+    format!("{}{topic}", icm_core::ARCHIVE_TOPIC_PREFIX)
+}
 
 /// One row of the `hook_events` telemetry table.
 #[derive(Debug, Clone)]
@@ -36,6 +46,85 @@ pub struct HookEventInsert {
     pub note: Option<String>,
 }
 
+/// A topic's heaviest memories condensed into one line. Precomputed by
+/// `refresh_topic_digests` so the fast prompt hook can inject a topic
+/// without scanning it.
+#[derive(Debug, Clone)]
+pub struct TopicDigest {
+    pub topic: String,
+    pub digest: String,
+    /// Memories in the topic when the digest was built.
+    pub memory_count: usize,
+    pub refreshed_at: DateTime<Utc>,
+}
+
+/// What one hook fire put into, or extracted from, an agent session.
+/// `icm sessions show` replays these to reconstruct what the agent knew.
+#[derive(Debug, Clone)]
+pub struct SessionContext {
+    pub id: i64,
+    pub session_id: String,
+    pub ts: DateTime<Utc>,
+    /// Hook event: `start`, `compact`, `prompt`, `post`, `pre-compact`,
+    /// `session-end`.
+    pub event: String,
+    /// Recall query, for `prompt`.
+    pub query: Option<String>,
+    /// Context text handed to the agent, empty for extraction-only fires.
+    pub injected: String,
+    /// Memories injected, when the hook knows them individually.
+    pub memory_ids: Vec<String>,
+    /// Facts extracted from the session and stored.
+    pub facts_extracted: usize,
+}
+
+/// Insert payload for one `session_context` row. `id` and `ts` are
+/// filled in by the store.
+#[derive(Debug, Clone, Default)]
+pub struct SessionContextInsert {
+    pub session_id: String,
+    pub event: String,
+    pub query: Option<String>,
+    pub injected: String,
+    pub memory_ids: Vec<String>,
+    pub facts_extracted: usize,
+}
+
+/// One link reached while walking a memory's link graph
+/// ([`crate::Store::memory_link_chain`]).
+#[derive(Debug, Clone)]
+pub struct LinkStep {
+    /// 1 for the starting memory's own links, 2 for its neighbours', …
+    pub depth: usize,
+    /// The memory the walk reached this link from.
+    pub from: String,
+    pub link: MemoryLink,
+}
+
+/// Summaries per topic that go into a [`TopicDigest`].
+pub const DIGEST_ENTRIES: usize = 3;
+
+/// Characters kept of each summary in a [`TopicDigest`].
+pub const DIGEST_ENTRY_CHARS: usize = 160;
+
+/// Join a topic's summaries (heaviest first) into its digest line.
+pub fn build_digest<'a>(summaries: impl IntoIterator<Item = &'a str>) -> String {
+    summaries
+        .into_iter()
+        .map(|s| {
+            let s = s.split_whitespace().collect::<Vec<_>>().join(" ");
+            if s.chars().count() > DIGEST_ENTRY_CHARS {
+                let mut cut: String = s.chars().take(DIGEST_ENTRY_CHARS).collect();
+                cut.push('…');
+                cut
+            } else {
+                s
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
 /// One row of the `code_areas` table. A code area is a file the agent
 /// touched during a session; the same `(project, file_path)` increments
 /// `touch_count` on each re-touch rather than producing a duplicate row.
@@ -64,7 +153,228 @@ pub struct HookStatsRow {
     pub p99_duration_ms: i64,
 }
 
+/// On-disk footprint of a store, for `icm stats --storage`.
+#[derive(Debug, Clone, Default)]
+pub struct StorageStats {
+    /// Main database file (page count × page size).
+    pub db_bytes: u64,
+    /// Uncheckpointed write-ahead log, if any.
+    pub wal_bytes: u64,
+    /// Bytes per table / index / shadow table, largest first.
+    pub objects: Vec<(String, u64)>,
+    /// Raw embedding blobs stored on `memories`.
+    pub embedding_bytes: u64,
+    /// Summary, raw excerpt and keyword text stored on `memories`.
+    pub text_bytes: u64,
+    /// Raw excerpts offloaded to the attachment store, and their count.
+    pub attachment_bytes: u64,
+    pub attachment_count: u64,
+    /// zstd-compressed raw excerpts and attachments: bytes on disk, and
+    /// what they would take uncompressed.
+    pub compressed_bytes: u64,
+    pub uncompressed_bytes: u64,
+}
+
+/// What [`crate::Store::store_idempotent`] did with a memory.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StoreOutcome {
+    pub id: String,
+    /// False when the memory's id was already taken, or the same topic
+    /// and summary were already stored: nothing new was written.
+    pub created: bool,
+}
+
+/// Where [`crate::Store::store_keyed`] put a memory.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyedOutcome {
+    pub id: String,
+    /// 1 for the key's first value, then one more per replacement.
+    pub revision: u32,
+}
+
+/// Raw excerpts at least this many bytes long are kept in the
+/// content-addressed attachment store instead of inline on the memory.
+pub const ATTACHMENT_MIN_BYTES: usize = 4 * 1024;
+
+/// A memory written by an agent (MCP or hooks) and held back from recall
+/// until a human approves it with `icm review`.
+#[derive(Debug, Clone)]
+pub struct ReviewItem {
+    pub memory: Memory,
+    /// Where the write came from (`mcp`, `hook`).
+    pub origin: String,
+    pub queued_at: DateTime<Utc>,
+}
+
 /// One row from the async extraction queue:
 /// `(id, project, tool_name, raw_output, captured_at)` where
 /// `captured_at` is RFC3339.
 pub type PendingRow = (String, String, String, String, String);
+
+/// Default share of full-text ranking that comes from text relevance
+/// (BM25) rather than memory weight; see [`crate::Store::with_fts_relevance`].
+pub const DEFAULT_FTS_RELEVANCE: f32 = 0.7;
+
+/// What the store does to a memory before writing it: secret scrubbing
+/// (with a running tally of redactions since the last
+/// [`crate::Store::take_scrub_report`]) and size limits.
+#[derive(Debug, Default)]
+pub(crate) struct WritePolicy {
+    pub(crate) scrubber: Option<Scrubber>,
+    pub(crate) limits: Option<MemoryLimits>,
+    report: Mutex<ScrubReport>,
+}
+
+impl WritePolicy {
+    /// Scrub `memory` in place, then normalize it and enforce the limits.
+    pub(crate) fn apply(&self, memory: &mut Memory) -> IcmResult<()> {
+        if let Some(scrubber) = &self.scrubber {
+            let report = scrubber.scrub_memory(memory);
+            if !report.is_empty() {
+                if let Ok(mut total) = self.report.lock() {
+                    total.merge(report);
+                }
+            }
+        }
+        match &self.limits {
+            Some(limits) => limits.apply(memory),
+            None => Ok(()),
+        }
+    }
+
+    pub(crate) fn take_report(&self) -> ScrubReport {
+        self.report
+            .lock()
+            .map(|mut r| std::mem::take(&mut *r))
+            .unwrap_or_default()
+    }
+}
+
+/// The id list `ids` held by row `owner` (a memory's `related_ids`, a
+/// concept's `source_memory_ids`) once the memories in `old` are gone:
+/// each is replaced by `new`, or dropped when `new` is `None`, without
+/// introducing duplicates or a self-reference. `None` when `ids` holds
+/// none of `old`, so the row needs no write.
+pub(crate) fn rewrite_memory_refs(
+    owner: &str,
+    ids: &[String],
+    old: &HashSet<&str>,
+    new: Option<&str>,
+) -> Option<Vec<String>> {
+    if !ids.iter().any(|id| old.contains(id.as_str())) {
+        return None;
+    }
+    let mut out: Vec<String> = Vec::with_capacity(ids.len());
+    for id in ids {
+        let id = match (old.contains(id.as_str()), new) {
+            (false, _) => id.as_str(),
+            (true, Some(new)) => new,
+            (true, None) => continue,
+        };
+        if id != owner && !out.iter().any(|o| o == id) {
+            out.push(id.to_string());
+        }
+    }
+    Some(out)
+}
+
+/// Rows fetched per page by [`MemoryIter`].
+pub(crate) const MEMORY_PAGE_SIZE: usize = 500;
+
+/// Keyset pagination over the memories table, in ascending id order.
+pub(crate) trait MemoryPages {
+    /// Up to `limit` memories with an id greater than `after` (all ids
+    /// when `None`), ordered by id.
+    fn memories_after(&self, after: Option<&str>, limit: usize) -> IcmResult<Vec<Memory>>;
+}
+
+/// Every memory in the store, in ascending id order, fetched a page at a
+/// time so a large database is never held in memory at once.
+///
+/// No statement stays open between pages, so the caller may write to the
+/// store while iterating. Rows written after the cursor has passed their
+/// id are not revisited.
+pub struct MemoryIter<'a> {
+    source: &'a dyn MemoryPages,
+    page: std::vec::IntoIter<Memory>,
+    last_id: Option<String>,
+    done: bool,
+}
+
+impl<'a> MemoryIter<'a> {
+    pub(crate) fn new(source: &'a dyn MemoryPages) -> Self {
+        Self {
+            source,
+            page: Vec::new().into_iter(),
+            last_id: None,
+            done: false,
+        }
+    }
+}
+
+impl Iterator for MemoryIter<'_> {
+    type Item = IcmResult<Memory>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(m) = self.page.next() {
+            return Some(Ok(m));
+        }
+        if self.done {
+            return None;
+        }
+        let page = match self
+            .source
+            .memories_after(self.last_id.as_deref(), MEMORY_PAGE_SIZE)
+        {
+            Ok(page) => page,
+            Err(e) => {
+                self.done = true;
+                return Some(Err(e));
+            }
+        };
+        self.done = page.len() < MEMORY_PAGE_SIZE;
+        self.last_id = page.last().map(|m| m.id.clone());
+        self.page = page.into_iter();
+        self.page.next().map(Ok)
+    }
+}
+
+/// The WHERE / ORDER BY clauses and LIKE patterns for a parsed keyword
+/// query, shared by the SQL backends. Placeholders are numbered from 1.
+pub(crate) struct KeywordSql {
+    pub(crate) where_clause: String,
+    pub(crate) order_by: String,
+    pub(crate) patterns: Vec<String>,
+}
+
+impl KeywordSql {
+    /// `like` is the (case-insensitive) LIKE operator, `placeholder` the
+    /// parameter sigil and `as_int` the cast turning a boolean into 0/1.
+    pub(crate) fn build(query: &KeywordQuery, like: &str, placeholder: char, as_int: &str) -> Self {
+        let mut patterns = Vec::new();
+        let mut term = |t: &str| {
+            patterns.push(format!("%{t}%"));
+            let p = format!("{placeholder}{}", patterns.len());
+            format!("(keywords {like} {p} OR summary {like} {p} OR topic {like} {p})")
+        };
+        let mut where_parts: Vec<String> = query.must.iter().map(|t| term(t)).collect();
+        where_parts.extend(query.exclude.iter().map(|t| format!("NOT {}", term(t))));
+        // Plain terms rank by how many of them matched, then by weight.
+        let mut order_by = "weight DESC".to_string();
+        if !query.should.is_empty() {
+            let hits = query
+                .should
+                .iter()
+                .map(|t| format!("{}{as_int}", term(t)))
+                .collect::<Vec<_>>()
+                .join(" + ");
+            where_parts.push(format!("({hits}) >= {}", query.min_should));
+            order_by = format!("({hits}) DESC, weight DESC");
+        }
+        Self {
+            where_clause: where_parts.join(" AND "),
+            order_by,
+            patterns,
+        }
+    }
+}
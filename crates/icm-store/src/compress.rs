@@ -0,0 +1,123 @@
+//! Transparent zstd compression of long text columns at rest.
+//!
+//! `memories.raw_excerpt` and `attachments.content` values of at least
+//! [`COMPRESS_MIN_BYTES`] are stored as a zstd frame (a BLOB) with the
+//! row's `compressed` flag set, and inflated again when the row is read.
+//! Summaries stay plain text: the FTS triggers index `summary` straight
+//! from the row, and condensing already keeps them short.
+
+use rusqlite::types::{Type, Value, ValueRef};
+use rusqlite::{params, Connection, Row};
+
+/// Text shorter than this is stored as-is; the zstd frame overhead and
+/// the decompression on read are not worth it.
+pub(crate) const COMPRESS_MIN_BYTES: usize = 1024;
+
+/// zstd's default level: most of the ratio for a fraction of the CPU.
+const LEVEL: i32 = 3;
+
+/// The value to bind for `text`, and whether it was compressed. Text that
+/// does not shrink is kept plain.
+pub(crate) fn encode(text: String) -> (Value, bool) {
+    if text.len() >= COMPRESS_MIN_BYTES {
+        if let Ok(frame) = zstd::bulk::compress(text.as_bytes(), LEVEL) {
+            if frame.len() < text.len() {
+                return (Value::Blob(frame), true);
+            }
+        }
+    }
+    (Value::Text(text), false)
+}
+
+/// [`encode`] for a nullable column.
+pub(crate) fn encode_opt(text: Option<String>) -> (Option<Value>, bool) {
+    match text {
+        Some(t) => {
+            let (v, compressed) = encode(t);
+            (Some(v), compressed)
+        }
+        None => (None, false),
+    }
+}
+
+/// Read a text column written through [`encode`]; `compressed` is the
+/// row's flag.
+pub(crate) fn read_text(
+    row: &Row,
+    idx: usize,
+    compressed: bool,
+) -> rusqlite::Result<Option<String>> {
+    match row.get_ref(idx)? {
+        ValueRef::Null => Ok(None),
+        ValueRef::Blob(frame) if compressed => decompress(frame)
+            .map(Some)
+            .map_err(|e| rusqlite::Error::FromSqlConversionFailure(idx, Type::Blob, e.into())),
+        _ => row.get(idx),
+    }
+}
+
+fn decompress(frame: &[u8]) -> std::io::Result<String> {
+    let bytes = zstd::stream::decode_all(frame)?;
+    String::from_utf8(bytes).map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))
+}
+
+/// Uncompressed size recorded in a frame's header, without inflating it.
+pub(crate) fn original_len(frame: &[u8]) -> Option<u64> {
+    zstd::zstd_safe::get_frame_content_size(frame)
+        .ok()
+        .flatten()
+}
+
+/// Compress the long values already in `table.column` that predate the
+/// `compressed` flag. `key` identifies the row. Returns how many rows
+/// were rewritten.
+pub(crate) fn compress_existing(
+    conn: &Connection,
+    table: &str,
+    key: &str,
+    column: &str,
+) -> rusqlite::Result<usize> {
+    let rows: Vec<(String, String)> = conn
+        .prepare(&format!(
+            "SELECT {key}, {column} FROM {table}
+             WHERE compressed = 0 AND typeof({column}) = 'text' AND length(CAST({column} AS BLOB)) >= ?1"
+        ))?
+        .query_map(params![COMPRESS_MIN_BYTES as i64], |r| Ok((r.get(0)?, r.get(1)?)))?
+        .collect::<Result<_, _>>()?;
+
+    let mut update = conn.prepare(&format!(
+        "UPDATE {table} SET {column} = ?1, compressed = 1 WHERE {key} = ?2"
+    ))?;
+    let mut rewritten = 0;
+    for (id, text) in rows {
+        if let (value, true) = encode(text) {
+            update.execute(params![value, id])?;
+            rewritten += 1;
+        }
+    }
+    Ok(rewritten)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn short_text_stays_plain() {
+        assert!(matches!(encode("short".into()), (Value::Text(_), false)));
+        assert!(matches!(encode_opt(None), (None, false)));
+    }
+
+    #[test]
+    fn long_text_round_trips() {
+        let text = "error: connection refused\n".repeat(200);
+        let (value, compressed) = encode(text.clone());
+        assert!(compressed);
+        let Value::Blob(frame) = value else {
+            panic!("expected a blob");
+        };
+        assert!(frame.len() < text.len() / 10);
+        assert_eq!(original_len(&frame), Some(text.len() as u64));
+        assert_eq!(decompress(&frame).unwrap(), text);
+    }
+}
@@ -29,7 +29,10 @@ compile_error!(
 
 mod backend;
 mod common;
+mod vector_index;
 
+#[cfg(feature = "backend-sqlite")]
+mod compress;
 #[cfg(feature = "backend-sqlite")]
 mod schema;
 #[cfg(feature = "backend-sqlite")]
@@ -42,7 +45,15 @@ mod postgres;
 mod opensearch;
 
 // Shared row types (backend-agnostic).
-pub use common::{CodeArea, HookEvent, HookEventInsert, HookStatsRow, PendingRow};
+pub use common::{
+    archived_topic, build_digest, CodeArea, HookEvent, HookEventInsert, HookStatsRow, KeyedOutcome,
+    LinkStep, MemoryIter, PendingRow, ReviewItem, SessionContext, SessionContextInsert,
+    StorageStats, StoreOutcome, TopicDigest, ATTACHMENT_MIN_BYTES, DEFAULT_FTS_RELEVANCE,
+    DIGEST_ENTRIES,
+};
+
+// Vector index selection for `search_by_embedding`.
+pub use vector_index::{index_path, HnswIndex, IndexKind, VectorIndex};
 
 // The runtime-dispatched store and the backend selector.
 pub use backend::{BackendKind, Store};
@@ -36,15 +36,22 @@ use chrono::{DateTime, TimeZone, Utc};
 use serde_json::{json, Value};
 
 use icm_core::{
-    Concept, ConceptLink, Embedder, Fact, FactsStats, FactsStore, Feedback, FeedbackStats,
-    FeedbackStore, IcmError, IcmResult, Importance, Label, Memoir, MemoirStats, MemoirStore,
-    Memory, MemorySource, MemoryStore, Message, PatternCluster, Relation, Role, Scope, Session,
-    StoreStats, TopicHealth, TranscriptHit, TranscriptStats, TranscriptStore,
+    Concept, ConceptLink, ConceptRevision, Embedder, Fact, FactsStats, FactsStore, Feedback,
+    FeedbackStats, FeedbackStore, HybridScore, IcmError, IcmResult, Importance, KeywordOptions,
+    KeywordQuery, Label, Memoir, MemoirStats, MemoirStore, Memory, MemoryLink, MemorySource,
+    MemoryStore, Message, PatternCluster, RecallFeedback, Reinforcement, Relation, Role, Scope,
+    Session, StoreStats, TopicHealth, TranscriptHit, TranscriptStats, TranscriptStore,
 };
 
 // Shared public row types live in `crate::common` (issue #301) so every
 // backend can be compiled into one binary without colliding definitions.
-pub use crate::common::{CodeArea, HookEvent, HookEventInsert, HookStatsRow, PendingRow};
+pub use crate::common::{
+    CodeArea, HookEvent, HookEventInsert, HookStatsRow, PendingRow, ReviewItem,
+};
+use crate::common::{
+    KeyedOutcome, MemoryIter, MemoryPages, SessionContext, SessionContextInsert, StorageStats,
+    TopicDigest, WritePolicy,
+};
 
 // ---------------------------------------------------------------------------
 // Index names
@@ -54,7 +61,13 @@ const IDX_MEMORIES: &str = "icm_memories";
 const IDX_METADATA: &str = "icm_metadata";
 const IDX_HOOKS: &str = "icm_hook_events";
 const IDX_PENDING: &str = "icm_pending_extractions";
+const IDX_REVIEW: &str = "icm_review_queue";
 const IDX_CODE_AREAS: &str = "icm_code_areas";
+const IDX_SESSION_INJECTIONS: &str = "icm_session_injections";
+
+/// Recall bookkeeping: count the access and apply the store's
+/// [`Reinforcement`] (never lowering a weight above the cap).
+const ACCESS_SCRIPT: &str = "ctx._source.access_count = (ctx._source.access_count == null ? 1 : ctx._source.access_count + 1); ctx._source.last_accessed = params.now; double w = ctx._source.weight == null ? 1.0 : ctx._source.weight; ctx._source.weight = Math.max(w, Math.min(params.cap, w + params.rate * (1.0 - w)));";
 
 // ---------------------------------------------------------------------------
 // Pure helpers (self-contained, mirror the other backends)
@@ -64,23 +77,22 @@ fn source_type(source: &MemorySource) -> &'static str {
     match source {
         MemorySource::ClaudeCode { .. } => "claude_code",
         MemorySource::Conversation { .. } => "conversation",
-        MemorySource::Manual => "manual",
+        MemorySource::Manual { .. } => "manual",
     }
 }
 
 fn source_data(source: &MemorySource) -> Option<String> {
     match source {
-        MemorySource::Manual => None,
+        MemorySource::Manual { provenance } if provenance.is_empty() => None,
         other => serde_json::to_string(other).ok(),
     }
 }
 
 fn parse_source(source_type_str: &str, source_data_str: Option<String>) -> MemorySource {
-    match source_type_str {
-        "manual" => MemorySource::Manual,
-        _ => source_data_str
-            .and_then(|d| serde_json::from_str(&d).ok())
-            .unwrap_or(MemorySource::Manual),
+    // Manual rows carry source_data only when provenance was captured.
+    match source_data_str.and_then(|d| serde_json::from_str::<MemorySource>(&d).ok()) {
+        Some(source) if source.kind() == source_type_str => source,
+        _ => MemorySource::default(),
     }
 }
 
@@ -153,6 +165,9 @@ pub struct OpenSearchStore {
     auth: Option<String>,
     embedding_dims: usize,
     readonly: bool,
+    policy: WritePolicy,
+    keyword_options: KeywordOptions,
+    reinforcement: Reinforcement,
 }
 
 impl OpenSearchStore {
@@ -269,6 +284,9 @@ impl OpenSearchStore {
             auth: Self::auth_header(),
             embedding_dims: icm_core::DEFAULT_EMBEDDING_DIMS,
             readonly: true,
+            policy: WritePolicy::default(),
+            keyword_options: KeywordOptions::default(),
+            reinforcement: Reinforcement::default(),
         };
         match store.get_metadata_int("embedding_dims") {
             Ok(Some(v)) => Ok(Some(v as usize)),
@@ -280,6 +298,38 @@ impl OpenSearchStore {
         self.readonly
     }
 
+    pub(crate) fn write_policy(&self) -> &WritePolicy {
+        &self.policy
+    }
+
+    pub(crate) fn write_policy_mut(&mut self) -> &mut WritePolicy {
+        &mut self.policy
+    }
+
+    /// No-op: `search_fts` is ranked by OpenSearch's own BM25 score.
+    pub(crate) fn set_fts_relevance(&mut self, _relevance: f32) {}
+
+    pub(crate) fn set_keyword_options(&mut self, options: KeywordOptions) {
+        self.keyword_options = options;
+    }
+
+    pub(crate) fn set_reinforcement(&mut self, reinforcement: Reinforcement) {
+        self.reinforcement = reinforcement;
+    }
+
+    fn access_params(&self) -> Value {
+        json!({
+            "now": Utc::now().to_rfc3339(),
+            "rate": self.reinforcement.rate as f64,
+            "cap": self.reinforcement.cap as f64,
+        })
+    }
+
+    /// Stream every memory in id order, a page at a time.
+    pub fn iter_memories(&self) -> MemoryIter<'_> {
+        MemoryIter::new(self)
+    }
+
     /// No-op on this backend (kept for API parity with the SQLite store).
     pub fn ensure_vec_init() {}
 
@@ -294,6 +344,9 @@ impl OpenSearchStore {
             auth: Self::auth_header(),
             embedding_dims: requested_dims,
             readonly,
+            policy: WritePolicy::default(),
+            keyword_options: KeywordOptions::default(),
+            reinforcement: Reinforcement::default(),
         };
         // Probe connectivity early with a clear error.
         store
@@ -368,6 +421,7 @@ impl OpenSearchStore {
                     "source_data": {"type": "text", "index": false},
                     "related_ids": {"type": "keyword"},
                     "summary_hash": {"type": "keyword"},
+                    "author": {"type": "keyword"},
                     "embedding": {
                         "type": "knn_vector",
                         "dimension": dims,
@@ -401,6 +455,23 @@ impl OpenSearchStore {
                 "captured_at": {"type": "date"}
             }}}),
         )?;
+        self.create_index(
+            IDX_REVIEW,
+            json!({"mappings": {"properties": {
+                "origin": {"type": "keyword"},
+                "memory": {"type": "text", "index": false},
+                "queued_at": {"type": "date"}
+            }}}),
+        )?;
+        self.create_index(
+            IDX_SESSION_INJECTIONS,
+            json!({"mappings": {"properties": {
+                "session_id": {"type": "keyword"},
+                "memory_id": {"type": "keyword"},
+                "memory_updated_at": {"type": "date"},
+                "injected_at": {"type": "date"}
+            }}}),
+        )?;
         self.create_index(
             IDX_CODE_AREAS,
             json!({"mappings": {"properties": {
@@ -440,7 +511,7 @@ impl OpenSearchStore {
     fn check_dims(&self, memory: &Memory) -> IcmResult<()> {
         if let Some(emb) = memory.embedding.as_ref() {
             if emb.len() != self.embedding_dims {
-                return Err(IcmError::InvalidInput(format!(
+                return Err(IcmError::DimensionMismatch(format!(
                     "embedding has {} dimensions, but this store uses {}",
                     emb.len(),
                     self.embedding_dims
@@ -468,6 +539,7 @@ impl OpenSearchStore {
             "source_data": source_data(&memory.source),
             "related_ids": memory.related_ids,
             "summary_hash": summary_hash(&memory.topic, &memory.summary),
+            "author": memory.author,
         });
         if let Some(emb) = memory.embedding.as_ref() {
             doc["embedding"] = json!(emb);
@@ -518,12 +590,16 @@ impl OpenSearchStore {
             topic: get_str("topic"),
             summary: get_str("summary"),
             raw_excerpt: opt_str("raw_excerpt"),
+            attachment: None,
             keywords: arr("keywords"),
             importance,
             source,
             related_ids: arr("related_ids"),
             embedding,
             scope: Scope::default(),
+            author: opt_str("author"),
+            metadata: Default::default(),
+            kind: Default::default(),
         }
     }
 
@@ -639,6 +715,18 @@ impl OpenSearchStore {
     }
 }
 
+impl MemoryPages for OpenSearchStore {
+    // `_id` can't be range-queried or sorted on by default, so page over
+    // the (capped) `list_all` result instead.
+    fn memories_after(&self, after: Option<&str>, limit: usize) -> IcmResult<Vec<Memory>> {
+        let mut all = self.list_all()?;
+        all.retain(|m| after.is_none_or(|a| m.id.as_str() > a));
+        all.sort_by(|a, b| a.id.cmp(&b.id));
+        all.truncate(limit);
+        Ok(all)
+    }
+}
+
 impl MemoryStore for OpenSearchStore {
     fn store(&self, memory: Memory) -> IcmResult<String> {
         if self.readonly {
@@ -691,23 +779,32 @@ impl MemoryStore for OpenSearchStore {
             None,
             true,
         )?;
+        self.replace_memory_refs(&[id], None)?;
         Ok(())
     }
 
     fn search_by_keywords(&self, keywords: &[&str], limit: usize) -> IcmResult<Vec<Memory>> {
-        if keywords.is_empty() {
+        let parsed = KeywordQuery::parse(keywords, &self.keyword_options);
+        if parsed.is_empty() {
             return Ok(Vec::new());
         }
-        let joined = keywords.join(" ");
+        let term = |t: &String| {
+            json!({"bool": {"should": [
+                {"term": {"keywords": t}},
+                {"multi_match": {"query": t, "fields": ["summary", "topic"]}}
+            ], "minimum_should_match": 1}})
+        };
+        let mut query = json!({
+            "must": parsed.must.iter().map(term).collect::<Vec<_>>(),
+            "must_not": parsed.exclude.iter().map(term).collect::<Vec<_>>(),
+        });
+        if !parsed.should.is_empty() {
+            query["should"] = parsed.should.iter().map(term).collect();
+            query["minimum_should_match"] = json!(parsed.min_should);
+        }
         let resp = self.post(
             &format!("{IDX_MEMORIES}/_search"),
-            json!({
-                "size": limit,
-                "query": {"bool": {"should": [
-                    {"terms": {"keywords": keywords}},
-                    {"multi_match": {"query": joined, "fields": ["summary", "topic"]}}
-                ], "minimum_should_match": 1}}
-            }),
+            json!({"size": limit, "query": {"bool": query}}),
         )?;
         Ok(Self::hits_to_memories(&resp))
     }
@@ -744,12 +841,12 @@ impl MemoryStore for OpenSearchStore {
         Ok(Self::hits_to_scored(&resp))
     }
 
-    fn search_hybrid(
+    fn search_hybrid_scored(
         &self,
         query: &str,
         embedding: &[f32],
         limit: usize,
-    ) -> IcmResult<Vec<(Memory, f32)>> {
+    ) -> IcmResult<Vec<(Memory, HybridScore)>> {
         let limit = limit.min(1000);
         let pool = limit * 4;
 
@@ -796,15 +893,23 @@ impl MemoryStore for OpenSearchStore {
         let fts_n = norm(&fts_scores);
         let vec_n = norm(&vec_scores);
 
-        let mut scored: Vec<(String, f32)> = memories
+        let mut scored: Vec<(String, HybridScore)> = memories
             .keys()
             .map(|id| {
-                let f = fts_n.get(id).copied().unwrap_or(0.0);
-                let v = vec_n.get(id).copied().unwrap_or(0.0);
-                (id.clone(), 0.3 * f + 0.7 * v)
+                let score = HybridScore::new(
+                    fts_scores.get(id).copied(),
+                    fts_n.get(id).copied().unwrap_or(0.0),
+                    vec_scores.get(id).copied(),
+                    vec_n.get(id).copied().unwrap_or(0.0),
+                );
+                (id.clone(), score)
             })
             .collect();
-        scored.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
+        scored.sort_by(|a, b| {
+            b.1.combined
+                .partial_cmp(&a.1.combined)
+                .unwrap_or(std::cmp::Ordering::Equal)
+        });
         scored.truncate(limit);
 
         Ok(scored
@@ -824,8 +929,8 @@ impl MemoryStore for OpenSearchStore {
             Some(json!({
                 "script": {
                     "lang": "painless",
-                    "source": "ctx._source.access_count = (ctx._source.access_count == null ? 1 : ctx._source.access_count + 1); ctx._source.last_accessed = params.now;",
-                    "params": {"now": Utc::now().to_rfc3339()}
+                    "source": ACCESS_SCRIPT,
+                    "params": self.access_params()
                 }
             })),
             true,
@@ -838,13 +943,16 @@ impl MemoryStore for OpenSearchStore {
             return Ok(0);
         }
         let resp = self.post(
-            &format!("{IDX_MEMORIES}/_update_by_query?{}&conflicts=proceed", self.refresh_param()),
+            &format!(
+                "{IDX_MEMORIES}/_update_by_query?{}&conflicts=proceed",
+                self.refresh_param()
+            ),
             json!({
                 "query": {"ids": {"values": ids}},
                 "script": {
                     "lang": "painless",
-                    "source": "ctx._source.access_count = (ctx._source.access_count == null ? 1 : ctx._source.access_count + 1); ctx._source.last_accessed = params.now;",
-                    "params": {"now": Utc::now().to_rfc3339()}
+                    "source": ACCESS_SCRIPT,
+                    "params": self.access_params()
                 }
             }),
         )?;
@@ -908,6 +1016,21 @@ impl MemoryStore for OpenSearchStore {
         Ok(Self::hits_to_memories(&resp))
     }
 
+    fn get_all(&self, limit: usize, offset: usize) -> IcmResult<Vec<Memory>> {
+        // `from + size` is capped by `index.max_result_window` (10k by
+        // default), the same ceiling as `list_all`.
+        let resp = self.post(
+            &format!("{IDX_MEMORIES}/_search"),
+            json!({
+                "from": offset,
+                "size": limit,
+                "query": {"match_all": {}},
+                "sort": [{"weight": "desc"}, {"created_at": "asc"}]
+            }),
+        )?;
+        Ok(Self::hits_to_memories(&resp))
+    }
+
     fn list_topics(&self) -> IcmResult<Vec<(String, usize)>> {
         let resp = self.post(
             &format!("{IDX_MEMORIES}/_search"),
@@ -1098,9 +1221,9 @@ fn bucket_counts(resp: &Value, agg: &str) -> Vec<(String, usize)> {
 // ---------------------------------------------------------------------------
 
 impl OpenSearchStore {
-    pub fn maybe_auto_decay(&self) -> IcmResult<()> {
+    pub fn maybe_auto_decay(&self, factor: f32) -> IcmResult<Option<usize>> {
         if self.readonly {
-            return Ok(());
+            return Ok(None);
         }
         // Atomic-ish claim via a scripted upsert on a metadata doc: only the
         // caller that flips `changed` to true runs the decay.
@@ -1123,10 +1246,10 @@ impl OpenSearchStore {
             .and_then(|s| s.get("changed"))
             .and_then(|c| c.as_bool())
             .unwrap_or(false);
-        if changed {
-            self.apply_decay(0.95)?;
+        if !changed {
+            return Ok(None);
         }
-        Ok(())
+        self.apply_decay(factor).map(Some)
     }
 
     pub fn increment_hook_counter(&self) -> IcmResult<usize> {
@@ -1221,6 +1344,67 @@ impl OpenSearchStore {
         Ok(resp.get("count").and_then(|v| v.as_u64()).unwrap_or(0) as usize)
     }
 
+    pub fn queue_for_review(&self, memory: &Memory, origin: &str) -> IcmResult<String> {
+        if self.readonly {
+            return Err(IcmError::ReadOnly("queue_for_review".into()));
+        }
+        self.request(
+            "PUT",
+            &format!("{IDX_REVIEW}/_doc/{}?{}", memory.id, self.refresh_param()),
+            Some(json!({
+                "origin": origin,
+                "memory": serde_json::to_string(memory)?,
+                "queued_at": Utc::now().to_rfc3339()
+            })),
+            false,
+        )?;
+        Ok(memory.id.clone())
+    }
+
+    pub fn list_review_queue(&self, limit: usize) -> IcmResult<Vec<ReviewItem>> {
+        let resp = self.post(
+            &format!("{IDX_REVIEW}/_search"),
+            json!({"size": limit, "query": {"match_all": {}}, "sort": [{"queued_at": "asc"}]}),
+        )?;
+        let items = resp
+            .get("hits")
+            .and_then(|h| h.get("hits"))
+            .and_then(|h| h.as_array())
+            .map(|hits| {
+                hits.iter()
+                    .filter_map(|h| {
+                        let s = h.get("_source")?;
+                        Some(ReviewItem {
+                            memory: serde_json::from_str(s.get("memory")?.as_str()?).ok()?,
+                            origin: s.get("origin")?.as_str()?.to_string(),
+                            queued_at: parse_dt(s.get("queued_at")?.as_str()?),
+                        })
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        Ok(items)
+    }
+
+    pub fn remove_from_review_queue(&self, id: &str) -> IcmResult<bool> {
+        if self.readonly {
+            return Err(IcmError::ReadOnly("remove_from_review_queue".into()));
+        }
+        let resp = self.post(
+            &format!(
+                "{IDX_REVIEW}/_delete_by_query?{}&conflicts=proceed",
+                self.refresh_param()
+            ),
+            json!({"query": {"ids": {"values": [id]}}}),
+        )?;
+        Ok(resp.get("deleted").and_then(|v| v.as_u64()).unwrap_or(0) > 0)
+    }
+
+    pub fn review_queue_count(&self) -> IcmResult<usize> {
+        let resp = self.post(&format!("{IDX_REVIEW}/_count"), json!({}))?;
+        Ok(resp.get("count").and_then(|v| v.as_u64()).unwrap_or(0) as usize)
+    }
+
     pub fn upsert_code_area(
         &self,
         project: &str,
@@ -1488,6 +1672,84 @@ impl OpenSearchStore {
         Ok(resp.get("count").and_then(|v| v.as_u64()).unwrap_or(0) as usize)
     }
 
+    pub fn session_injections(
+        &self,
+        session_id: &str,
+    ) -> IcmResult<HashMap<String, DateTime<Utc>>> {
+        let resp = self.post(
+            &format!("{IDX_SESSION_INJECTIONS}/_search"),
+            json!({"size": 10000, "query": {"term": {"session_id": session_id}}}),
+        )?;
+        let rows = resp
+            .get("hits")
+            .and_then(|h| h.get("hits"))
+            .and_then(|h| h.as_array())
+            .map(|hits| {
+                hits.iter()
+                    .filter_map(|h| {
+                        let s = h.get("_source")?;
+                        Some((
+                            s.get("memory_id")?.as_str()?.to_string(),
+                            parse_dt(s.get("memory_updated_at")?.as_str()?),
+                        ))
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        Ok(rows)
+    }
+
+    pub fn record_session_injections(
+        &self,
+        session_id: &str,
+        memories: &[Memory],
+    ) -> IcmResult<()> {
+        if self.readonly {
+            return Err(IcmError::ReadOnly("record_session_injections".into()));
+        }
+        let now = Utc::now().to_rfc3339();
+        for m in memories {
+            // Deterministic id makes re-injection overwrite the row.
+            let key = B64.encode(format!("{session_id}\0{}", m.id));
+            self.request(
+                "PUT",
+                &format!(
+                    "{IDX_SESSION_INJECTIONS}/_doc/{key}?{}",
+                    self.refresh_param()
+                ),
+                Some(json!({
+                    "session_id": session_id,
+                    "memory_id": m.id,
+                    "memory_updated_at": m.updated_at.to_rfc3339(),
+                    "injected_at": now
+                })),
+                false,
+            )?;
+        }
+        Ok(())
+    }
+
+    pub fn clear_session_injections(
+        &self,
+        session_id: &str,
+        cutoff_rfc3339: &str,
+    ) -> IcmResult<usize> {
+        if self.readonly {
+            return Err(IcmError::ReadOnly("clear_session_injections".into()));
+        }
+        let resp = self.post(
+            &format!(
+                "{IDX_SESSION_INJECTIONS}/_delete_by_query?{}&conflicts=proceed",
+                self.refresh_param()
+            ),
+            json!({"query": {"bool": {"should": [
+                {"term": {"session_id": session_id}},
+                {"range": {"injected_at": {"lt": cutoff_rfc3339}}}
+            ]}}}),
+        )?;
+        Ok(resp.get("deleted").and_then(|v| v.as_u64()).unwrap_or(0) as usize)
+    }
+
     /// Auto-consolidation is not yet implemented on this backend; it is a
     /// no-op (returns `false`) so the normal store path keeps working.
     pub fn auto_consolidate(&self, _topic: &str, _threshold: usize) -> IcmResult<bool> {
@@ -1504,6 +1766,110 @@ impl OpenSearchStore {
         Ok(false)
     }
 
+    /// See [`Self::auto_consolidate`].
+    pub fn auto_consolidate_with_opts(
+        &self,
+        _topic: &str,
+        _threshold: usize,
+        _embedder: Option<&dyn Embedder>,
+        _keep_originals: bool,
+    ) -> IcmResult<bool> {
+        Ok(false)
+    }
+
+    /// Vector search is served natively (`knn_vector`); the SQLite-side
+    /// index switch does not apply.
+    pub fn rebuild_vector_index(&self) -> IcmResult<usize> {
+        Err(IcmError::Unsupported("rebuild_vector_index".into()))
+    }
+
+    /// Disk usage is managed by the server; there is no local file to
+    /// break down.
+    pub fn storage_stats(&self) -> IcmResult<StorageStats> {
+        Err(IcmError::Unsupported("storage_stats".into()))
+    }
+
+    /// Session context logs are SQLite-only for now; hooks skip
+    /// recording them here.
+    pub fn record_session_context(&self, _ctx: &SessionContextInsert) -> IcmResult<i64> {
+        unsupported("record_session_context")
+    }
+
+    pub fn session_context(&self, _session_id: &str) -> IcmResult<Vec<SessionContext>> {
+        unsupported("session_context")
+    }
+
+    pub fn store_keyed(&self, _key: &str, _memory: Memory) -> IcmResult<KeyedOutcome> {
+        unsupported("store --key")
+    }
+
+    /// Raw excerpts are always stored inline here, so no memory ever
+    /// references an attachment.
+    pub fn attachment(&self, _hash: &str) -> IcmResult<Option<String>> {
+        Ok(None)
+    }
+
+    /// Topic digests are not yet available on the OpenSearch backend; the
+    /// fast prompt hook then serves keyword hits only.
+    pub fn refresh_topic_digests(&self, _per_topic: usize) -> IcmResult<usize> {
+        Err(IcmError::Unsupported(
+            "refresh_topic_digests (use the default SQLite backend)".into(),
+        ))
+    }
+
+    pub fn topic_digests(&self) -> IcmResult<Vec<TopicDigest>> {
+        Err(IcmError::Unsupported(
+            "topic_digests (use the default SQLite backend)".into(),
+        ))
+    }
+
+    /// No centroid cache on the OpenSearch backend: importance suggestions
+    /// fall back to the rule pass.
+    pub fn importance_centroids(&self) -> IcmResult<Vec<(Importance, Vec<f32>)>> {
+        Ok(Vec::new())
+    }
+
+    pub fn refresh_importance_centroids(&self) -> IcmResult<Vec<(Importance, Vec<f32>)>> {
+        Err(IcmError::Unsupported(
+            "refresh_importance_centroids (use the default SQLite backend)".into(),
+        ))
+    }
+
+    /// See [`Self::auto_consolidate`]: never proposes anything.
+    pub fn consolidation_proposal(
+        &self,
+        _topic: &str,
+        _threshold: usize,
+    ) -> IcmResult<Option<Memory>> {
+        Ok(None)
+    }
+
+    /// Point every memory's `related_ids` entries for `old` at `new`, or
+    /// drop them when `new` is `None`. Returns the documents rewritten.
+    /// Bulk deletes (`prune`, `consolidate_topic`) go through
+    /// `_delete_by_query` without learning the ids, so references they
+    /// leave are repaired by `icm doctor --fix-references`.
+    pub fn replace_memory_refs(&self, old: &[&str], new: Option<&str>) -> IcmResult<usize> {
+        if self.readonly {
+            return Err(IcmError::ReadOnly("replace_memory_refs".into()));
+        }
+        if old.is_empty() {
+            return Ok(0);
+        }
+        let resp = self.post(
+            &format!("{IDX_MEMORIES}/_update_by_query?{}&conflicts=proceed", self.refresh_param()),
+            json!({
+                "query": {"terms": {"related_ids": old}},
+                "script": {
+                    "lang": "painless",
+                    "source": "def out = new ArrayList(); for (def r : ctx._source.related_ids) { def v = params.old.contains(r) ? params.new : r; if (v != null && v != ctx._id && !out.contains(v)) { out.add(v); } } ctx._source.related_ids = out;",
+                    "params": {"old": old, "new": new}
+                }
+            }),
+        )?;
+        Ok(resp.get("updated").and_then(|v| v.as_u64()).unwrap_or(0) as usize)
+    }
+
     pub fn get_many(&self, ids: &[&str]) -> IcmResult<HashMap<String, Memory>> {
         if ids.is_empty() {
             return Ok(HashMap::new());
@@ -1536,6 +1902,57 @@ impl OpenSearchStore {
         Ok(Self::hits_to_memories(&resp))
     }
 
+    /// The `limit` most recently created memories, newest first. Same
+    /// filters as the SQLite store.
+    pub fn list_recent(
+        &self,
+        topic: Option<&str>,
+        source: Option<&str>,
+        since: Option<DateTime<Utc>>,
+        limit: usize,
+    ) -> IcmResult<Vec<Memory>> {
+        let mut filters: Vec<Value> = Vec::new();
+        if let Some(t) = topic {
+            filters.push(match t.strip_suffix('*') {
+                Some(prefix) => json!({"prefix": {"topic.keyword": prefix}}),
+                None => json!({"term": {"topic.keyword": t}}),
+            });
+        }
+        if let Some(s) = source {
+            filters.push(json!({"term": {"source_type": s}}));
+        }
+        if let Some(s) = since {
+            filters.push(json!({"range": {"created_at": {"gte": s.to_rfc3339()}}}));
+        }
+        let query = if filters.is_empty() {
+            json!({"match_all": {}})
+        } else {
+            json!({"bool": {"filter": filters}})
+        };
+        let resp = self.post(
+            &format!("{IDX_MEMORIES}/_search"),
+            json!({"size": limit, "query": query, "sort": [{"created_at": "desc"}]}),
+        )?;
+        Ok(Self::hits_to_memories(&resp))
+    }
+
+    pub fn add_memory_link(&self, _link: MemoryLink) -> IcmResult<String> {
+        unsupported("add_memory_link")
+    }
+
+    pub fn remove_memory_link(
+        &self,
+        _source_id: &str,
+        _target_id: &str,
+        _relation: Option<Relation>,
+    ) -> IcmResult<usize> {
+        unsupported("remove_memory_link")
+    }
+
+    pub fn memory_links(&self, _memory_id: &str) -> IcmResult<Vec<MemoryLink>> {
+        unsupported("memory_links")
+    }
+
     pub fn list_topics_with_prefix(&self, prefix: Option<&str>) -> IcmResult<Vec<(String, usize)>> {
         let mut topics = self.list_topics()?;
         if let Some(p) = prefix {
@@ -1665,13 +2082,14 @@ impl MemoirStore for OpenSearchStore {
     ) -> IcmResult<Vec<Concept>> {
         unsupported("memoir.search_concepts_fts")
     }
-    fn search_concepts_by_label(
+    fn search_concepts_by_labels(
         &self,
         _memoir_id: &str,
-        _label: &Label,
+        _labels: &[Label],
+        _match_all: bool,
         _limit: usize,
     ) -> IcmResult<Vec<Concept>> {
-        unsupported("memoir.search_concepts_by_label")
+        unsupported("memoir.search_concepts_by_labels")
     }
     fn search_all_concepts_fts(&self, _query: &str, _limit: usize) -> IcmResult<Vec<Concept>> {
         unsupported("memoir.search_all_concepts_fts")
@@ -1684,6 +2102,12 @@ impl MemoirStore for OpenSearchStore {
     ) -> IcmResult<()> {
         unsupported("memoir.refine_concept")
     }
+    fn concept_revisions(&self, _concept_id: &str) -> IcmResult<Vec<ConceptRevision>> {
+        unsupported("memoir.concept_revisions")
+    }
+    fn rollback_concept(&self, _concept_id: &str, _revision: u32) -> IcmResult<()> {
+        unsupported("memoir.rollback_concept")
+    }
     fn add_link(&self, _link: ConceptLink) -> IcmResult<String> {
         unsupported("memoir.add_link")
     }
@@ -1696,6 +2120,12 @@ impl MemoirStore for OpenSearchStore {
     fn delete_link(&self, _id: &str) -> IcmResult<()> {
         unsupported("memoir.delete_link")
     }
+    fn update_link_weight(&self, _id: &str, _weight: f32) -> IcmResult<()> {
+        unsupported("memoir.update_link_weight")
+    }
+    fn decay_links(&self, _factor: f32) -> IcmResult<usize> {
+        unsupported("memoir.decay_links")
+    }
     fn get_neighbors(
         &self,
         _concept_id: &str,
@@ -1745,6 +2175,16 @@ impl FeedbackStore for OpenSearchStore {
     fn feedback_stats(&self) -> IcmResult<FeedbackStats> {
         unsupported("feedback.feedback_stats")
     }
+    fn record_recall_feedback(&self, _feedback: RecallFeedback) -> IcmResult<f32> {
+        unsupported("feedback.record_recall_feedback")
+    }
+    fn list_recall_feedback(
+        &self,
+        _memory_id: Option<&str>,
+        _limit: usize,
+    ) -> IcmResult<Vec<RecallFeedback>> {
+        unsupported("feedback.list_recall_feedback")
+    }
 }
 
 impl FactsStore for OpenSearchStore {
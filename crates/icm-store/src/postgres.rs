@@ -41,22 +41,42 @@ use postgres::types::ToSql;
 use postgres::{Client, GenericClient, NoTls};
 
 use icm_core::{
-    Concept, ConceptLink, Embedder, Fact, FactsStats, FactsStore, Feedback, FeedbackStats,
-    FeedbackStore, IcmError, IcmResult, Importance, Label, Memoir, MemoirStats, MemoirStore,
-    Memory, MemorySource, MemoryStore, Message, PatternCluster, Relation, Role, Session,
+    Concept, ConceptLink, ConceptRevision, Embedder, Fact, FactsStats, FactsStore, Feedback,
+    FeedbackStats, FeedbackStore, HybridScore, IcmError, IcmResult, Importance, KeywordOptions,
+    KeywordQuery, Label, Memoir, MemoirStats, MemoirStore, Memory, MemoryLink, MemorySource,
+    MemoryStore, Message, PatternCluster, RecallFeedback, Reinforcement, Relation, Role, Session,
     StoreStats, TopicHealth, TranscriptHit, TranscriptStats, TranscriptStore,
 };
 
 // Shared public row types live in `crate::common` (issue #301) so every
 // backend can be compiled into one binary without colliding definitions.
-pub use crate::common::{CodeArea, HookEvent, HookEventInsert, HookStatsRow, PendingRow};
+use crate::common::{
+    rewrite_memory_refs, KeyedOutcome, KeywordSql, MemoryIter, MemoryPages, SessionContext,
+    SessionContextInsert, StorageStats, TopicDigest, WritePolicy, DEFAULT_FTS_RELEVANCE,
+};
+pub use crate::common::{
+    CodeArea, HookEvent, HookEventInsert, HookStatsRow, PendingRow, ReviewItem,
+};
 
 // ---------------------------------------------------------------------------
 // Helpers (mirrored from the SQLite backend so behaviour matches)
 // ---------------------------------------------------------------------------
 
+/// Map a PostgreSQL error to the matching IcmError variant by SQLSTATE.
 fn pg_err(e: postgres::Error) -> IcmError {
-    IcmError::Database(e.to_string())
+    let msg = e.to_string();
+    let Some(state) = e.code() else {
+        return IcmError::Database(msg);
+    };
+    match state.code() {
+        // Class 23: integrity constraint violation.
+        c if c.starts_with("23") => IcmError::Constraint(msg),
+        // serialization_failure, deadlock_detected, lock_not_available
+        "40001" | "40P01" | "55P03" => IcmError::Busy(msg),
+        // data_corrupted, index_corrupted
+        "XX001" | "XX002" => IcmError::Corrupt(msg),
+        _ => IcmError::Database(msg),
+    }
 }
 
 fn lock_err() -> IcmError {
@@ -67,23 +87,22 @@ fn source_type(source: &MemorySource) -> &'static str {
     match source {
         MemorySource::ClaudeCode { .. } => "claude_code",
         MemorySource::Conversation { .. } => "conversation",
-        MemorySource::Manual => "manual",
+        MemorySource::Manual { .. } => "manual",
     }
 }
 
 fn source_data(source: &MemorySource) -> Option<String> {
     match source {
-        MemorySource::Manual => None,
+        MemorySource::Manual { provenance } if provenance.is_empty() => None,
         other => serde_json::to_string(other).ok(),
     }
 }
 
 fn parse_source(source_type_str: &str, source_data_str: Option<String>) -> MemorySource {
-    match source_type_str {
-        "manual" => MemorySource::Manual,
-        _ => source_data_str
-            .and_then(|d| serde_json::from_str(&d).ok())
-            .unwrap_or(MemorySource::Manual),
+    // Manual rows carry source_data only when provenance was captured.
+    match source_data_str.and_then(|d| serde_json::from_str::<MemorySource>(&d).ok()) {
+        Some(source) if source.kind() == source_type_str => source,
+        _ => MemorySource::default(),
     }
 }
 
@@ -165,7 +184,7 @@ fn validate_and_normalize(mut memory: Memory) -> IcmResult<Memory> {
 
 const SELECT_COLS: &str = "id, created_at, updated_at, last_accessed, access_count, weight, \
                            topic, summary, raw_excerpt, keywords, \
-                           importance, source_type, source_data, related_ids, embedding";
+                           importance, source_type, source_data, related_ids, embedding, author";
 
 /// Map a `memories` row (selected via [`SELECT_COLS`]) to a [`Memory`].
 fn row_to_memory(row: &postgres::Row) -> Memory {
@@ -202,12 +221,16 @@ fn row_to_memory(row: &postgres::Row) -> Memory {
         topic: row.get(6),
         summary: row.get(7),
         raw_excerpt: row.get(8),
+        attachment: None,
         keywords,
         importance,
         source,
         related_ids,
         embedding,
         scope: icm_core::Scope::User,
+        author: row.get(15),
+        metadata: Default::default(),
+        kind: Default::default(),
     }
 }
 
@@ -217,6 +240,44 @@ fn row_to_memory(row: &postgres::Row) -> Memory {
 /// `(LOWER(topic), summary_hash)` is ignored and the existing row's id is
 /// returned, after merging the caller's importance (take max), keywords
 /// (union), and `raw_excerpt` (prefer new) into it.
+/// [`PostgresStore::replace_memory_refs`] on an open client or
+/// transaction. Memoirs are SQLite-only, so only `related_ids` is
+/// rewritten.
+fn replace_memory_refs_in<C: GenericClient>(
+    c: &mut C,
+    old: &[&str],
+    new: Option<&str>,
+) -> IcmResult<usize> {
+    if old.is_empty() {
+        return Ok(0);
+    }
+    let old_set: HashSet<&str> = old.iter().copied().collect();
+    let old_vec: Vec<String> = old.iter().map(|s| s.to_string()).collect();
+    let rows = c
+        .query(
+            "SELECT id, related_ids FROM memories \
+             WHERE related_ids IS NOT NULL AND related_ids::jsonb ?| $1",
+            &[&old_vec],
+        )
+        .map_err(pg_err)?;
+    let mut rewritten = 0;
+    for row in &rows {
+        let id: String = row.get(0);
+        let json: String = row.get(1);
+        let ids: Vec<String> = serde_json::from_str(&json).unwrap_or_default();
+        let Some(ids) = rewrite_memory_refs(&id, &ids, &old_set, new) else {
+            continue;
+        };
+        c.execute(
+            "UPDATE memories SET related_ids = $2 WHERE id = $1",
+            &[&id, &serde_json::to_string(&ids)?],
+        )
+        .map_err(pg_err)?;
+        rewritten += 1;
+    }
+    Ok(rewritten)
+}
+
 fn insert_or_merge_memory<C: GenericClient>(c: &mut C, memory: &Memory) -> IcmResult<String> {
     let keywords_json = serde_json::to_string(&memory.keywords)?;
     let related_json = serde_json::to_string(&memory.related_ids)?;
@@ -235,8 +296,8 @@ fn insert_or_merge_memory<C: GenericClient>(c: &mut C, memory: &Memory) -> IcmRe
             "INSERT INTO memories
              (id, created_at, updated_at, last_accessed, access_count, weight,
               topic, summary, raw_excerpt, keywords, importance,
-              source_type, source_data, related_ids, summary_hash, embedding)
-             VALUES ($1,$2,$3,$4,$5,$6,$7,$8,$9,$10,$11,$12,$13,$14,$15,$16)
+              source_type, source_data, related_ids, summary_hash, embedding, author)
+             VALUES ($1,$2,$3,$4,$5,$6,$7,$8,$9,$10,$11,$12,$13,$14,$15,$16,$17)
              ON CONFLICT (LOWER(topic), summary_hash) WHERE summary_hash IS NOT NULL
              DO NOTHING
              RETURNING id",
@@ -257,6 +318,7 @@ fn insert_or_merge_memory<C: GenericClient>(c: &mut C, memory: &Memory) -> IcmRe
                 &related_json,
                 &hash,
                 &emb,
+                &memory.author,
             ],
         )
         .map_err(pg_err)?;
@@ -329,6 +391,10 @@ pub struct PostgresStore {
     client: Mutex<Client>,
     embedding_dims: usize,
     readonly: bool,
+    policy: WritePolicy,
+    fts_relevance: f32,
+    keyword_options: KeywordOptions,
+    reinforcement: Reinforcement,
 }
 
 impl PostgresStore {
@@ -367,6 +433,10 @@ impl PostgresStore {
             client: Mutex::new(client),
             embedding_dims: dims,
             readonly,
+            policy: WritePolicy::default(),
+            fts_relevance: DEFAULT_FTS_RELEVANCE,
+            keyword_options: KeywordOptions::default(),
+            reinforcement: Reinforcement::default(),
         })
     }
 
@@ -376,7 +446,7 @@ impl PostgresStore {
     fn check_dims(&self, memory: &Memory) -> IcmResult<()> {
         if let Some(emb) = memory.embedding.as_ref() {
             if emb.len() != self.embedding_dims {
-                return Err(IcmError::InvalidInput(format!(
+                return Err(IcmError::DimensionMismatch(format!(
                     "embedding has {} dimensions, but this store uses {}",
                     emb.len(),
                     self.embedding_dims
@@ -426,15 +496,40 @@ impl PostgresStore {
         self.readonly
     }
 
+    pub(crate) fn write_policy(&self) -> &WritePolicy {
+        &self.policy
+    }
+
+    pub(crate) fn write_policy_mut(&mut self) -> &mut WritePolicy {
+        &mut self.policy
+    }
+
+    pub(crate) fn set_fts_relevance(&mut self, relevance: f32) {
+        self.fts_relevance = relevance;
+    }
+
+    pub(crate) fn set_keyword_options(&mut self, options: KeywordOptions) {
+        self.keyword_options = options;
+    }
+
+    pub(crate) fn set_reinforcement(&mut self, reinforcement: Reinforcement) {
+        self.reinforcement = reinforcement;
+    }
+
+    /// Stream every memory in id order, a page at a time.
+    pub fn iter_memories(&self) -> MemoryIter<'_> {
+        MemoryIter::new(self)
+    }
+
     /// No-op on PostgreSQL (the SQLite backend uses this to load the
     /// `sqlite-vec` extension; `pgvector` lives server-side).
     pub fn ensure_vec_init() {}
 
     /// Apply decay if more than 24 hours since the last run. Mirrors the
     /// SQLite backend's atomic check-and-claim via `icm_metadata`.
-    pub fn maybe_auto_decay(&self) -> IcmResult<()> {
+    pub fn maybe_auto_decay(&self, factor: f32) -> IcmResult<Option<usize>> {
         if self.readonly {
-            return Ok(());
+            return Ok(None);
         }
         let now = Utc::now();
         let claimed = {
@@ -448,10 +543,10 @@ impl PostgresStore {
             )
             .map_err(pg_err)?
         };
-        if claimed > 0 {
-            self.apply_decay(0.95)?;
+        if claimed == 0 {
+            return Ok(None);
         }
-        Ok(())
+        self.apply_decay(factor).map(Some)
     }
 
     /// Atomically increment the hook call counter and return the new value.
@@ -554,6 +649,69 @@ impl PostgresStore {
         Ok(n.max(0) as usize)
     }
 
+    // ── Review queue ───────────────────────────────────────────────────
+
+    /// Hold `memory` for `icm review`. Returns its id.
+    pub fn queue_for_review(&self, memory: &Memory, origin: &str) -> IcmResult<String> {
+        if self.readonly {
+            return Err(IcmError::ReadOnly("queue_for_review".into()));
+        }
+        let json = serde_json::to_string(memory)?;
+        let mut c = self.conn()?;
+        c.execute(
+            "INSERT INTO review_queue (id, origin, memory, queued_at)
+             VALUES ($1, $2, $3, $4)
+             ON CONFLICT (id) DO UPDATE SET origin = $2, memory = $3, queued_at = $4",
+            &[&memory.id, &origin, &json, &Utc::now()],
+        )
+        .map_err(pg_err)?;
+        Ok(memory.id.clone())
+    }
+
+    /// Up to `limit` queued memories, oldest first.
+    pub fn list_review_queue(&self, limit: usize) -> IcmResult<Vec<ReviewItem>> {
+        let mut c = self.conn()?;
+        let rows = c
+            .query(
+                "SELECT origin, memory, queued_at FROM review_queue
+                 ORDER BY queued_at ASC LIMIT $1",
+                &[&(limit as i64)],
+            )
+            .map_err(pg_err)?;
+        rows.iter()
+            .map(|row| {
+                let json: String = row.get(1);
+                Ok(ReviewItem {
+                    memory: serde_json::from_str(&json)?,
+                    origin: row.get(0),
+                    queued_at: row.get(2),
+                })
+            })
+            .collect()
+    }
+
+    /// Drop a queued memory. Returns `false` when it was not queued.
+    pub fn remove_from_review_queue(&self, id: &str) -> IcmResult<bool> {
+        if self.readonly {
+            return Err(IcmError::ReadOnly("remove_from_review_queue".into()));
+        }
+        let mut c = self.conn()?;
+        let n = c
+            .execute("DELETE FROM review_queue WHERE id = $1", &[&id])
+            .map_err(pg_err)?;
+        Ok(n > 0)
+    }
+
+    /// Memories currently awaiting review.
+    pub fn review_queue_count(&self) -> IcmResult<usize> {
+        let mut c = self.conn()?;
+        let row = c
+            .query_one("SELECT COUNT(*) FROM review_queue", &[])
+            .map_err(pg_err)?;
+        let n: i64 = row.get(0);
+        Ok(n.max(0) as usize)
+    }
+
     // ── Code areas (issue #196) ────────────────────────────────────────
 
     /// Insert or refresh a row for `(project, file_path)`.
@@ -782,8 +940,86 @@ impl PostgresStore {
         Ok(n.max(0) as usize)
     }
 
+    // ── Session injections (delta recall) ──────────────────────────────
+
+    /// Memories already injected into `session_id`, mapped to their
+    /// `updated_at` when injected.
+    pub fn session_injections(
+        &self,
+        session_id: &str,
+    ) -> IcmResult<HashMap<String, DateTime<Utc>>> {
+        let mut c = self.conn()?;
+        let rows = c
+            .query(
+                "SELECT memory_id, memory_updated_at FROM session_injections
+                 WHERE session_id = $1",
+                &[&session_id],
+            )
+            .map_err(pg_err)?;
+        Ok(rows.iter().map(|row| (row.get(0), row.get(1))).collect())
+    }
+
+    /// Record `memories` as injected into `session_id`.
+    pub fn record_session_injections(
+        &self,
+        session_id: &str,
+        memories: &[Memory],
+    ) -> IcmResult<()> {
+        if self.readonly {
+            return Err(IcmError::ReadOnly("record_session_injections".into()));
+        }
+        let now = Utc::now();
+        let mut c = self.conn()?;
+        let mut tx = c.transaction().map_err(pg_err)?;
+        for m in memories {
+            tx.execute(
+                "INSERT INTO session_injections
+                 (session_id, memory_id, memory_updated_at, injected_at)
+                 VALUES ($1, $2, $3, $4)
+                 ON CONFLICT (session_id, memory_id)
+                 DO UPDATE SET memory_updated_at = $3, injected_at = $4",
+                &[&session_id, &m.id, &m.updated_at, &now],
+            )
+            .map_err(pg_err)?;
+        }
+        tx.commit().map_err(pg_err)
+    }
+
+    /// Forget what was injected into `session_id`, and any session rows
+    /// older than `cutoff_rfc3339`.
+    pub fn clear_session_injections(
+        &self,
+        session_id: &str,
+        cutoff_rfc3339: &str,
+    ) -> IcmResult<usize> {
+        if self.readonly {
+            return Err(IcmError::ReadOnly("clear_session_injections".into()));
+        }
+        let cutoff = DateTime::parse_from_rfc3339(cutoff_rfc3339)
+            .map(|d| d.with_timezone(&Utc))
+            .map_err(|e| IcmError::InvalidInput(format!("invalid cutoff timestamp: {e}")))?;
+        let mut c = self.conn()?;
+        let n = c
+            .execute(
+                "DELETE FROM session_injections WHERE session_id = $1 OR injected_at < $2",
+                &[&session_id, &cutoff],
+            )
+            .map_err(pg_err)?;
+        Ok(n as usize)
+    }
+
     // ── Memory reads used by recall expansion ──────────────────────────
 
+    /// Point every memory's `related_ids` entries for `old` at `new`, or
+    /// drop them when `new` is `None`. Returns the rows rewritten.
+    pub fn replace_memory_refs(&self, old: &[&str], new: Option<&str>) -> IcmResult<usize> {
+        if self.readonly {
+            return Err(IcmError::ReadOnly("replace_memory_refs".into()));
+        }
+        let mut c = self.conn()?;
+        replace_memory_refs_in(&mut *c, old, new)
+    }
+
     /// Fetch many memories by id in one round-trip, deduplicated by id.
     pub fn get_many(&self, ids: &[&str]) -> IcmResult<HashMap<String, Memory>> {
         if ids.is_empty() {
@@ -870,6 +1106,66 @@ impl PostgresStore {
         Ok(rows.iter().map(row_to_memory).collect())
     }
 
+    /// The `limit` most recently created memories, newest first. Same
+    /// filters as the SQLite store.
+    pub fn list_recent(
+        &self,
+        topic: Option<&str>,
+        source: Option<&str>,
+        since: Option<DateTime<Utc>>,
+        limit: usize,
+    ) -> IcmResult<Vec<Memory>> {
+        let mut sql = format!("SELECT {SELECT_COLS} FROM memories WHERE TRUE");
+        let mut owned: Vec<Box<dyn ToSql + Sync>> = Vec::new();
+        if let Some(t) = topic {
+            match t.strip_suffix('*') {
+                Some(prefix) => {
+                    owned.push(Box::new(format!("{prefix}%")));
+                    sql.push_str(&format!(" AND topic LIKE ${}", owned.len()));
+                }
+                None => {
+                    owned.push(Box::new(t.to_string()));
+                    sql.push_str(&format!(" AND topic = ${}", owned.len()));
+                }
+            }
+        }
+        if let Some(s) = source {
+            owned.push(Box::new(s.to_string()));
+            sql.push_str(&format!(" AND source_type = ${}", owned.len()));
+        }
+        if let Some(t) = since {
+            owned.push(Box::new(t));
+            sql.push_str(&format!(" AND created_at >= ${}", owned.len()));
+        }
+        owned.push(Box::new(limit as i64));
+        sql.push_str(&format!(
+            " ORDER BY created_at DESC, id DESC LIMIT ${}",
+            owned.len()
+        ));
+
+        let params: Vec<&(dyn ToSql + Sync)> = owned.iter().map(|b| b.as_ref()).collect();
+        let mut c = self.conn()?;
+        let rows = c.query(&sql, &params).map_err(pg_err)?;
+        Ok(rows.iter().map(row_to_memory).collect())
+    }
+
+    pub fn add_memory_link(&self, _link: MemoryLink) -> IcmResult<String> {
+        unsupported("add_memory_link")
+    }
+
+    pub fn remove_memory_link(
+        &self,
+        _source_id: &str,
+        _target_id: &str,
+        _relation: Option<Relation>,
+    ) -> IcmResult<usize> {
+        unsupported("remove_memory_link")
+    }
+
+    pub fn memory_links(&self, _memory_id: &str) -> IcmResult<Vec<MemoryLink>> {
+        unsupported("memory_links")
+    }
+
     /// Distinct topics (optionally prefix-filtered) with their counts.
     pub fn list_topics_with_prefix(&self, prefix: Option<&str>) -> IcmResult<Vec<(String, usize)>> {
         let mut c = self.conn()?;
@@ -916,6 +1212,84 @@ impl PostgresStore {
         Ok(false)
     }
 
+    /// See [`Self::auto_consolidate`].
+    pub fn auto_consolidate_with_opts(
+        &self,
+        _topic: &str,
+        _threshold: usize,
+        _embedder: Option<&dyn Embedder>,
+        _keep_originals: bool,
+    ) -> IcmResult<bool> {
+        Ok(false)
+    }
+
+    /// Vector search is served natively (pgvector); the SQLite-side
+    /// index switch does not apply.
+    pub fn rebuild_vector_index(&self) -> IcmResult<usize> {
+        Err(IcmError::Unsupported("rebuild_vector_index".into()))
+    }
+
+    /// Disk usage is managed by the server; there is no local file to
+    /// break down.
+    pub fn storage_stats(&self) -> IcmResult<StorageStats> {
+        Err(IcmError::Unsupported("storage_stats".into()))
+    }
+
+    /// Session context logs are SQLite-only for now; hooks skip
+    /// recording them here.
+    pub fn record_session_context(&self, _ctx: &SessionContextInsert) -> IcmResult<i64> {
+        unsupported("record_session_context")
+    }
+
+    pub fn session_context(&self, _session_id: &str) -> IcmResult<Vec<SessionContext>> {
+        unsupported("session_context")
+    }
+
+    pub fn store_keyed(&self, _key: &str, _memory: Memory) -> IcmResult<KeyedOutcome> {
+        unsupported("store --key")
+    }
+
+    /// Raw excerpts are always stored inline here, so no memory ever
+    /// references an attachment.
+    pub fn attachment(&self, _hash: &str) -> IcmResult<Option<String>> {
+        Ok(None)
+    }
+
+    /// Topic digests are not yet available on the PostgreSQL backend; the
+    /// fast prompt hook then serves keyword hits only.
+    pub fn refresh_topic_digests(&self, _per_topic: usize) -> IcmResult<usize> {
+        Err(IcmError::Unsupported(
+            "refresh_topic_digests (use the default SQLite backend)".into(),
+        ))
+    }
+
+    pub fn topic_digests(&self) -> IcmResult<Vec<TopicDigest>> {
+        Err(IcmError::Unsupported(
+            "topic_digests (use the default SQLite backend)".into(),
+        ))
+    }
+
+    /// No centroid cache on the PostgreSQL backend: importance suggestions
+    /// fall back to the rule pass.
+    pub fn importance_centroids(&self) -> IcmResult<Vec<(Importance, Vec<f32>)>> {
+        Ok(Vec::new())
+    }
+
+    pub fn refresh_importance_centroids(&self) -> IcmResult<Vec<(Importance, Vec<f32>)>> {
+        Err(IcmError::Unsupported(
+            "refresh_importance_centroids (use the default SQLite backend)".into(),
+        ))
+    }
+
+    /// See [`Self::auto_consolidate`]: never proposes anything.
+    pub fn consolidation_proposal(
+        &self,
+        _topic: &str,
+        _threshold: usize,
+    ) -> IcmResult<Option<Memory>> {
+        Ok(None)
+    }
+
     /// Pattern mining is not yet available on the PostgreSQL backend.
     pub fn detect_patterns(
         &self,
@@ -995,6 +1369,7 @@ fn init_schema(client: &mut Client, requested_dims: usize) -> IcmResult<usize> {
                 related_ids TEXT,
                 summary_hash TEXT,
                 embedding vector({dims}),
+                author TEXT,
                 fts tsvector GENERATED ALWAYS AS (
                     to_tsvector('simple',
                         coalesce(topic, '') || ' ' ||
@@ -1003,6 +1378,8 @@ fn init_schema(client: &mut Client, requested_dims: usize) -> IcmResult<usize> {
                 ) STORED
             );
 
+            ALTER TABLE memories ADD COLUMN IF NOT EXISTS author TEXT;
+
             CREATE INDEX IF NOT EXISTS idx_memories_topic ON memories(topic);
             CREATE INDEX IF NOT EXISTS idx_memories_weight ON memories(weight);
             CREATE INDEX IF NOT EXISTS idx_memories_created ON memories(created_at);
@@ -1018,6 +1395,13 @@ fn init_schema(client: &mut Client, requested_dims: usize) -> IcmResult<usize> {
                 captured_at TIMESTAMPTZ NOT NULL
             );
 
+            CREATE TABLE IF NOT EXISTS review_queue (
+                id TEXT PRIMARY KEY,
+                origin TEXT NOT NULL,
+                memory TEXT NOT NULL,
+                queued_at TIMESTAMPTZ NOT NULL
+            );
+
             CREATE TABLE IF NOT EXISTS code_areas (
                 id BIGSERIAL PRIMARY KEY,
                 project TEXT NOT NULL,
@@ -1044,7 +1428,17 @@ fn init_schema(client: &mut Client, requested_dims: usize) -> IcmResult<usize> {
                 note TEXT
             );
             CREATE INDEX IF NOT EXISTS idx_hook_events_ts ON hook_events(ts);
-            CREATE INDEX IF NOT EXISTS idx_hook_events_event ON hook_events(event);"
+            CREATE INDEX IF NOT EXISTS idx_hook_events_event ON hook_events(event);
+
+            CREATE TABLE IF NOT EXISTS session_injections (
+                session_id TEXT NOT NULL,
+                memory_id TEXT NOT NULL,
+                memory_updated_at TIMESTAMPTZ NOT NULL,
+                injected_at TIMESTAMPTZ NOT NULL,
+                PRIMARY KEY (session_id, memory_id)
+            );
+            CREATE INDEX IF NOT EXISTS idx_session_injections_injected
+                ON session_injections(injected_at);"
         ))
         .map_err(pg_err)?;
 
@@ -1073,6 +1467,19 @@ fn init_schema(client: &mut Client, requested_dims: usize) -> IcmResult<usize> {
 // MemoryStore
 // ---------------------------------------------------------------------------
 
+impl MemoryPages for PostgresStore {
+    fn memories_after(&self, after: Option<&str>, limit: usize) -> IcmResult<Vec<Memory>> {
+        let mut c = self.conn()?;
+        let rows = c
+            .query(
+                &format!("SELECT {SELECT_COLS} FROM memories WHERE id > $1 ORDER BY id LIMIT $2"),
+                &[&after.unwrap_or(""), &(limit as i64)],
+            )
+            .map_err(pg_err)?;
+        Ok(rows.iter().map(row_to_memory).collect())
+    }
+}
+
 impl MemoryStore for PostgresStore {
     fn store(&self, memory: Memory) -> IcmResult<String> {
         if self.readonly {
@@ -1122,7 +1529,7 @@ impl MemoryStore for PostgresStore {
                     updated_at = $2, last_accessed = $3, access_count = $4, weight = $5,
                     topic = $6, summary = $7, raw_excerpt = $8, keywords = $9,
                     importance = $10, source_type = $11, source_data = $12, related_ids = $13,
-                    embedding = $14, summary_hash = $15
+                    embedding = $14, summary_hash = $15, author = $16
                  WHERE id = $1",
                 &[
                     &memory.id,
@@ -1140,6 +1547,7 @@ impl MemoryStore for PostgresStore {
                     &related_json,
                     &emb,
                     &hash,
+                    &memory.author,
                 ],
             )
             .map_err(pg_err)?;
@@ -1160,29 +1568,28 @@ impl MemoryStore for PostgresStore {
         if changed == 0 {
             return Err(IcmError::NotFound(id.to_string()));
         }
+        replace_memory_refs_in(&mut *c, &[id], None)?;
         Ok(())
     }
 
     fn search_by_keywords(&self, keywords: &[&str], limit: usize) -> IcmResult<Vec<Memory>> {
-        if keywords.is_empty() {
+        let parsed = KeywordQuery::parse(keywords, &self.keyword_options);
+        if parsed.is_empty() {
             return Ok(Vec::new());
         }
-        let keywords = &keywords[..keywords.len().min(50)];
         let limit = limit.min(100);
 
-        let mut owned: Vec<Box<dyn ToSql + Sync>> = Vec::new();
-        let mut where_parts: Vec<String> = Vec::new();
-        for k in keywords {
-            owned.push(Box::new(format!("%{k}%")));
-            let p = owned.len();
-            where_parts.push(format!(
-                "(keywords ILIKE ${p} OR summary ILIKE ${p} OR topic ILIKE ${p})"
-            ));
-        }
+        let clauses = KeywordSql::build(&parsed, "ILIKE", '$', "::int");
+        let mut owned: Vec<Box<dyn ToSql + Sync>> = clauses
+            .patterns
+            .into_iter()
+            .map(|p| Box::new(p) as Box<dyn ToSql + Sync>)
+            .collect();
         owned.push(Box::new(limit as i64));
         let sql = format!(
-            "SELECT {SELECT_COLS} FROM memories WHERE {} ORDER BY weight DESC LIMIT ${}",
-            where_parts.join(" OR "),
+            "SELECT {SELECT_COLS} FROM memories WHERE {} ORDER BY {} LIMIT ${}",
+            clauses.where_clause,
+            clauses.order_by,
             owned.len()
         );
         let params: Vec<&(dyn ToSql + Sync)> = owned.iter().map(|b| b.as_ref()).collect();
@@ -1200,11 +1607,16 @@ impl MemoryStore for PostgresStore {
         let rows = c
             .query(
                 &format!(
-                    "SELECT {SELECT_COLS} FROM memories \
-                     WHERE fts @@ websearch_to_tsquery('simple', $1) \
-                     ORDER BY weight DESC LIMIT $2"
+                    "SELECT {SELECT_COLS} FROM ( \
+                         SELECT m.*, ts_rank_cd(m.fts, q) AS rel \
+                         FROM memories m, websearch_to_tsquery('simple', $1) q \
+                         WHERE m.fts @@ q \
+                     ) hits \
+                     ORDER BY $3::real * rel / GREATEST(MAX(rel) OVER (), 1e-9) \
+                              + (1 - $3::real) * weight DESC, weight DESC \
+                     LIMIT $2"
                 ),
-                &[&query, &(limit as i64)],
+                &[&query, &(limit as i64), &self.fts_relevance],
             )
             .map_err(pg_err)?;
         Ok(rows.iter().map(row_to_memory).collect())
@@ -1229,18 +1641,18 @@ impl MemoryStore for PostgresStore {
         Ok(rows
             .iter()
             .map(|row| {
-                let distance: f64 = row.get(15);
+                let distance: f64 = row.get(16);
                 (row_to_memory(row), 1.0 - distance as f32)
             })
             .collect())
     }
 
-    fn search_hybrid(
+    fn search_hybrid_scored(
         &self,
         query: &str,
         embedding: &[f32],
         limit: usize,
-    ) -> IcmResult<Vec<(Memory, f32)>> {
+    ) -> IcmResult<Vec<(Memory, HybridScore)>> {
         let limit = limit.min(1000);
         let pool_size = limit * 4;
 
@@ -1274,7 +1686,7 @@ impl MemoryStore for PostgresStore {
 
         // Normalize FTS ranks into 0..1 within the pool (higher is better).
         let max_rank = fts_pairs.iter().map(|(_, r)| *r).fold(0.0_f64, f64::max);
-        let mut fts_scores: HashMap<String, f32> = HashMap::new();
+        let mut fts_scores: HashMap<String, (f32, f32)> = HashMap::new();
         let missing: Vec<String> = fts_pairs
             .iter()
             .filter(|(id, _)| !all_memories.contains_key(id))
@@ -1293,19 +1705,29 @@ impl MemoryStore for PostgresStore {
             } else {
                 0.0
             };
-            fts_scores.insert(id, score);
+            fts_scores.insert(id, (rank as f32, score));
         }
 
         // 4. Blend: 30% FTS + 70% vector (matches the SQLite backend).
-        let mut scored: Vec<(String, f32)> = all_memories
+        let mut scored: Vec<(String, HybridScore)> = all_memories
             .keys()
             .map(|id| {
-                let fts = fts_scores.get(id).copied().unwrap_or(0.0);
-                let vec = vec_scores.get(id).copied().unwrap_or(0.0);
-                (id.clone(), 0.3 * fts + 0.7 * vec)
+                let fts = fts_scores.get(id).copied();
+                let similarity = vec_scores.get(id).copied();
+                let score = HybridScore::new(
+                    fts.map(|(rank, _)| rank),
+                    fts.map_or(0.0, |(_, score)| score),
+                    similarity,
+                    similarity.unwrap_or(0.0),
+                );
+                (id.clone(), score)
             })
             .collect();
-        scored.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
+        scored.sort_by(|a, b| {
+            b.1.combined
+                .partial_cmp(&a.1.combined)
+                .unwrap_or(std::cmp::Ordering::Equal)
+        });
         scored.truncate(limit);
 
         Ok(scored
@@ -1318,12 +1740,14 @@ impl MemoryStore for PostgresStore {
         if self.readonly {
             return Ok(());
         }
+        let Reinforcement { rate, cap } = self.reinforcement;
         let mut c = self.conn()?;
         let changed = c
             .execute(
-                "UPDATE memories SET last_accessed = $1, access_count = access_count + 1 \
-                 WHERE id = $2",
-                &[&Utc::now(), &id],
+                "UPDATE memories SET last_accessed = $1, access_count = access_count + 1, \
+                 weight = GREATEST(weight, LEAST($3::float8, weight + $2::float8 * (1.0 - weight))) \
+                 WHERE id = $4",
+                &[&Utc::now(), &(rate as f64), &(cap as f64), &id],
             )
             .map_err(pg_err)?;
         if changed == 0 {
@@ -1337,12 +1761,14 @@ impl MemoryStore for PostgresStore {
             return Ok(0);
         }
         let id_vec: Vec<String> = ids.iter().map(|s| s.to_string()).collect();
+        let Reinforcement { rate, cap } = self.reinforcement;
         let mut c = self.conn()?;
         let changed = c
             .execute(
-                "UPDATE memories SET last_accessed = $1, access_count = access_count + 1 \
-                 WHERE id = ANY($2)",
-                &[&Utc::now(), &id_vec],
+                "UPDATE memories SET last_accessed = $1, access_count = access_count + 1, \
+                 weight = GREATEST(weight, LEAST($3::float8, weight + $2::float8 * (1.0 - weight))) \
+                 WHERE id = ANY($4)",
+                &[&Utc::now(), &(rate as f64), &(cap as f64), &id_vec],
             )
             .map_err(pg_err)?;
         Ok(changed as usize)
@@ -1380,14 +1806,20 @@ impl MemoryStore for PostgresStore {
             return Err(IcmError::ReadOnly("prune".into()));
         }
         let mut c = self.conn()?;
-        let changed = c
-            .execute(
+        let doomed: Vec<String> = c
+            .query(
                 "DELETE FROM memories \
-                 WHERE weight < $1::float8 AND importance NOT IN ('critical', 'high')",
+                 WHERE weight < $1::float8 AND importance NOT IN ('critical', 'high') \
+                 RETURNING id",
                 &[&(weight_threshold as f64)],
             )
-            .map_err(pg_err)?;
-        Ok(changed as usize)
+            .map_err(pg_err)?
+            .iter()
+            .map(|row| row.get(0))
+            .collect();
+        let ids: Vec<&str> = doomed.iter().map(String::as_str).collect();
+        replace_memory_refs_in(&mut *c, &ids, None)?;
+        Ok(doomed.len())
     }
 
     fn list_all(&self) -> IcmResult<Vec<Memory>> {
@@ -1401,6 +1833,20 @@ impl MemoryStore for PostgresStore {
         Ok(rows.iter().map(row_to_memory).collect())
     }
 
+    fn get_all(&self, limit: usize, offset: usize) -> IcmResult<Vec<Memory>> {
+        let mut c = self.conn()?;
+        let rows = c
+            .query(
+                &format!(
+                    "SELECT {SELECT_COLS} FROM memories ORDER BY weight DESC, id \
+                     LIMIT $1 OFFSET $2"
+                ),
+                &[&(limit as i64), &(offset as i64)],
+            )
+            .map_err(pg_err)?;
+        Ok(rows.iter().map(row_to_memory).collect())
+    }
+
     fn get_by_topic(&self, topic: &str) -> IcmResult<Vec<Memory>> {
         let mut c = self.conn()?;
         let rows = c
@@ -1419,15 +1865,25 @@ impl MemoryStore for PostgresStore {
         self.list_topics_with_prefix(None)
     }
 
-    fn consolidate_topic(&self, topic: &str, consolidated: Memory) -> IcmResult<()> {
+    fn consolidate_topic(&self, topic: &str, mut consolidated: Memory) -> IcmResult<()> {
         if self.readonly {
             return Err(IcmError::ReadOnly("consolidate_topic".into()));
         }
         let mut c = self.conn()?;
         let mut tx = c.transaction().map_err(pg_err)?;
-        tx.execute("DELETE FROM memories WHERE topic = $1", &[&topic])
-            .map_err(pg_err)?;
-        insert_or_merge_memory(&mut tx, &consolidated)?;
+        let doomed: Vec<String> = tx
+            .query(
+                "DELETE FROM memories WHERE topic = $1 RETURNING id",
+                &[&topic],
+            )
+            .map_err(pg_err)?
+            .iter()
+            .map(|row| row.get(0))
+            .collect();
+        consolidated.related_ids.retain(|id| !doomed.contains(id));
+        let id = insert_or_merge_memory(&mut tx, &consolidated)?;
+        let ids: Vec<&str> = doomed.iter().map(String::as_str).collect();
+        replace_memory_refs_in(&mut tx, &ids, Some(&id))?;
         tx.commit().map_err(pg_err)?;
         Ok(())
     }
@@ -1569,13 +2025,14 @@ impl MemoirStore for PostgresStore {
     ) -> IcmResult<Vec<Concept>> {
         unsupported("memoir.search_concepts_fts")
     }
-    fn search_concepts_by_label(
+    fn search_concepts_by_labels(
         &self,
         _memoir_id: &str,
-        _label: &Label,
+        _labels: &[Label],
+        _match_all: bool,
         _limit: usize,
     ) -> IcmResult<Vec<Concept>> {
-        unsupported("memoir.search_concepts_by_label")
+        unsupported("memoir.search_concepts_by_labels")
     }
     fn search_all_concepts_fts(&self, _query: &str, _limit: usize) -> IcmResult<Vec<Concept>> {
         unsupported("memoir.search_all_concepts_fts")
@@ -1588,6 +2045,12 @@ impl MemoirStore for PostgresStore {
     ) -> IcmResult<()> {
         unsupported("memoir.refine_concept")
     }
+    fn concept_revisions(&self, _concept_id: &str) -> IcmResult<Vec<ConceptRevision>> {
+        unsupported("memoir.concept_revisions")
+    }
+    fn rollback_concept(&self, _concept_id: &str, _revision: u32) -> IcmResult<()> {
+        unsupported("memoir.rollback_concept")
+    }
     fn add_link(&self, _link: ConceptLink) -> IcmResult<String> {
         unsupported("memoir.add_link")
     }
@@ -1600,6 +2063,12 @@ impl MemoirStore for PostgresStore {
     fn delete_link(&self, _id: &str) -> IcmResult<()> {
         unsupported("memoir.delete_link")
     }
+    fn update_link_weight(&self, _id: &str, _weight: f32) -> IcmResult<()> {
+        unsupported("memoir.update_link_weight")
+    }
+    fn decay_links(&self, _factor: f32) -> IcmResult<usize> {
+        unsupported("memoir.decay_links")
+    }
     fn get_neighbors(
         &self,
         _concept_id: &str,
@@ -1649,6 +2118,16 @@ impl FeedbackStore for PostgresStore {
     fn feedback_stats(&self) -> IcmResult<FeedbackStats> {
         unsupported("feedback.feedback_stats")
     }
+    fn record_recall_feedback(&self, _feedback: RecallFeedback) -> IcmResult<f32> {
+        unsupported("feedback.record_recall_feedback")
+    }
+    fn list_recall_feedback(
+        &self,
+        _memory_id: Option<&str>,
+        _limit: usize,
+    ) -> IcmResult<Vec<RecallFeedback>> {
+        unsupported("feedback.list_recall_feedback")
+    }
 }
 
 impl FactsStore for PostgresStore {
@@ -2,6 +2,7 @@ use rusqlite::Connection;
 
 use icm_core::{IcmError, IcmResult};
 
+use crate::compress::compress_existing;
 use crate::store::db_err;
 
 /// Check if a FTS virtual table exists in sqlite_master.
@@ -122,6 +123,27 @@ pub fn init_db_with_dims(conn: &Connection, embedding_dims: usize) -> Result<(),
 
         CREATE INDEX IF NOT EXISTS idx_concept_links_source ON concept_links(source_id);
         CREATE INDEX IF NOT EXISTS idx_concept_links_target ON concept_links(target_id);
+
+        -- Superseded concept definitions, one row per past revision.
+        CREATE TABLE IF NOT EXISTS concept_revisions (
+            concept_id TEXT NOT NULL REFERENCES concepts(id) ON DELETE CASCADE,
+            revision INTEGER NOT NULL,
+            definition TEXT NOT NULL,
+            confidence REAL NOT NULL,
+            updated_at TEXT NOT NULL,
+            PRIMARY KEY (concept_id, revision)
+        );
+
+        -- Snapshot the outgoing revision whenever the definition or the
+        -- revision number changes (refine, rollback, update_concept).
+        CREATE TRIGGER IF NOT EXISTS concept_revisions_au
+        AFTER UPDATE OF definition, revision ON concepts
+        WHEN old.definition IS NOT new.definition OR old.revision IS NOT new.revision
+        BEGIN
+            INSERT OR IGNORE INTO concept_revisions
+                (concept_id, revision, definition, confidence, updated_at)
+            VALUES (old.id, old.revision, old.definition, old.confidence, old.updated_at);
+        END;
         ",
     )
     .map_err(db_err)?;
@@ -135,6 +157,118 @@ pub fn init_db_with_dims(conn: &Connection, embedding_dims: usize) -> Result<(),
             return Err(db_err(e));
         }
     }
+    // Migration: `author` attribution for shared team DBs. NULL on rows
+    // stored before the column existed.
+    if let Err(e) = conn.execute("ALTER TABLE memories ADD COLUMN author TEXT", []) {
+        let msg = e.to_string();
+        if !msg.contains("duplicate column name") {
+            return Err(db_err(e));
+        }
+    }
+    // Migration: user key/value metadata (`icm store --meta`), a JSON
+    // object. NULL on untagged rows.
+    if let Err(e) = conn.execute("ALTER TABLE memories ADD COLUMN metadata TEXT", []) {
+        let msg = e.to_string();
+        if !msg.contains("duplicate column name") {
+            return Err(db_err(e));
+        }
+    }
+    // Migration: memory kind (`fact`, `preference`, `instruction`).
+    // Rows stored before the column existed are facts.
+    if let Err(e) = conn.execute(
+        "ALTER TABLE memories ADD COLUMN kind TEXT NOT NULL DEFAULT 'fact'",
+        [],
+    ) {
+        let msg = e.to_string();
+        if !msg.contains("duplicate column name") {
+            return Err(db_err(e));
+        }
+    }
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_memories_kind ON memories(kind) WHERE kind != 'fact'",
+        [],
+    )
+    .map_err(db_err)?;
+    // Migration: content-addressed attachments. Large raw excerpts live in
+    // `attachments` keyed by their SHA-256 and are referenced from
+    // `memories.attachment`; they are never indexed by FTS.
+    if let Err(e) = conn.execute("ALTER TABLE memories ADD COLUMN attachment TEXT", []) {
+        let msg = e.to_string();
+        if !msg.contains("duplicate column name") {
+            return Err(db_err(e));
+        }
+    }
+    conn.execute_batch(
+        "
+        CREATE TABLE IF NOT EXISTS attachments (
+            hash TEXT PRIMARY KEY,
+            content TEXT NOT NULL,
+            bytes INTEGER NOT NULL,
+            created_at TEXT NOT NULL
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_memories_attachment
+            ON memories(attachment) WHERE attachment IS NOT NULL;
+
+        -- Drop an attachment once the last memory referencing it is
+        -- deleted or points elsewhere (prune, forget, consolidate, update).
+        CREATE TRIGGER IF NOT EXISTS memories_attachment_ad
+        AFTER DELETE ON memories WHEN old.attachment IS NOT NULL
+        BEGIN
+            DELETE FROM attachments WHERE hash = old.attachment
+                AND NOT EXISTS (SELECT 1 FROM memories WHERE attachment = old.attachment);
+        END;
+
+        CREATE TRIGGER IF NOT EXISTS memories_attachment_au
+        AFTER UPDATE OF attachment ON memories
+        WHEN old.attachment IS NOT NULL AND old.attachment IS NOT new.attachment
+        BEGIN
+            DELETE FROM attachments WHERE hash = old.attachment
+                AND NOT EXISTS (SELECT 1 FROM memories WHERE attachment = old.attachment);
+        END;
+        ",
+    )
+    .map_err(db_err)?;
+    // Stable user keys (`icm store --key`): the memory currently holding
+    // each key's value, and how many times it was replaced.
+    conn.execute_batch(
+        "
+        CREATE TABLE IF NOT EXISTS memory_keys (
+            key TEXT PRIMARY KEY,
+            memory_id TEXT NOT NULL,
+            revision INTEGER NOT NULL DEFAULT 1,
+            updated_at TEXT NOT NULL
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_memory_keys_memory ON memory_keys(memory_id);
+
+        CREATE TRIGGER IF NOT EXISTS memories_key_ad
+        AFTER DELETE ON memories
+        BEGIN
+            DELETE FROM memory_keys WHERE memory_id = old.id;
+        END;
+        ",
+    )
+    .map_err(db_err)?;
+    // Migration: zstd compression of long raw excerpts and attachment
+    // content (see `compress.rs`). The first open after the upgrade
+    // compresses the rows already stored.
+    for (table, key, column) in [
+        ("memories", "id", "raw_excerpt"),
+        ("attachments", "hash", "content"),
+    ] {
+        let added = conn.execute(
+            &format!("ALTER TABLE {table} ADD COLUMN compressed INTEGER NOT NULL DEFAULT 0"),
+            [],
+        );
+        match added {
+            Ok(_) => {
+                compress_existing(conn, table, key, column).map_err(db_err)?;
+            }
+            Err(e) if e.to_string().contains("duplicate column name") => {}
+            Err(e) => return Err(db_err(e)),
+        }
+    }
     // Ensure the partial unique index exists even on DBs that ran an old
     // CREATE TABLE (which had no summary_hash column to index against).
     conn.execute_batch(
@@ -211,6 +345,8 @@ pub fn init_db_with_dims(conn: &Connection, embedding_dims: usize) -> Result<(),
         .map_err(db_err)?;
     }
 
+    migrate_concept_labels(conn)?;
+
     // Metadata key-value table for internal state (e.g. last_decay_at)
     conn.execute_batch(
         "CREATE TABLE IF NOT EXISTS icm_metadata (
@@ -235,6 +371,19 @@ pub fn init_db_with_dims(conn: &Connection, embedding_dims: usize) -> Result<(),
             applied_count INTEGER DEFAULT 0
         );
         CREATE INDEX IF NOT EXISTS idx_feedback_topic ON feedback(topic);
+
+        -- Useful / noise marks on recalled memories (`icm feedback <id>`).
+        -- No foreign key: the signals are kept for ranking tuning even
+        -- after the memory they rate is deleted.
+        CREATE TABLE IF NOT EXISTS recall_feedback (
+            id TEXT PRIMARY KEY,
+            memory_id TEXT NOT NULL,
+            signal TEXT NOT NULL,
+            query TEXT,
+            source TEXT NOT NULL DEFAULT '',
+            created_at TEXT NOT NULL
+        );
+        CREATE INDEX IF NOT EXISTS idx_recall_feedback_memory ON recall_feedback(memory_id);
         ",
     )
     .map_err(db_err)?;
@@ -293,6 +442,25 @@ pub fn init_db_with_dims(conn: &Connection, embedding_dims: usize) -> Result<(),
     )
     .map_err(db_err)?;
 
+    // Typed memory-to-memory links (`icm link`): error → fix,
+    // decision → revision. Deleting either memory drops the link.
+    conn.execute_batch(
+        "
+        CREATE TABLE IF NOT EXISTS memory_links (
+            id TEXT PRIMARY KEY,
+            source_id TEXT NOT NULL REFERENCES memories(id) ON DELETE CASCADE,
+            target_id TEXT NOT NULL REFERENCES memories(id) ON DELETE CASCADE,
+            relation TEXT NOT NULL,
+            created_at TEXT NOT NULL,
+            UNIQUE(source_id, target_id, relation),
+            CHECK(source_id != target_id)
+        );
+        CREATE INDEX IF NOT EXISTS idx_memory_links_source ON memory_links(source_id);
+        CREATE INDEX IF NOT EXISTS idx_memory_links_target ON memory_links(target_id);
+        ",
+    )
+    .map_err(db_err)?;
+
     // Transcripts (verbatim sessions + messages)
     conn.execute_batch(
         "
@@ -338,6 +506,16 @@ pub fn init_db_with_dims(conn: &Connection, embedding_dims: usize) -> Result<(),
         CREATE INDEX IF NOT EXISTS idx_pending_extractions_captured
             ON pending_extractions(captured_at);
 
+        -- Agent-written memories awaiting `icm review` when
+        -- `[memory] review_agent_writes` is on. `memory` is the
+        -- serialized Memory; approval stores it into `memories`.
+        CREATE TABLE IF NOT EXISTS review_queue (
+            id TEXT PRIMARY KEY,
+            origin TEXT NOT NULL,
+            memory TEXT NOT NULL,
+            queued_at TEXT NOT NULL
+        );
+
         -- Structured hook telemetry. Every `icm hook <event>` invocation
         -- records one row so users can audit what fired, how long it took,
         -- what its outcome was, and whether the async extraction path was
@@ -360,6 +538,46 @@ pub fn init_db_with_dims(conn: &Connection, embedding_dims: usize) -> Result<(),
         CREATE INDEX IF NOT EXISTS idx_hook_events_event
             ON hook_events(event);
 
+        -- Memories the prompt hook already injected into a session, with
+        -- the memory's `updated_at` at the time. Later prompts in the same
+        -- session skip them unless the memory changed (delta recall).
+        -- Cleared by the SessionEnd hook.
+        CREATE TABLE IF NOT EXISTS session_injections (
+            session_id TEXT NOT NULL,
+            memory_id TEXT NOT NULL,
+            memory_updated_at TEXT NOT NULL,
+            injected_at TEXT NOT NULL,
+            PRIMARY KEY (session_id, memory_id)
+        );
+        CREATE INDEX IF NOT EXISTS idx_session_injections_injected
+            ON session_injections(injected_at);
+
+        -- What each hook fire injected into (or extracted from) a
+        -- session, for `icm sessions show`. Unlike session_injections it
+        -- is never swept; it goes away with its session.
+        CREATE TABLE IF NOT EXISTS session_context (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            session_id TEXT NOT NULL REFERENCES sessions(id) ON DELETE CASCADE,
+            ts TEXT NOT NULL,
+            event TEXT NOT NULL,
+            query TEXT,
+            injected TEXT NOT NULL DEFAULT '',
+            memory_ids TEXT NOT NULL DEFAULT '[]', -- JSON array
+            facts_extracted INTEGER NOT NULL DEFAULT 0
+        );
+        CREATE INDEX IF NOT EXISTS idx_session_context_session
+            ON session_context(session_id);
+
+        -- One line per topic summarising its heaviest memories, rebuilt
+        -- by `icm serve` and `icm index digests`. `icm hook prompt --fast`
+        -- injects these instead of scanning topics on every prompt.
+        CREATE TABLE IF NOT EXISTS topic_digests (
+            topic TEXT PRIMARY KEY,
+            digest TEXT NOT NULL,
+            memory_count INTEGER NOT NULL,
+            refreshed_at TEXT NOT NULL
+        );
+
         -- Auto-captured 'code areas' the agent worked in during a
         -- session. Populated by the PostToolUse hook
         -- (`icm hook post`) whenever the upstream tool call is an
@@ -447,6 +665,60 @@ pub fn init_db_with_dims(conn: &Connection, embedding_dims: usize) -> Result<(),
             .map_err(db_err)?;
     }
 
+    // Vector change tracking for HNSW indexes (`vector_index.rs`): every
+    // embedding write or delete, from any process and any code path,
+    // bumps `vector_generation` and stamps the memory with it, so an
+    // index copy at generation N catches up from the rows above N.
+    // DELETE + INSERT rather than INSERT OR REPLACE: an `OR IGNORE` on the
+    // statement that fired the trigger would override REPLACE here.
+    conn.execute_batch(
+        "
+        INSERT OR IGNORE INTO icm_metadata (key, value) VALUES ('vector_generation', '0');
+
+        CREATE TABLE IF NOT EXISTS vector_changes (
+            memory_id TEXT PRIMARY KEY,
+            generation INTEGER NOT NULL
+        );
+        CREATE INDEX IF NOT EXISTS idx_vector_changes_generation
+            ON vector_changes(generation);
+
+        CREATE TRIGGER IF NOT EXISTS memories_vector_ai
+        AFTER INSERT ON memories WHEN new.embedding IS NOT NULL
+        BEGIN
+            UPDATE icm_metadata SET value = CAST(value AS INTEGER) + 1
+                WHERE key = 'vector_generation';
+            DELETE FROM vector_changes WHERE memory_id = new.id;
+            INSERT INTO vector_changes (memory_id, generation)
+                SELECT new.id, CAST(value AS INTEGER) FROM icm_metadata
+                WHERE key = 'vector_generation';
+        END;
+
+        CREATE TRIGGER IF NOT EXISTS memories_vector_ad
+        AFTER DELETE ON memories WHEN old.embedding IS NOT NULL
+        BEGIN
+            UPDATE icm_metadata SET value = CAST(value AS INTEGER) + 1
+                WHERE key = 'vector_generation';
+            DELETE FROM vector_changes WHERE memory_id = old.id;
+            INSERT INTO vector_changes (memory_id, generation)
+                SELECT old.id, CAST(value AS INTEGER) FROM icm_metadata
+                WHERE key = 'vector_generation';
+        END;
+
+        CREATE TRIGGER IF NOT EXISTS memories_vector_au
+        AFTER UPDATE OF embedding ON memories
+        WHEN old.embedding IS NOT new.embedding
+        BEGIN
+            UPDATE icm_metadata SET value = CAST(value AS INTEGER) + 1
+                WHERE key = 'vector_generation';
+            DELETE FROM vector_changes WHERE memory_id = new.id;
+            INSERT INTO vector_changes (memory_id, generation)
+                SELECT new.id, CAST(value AS INTEGER) FROM icm_metadata
+                WHERE key = 'vector_generation';
+        END;
+        ",
+    )
+    .map_err(db_err)?;
+
     // Migration: scope FTS UPDATE trigger to indexed columns only (fixes #44).
     // The old trigger fired on ANY update (including update_access, apply_decay)
     // which churned the FTS index and could create ghost entries.
@@ -514,6 +786,59 @@ pub fn init_db_with_dims(conn: &Connection, embedding_dims: usize) -> Result<(),
     Ok(())
 }
 
+/// Normalized `concepts.labels`, one row per (concept, label), for
+/// indexed label queries. Triggers keep it in sync with the JSON column,
+/// so every write path (add, update, restore, import) is covered. On first
+/// creation the table is backfilled from existing concepts.
+fn migrate_concept_labels(conn: &Connection) -> IcmResult<()> {
+    let exists: bool = conn
+        .query_row(
+            "SELECT COUNT(*) > 0 FROM sqlite_master WHERE type='table' AND name='concept_labels'",
+            [],
+            |row| row.get(0),
+        )
+        .map_err(db_err)?;
+    if exists {
+        return Ok(());
+    }
+
+    let tx = conn.unchecked_transaction().map_err(db_err)?;
+    tx.execute_batch(
+        "
+        CREATE TABLE concept_labels (
+            concept_id TEXT NOT NULL REFERENCES concepts(id) ON DELETE CASCADE,
+            namespace TEXT NOT NULL,
+            value TEXT NOT NULL,
+            PRIMARY KEY (concept_id, namespace, value)
+        );
+
+        CREATE INDEX idx_concept_labels_label ON concept_labels(namespace, value);
+
+        DROP TRIGGER IF EXISTS concept_labels_ai;
+        DROP TRIGGER IF EXISTS concept_labels_au;
+
+        CREATE TRIGGER concept_labels_ai AFTER INSERT ON concepts BEGIN
+            INSERT OR IGNORE INTO concept_labels (concept_id, namespace, value)
+            SELECT new.id, json_extract(l.value, '$.namespace'), json_extract(l.value, '$.value')
+            FROM json_each(new.labels) l;
+        END;
+
+        CREATE TRIGGER concept_labels_au AFTER UPDATE OF id, labels ON concepts BEGIN
+            DELETE FROM concept_labels WHERE concept_id = old.id;
+            INSERT OR IGNORE INTO concept_labels (concept_id, namespace, value)
+            SELECT new.id, json_extract(l.value, '$.namespace'), json_extract(l.value, '$.value')
+            FROM json_each(new.labels) l;
+        END;
+
+        INSERT OR IGNORE INTO concept_labels (concept_id, namespace, value)
+        SELECT c.id, json_extract(l.value, '$.namespace'), json_extract(l.value, '$.value')
+        FROM concepts c, json_each(c.labels) l;
+        ",
+    )
+    .map_err(db_err)?;
+    tx.commit().map_err(db_err)
+}
+
 /// Migrate existing DBs: replace the broad `memories_au` trigger with one
 /// scoped to `UPDATE OF topic, summary, keywords` so that `update_access` /
 /// `apply_decay` no longer churn the FTS index.  Also rebuilds the FTS index
@@ -658,6 +983,46 @@ mod tests {
         init_db(&conn).expect("re-running migration must be a no-op");
     }
 
+    /// Long raw excerpts stored before the `compressed` flag existed are
+    /// compressed by the upgrade; short ones are left alone.
+    #[test]
+    fn test_migration_compresses_existing_raw_excerpts() {
+        ensure_vec_init();
+        let conn = Connection::open_in_memory().unwrap();
+        init_db(&conn).unwrap();
+        // Roll back to the pre-compression layout, keeping the rows.
+        conn.execute_batch(
+            "ALTER TABLE memories DROP COLUMN compressed;
+             ALTER TABLE attachments DROP COLUMN compressed;",
+        )
+        .unwrap();
+        let long = "panic: index out of range\n".repeat(100);
+        for (id, raw) in [("long", long.as_str()), ("short", "tiny")] {
+            conn.execute(
+                "INSERT INTO memories (id, created_at, last_accessed, topic, summary, raw_excerpt,
+                                       importance, source_type)
+                 VALUES (?1, '2026-01-01T00:00:00Z', '2026-01-01T00:00:00Z', 't', ?1, ?2,
+                         'medium', 'manual')",
+                [id, raw],
+            )
+            .unwrap();
+        }
+
+        init_db(&conn).expect("upgrade must succeed");
+
+        let row = |id: &str| -> (String, bool) {
+            conn.query_row(
+                "SELECT typeof(raw_excerpt), compressed FROM memories WHERE id = ?1",
+                [id],
+                |r| Ok((r.get(0)?, r.get(1)?)),
+            )
+            .unwrap()
+        };
+        assert_eq!(row("long"), ("blob".to_string(), true));
+        assert_eq!(row("short"), ("text".to_string(), false));
+        init_db(&conn).expect("re-running migration must be a no-op");
+    }
+
     #[test]
     fn test_embedding_dims_too_small() {
         ensure_vec_init();
@@ -714,6 +1079,50 @@ mod tests {
         assert!(tables.contains(&"concept_links".to_string()));
         assert!(tables.contains(&"concepts_fts".to_string()));
         assert!(tables.contains(&"vec_memories".to_string()));
+        assert!(tables.contains(&"concept_labels".to_string()));
+        assert!(tables.contains(&"concept_revisions".to_string()));
+        assert!(tables.contains(&"memory_links".to_string()));
+        assert!(tables.contains(&"session_context".to_string()));
+    }
+
+    #[test]
+    fn test_concept_labels_backfilled_and_synced() {
+        ensure_vec_init();
+        let conn = Connection::open_in_memory().unwrap();
+        init_db(&conn).unwrap();
+        // Simulate a DB from before the join table: labels only in JSON.
+        conn.execute_batch(
+            "DROP TRIGGER concept_labels_ai;
+             DROP TRIGGER concept_labels_au;
+             DROP TABLE concept_labels;
+             INSERT INTO memoirs (id, name, created_at, updated_at) VALUES ('m', 'm', '', '');
+             INSERT INTO concepts (id, memoir_id, name, definition, labels, created_at, updated_at)
+             VALUES ('c', 'm', 'c', 'd',
+                     '[{\"namespace\":\"domain\",\"value\":\"arch\"}]', '', '');",
+        )
+        .unwrap();
+        init_db(&conn).unwrap();
+
+        let labels = |conn: &Connection| -> Vec<String> {
+            let mut stmt = conn
+                .prepare("SELECT namespace || ':' || value FROM concept_labels ORDER BY 1")
+                .unwrap();
+            stmt.query_map([], |row| row.get(0))
+                .unwrap()
+                .map(|r| r.unwrap())
+                .collect()
+        };
+        assert_eq!(labels(&conn), ["domain:arch"]);
+
+        conn.execute(
+            "UPDATE concepts SET labels = '[{\"namespace\":\"type\",\"value\":\"x\"}]'",
+            [],
+        )
+        .unwrap();
+        assert_eq!(labels(&conn), ["type:x"]);
+        conn.execute_batch("PRAGMA foreign_keys=ON; DELETE FROM concepts;")
+            .unwrap();
+        assert!(labels(&conn).is_empty());
     }
 
     /// Insert a memories row with a raw embedding BLOB of the given dim.
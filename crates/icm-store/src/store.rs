@@ -1,6 +1,6 @@
-use std::collections::{HashMap, HashSet, VecDeque};
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
 use std::num::NonZeroUsize;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::sync::{Mutex, Once};
 
 use chrono::{DateTime, Utc};
@@ -10,17 +10,36 @@ use sha2::{Digest, Sha256};
 use zerocopy::IntoBytes;
 
 use icm_core::{
-    Concept, ConceptLink, Embedder, Fact, FactsStats, FactsStore, Feedback, FeedbackStats,
-    FeedbackStore, IcmError, IcmResult, Importance, Label, Memoir, MemoirStats, MemoirStore,
-    Memory, MemorySource, MemoryStore, Message, PatternCluster, Relation, Role, Session,
-    StoreStats, TopicHealth, TranscriptHit, TranscriptStats, TranscriptStore,
+    Concept, ConceptLink, ConceptRevision, Embedder, Fact, FactsStats, FactsStore, Feedback,
+    FeedbackStats, FeedbackStore, HybridScore, IcmError, IcmResult, Importance, KeywordOptions,
+    KeywordQuery, Label, Memoir, MemoirStats, MemoirStore, Memory, MemoryKind, MemoryLink,
+    MemorySource, MemoryStore, Message, PatternCluster, RecallFeedback, RecallSignal,
+    Reinforcement, Relation, Role, Session, StoreStats, TopicHealth, TranscriptHit,
+    TranscriptStats, TranscriptStore, MAX_LINK_WEIGHT,
 };
 
+use crate::common::{
+    archived_topic, build_digest, rewrite_memory_refs, KeyedOutcome, KeywordSql, MemoryIter,
+    MemoryPages, ReviewItem, SessionContext, SessionContextInsert, StorageStats, StoreOutcome,
+    TopicDigest, WritePolicy, ATTACHMENT_MIN_BYTES, DEFAULT_FTS_RELEVANCE,
+};
+use crate::compress;
 use crate::schema::init_db_with_dims;
+use crate::vector_index::{HnswIndex, IndexKind, VectorIndex};
 
-/// Convert rusqlite::Error to IcmError::Database
+/// Convert a rusqlite::Error to the matching IcmError variant, falling
+/// back to IcmError::Database.
 pub(crate) fn db_err(e: rusqlite::Error) -> IcmError {
-    IcmError::Database(e.to_string())
+    use rusqlite::ErrorCode;
+    let msg = e.to_string();
+    match e.sqlite_error_code() {
+        Some(ErrorCode::ConstraintViolation) => IcmError::Constraint(msg),
+        Some(ErrorCode::DatabaseBusy | ErrorCode::DatabaseLocked) => IcmError::Busy(msg),
+        Some(ErrorCode::DatabaseCorrupt | ErrorCode::NotADatabase) => IcmError::Corrupt(msg),
+        // sqlite-vec reports this as a generic error.
+        _ if msg.contains("Dimension mismatch") => IcmError::DimensionMismatch(msg),
+        _ => IcmError::Database(msg),
+    }
 }
 
 // Shared public row types live in `crate::common` so all backends can be
@@ -59,6 +78,23 @@ fn ensure_sqlite_vec() {
 /// URI-encodes the path so a backslash on Windows or a `?`/`#` in a
 /// pathological filename can't break the URI parser.
 fn open_readonly_immutable(path: &Path) -> IcmResult<Connection> {
+    open_readonly_uri(path, "mode=ro&immutable=1")
+}
+
+/// Read-only connection that still follows the WAL, so it sees commits
+/// a concurrent writer has not checkpointed yet. It takes shared read
+/// locks only, but needs the `-shm` file; the first query is run here so
+/// a sandbox that cannot map it fails now rather than mid-command.
+fn open_readonly_live(path: &Path) -> IcmResult<Connection> {
+    let conn = open_readonly_uri(path, "mode=ro")?;
+    conn.query_row("SELECT COUNT(*) FROM sqlite_master", [], |r| {
+        r.get::<_, i64>(0)
+    })
+    .map_err(|e| IcmError::Database(format!("cannot open database read-only: {e}")))?;
+    Ok(conn)
+}
+
+fn open_readonly_uri(path: &Path, query: &str) -> IcmResult<Connection> {
     let raw = path.to_string_lossy();
     let encoded: String = raw
         .chars()
@@ -69,7 +105,7 @@ fn open_readonly_immutable(path: &Path) -> IcmResult<Connection> {
             other => other.to_string(),
         })
         .collect();
-    let uri = format!("file:{encoded}?mode=ro&immutable=1");
+    let uri = format!("file:{encoded}?{query}");
     Connection::open_with_flags(
         uri,
         rusqlite::OpenFlags::SQLITE_OPEN_READ_ONLY
@@ -79,6 +115,86 @@ fn open_readonly_immutable(path: &Path) -> IcmResult<Connection> {
     .map_err(|e| IcmError::Database(format!("cannot open database read-only: {e}")))
 }
 
+/// Migrations can't run on a read-only connection, so shadow tables that
+/// predate a column with temp views supplying its default (temp objects
+/// live in memory). Keeps the memory queries valid on any DB that
+/// `init_db` would otherwise upgrade.
+fn shim_legacy_schema(conn: &Connection) -> IcmResult<()> {
+    let has_column = |table: &str, column: &str| -> IcmResult<bool> {
+        conn.query_row(
+            "SELECT COUNT(*) > 0 FROM pragma_table_info(?1) WHERE name = ?2",
+            params![table, column],
+            |row| row.get(0),
+        )
+        .map_err(db_err)
+    };
+
+    let mut extra = String::new();
+    for (column, default) in [
+        ("author", "NULL"),
+        ("attachment", "NULL"),
+        ("compressed", "0"),
+        ("metadata", "NULL"),
+        ("kind", "'fact'"),
+    ] {
+        if !has_column("memories", column)? {
+            extra.push_str(&format!(", {default} AS {column}"));
+        }
+    }
+    if !extra.is_empty() {
+        conn.execute_batch(&format!(
+            "CREATE TEMP VIEW memories AS SELECT *{extra} FROM main.memories;"
+        ))
+        .map_err(db_err)?;
+    }
+
+    if !has_column("attachments", "hash")? {
+        conn.execute_batch(
+            "CREATE TEMP TABLE attachments (
+                hash TEXT PRIMARY KEY, content TEXT NOT NULL, bytes INTEGER NOT NULL,
+                created_at TEXT NOT NULL, compressed INTEGER NOT NULL DEFAULT 0
+            );",
+        )
+        .map_err(db_err)?;
+    } else if !has_column("attachments", "compressed")? {
+        conn.execute_batch(
+            "CREATE TEMP VIEW attachments AS SELECT *, 0 AS compressed FROM main.attachments;",
+        )
+        .map_err(db_err)?;
+    }
+    Ok(())
+}
+
+/// Prepared statements kept per connection by `prepare_cached`. rusqlite's
+/// default of 16 is smaller than the set of queries one `icm serve` or
+/// hook process cycles through, which would evict and re-prepare them.
+const STATEMENT_CACHE_CAPACITY: usize = 64;
+
+/// VM steps between two polls of the check set by
+/// [`SqliteStore::set_interrupt_check`].
+const INTERRUPT_CHECK_OPS: i32 = 1000;
+
+/// Most recently updated embedded memories per importance class that
+/// [`SqliteStore::refresh_importance_centroids`] averages.
+const CENTROID_SAMPLES_PER_CLASS: usize = 256;
+
+/// Embedding writes (`vector_generation` steps) after which cached
+/// importance centroids are recomputed.
+const CENTROID_REFRESH_WRITES: u64 = 100;
+
+/// Age after which cached importance centroids are recomputed even
+/// without embedding writes (importance edits do not bump the
+/// generation).
+const CENTROID_TTL_HOURS: i64 = 24;
+
+/// Classes inference can suggest; centroids are only cached once the
+/// sample covers all of them. Critical is explicit-only.
+const INFERRED_CLASSES: [Importance; 3] = [Importance::High, Importance::Medium, Importance::Low];
+
+/// `icm_metadata.importance_centroids`: the `vector_generation` and time
+/// they were computed at, then the centroids.
+type CentroidCache = (u64, String, Vec<(Importance, Vec<f32>)>);
+
 /// In-process LRU cache size for hot memories. Each entry is one
 /// fully-hydrated `Memory` (incl. optional 384×f32 embedding ≈ 1.5KB),
 /// so 256 entries cap RAM at ~400KB worst case. Helps long-running
@@ -96,6 +212,43 @@ pub struct SqliteStore {
     /// (`store`, `update`, `delete`, etc.) check this and return
     /// `IcmError::ReadOnly`. Issue #263.
     readonly: bool,
+    /// HNSW index replacing the `vec0` scan in `search_by_embedding`,
+    /// when enabled via [`Self::with_vector_index`].
+    ann: Option<Mutex<AnnIndex>>,
+    /// Scrubbing and size limits for writes, set via
+    /// [`crate::Store::with_scrubber`] / [`crate::Store::with_limits`].
+    policy: WritePolicy,
+    /// BM25-vs-weight blend for `search_fts`, set via
+    /// [`crate::Store::with_fts_relevance`].
+    fts_relevance: f32,
+    /// Term handling for `search_by_keywords`, set via
+    /// [`crate::Store::with_keyword_options`].
+    keyword_options: KeywordOptions,
+    /// Weight gain applied by `update_access` / `batch_update_access`,
+    /// set via [`crate::Store::with_reinforcement`].
+    reinforcement: Reinforcement,
+}
+
+/// A loaded ANN index and where to persist it. Written back on drop
+/// when catch-ups have moved it past the file on disk.
+struct AnnIndex {
+    index: Box<dyn VectorIndex>,
+    path: PathBuf,
+    dirty: bool,
+}
+
+impl Drop for SqliteStore {
+    fn drop(&mut self) {
+        if let Some(ann) = self.ann.as_ref().and_then(|m| m.lock().ok()) {
+            // Another process may already have saved a newer copy.
+            let on_disk = HnswIndex::stored_generation(&ann.path);
+            if ann.dirty && on_disk.is_none_or(|g| g < ann.index.generation()) {
+                if let Err(e) = ann.index.save(&ann.path) {
+                    tracing::warn!(error = %e, "failed to persist vector index");
+                }
+            }
+        }
+    }
 }
 
 impl SqliteStore {
@@ -109,6 +262,9 @@ impl SqliteStore {
     /// - The parent directory is NOT created.
     /// - The connection is opened with `SQLITE_OPEN_READ_ONLY` — SQLite
     ///   itself refuses any DDL/DML that the application might miss.
+    ///   It follows the WAL and takes shared locks only, so a concurrent
+    ///   writer is never blocked and its commits are visible; where the
+    ///   `-shm` file can't be used it reads an immutable snapshot.
     /// - No `PRAGMA journal_mode=WAL` (WAL requires writable access).
     /// - No `init_db_with_dims` (schema migration would mutate the DB).
     ///
@@ -123,15 +279,30 @@ impl SqliteStore {
                 path.display()
             )));
         }
-        let conn = open_readonly_immutable(path)?;
+        // Follow the WAL when we can, so analysis sees the latest writes;
+        // a `chmod -w` sandbox gets a snapshot of the main file instead.
+        let conn = match open_readonly_live(path) {
+            Ok(conn) => conn,
+            Err(e) => {
+                tracing::debug!(error = %e, "live read-only open failed, using an immutable snapshot");
+                open_readonly_immutable(path)?
+            }
+        };
         // foreign_keys is a no-op for reads; busy_timeout is still useful
         // when another writer holds the file.
         conn.execute_batch("PRAGMA foreign_keys=ON; PRAGMA busy_timeout=30000;")
             .map_err(db_err)?;
+        shim_legacy_schema(&conn)?;
+        conn.set_prepared_statement_cache_capacity(STATEMENT_CACHE_CAPACITY);
         Ok(Self {
             conn,
             cache: Mutex::new(new_cache()),
             readonly: true,
+            ann: None,
+            policy: WritePolicy::default(),
+            fts_relevance: DEFAULT_FTS_RELEVANCE,
+            keyword_options: KeywordOptions::default(),
+            reinforcement: Reinforcement::default(),
         })
     }
 
@@ -143,6 +314,26 @@ impl SqliteStore {
         self.readonly
     }
 
+    pub(crate) fn write_policy(&self) -> &WritePolicy {
+        &self.policy
+    }
+
+    pub(crate) fn write_policy_mut(&mut self) -> &mut WritePolicy {
+        &mut self.policy
+    }
+
+    pub(crate) fn set_fts_relevance(&mut self, relevance: f32) {
+        self.fts_relevance = relevance;
+    }
+
+    pub(crate) fn set_keyword_options(&mut self, options: KeywordOptions) {
+        self.keyword_options = options;
+    }
+
+    pub(crate) fn set_reinforcement(&mut self, reinforcement: Reinforcement) {
+        self.reinforcement = reinforcement;
+    }
+
     /// Peek `icm_metadata.embedding_dims` without running any schema
     /// migration. Returns `Ok(None)` when the DB file is absent, the
     /// metadata table doesn't exist (legacy DB), or the row is missing.
@@ -189,6 +380,35 @@ impl SqliteStore {
         Ok(row.and_then(|s| s.parse().ok()))
     }
 
+    /// Count memories holding an embedding, peeked like
+    /// [`Self::read_stored_embedding_dims`] (no migration, immutable
+    /// open). A missing DB counts as zero.
+    pub fn count_stored_embeddings(path: &Path) -> IcmResult<usize> {
+        if !path.exists() {
+            return Ok(0);
+        }
+        let conn = open_readonly_immutable(path)?;
+        let has_table: bool = conn
+            .query_row(
+                "SELECT COUNT(*) > 0 FROM sqlite_master
+                 WHERE type = 'table' AND name = 'memories'",
+                [],
+                |row| row.get(0),
+            )
+            .map_err(db_err)?;
+        if !has_table {
+            return Ok(0);
+        }
+        let count: i64 = conn
+            .query_row(
+                "SELECT COUNT(*) FROM memories WHERE embedding IS NOT NULL",
+                [],
+                |row| row.get(0),
+            )
+            .map_err(db_err)?;
+        Ok(count as usize)
+    }
+
     /// Open or create a store with a specific embedding dimension.
     pub fn with_dims(path: &Path, embedding_dims: usize) -> IcmResult<Self> {
         ensure_sqlite_vec();
@@ -203,23 +423,319 @@ impl SqliteStore {
         )
         .map_err(db_err)?;
         init_db_with_dims(&conn, embedding_dims)?;
+        conn.set_prepared_statement_cache_capacity(STATEMENT_CACHE_CAPACITY);
         Ok(Self {
             conn,
             cache: Mutex::new(new_cache()),
             readonly: false,
+            ann: None,
+            policy: WritePolicy::default(),
+            fts_relevance: DEFAULT_FTS_RELEVANCE,
+            keyword_options: KeywordOptions::default(),
+            reinforcement: Reinforcement::default(),
+        })
+    }
+
+    /// Serve `search_by_embedding` from `kind`. For [`IndexKind::Hnsw`]
+    /// the index at `path` is loaded and caught up with changes made
+    /// since it was saved, or built from the stored embeddings (and
+    /// saved) when it is missing or unusable.
+    pub fn with_vector_index(mut self, kind: IndexKind, path: &Path) -> IcmResult<Self> {
+        if kind == IndexKind::SqliteVec {
+            self.ann = None;
+            return Ok(self);
+        }
+        let dims = self.stored_dims()?;
+        let mut ann = match HnswIndex::load(path, dims) {
+            Ok(index) => AnnIndex {
+                index: Box::new(index),
+                path: path.to_path_buf(),
+                dirty: false,
+            },
+            Err(e) => {
+                if path.exists() {
+                    tracing::warn!(error = %e, "rebuilding unusable vector index");
+                }
+                let index = self.build_hnsw(dims)?;
+                index.save(path)?;
+                AnnIndex {
+                    index: Box::new(index),
+                    path: path.to_path_buf(),
+                    dirty: false,
+                }
+            }
+        };
+        self.ann_catch_up(&mut ann)?;
+        self.ann = Some(Mutex::new(ann));
+        Ok(self)
+    }
+
+    /// The index currently serving vector search.
+    pub fn vector_index_kind(&self) -> IndexKind {
+        if self.ann.is_some() {
+            IndexKind::Hnsw
+        } else {
+            IndexKind::SqliteVec
+        }
+    }
+
+    /// Rebuild the active vector index from `memories.embedding` and
+    /// return the number of vectors indexed. For HNSW this rewrites the
+    /// index file (dropping tombstones and stale entries); for
+    /// `sqlite-vec` it re-syncs the `vec0` table.
+    pub fn rebuild_vector_index(&self) -> IcmResult<usize> {
+        let dims = self.stored_dims()?;
+        if let Some(ann) = &self.ann {
+            let index = self.build_hnsw(dims)?;
+            let count = index.len();
+            let mut ann = ann
+                .lock()
+                .map_err(|_| IcmError::Database("vector index lock poisoned".into()))?;
+            index.save(&ann.path)?;
+            ann.index = Box::new(index);
+            ann.dirty = false;
+            if !self.readonly {
+                // Stamps of deleted memories only exist so other copies
+                // drop them; a stale ID is filtered at search time anyway.
+                self.conn
+                    .execute(
+                        "DELETE FROM vector_changes
+                         WHERE memory_id NOT IN (SELECT id FROM memories)",
+                        [],
+                    )
+                    .map_err(db_err)?;
+            }
+            return Ok(count);
+        }
+        if self.readonly {
+            return Err(IcmError::ReadOnly("rebuild_vector_index".into()));
+        }
+        let tx = self.conn.unchecked_transaction().map_err(db_err)?;
+        tx.execute("DELETE FROM vec_memories", []).map_err(db_err)?;
+        let count = tx
+            .execute(
+                "INSERT INTO vec_memories (memory_id, embedding)
+                 SELECT id, embedding FROM memories
+                 WHERE embedding IS NOT NULL AND length(embedding) = ?1",
+                params![(dims * 4) as i64],
+            )
+            .map_err(db_err)?;
+        tx.commit().map_err(db_err)?;
+        Ok(count)
+    }
+
+    /// Disk usage broken down by table and index (via the `dbstat`
+    /// virtual table), plus the WAL and the embedding/text split of the
+    /// `memories` rows.
+    pub fn storage_stats(&self) -> IcmResult<StorageStats> {
+        let page_size: u64 = self
+            .conn
+            .query_row("PRAGMA page_size", [], |r| r.get::<_, i64>(0))
+            .map_err(db_err)? as u64;
+        let page_count: u64 = self
+            .conn
+            .query_row("PRAGMA page_count", [], |r| r.get::<_, i64>(0))
+            .map_err(db_err)? as u64;
+
+        let mut stmt = self
+            .conn
+            .prepare_cached("SELECT name, SUM(pgsize) FROM dbstat GROUP BY name ORDER BY 2 DESC, 1")
+            .map_err(db_err)?;
+        let objects = stmt
+            .query_map([], |r| {
+                Ok((r.get::<_, String>(0)?, r.get::<_, i64>(1)? as u64))
+            })
+            .map_err(db_err)?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(db_err)?;
+
+        let (embedding_bytes, text_bytes): (i64, i64) = self
+            .conn
+            .query_row(
+                "SELECT COALESCE(SUM(length(embedding)), 0),
+                        COALESCE(SUM(length(summary) + COALESCE(length(raw_excerpt), 0)
+                                     + length(keywords)), 0)
+                 FROM memories",
+                [],
+                |r| Ok((r.get(0)?, r.get(1)?)),
+            )
+            .map_err(db_err)?;
+
+        let (attachment_bytes, attachment_count): (i64, i64) = self
+            .conn
+            .query_row(
+                "SELECT COALESCE(SUM(bytes), 0), COUNT(*) FROM attachments",
+                [],
+                |r| Ok((r.get(0)?, r.get(1)?)),
+            )
+            .map_err(db_err)?;
+
+        let (mut compressed_bytes, mut uncompressed_bytes) = (0u64, 0u64);
+        let mut stmt = self
+            .conn
+            .prepare_cached(
+                "SELECT raw_excerpt FROM memories WHERE compressed = 1
+                 UNION ALL SELECT content FROM attachments WHERE compressed = 1",
+            )
+            .map_err(db_err)?;
+        let mut rows = stmt.query([]).map_err(db_err)?;
+        while let Some(row) = rows.next().map_err(db_err)? {
+            if let rusqlite::types::ValueRef::Blob(frame) = row.get_ref(0).map_err(db_err)? {
+                compressed_bytes += frame.len() as u64;
+                uncompressed_bytes += compress::original_len(frame).unwrap_or(frame.len() as u64);
+            }
+        }
+
+        // `database_list` reports "" for in-memory databases.
+        let file: String = self
+            .conn
+            .query_row(
+                "SELECT file FROM pragma_database_list WHERE name = 'main'",
+                [],
+                |r| r.get(0),
+            )
+            .map_err(db_err)?;
+        let wal_bytes = if file.is_empty() {
+            0
+        } else {
+            std::fs::metadata(format!("{file}-wal"))
+                .map(|m| m.len())
+                .unwrap_or(0)
+        };
+
+        Ok(StorageStats {
+            db_bytes: page_size * page_count,
+            wal_bytes,
+            objects,
+            embedding_bytes: embedding_bytes as u64,
+            text_bytes: text_bytes as u64,
+            attachment_bytes: attachment_bytes as u64,
+            attachment_count: attachment_count as u64,
+            compressed_bytes,
+            uncompressed_bytes,
         })
     }
 
-    /// Apply decay if more than 24 hours since last decay.
-    /// Called automatically on recall to avoid manual `icm decay` cron.
+    fn stored_dims(&self) -> IcmResult<usize> {
+        let stored: Option<String> = self
+            .conn
+            .query_row(
+                "SELECT value FROM icm_metadata WHERE key = 'embedding_dims'",
+                [],
+                |row| row.get(0),
+            )
+            .ok();
+        Ok(stored
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(icm_core::DEFAULT_EMBEDDING_DIMS))
+    }
+
+    /// Abort SQLite work once `should_stop` returns true. It is polled
+    /// every [`INTERRUPT_CHECK_OPS`] VM steps of any statement, which then
+    /// fails with `SQLITE_INTERRUPT`; statements started later fail the
+    /// same way until the check is removed with `None`.
+    pub fn set_interrupt_check(&self, should_stop: Option<Box<dyn FnMut() -> bool + Send>>) {
+        self.conn.progress_handler(INTERRUPT_CHECK_OPS, should_stop);
+    }
+
+    /// Current `vector_generation`; 0 on legacy read-only databases that
+    /// predate change tracking.
+    fn vector_generation(&self) -> IcmResult<u64> {
+        let generation: Option<i64> = self
+            .conn
+            .query_row(
+                "SELECT CAST(value AS INTEGER) FROM icm_metadata WHERE key = 'vector_generation'",
+                [],
+                |row| row.get(0),
+            )
+            .optional()
+            .map_err(db_err)?;
+        Ok(generation.unwrap_or(0) as u64)
+    }
+
+    fn build_hnsw(&self, dims: usize) -> IcmResult<HnswIndex> {
+        // Read the generation first: a write racing the scan below is
+        // replayed by the next catch-up, which is idempotent.
+        let generation = self.vector_generation()?;
+        let mut stmt = self
+            .conn
+            .prepare_cached("SELECT id, embedding FROM memories WHERE embedding IS NOT NULL")
+            .map_err(db_err)?;
+        let rows = stmt
+            .query_map([], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, Vec<u8>>(1)?))
+            })
+            .map_err(db_err)?;
+        let mut index = HnswIndex::new(dims);
+        for (id, blob) in rows.flatten() {
+            let emb = blob_to_embedding(&blob);
+            if emb.len() == dims {
+                index.upsert(&id, &emb);
+            }
+        }
+        index.set_generation(generation);
+        Ok(index)
+    }
+
+    /// Bring `ann` up to the current `vector_generation` by replaying the
+    /// memories stamped since its generation, whichever process or code
+    /// path wrote them. Rebuilds when the index is ahead of the database
+    /// (e.g. the DB was restored from a backup).
+    fn ann_catch_up(&self, ann: &mut AnnIndex) -> IcmResult<()> {
+        let head = self.vector_generation()?;
+        let current = ann.index.generation();
+        if head == current {
+            return Ok(());
+        }
+        if current > head {
+            let dims = self.stored_dims()?;
+            ann.index = Box::new(self.build_hnsw(dims)?);
+            ann.dirty = true;
+            return Ok(());
+        }
+        let mut stmt = self
+            .conn
+            .prepare_cached(
+                "SELECT c.memory_id, c.generation, m.embedding
+                 FROM vector_changes c LEFT JOIN memories m ON m.id = c.memory_id
+                 WHERE c.generation > ?1",
+            )
+            .map_err(db_err)?;
+        let rows = stmt
+            .query_map(params![current as i64], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, i64>(1)?,
+                    row.get::<_, Option<Vec<u8>>>(2)?,
+                ))
+            })
+            .map_err(db_err)?;
+        let mut generation = head;
+        for row in rows {
+            let (id, stamp, blob) = row.map_err(db_err)?;
+            generation = generation.max(stamp as u64);
+            match blob {
+                Some(blob) => ann.index.upsert(&id, &blob_to_embedding(&blob)),
+                None => ann.index.remove(&id),
+            }
+        }
+        ann.index.set_generation(generation);
+        ann.dirty = true;
+        Ok(())
+    }
+
+    /// Apply one decay pass with `factor` if more than 24 hours have
+    /// passed since the last one. Returns the number of memories decayed,
+    /// or `None` when no pass was due. Callers decide when to try
+    /// (`[memory] auto_decay`): on recall, or from the MCP server loop.
     ///
     /// No-op when the store is read-only (issue #263): recall must work
     /// against a DB the process cannot write to, and the bookkeeping
     /// writes here would otherwise abort the whole read with
     /// "attempt to write a readonly database".
-    pub fn maybe_auto_decay(&self) -> IcmResult<()> {
+    pub fn maybe_auto_decay(&self, factor: f32) -> IcmResult<Option<usize>> {
         if self.readonly {
-            return Ok(());
+            return Ok(None);
         }
         let now = Utc::now();
         let now_str = now.to_rfc3339();
@@ -236,11 +752,10 @@ impl SqliteStore {
             )
             .map_err(db_err)?;
 
-        if changed > 0 {
-            self.apply_decay(0.95)?;
+        if changed == 0 {
+            return Ok(None);
         }
-
-        Ok(())
+        self.apply_decay(factor).map(Some)
     }
 
     /// Atomically increment the hook call counter and return the new value.
@@ -304,7 +819,7 @@ impl SqliteStore {
     pub fn list_pending_extractions(&self, limit: usize) -> IcmResult<Vec<PendingRow>> {
         let mut stmt = self
             .conn
-            .prepare(
+            .prepare_cached(
                 "SELECT id, project, tool_name, raw_output, captured_at
                  FROM pending_extractions
                  ORDER BY captured_at ASC
@@ -349,6 +864,81 @@ impl SqliteStore {
         Ok(n as usize)
     }
 
+    // ── Review queue ───────────────────────────────────────────────────
+    //
+    // With `[memory] review_agent_writes`, memories written through MCP or
+    // hooks are parked here as JSON instead of in `memories`, so no search
+    // path can surface them. `icm review` stores approved ones for real.
+
+    /// Hold `memory` for review. Returns its id.
+    pub fn queue_for_review(&self, memory: &Memory, origin: &str) -> IcmResult<String> {
+        if self.readonly {
+            return Err(IcmError::ReadOnly("queue_for_review".into()));
+        }
+        let json = serde_json::to_string(memory)?;
+        self.conn
+            .execute(
+                "INSERT OR REPLACE INTO review_queue (id, origin, memory, queued_at)
+                 VALUES (?1, ?2, ?3, ?4)",
+                params![memory.id, origin, json, Utc::now().to_rfc3339()],
+            )
+            .map_err(db_err)?;
+        Ok(memory.id.clone())
+    }
+
+    /// Up to `limit` queued memories, oldest first.
+    pub fn list_review_queue(&self, limit: usize) -> IcmResult<Vec<ReviewItem>> {
+        let mut stmt = self
+            .conn
+            .prepare_cached(
+                "SELECT origin, memory, queued_at FROM review_queue
+                 ORDER BY queued_at ASC LIMIT ?1",
+            )
+            .map_err(db_err)?;
+        let rows = stmt
+            .query_map([limit as i64], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, String>(2)?,
+                ))
+            })
+            .map_err(db_err)?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(db_err)?;
+        rows.into_iter()
+            .map(|(origin, json, queued_at)| {
+                Ok(ReviewItem {
+                    memory: serde_json::from_str(&json)?,
+                    origin,
+                    queued_at: parse_dt(&queued_at),
+                })
+            })
+            .collect()
+    }
+
+    /// Drop a queued memory (after approving or rejecting it). Returns
+    /// `false` when it was not queued.
+    pub fn remove_from_review_queue(&self, id: &str) -> IcmResult<bool> {
+        if self.readonly {
+            return Err(IcmError::ReadOnly("remove_from_review_queue".into()));
+        }
+        let n = self
+            .conn
+            .execute("DELETE FROM review_queue WHERE id = ?1", [id])
+            .map_err(db_err)?;
+        Ok(n > 0)
+    }
+
+    /// Memories currently awaiting review.
+    pub fn review_queue_count(&self) -> IcmResult<usize> {
+        let n: i64 = self
+            .conn
+            .query_row("SELECT COUNT(*) FROM review_queue", [], |r| r.get(0))
+            .map_err(db_err)?;
+        Ok(n as usize)
+    }
+
     // ── Code areas (auto-captured file edits — issue #196) ────────────
     //
     // `cmd_hook_post` calls `upsert_code_area` whenever the upstream
@@ -423,7 +1013,7 @@ impl SqliteStore {
         sql.push_str(" ORDER BY last_touched_at DESC LIMIT ?");
         params.push(Box::new(limit as i64));
 
-        let mut stmt = self.conn.prepare(&sql).map_err(db_err)?;
+        let mut stmt = self.conn.prepare_cached(&sql).map_err(db_err)?;
         let param_refs: Vec<&dyn rusqlite::ToSql> = params
             .iter()
             .map(|p| p.as_ref() as &dyn rusqlite::ToSql)
@@ -497,23 +1087,76 @@ impl SqliteStore {
         Ok(self.conn.last_insert_rowid())
     }
 
-    /// Most recent `limit` hook events, newest first. Optional `event`
-    /// filter (e.g. `Some("end")` to see only SessionEnd hooks).
-    pub fn hook_events_recent(
-        &self,
-        limit: usize,
-        event_filter: Option<&str>,
-    ) -> IcmResult<Vec<HookEvent>> {
-        let limit_i64 = limit as i64;
-        let row_to_event = |row: &rusqlite::Row<'_>| -> rusqlite::Result<HookEvent> {
-            let ts_str: String = row.get(1)?;
-            let ts = chrono::DateTime::parse_from_rfc3339(&ts_str)
-                .map(|t| t.with_timezone(&Utc))
-                .unwrap_or_else(|_| Utc::now());
-            Ok(HookEvent {
-                id: row.get(0)?,
-                ts,
-                event: row.get(2)?,
+    /// Append what a hook fire injected into or extracted from a session.
+    /// The session row must exist (`ensure_session`).
+    pub fn record_session_context(&self, ctx: &SessionContextInsert) -> IcmResult<i64> {
+        if self.readonly {
+            return Err(IcmError::ReadOnly("record_session_context".into()));
+        }
+        let memory_ids = serde_json::to_string(&ctx.memory_ids)?;
+        self.conn
+            .execute(
+                "INSERT INTO session_context
+                 (session_id, ts, event, query, injected, memory_ids, facts_extracted)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+                params![
+                    ctx.session_id,
+                    Utc::now().to_rfc3339(),
+                    ctx.event,
+                    ctx.query,
+                    ctx.injected,
+                    memory_ids,
+                    ctx.facts_extracted as i64,
+                ],
+            )
+            .map_err(db_err)?;
+        Ok(self.conn.last_insert_rowid())
+    }
+
+    /// A session's context log, oldest first.
+    pub fn session_context(&self, session_id: &str) -> IcmResult<Vec<SessionContext>> {
+        let mut stmt = self
+            .conn
+            .prepare_cached(
+                "SELECT id, session_id, ts, event, query, injected, memory_ids, facts_extracted
+                 FROM session_context WHERE session_id = ?1 ORDER BY id",
+            )
+            .map_err(db_err)?;
+        let rows = stmt
+            .query_map(params![session_id], |row| {
+                let memory_ids: String = row.get(6)?;
+                Ok(SessionContext {
+                    id: row.get(0)?,
+                    session_id: row.get(1)?,
+                    ts: parse_dt(&row.get::<_, String>(2)?),
+                    event: row.get(3)?,
+                    query: row.get(4)?,
+                    injected: row.get(5)?,
+                    memory_ids: serde_json::from_str(&memory_ids).unwrap_or_default(),
+                    facts_extracted: row.get::<_, i64>(7)? as usize,
+                })
+            })
+            .map_err(db_err)?;
+        collect_rows(rows)
+    }
+
+    /// Most recent `limit` hook events, newest first. Optional `event`
+    /// filter (e.g. `Some("end")` to see only SessionEnd hooks).
+    pub fn hook_events_recent(
+        &self,
+        limit: usize,
+        event_filter: Option<&str>,
+    ) -> IcmResult<Vec<HookEvent>> {
+        let limit_i64 = limit as i64;
+        let row_to_event = |row: &rusqlite::Row<'_>| -> rusqlite::Result<HookEvent> {
+            let ts_str: String = row.get(1)?;
+            let ts = chrono::DateTime::parse_from_rfc3339(&ts_str)
+                .map(|t| t.with_timezone(&Utc))
+                .unwrap_or_else(|_| Utc::now());
+            Ok(HookEvent {
+                id: row.get(0)?,
+                ts,
+                event: row.get(2)?,
                 project: row.get(3)?,
                 session_id: row.get(4)?,
                 tool_name: row.get(5)?,
@@ -527,7 +1170,7 @@ impl SqliteStore {
             Some(e) => {
                 let mut stmt = self
                     .conn
-                    .prepare(
+                    .prepare_cached(
                         "SELECT id, ts, event, project, session_id, tool_name,
                                 duration_ms, exit_code, payload_size, note
                          FROM hook_events
@@ -544,7 +1187,7 @@ impl SqliteStore {
             None => {
                 let mut stmt = self
                     .conn
-                    .prepare(
+                    .prepare_cached(
                         "SELECT id, ts, event, project, session_id, tool_name,
                                 duration_ms, exit_code, payload_size, note
                          FROM hook_events
@@ -568,7 +1211,7 @@ impl SqliteStore {
         // enough (~1k/day worst case) that an in-process sort is fine.
         let mut stmt = self
             .conn
-            .prepare(
+            .prepare_cached(
                 "SELECT event, duration_ms, exit_code
                  FROM hook_events
                  WHERE ts >= ?1
@@ -645,6 +1288,248 @@ impl SqliteStore {
         Ok(n as usize)
     }
 
+    // ── Session injections (delta recall) ─────────────────────────────
+    //
+    // The prompt hook records what it injected per hook `session_id`, so
+    // later prompts in the session only add memories that are new or
+    // were updated since.
+
+    /// Memories already injected into `session_id`, mapped to their
+    /// `updated_at` when injected.
+    pub fn session_injections(
+        &self,
+        session_id: &str,
+    ) -> IcmResult<HashMap<String, DateTime<Utc>>> {
+        let mut stmt = self
+            .conn
+            .prepare_cached(
+                "SELECT memory_id, memory_updated_at FROM session_injections
+                 WHERE session_id = ?1",
+            )
+            .map_err(db_err)?;
+        let rows = stmt
+            .query_map([session_id], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+            })
+            .map_err(db_err)?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(db_err)?;
+        Ok(rows
+            .into_iter()
+            .map(|(id, updated_at)| (id, parse_dt(&updated_at)))
+            .collect())
+    }
+
+    /// Record `memories` as injected into `session_id`.
+    pub fn record_session_injections(
+        &self,
+        session_id: &str,
+        memories: &[Memory],
+    ) -> IcmResult<()> {
+        if self.readonly {
+            return Err(IcmError::ReadOnly("record_session_injections".into()));
+        }
+        let now = Utc::now().to_rfc3339();
+        let tx = self.conn.unchecked_transaction().map_err(db_err)?;
+        {
+            let mut stmt = tx
+                .prepare_cached(
+                    "INSERT OR REPLACE INTO session_injections
+                     (session_id, memory_id, memory_updated_at, injected_at)
+                     VALUES (?1, ?2, ?3, ?4)",
+                )
+                .map_err(db_err)?;
+            for m in memories {
+                stmt.execute(params![session_id, m.id, m.updated_at.to_rfc3339(), now])
+                    .map_err(db_err)?;
+            }
+        }
+        tx.commit().map_err(db_err)
+    }
+
+    /// Forget what was injected into `session_id`, and any session rows
+    /// older than `cutoff_rfc3339` (sessions whose end hook never ran).
+    pub fn clear_session_injections(
+        &self,
+        session_id: &str,
+        cutoff_rfc3339: &str,
+    ) -> IcmResult<usize> {
+        if self.readonly {
+            return Err(IcmError::ReadOnly("clear_session_injections".into()));
+        }
+        self.conn
+            .execute(
+                "DELETE FROM session_injections WHERE session_id = ?1 OR injected_at < ?2",
+                params![session_id, cutoff_rfc3339],
+            )
+            .map_err(db_err)
+    }
+
+    // ── Topic digests (fast prompt hook) ──────────────────────────────
+
+    /// Rebuild every topic's digest from its `per_topic` heaviest
+    /// memories. Returns the number of topics digested.
+    pub fn refresh_topic_digests(&self, per_topic: usize) -> IcmResult<usize> {
+        if self.readonly {
+            return Err(IcmError::ReadOnly("refresh_topic_digests".into()));
+        }
+        let rows: Vec<(String, String, usize)> = {
+            let mut stmt = self
+                .conn
+                .prepare_cached(
+                    "SELECT topic, summary, n FROM (
+                         SELECT topic, summary,
+                                ROW_NUMBER() OVER (
+                                    PARTITION BY topic ORDER BY weight DESC, updated_at DESC
+                                ) AS rn,
+                                COUNT(*) OVER (PARTITION BY topic) AS n
+                         FROM memories
+                     ) WHERE rn <= ?1 ORDER BY topic, rn",
+                )
+                .map_err(db_err)?;
+            let rows = stmt
+                .query_map([per_topic as i64], |row| {
+                    Ok((row.get(0)?, row.get(1)?, row.get::<_, usize>(2)?))
+                })
+                .map_err(db_err)?;
+            collect_rows(rows)?
+        };
+        let now = Utc::now().to_rfc3339();
+        let tx = self.conn.unchecked_transaction().map_err(db_err)?;
+        tx.execute("DELETE FROM topic_digests", [])
+            .map_err(db_err)?;
+        let mut topics = 0;
+        {
+            let mut stmt = tx
+                .prepare_cached(
+                    "INSERT INTO topic_digests (topic, digest, memory_count, refreshed_at)
+                     VALUES (?1, ?2, ?3, ?4)",
+                )
+                .map_err(db_err)?;
+            for chunk in rows.chunk_by(|a, b| a.0 == b.0) {
+                let digest = build_digest(chunk.iter().map(|(_, summary, _)| summary.as_str()));
+                stmt.execute(params![chunk[0].0, digest, chunk[0].2 as i64, now])
+                    .map_err(db_err)?;
+                topics += 1;
+            }
+        }
+        tx.commit().map_err(db_err)?;
+        Ok(topics)
+    }
+
+    /// Every stored topic digest, by topic.
+    pub fn topic_digests(&self) -> IcmResult<Vec<TopicDigest>> {
+        let mut stmt = self
+            .conn
+            .prepare_cached(
+                "SELECT topic, digest, memory_count, refreshed_at FROM topic_digests
+                 ORDER BY topic",
+            )
+            .map_err(db_err)?;
+        let rows = stmt
+            .query_map([], |row| {
+                Ok(TopicDigest {
+                    topic: row.get(0)?,
+                    digest: row.get(1)?,
+                    memory_count: row.get(2)?,
+                    refreshed_at: parse_dt(&row.get::<_, String>(3)?),
+                })
+            })
+            .map_err(db_err)?;
+        collect_rows(rows)
+    }
+
+    // ── Importance centroids (write-path classification) ──────────────
+
+    /// Per-importance embedding centroids used to suggest an importance
+    /// for new memories. Served from the `icm_metadata` cache while it is
+    /// fresh: fewer than [`CENTROID_REFRESH_WRITES`] embedding writes and
+    /// [`CENTROID_TTL_HOURS`] old. Otherwise recomputed from a bounded
+    /// sample (and re-cached when writable).
+    pub fn importance_centroids(&self) -> IcmResult<Vec<(Importance, Vec<f32>)>> {
+        let generation = self.vector_generation()?;
+        let cached: Option<String> = self
+            .conn
+            .query_row(
+                "SELECT value FROM icm_metadata WHERE key = 'importance_centroids'",
+                [],
+                |row| row.get(0),
+            )
+            .optional()
+            .map_err(db_err)?;
+        let cached = cached.and_then(|json| serde_json::from_str::<CentroidCache>(&json).ok());
+        if let Some((at_generation, computed_at, centroids)) = cached {
+            let writes = generation.checked_sub(at_generation);
+            let recent = DateTime::parse_from_rfc3339(&computed_at).is_ok_and(|at| {
+                Utc::now() - at.with_timezone(&Utc) < chrono::Duration::hours(CENTROID_TTL_HOURS)
+            });
+            if recent && writes.is_some_and(|w| w < CENTROID_REFRESH_WRITES) {
+                return Ok(centroids);
+            }
+        }
+        if self.readonly {
+            return self.sample_importance_centroids();
+        }
+        self.refresh_importance_centroids()
+    }
+
+    /// Recompute the importance centroids from the most recently updated
+    /// embedded memories of each class. They are cached only when every
+    /// inferable class has one, so a young store keeps learning instead
+    /// of freezing an empty or partial result.
+    pub fn refresh_importance_centroids(&self) -> IcmResult<Vec<(Importance, Vec<f32>)>> {
+        if self.readonly {
+            return Err(IcmError::ReadOnly("refresh_importance_centroids".into()));
+        }
+        let generation = self.vector_generation()?;
+        let centroids = self.sample_importance_centroids()?;
+        let complete = INFERRED_CLASSES
+            .iter()
+            .all(|class| centroids.iter().any(|(imp, _)| imp == class));
+        if complete {
+            let json = serde_json::to_string(&(generation, Utc::now().to_rfc3339(), &centroids))?;
+            self.conn
+                .execute(
+                    "INSERT INTO icm_metadata (key, value) VALUES ('importance_centroids', ?1)
+                     ON CONFLICT(key) DO UPDATE SET value = ?1",
+                    params![json],
+                )
+                .map_err(db_err)?;
+        }
+        Ok(centroids)
+    }
+
+    /// Bounded sample: the window runs over row ids only, so embedding
+    /// blobs are read for at most [`CENTROID_SAMPLES_PER_CLASS`] rows per
+    /// class however large the table is.
+    fn sample_importance_centroids(&self) -> IcmResult<Vec<(Importance, Vec<f32>)>> {
+        let mut stmt = self
+            .conn
+            .prepare_cached(
+                "SELECT importance, embedding FROM memories WHERE rowid IN (
+                     SELECT rowid FROM (
+                         SELECT rowid, ROW_NUMBER() OVER (
+                             PARTITION BY importance ORDER BY updated_at DESC
+                         ) AS rn
+                         FROM memories WHERE embedding IS NOT NULL
+                     ) WHERE rn <= ?1
+                 )",
+            )
+            .map_err(db_err)?;
+        let rows = stmt
+            .query_map([CENTROID_SAMPLES_PER_CLASS as i64], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, Vec<u8>>(1)?))
+            })
+            .map_err(db_err)?;
+        let samples: Vec<(Importance, Vec<f32>)> = collect_rows(rows)?
+            .into_iter()
+            .filter_map(|(imp, blob)| Some((imp.parse().ok()?, blob_to_embedding(&blob))))
+            .collect();
+        Ok(icm_core::importance_centroids(
+            samples.iter().map(|(imp, emb)| (*imp, emb.as_slice())),
+        ))
+    }
+
     pub fn in_memory() -> IcmResult<Self> {
         Self::in_memory_with_dims(icm_core::DEFAULT_EMBEDDING_DIMS)
     }
@@ -658,10 +1543,16 @@ impl SqliteStore {
         conn.execute_batch("PRAGMA foreign_keys=ON; PRAGMA busy_timeout=30000;")
             .map_err(db_err)?;
         init_db_with_dims(&conn, embedding_dims)?;
+        conn.set_prepared_statement_cache_capacity(STATEMENT_CACHE_CAPACITY);
         Ok(Self {
             conn,
             cache: Mutex::new(new_cache()),
             readonly: false,
+            ann: None,
+            policy: WritePolicy::default(),
+            fts_relevance: DEFAULT_FTS_RELEVANCE,
+            keyword_options: KeywordOptions::default(),
+            reinforcement: Reinforcement::default(),
         })
     }
 
@@ -710,23 +1601,22 @@ fn source_type(source: &MemorySource) -> &'static str {
     match source {
         MemorySource::ClaudeCode { .. } => "claude_code",
         MemorySource::Conversation { .. } => "conversation",
-        MemorySource::Manual => "manual",
+        MemorySource::Manual { .. } => "manual",
     }
 }
 
 fn source_data(source: &MemorySource) -> Option<String> {
     match source {
-        MemorySource::Manual => None,
+        MemorySource::Manual { provenance } if provenance.is_empty() => None,
         other => serde_json::to_string(other).ok(),
     }
 }
 
 fn parse_source(source_type_str: &str, source_data_str: Option<String>) -> MemorySource {
-    match source_type_str {
-        "manual" => MemorySource::Manual,
-        _ => source_data_str
-            .and_then(|d| serde_json::from_str(&d).ok())
-            .unwrap_or(MemorySource::Manual),
+    // Manual rows carry source_data only when provenance was captured.
+    match source_data_str.and_then(|d| serde_json::from_str::<MemorySource>(&d).ok()) {
+        Some(source) if source.kind() == source_type_str => source,
+        _ => MemorySource::default(),
     }
 }
 
@@ -750,7 +1640,8 @@ fn row_to_memory(row: &rusqlite::Row) -> rusqlite::Result<Memory> {
     // Column order: id(0), created_at(1), updated_at(2), last_accessed(3),
     //   access_count(4), weight(5), topic(6), summary(7), raw_excerpt(8),
     //   keywords(9), importance(10), source_type(11), source_data(12),
-    //   related_ids(13), embedding(14)
+    //   related_ids(13), embedding(14), author(15), attachment(16),
+    //   compressed(17), metadata(18), kind(19)
     let keywords_json: String = row.get::<_, Option<String>>(9)?.unwrap_or_default();
     let keywords: Vec<String> = serde_json::from_str(&keywords_json).unwrap_or_default();
 
@@ -773,6 +1664,12 @@ fn row_to_memory(row: &rusqlite::Row) -> rusqlite::Result<Memory> {
     let last_accessed_str: String = row.get(3)?;
 
     let created_at = parse_dt(&created_at_str);
+    let compressed: bool = row.get(17)?;
+    let metadata = parse_metadata(row.get::<_, Option<String>>(18)?.as_deref());
+    let kind = row
+        .get::<_, Option<String>>(19)?
+        .and_then(|k| k.parse().ok())
+        .unwrap_or_default();
 
     Ok(Memory {
         id: row.get(0)?,
@@ -787,19 +1684,49 @@ fn row_to_memory(row: &rusqlite::Row) -> rusqlite::Result<Memory> {
         weight: row.get(5)?,
         topic: row.get(6)?,
         summary: row.get(7)?,
-        raw_excerpt: row.get(8)?,
+        raw_excerpt: compress::read_text(row, 8, compressed)?,
+        attachment: row.get(16)?,
         keywords,
         importance,
         source,
         related_ids,
         embedding,
         scope: icm_core::Scope::User, // default for existing local memories
+        author: row.get(15)?,
+        metadata,
+        kind,
     })
 }
 
-const SELECT_COLS: &str = "id, created_at, updated_at, last_accessed, access_count, weight, \
-                           topic, summary, raw_excerpt, keywords, \
-                           importance, source_type, source_data, related_ids, embedding";
+/// Decode the `memories.metadata` column; NULL and malformed JSON read
+/// as no metadata.
+fn parse_metadata(json: Option<&str>) -> BTreeMap<String, String> {
+    json.and_then(|j| serde_json::from_str(j).ok())
+        .unwrap_or_default()
+}
+
+/// The value to bind for `memories.metadata`: NULL when there is none, so
+/// untagged rows stay as they were before the column existed.
+fn metadata_json(metadata: &BTreeMap<String, String>) -> IcmResult<Option<String>> {
+    if metadata.is_empty() {
+        Ok(None)
+    } else {
+        Ok(Some(serde_json::to_string(metadata)?))
+    }
+}
+
+/// The `memories` columns read by [`row_to_memory`], as a literal so
+/// constant queries can be assembled with `concat!` instead of `format!`.
+macro_rules! select_cols {
+    () => {
+        "id, created_at, updated_at, last_accessed, access_count, weight, \
+         topic, summary, raw_excerpt, keywords, \
+         importance, source_type, source_data, related_ids, embedding, author, attachment, \
+         compressed, metadata, kind"
+    };
+}
+
+const SELECT_COLS: &str = select_cols!();
 
 /// Sanitize a query string for FTS5 MATCH.
 ///
@@ -967,6 +1894,174 @@ pub(crate) fn summary_hash(topic: &str, summary: &str) -> String {
 }
 
 impl SqliteStore {
+    /// The `(raw_excerpt, attachment)` columns to write for `memory`.
+    /// Excerpts of [`ATTACHMENT_MIN_BYTES`] or more are moved to the
+    /// attachment store and referenced by hash; a memory whose excerpt
+    /// was never loaded keeps the attachment it already points to.
+    fn raw_columns(&self, memory: &Memory) -> IcmResult<(Option<String>, Option<String>)> {
+        match memory.raw_excerpt.as_deref() {
+            Some(raw) if raw.len() >= ATTACHMENT_MIN_BYTES => {
+                Ok((None, Some(self.put_attachment(raw)?)))
+            }
+            Some(raw) => Ok((Some(raw.to_string()), None)),
+            None => Ok((None, memory.attachment.clone())),
+        }
+    }
+
+    /// Store `content` under its SHA-256 (hex) and return the hash.
+    /// Identical excerpts share one row.
+    fn put_attachment(&self, content: &str) -> IcmResult<String> {
+        let hash = format!("{:x}", Sha256::digest(content.as_bytes()));
+        let (value, compressed) = compress::encode(content.to_string());
+        self.conn
+            .prepare_cached(
+                "INSERT OR IGNORE INTO attachments (hash, content, bytes, created_at, compressed)
+                 VALUES (?1, ?2, ?3, ?4, ?5)",
+            )
+            .and_then(|mut stmt| {
+                stmt.execute(params![
+                    hash,
+                    value,
+                    content.len() as i64,
+                    Utc::now().to_rfc3339(),
+                    compressed,
+                ])
+            })
+            .map_err(db_err)?;
+        Ok(hash)
+    }
+
+    /// Content of the attachment with this hash, if it is still stored.
+    pub fn attachment(&self, hash: &str) -> IcmResult<Option<String>> {
+        self.conn
+            .prepare_cached("SELECT content, compressed FROM attachments WHERE hash = ?1")
+            .and_then(|mut stmt| {
+                stmt.query_row(params![hash], |r| compress::read_text(r, 0, r.get(1)?))
+                    .optional()
+            })
+            .map(Option::flatten)
+            .map_err(db_err)
+    }
+
+    /// Store `memory` unless a memory with its id already exists. The
+    /// check and the insert share one transaction, so concurrent retries
+    /// of the same idempotent write create a single row.
+    pub fn store_idempotent(&self, memory: Memory) -> IcmResult<StoreOutcome> {
+        let memory = validate_and_normalize(memory)?;
+
+        self.conn
+            .execute_batch("BEGIN IMMEDIATE;")
+            .map_err(db_err)?;
+
+        let result = self
+            .conn
+            .query_row(
+                "SELECT 1 FROM memories WHERE id = ?1",
+                params![memory.id],
+                |_| Ok(()),
+            )
+            .optional()
+            .map_err(db_err)
+            .and_then(|taken| match taken {
+                Some(()) => Ok(StoreOutcome {
+                    id: memory.id.clone(),
+                    created: false,
+                }),
+                None => self.store_inner(&memory).map(|id| StoreOutcome {
+                    created: id == memory.id,
+                    id,
+                }),
+            });
+        match result {
+            Ok(outcome) => {
+                self.conn.execute_batch("COMMIT;").map_err(db_err)?;
+                Ok(outcome)
+            }
+            Err(e) => {
+                let _ = self.conn.execute_batch("ROLLBACK;");
+                Err(e)
+            }
+        }
+    }
+
+    /// Store `memory` as the value of `key`. When the key already names a
+    /// memory, that memory takes the new content in place: it keeps its
+    /// id, `created_at` and access history, and the key's revision goes
+    /// up by one. Otherwise the memory is stored (or matched on topic and
+    /// summary) and the key starts at revision 1.
+    pub fn store_keyed(&self, key: &str, memory: Memory) -> IcmResult<KeyedOutcome> {
+        let memory = validate_and_normalize(memory)?;
+
+        self.conn
+            .execute_batch("BEGIN IMMEDIATE;")
+            .map_err(db_err)?;
+        match self.store_keyed_inner(key, memory) {
+            Ok(outcome) => {
+                self.conn.execute_batch("COMMIT;").map_err(db_err)?;
+                Ok(outcome)
+            }
+            Err(e) => {
+                let _ = self.conn.execute_batch("ROLLBACK;");
+                Err(e)
+            }
+        }
+    }
+
+    fn store_keyed_inner(&self, key: &str, memory: Memory) -> IcmResult<KeyedOutcome> {
+        let now = Utc::now();
+        let held: Option<(String, u32)> = self
+            .conn
+            .query_row(
+                "SELECT memory_id, revision FROM memory_keys WHERE key = ?1",
+                params![key],
+                |r| Ok((r.get(0)?, r.get(1)?)),
+            )
+            .optional()
+            .map_err(db_err)?;
+
+        if let Some((id, revision)) = held {
+            if let Some(existing) = self.get(&id)? {
+                let replaced = Memory {
+                    id: existing.id,
+                    created_at: existing.created_at,
+                    updated_at: now,
+                    last_accessed: existing.last_accessed,
+                    access_count: existing.access_count,
+                    weight: 1.0,
+                    related_ids: if memory.related_ids.is_empty() {
+                        existing.related_ids
+                    } else {
+                        memory.related_ids
+                    },
+                    scope: existing.scope,
+                    author: memory.author.or(existing.author),
+                    ..memory
+                };
+                self.update(&replaced)?;
+                self.conn
+                    .execute(
+                        "UPDATE memory_keys SET revision = ?2, updated_at = ?3 WHERE key = ?1",
+                        params![key, revision + 1, now.to_rfc3339()],
+                    )
+                    .map_err(db_err)?;
+                return Ok(KeyedOutcome {
+                    id: replaced.id,
+                    revision: revision + 1,
+                });
+            }
+        }
+
+        let id = self.store_inner(&memory)?;
+        self.conn
+            .execute(
+                "INSERT OR REPLACE INTO memory_keys (key, memory_id, revision, updated_at)
+                 VALUES (?1, ?2, 1, ?3)",
+                params![key, id, now.to_rfc3339()],
+            )
+            .map_err(db_err)?;
+        Ok(KeyedOutcome { id, revision: 1 })
+    }
+
     /// Insert a memory into the database without transaction management.
     /// Callers are responsible for wrapping this in a transaction.
     ///
@@ -982,15 +2077,22 @@ impl SqliteStore {
         let sd = source_data(&memory.source);
         let emb_blob = memory.embedding.as_deref().map(embedding_to_blob);
         let hash = summary_hash(&memory.topic, &memory.summary);
+        let (raw, attachment) = self.raw_columns(memory)?;
+        let (raw_value, compressed) = compress::encode_opt(raw.clone());
+        let metadata = metadata_json(&memory.metadata)?;
 
         let inserted = self
             .conn
-            .execute(
+            .prepare_cached(
                 "INSERT OR IGNORE INTO memories (id, created_at, updated_at, last_accessed, access_count, weight,
                  topic, summary, raw_excerpt, keywords,
-                 importance, source_type, source_data, related_ids, embedding, summary_hash)
-                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16)",
-                params![
+                 importance, source_type, source_data, related_ids, embedding, summary_hash, author,
+                 attachment, compressed, metadata, kind)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18,
+                         ?19, ?20, ?21)",
+            )
+            .and_then(|mut stmt| {
+                stmt.execute(params![
                     memory.id,
                     memory.created_at.to_rfc3339(),
                     memory.updated_at.to_rfc3339(),
@@ -999,7 +2101,7 @@ impl SqliteStore {
                     memory.weight,
                     memory.topic,
                     memory.summary,
-                    memory.raw_excerpt,
+                    raw_value,
                     keywords_json,
                     memory.importance.to_string(),
                     st,
@@ -1007,8 +2109,13 @@ impl SqliteStore {
                     related_json,
                     emb_blob,
                     hash,
-                ],
-            )
+                    memory.author,
+                    attachment,
+                    compressed,
+                    metadata,
+                    memory.kind.to_string(),
+                ])
+            })
             .map_err(db_err)?;
 
         if inserted == 0 {
@@ -1030,21 +2137,48 @@ impl SqliteStore {
             // - keywords: union, preserving existing order then
             //   appending new ones not already present.
             // - raw_excerpt: prefer the new value if non-None,
-            //   otherwise keep existing.
+            //   otherwise keep existing (inline or attachment).
+            // - metadata: the caller's keys overwrite, others are kept.
+            // - kind: a preference/instruction store re-labels a fact;
+            //   a plain fact store keeps the existing kind.
             // - updated_at: bumped whenever any field actually changed.
-            let (existing_id, existing_importance_str, existing_keywords_json, existing_raw): (
+            let (
+                existing_id,
+                existing_importance_str,
+                existing_keywords_json,
+                existing_raw,
+                existing_attachment,
+                existing_metadata_json,
+                existing_kind_str,
+            ): (
                 String,
                 String,
                 String,
                 Option<String>,
+                Option<String>,
+                Option<String>,
+                String,
             ) = self
                 .conn
-                .query_row(
-                    "SELECT id, importance, keywords, raw_excerpt FROM memories
+                .prepare_cached(
+                    "SELECT id, importance, keywords, raw_excerpt, attachment, compressed, metadata,
+                            kind
+                     FROM memories
                      WHERE LOWER(topic) = LOWER(?1) AND summary_hash = ?2",
-                    params![memory.topic, hash],
-                    |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)),
                 )
+                .and_then(|mut stmt| {
+                    stmt.query_row(params![memory.topic, hash], |row| {
+                        Ok((
+                            row.get(0)?,
+                            row.get(1)?,
+                            row.get(2)?,
+                            compress::read_text(row, 3, row.get(5)?)?,
+                            row.get(4)?,
+                            row.get(6)?,
+                            row.get(7)?,
+                        ))
+                    })
+                })
                 .map_err(db_err)?;
 
             let existing_importance: Importance = existing_importance_str
@@ -1061,24 +2195,52 @@ impl SqliteStore {
                 }
             }
 
-            let merged_raw = memory.raw_excerpt.clone().or(existing_raw.clone());
+            let (merged_raw, merged_attachment) = if raw.is_some() || attachment.is_some() {
+                (raw, attachment)
+            } else {
+                (existing_raw.clone(), existing_attachment.clone())
+            };
+
+            let existing_metadata = parse_metadata(existing_metadata_json.as_deref());
+            let mut merged_metadata = existing_metadata.clone();
+            merged_metadata.extend(memory.metadata.clone());
 
             let importance_changed = merged_importance != existing_importance;
             let keywords_changed = merged_keywords != existing_keywords;
-            let raw_changed = merged_raw != existing_raw;
-            if importance_changed || keywords_changed || raw_changed {
+            let raw_changed =
+                merged_raw != existing_raw || merged_attachment != existing_attachment;
+            let metadata_changed = merged_metadata != existing_metadata;
+            let existing_kind: MemoryKind = existing_kind_str.parse().unwrap_or_default();
+            let merged_kind = if memory.kind.is_fact() {
+                existing_kind
+            } else {
+                memory.kind
+            };
+            let kind_changed = merged_kind != existing_kind;
+            if importance_changed
+                || keywords_changed
+                || raw_changed
+                || metadata_changed
+                || kind_changed
+            {
                 let merged_keywords_json = serde_json::to_string(&merged_keywords)?;
+                let (merged_value, merged_compressed) = compress::encode_opt(merged_raw);
                 self.conn
                     .execute(
                         "UPDATE memories
-                         SET importance = ?1, keywords = ?2, raw_excerpt = ?3, updated_at = ?4
+                         SET importance = ?1, keywords = ?2, raw_excerpt = ?3, updated_at = ?4,
+                             attachment = ?6, compressed = ?7, metadata = ?8, kind = ?9
                          WHERE id = ?5",
                         params![
                             merged_importance.to_string(),
                             merged_keywords_json,
-                            merged_raw,
+                            merged_value,
                             Utc::now().to_rfc3339(),
                             existing_id,
+                            merged_attachment,
+                            merged_compressed,
+                            metadata_json(&merged_metadata)?,
+                            merged_kind.to_string(),
                         ],
                     )
                     .map_err(db_err)?;
@@ -1111,14 +2273,40 @@ impl SqliteStore {
     }
 }
 
-impl MemoryStore for SqliteStore {
-    fn store(&self, memory: Memory) -> IcmResult<String> {
-        let memory = validate_and_normalize(memory)?;
+impl SqliteStore {
+    /// Stream every memory in id order, a page at a time. Unlike
+    /// [`MemoryStore::list_all`] this has no row cap.
+    pub fn iter_memories(&self) -> MemoryIter<'_> {
+        MemoryIter::new(self)
+    }
+}
 
-        self.conn
-            .execute_batch("BEGIN IMMEDIATE;")
+impl MemoryPages for SqliteStore {
+    fn memories_after(&self, after: Option<&str>, limit: usize) -> IcmResult<Vec<Memory>> {
+        let mut stmt = self
+            .conn
+            .prepare_cached(concat!(
+                "SELECT ",
+                select_cols!(),
+                " FROM memories WHERE id > ?1 ORDER BY id LIMIT ?2"
+            ))
             .map_err(db_err)?;
-
+        // Every id sorts after the empty string.
+        let rows = stmt
+            .query_map(params![after.unwrap_or(""), limit as i64], row_to_memory)
+            .map_err(db_err)?;
+        collect_rows(rows)
+    }
+}
+
+impl MemoryStore for SqliteStore {
+    fn store(&self, memory: Memory) -> IcmResult<String> {
+        let memory = validate_and_normalize(memory)?;
+
+        self.conn
+            .execute_batch("BEGIN IMMEDIATE;")
+            .map_err(db_err)?;
+
         match self.store_inner(&memory) {
             Ok(id) => {
                 self.conn.execute_batch("COMMIT;").map_err(db_err)?;
@@ -1138,7 +2326,11 @@ impl MemoryStore for SqliteStore {
 
         let mut stmt = self
             .conn
-            .prepare(&format!("SELECT {SELECT_COLS} FROM memories WHERE id = ?1"))
+            .prepare_cached(concat!(
+                "SELECT ",
+                select_cols!(),
+                " FROM memories WHERE id = ?1"
+            ))
             .map_err(db_err)?;
 
         let result = stmt
@@ -1163,6 +2355,8 @@ impl MemoryStore for SqliteStore {
         // changed, and the partial unique index on (topic, summary_hash)
         // would otherwise reflect stale state.
         let hash = summary_hash(&memory.topic, &memory.summary);
+        let (raw, attachment) = self.raw_columns(memory)?;
+        let (raw_value, compressed) = compress::encode_opt(raw);
 
         let changed = self
             .conn
@@ -1171,7 +2365,8 @@ impl MemoryStore for SqliteStore {
                  updated_at = ?2, last_accessed = ?3, access_count = ?4, weight = ?5,
                  topic = ?6, summary = ?7, raw_excerpt = ?8, keywords = ?9,
                  importance = ?10, source_type = ?11, source_data = ?12, related_ids = ?13,
-                 embedding = ?14, summary_hash = ?15
+                 embedding = ?14, summary_hash = ?15, author = ?16, attachment = ?17,
+                 compressed = ?18, metadata = ?19, kind = ?20
                  WHERE id = ?1",
                 params![
                     memory.id,
@@ -1181,7 +2376,7 @@ impl MemoryStore for SqliteStore {
                     memory.weight,
                     memory.topic,
                     memory.summary,
-                    memory.raw_excerpt,
+                    raw_value,
                     keywords_json,
                     memory.importance.to_string(),
                     st,
@@ -1189,6 +2384,11 @@ impl MemoryStore for SqliteStore {
                     related_json,
                     emb_blob,
                     hash,
+                    memory.author,
+                    attachment,
+                    compressed,
+                    metadata_json(&memory.metadata)?,
+                    memory.kind.to_string(),
                 ],
             )
             .map_err(db_err)?;
@@ -1229,36 +2429,33 @@ impl MemoryStore for SqliteStore {
             return Err(IcmError::NotFound(id.to_string()));
         }
         self.cache_invalidate(id);
+        self.replace_memory_refs(&[id], None)?;
         Ok(())
     }
 
     fn search_by_keywords(&self, keywords: &[&str], limit: usize) -> IcmResult<Vec<Memory>> {
-        if keywords.is_empty() {
+        // Parsing caps the term count, bounding the generated SQL.
+        let parsed = KeywordQuery::parse(keywords, &self.keyword_options);
+        if parsed.is_empty() {
             return Ok(Vec::new());
         }
-
-        // Cap keywords to avoid massive SQL generation
-        let keywords = &keywords[..keywords.len().min(50)];
         let limit = limit.min(100);
 
-        let where_parts: Vec<String> = (0..keywords.len())
-            .map(|i| {
-                let p = i + 1;
-                format!("(keywords LIKE ?{p} OR summary LIKE ?{p} OR topic LIKE ?{p})")
-            })
-            .collect();
-        let where_clause = where_parts.join(" OR ");
-
+        // SQLite's LIKE is already case-insensitive for ASCII.
+        let sql = KeywordSql::build(&parsed, "LIKE", '?', "");
         let query = format!(
-            "SELECT {SELECT_COLS} FROM memories WHERE {where_clause} ORDER BY weight DESC LIMIT ?{}",
-            keywords.len() + 1
+            "SELECT {SELECT_COLS} FROM memories WHERE {} ORDER BY {} LIMIT ?{}",
+            sql.where_clause,
+            sql.order_by,
+            sql.patterns.len() + 1
         );
 
-        let mut stmt = self.conn.prepare(&query).map_err(db_err)?;
+        let mut stmt = self.conn.prepare_cached(&query).map_err(db_err)?;
 
-        let mut param_values: Vec<Box<dyn rusqlite::types::ToSql>> = keywords
-            .iter()
-            .map(|k| Box::new(format!("%{k}%")) as Box<dyn rusqlite::types::ToSql>)
+        let mut param_values: Vec<Box<dyn rusqlite::types::ToSql>> = sql
+            .patterns
+            .into_iter()
+            .map(|p| Box::new(p) as Box<dyn rusqlite::types::ToSql>)
             .collect();
         param_values.push(Box::new(limit as i64));
 
@@ -1279,19 +2476,30 @@ impl MemoryStore for SqliteStore {
             return Ok(Vec::new());
         }
 
-        let sql = format!(
-            "SELECT {SELECT_COLS} FROM memories
-             WHERE id IN (
-                 SELECT id FROM memories_fts WHERE memories_fts MATCH ?1
-             )
-             ORDER BY weight DESC
-             LIMIT ?2"
-        );
-
-        let mut stmt = self.conn.prepare(&sql).map_err(db_err)?;
+        let mut stmt = self
+            .conn
+            .prepare_cached(concat!(
+                "WITH hits AS (
+                     SELECT id AS hit_id, -bm25(memories_fts, 0.0, 1.0, 1.0, 1.0) AS rel
+                     FROM memories_fts WHERE memories_fts MATCH ?1
+                 )
+                 SELECT ",
+                select_cols!(),
+                " FROM memories JOIN hits ON hit_id = id
+                 ORDER BY ?3 * rel / MAX(MAX(rel) OVER (), 1e-9) + (1.0 - ?3) * weight DESC,
+                          weight DESC
+                 LIMIT ?2"
+            ))
+            .map_err(db_err)?;
 
+        // BM25 (negated so higher is better) is scaled to 0..=1 against
+        // the best hit so it blends with weight on a comparable scale.
+        // The id column is indexed for lookups only and doesn't score.
         let rows = stmt
-            .query_map(params![sanitized, limit as i64], row_to_memory)
+            .query_map(
+                params![sanitized, limit as i64, self.fts_relevance as f64],
+                row_to_memory,
+            )
             .map_err(db_err)?;
 
         collect_rows(rows)
@@ -1302,27 +2510,40 @@ impl MemoryStore for SqliteStore {
         embedding: &[f32],
         limit: usize,
     ) -> IcmResult<Vec<(Memory, f32)>> {
-        let query_blob = embedding_to_blob(embedding);
+        let knn_rows: Vec<(String, f32)> = match self.ann.as_ref().and_then(|m| m.lock().ok()) {
+            // Over-fetch: the ANN index may hold IDs that were removed
+            // from the table since; those drop out below.
+            Some(mut ann) => {
+                if let Err(e) = self.ann_catch_up(&mut ann) {
+                    tracing::warn!(error = %e, "vector index catch-up failed; searching as is");
+                }
+                ann.index.search(embedding, limit.saturating_mul(2))
+            }
+            None => {
+                let query_blob = embedding_to_blob(embedding);
 
-        // KNN query on vec0 virtual table (requires LIMIT in the query itself)
-        let mut knn_stmt = self
-            .conn
-            .prepare(
-                "SELECT memory_id, distance
-                 FROM vec_memories
-                 WHERE embedding MATCH ?1
-                 ORDER BY distance
-                 LIMIT ?2",
-            )
-            .map_err(db_err)?;
+                // KNN query on vec0 virtual table (requires LIMIT in the query itself)
+                let mut knn_stmt = self
+                    .conn
+                    .prepare_cached(
+                        "SELECT memory_id, distance
+                         FROM vec_memories
+                         WHERE embedding MATCH ?1
+                         ORDER BY distance
+                         LIMIT ?2",
+                    )
+                    .map_err(db_err)?;
 
-        let knn_rows: Vec<(String, f32)> = knn_stmt
-            .query_map(params![query_blob, limit as i64], |row| {
-                Ok((row.get::<_, String>(0)?, row.get::<_, f32>(1)?))
-            })
-            .map_err(db_err)?
-            .filter_map(|r| r.ok())
-            .collect();
+                let rows: Vec<(String, f32)> = knn_stmt
+                    .query_map(params![query_blob, limit as i64], |row| {
+                        Ok((row.get::<_, String>(0)?, row.get::<_, f32>(1)?))
+                    })
+                    .map_err(db_err)?
+                    .filter_map(|r| r.ok())
+                    .collect();
+                rows
+            }
+        };
 
         if knn_rows.is_empty() {
             return Ok(Vec::new());
@@ -1334,6 +2555,7 @@ impl MemoryStore for SqliteStore {
             "SELECT {SELECT_COLS} FROM memories WHERE id IN ({})",
             placeholders.join(", ")
         );
+        // Not cached: the IN list, and so the SQL, differs per call.
         let mut stmt = self.conn.prepare(&sql).map_err(db_err)?;
 
         let ids: Vec<&str> = knn_rows.iter().map(|(id, _)| id.as_str()).collect();
@@ -1353,17 +2575,18 @@ impl MemoryStore for SqliteStore {
         let results: Vec<(Memory, f32)> = knn_rows
             .into_iter()
             .filter_map(|(id, distance)| memory_map.remove(&id).map(|mem| (mem, 1.0 - distance)))
+            .take(limit)
             .collect();
 
         Ok(results)
     }
 
-    fn search_hybrid(
+    fn search_hybrid_scored(
         &self,
         query: &str,
         embedding: &[f32],
         limit: usize,
-    ) -> IcmResult<Vec<(Memory, f32)>> {
+    ) -> IcmResult<Vec<(Memory, HybridScore)>> {
         let limit = limit.min(1000);
         let pool_size = limit * 4;
         let sanitized = sanitize_fts_query(query);
@@ -1373,29 +2596,29 @@ impl MemoryStore for SqliteStore {
             "SELECT m.id, m.created_at, m.updated_at, m.last_accessed, m.access_count, m.weight, \
                     m.topic, m.summary, m.raw_excerpt, m.keywords, \
                     m.importance, m.source_type, m.source_data, m.related_ids, m.embedding, \
-                    fts.rank \
+                    m.author, m.attachment, m.compressed, m.metadata, m.kind, fts.rank \
              FROM memories_fts fts \
              JOIN memories m ON m.id = fts.id \
              WHERE memories_fts MATCH ?1 \
              ORDER BY fts.rank \
              LIMIT ?2";
 
-        let mut fts_scores: HashMap<String, f32> = HashMap::with_capacity(pool_size);
+        let mut fts_scores: HashMap<String, (f32, f32)> = HashMap::with_capacity(pool_size);
         let mut all_memories: HashMap<String, Memory> = HashMap::with_capacity(pool_size);
 
         if !sanitized.is_empty() {
-            if let Ok(mut stmt) = self.conn.prepare(fts_sql) {
+            if let Ok(mut stmt) = self.conn.prepare_cached(fts_sql) {
                 if let Ok(rows) = stmt.query_map(params![sanitized, pool_size as i64], |row| {
                     let memory = row_to_memory(row)?;
-                    let rank: f32 = row.get(15)?;
+                    let rank: f32 = row.get(20)?;
                     Ok((memory, rank))
                 }) {
                     for row in rows.flatten() {
                         let (memory, rank) = row;
                         // Normalize FTS rank (lower is better, typically negative)
                         // Convert to 0..1 score where higher is better
-                        let score = 1.0 / (1.0 + rank.abs());
-                        fts_scores.insert(memory.id.clone(), score);
+                        let score = icm_core::fts_score(rank);
+                        fts_scores.insert(memory.id.clone(), (rank, score));
                         all_memories.insert(memory.id.clone(), memory);
                     }
                 }
@@ -1412,19 +2635,28 @@ impl MemoryStore for SqliteStore {
 
         // 3. Combine scores: 30% FTS + 70% vector
         let keys: Vec<String> = all_memories.keys().cloned().collect();
-        let mut scored: Vec<(String, f32)> = Vec::with_capacity(keys.len());
+        let mut scored: Vec<(String, HybridScore)> = Vec::with_capacity(keys.len());
         for id in keys {
-            let fts_score = fts_scores.get(&id).copied().unwrap_or(0.0);
-            let vec_score = vec_scores.get(&id).copied().unwrap_or(0.0);
-            let combined = 0.3 * fts_score + 0.7 * vec_score;
-            scored.push((id, combined));
+            let fts = fts_scores.get(&id).copied();
+            let similarity = vec_scores.get(&id).copied();
+            let score = HybridScore::new(
+                fts.map(|(rank, _)| rank),
+                fts.map_or(0.0, |(_, score)| score),
+                similarity,
+                similarity.unwrap_or(0.0),
+            );
+            scored.push((id, score));
         }
 
         // Sort by combined score descending
-        scored.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
+        scored.sort_by(|a, b| {
+            b.1.combined
+                .partial_cmp(&a.1.combined)
+                .unwrap_or(std::cmp::Ordering::Equal)
+        });
         scored.truncate(limit);
 
-        let results: Vec<(Memory, f32)> = scored
+        let results: Vec<(Memory, HybridScore)> = scored
             .into_iter()
             .filter_map(|(id, score)| all_memories.remove(&id).map(|mem| (mem, score)))
             .collect();
@@ -1441,12 +2673,14 @@ impl MemoryStore for SqliteStore {
             return Ok(());
         }
         let now = Utc::now().to_rfc3339();
+        let Reinforcement { rate, cap } = self.reinforcement;
         let changed = self
             .conn
-            .execute(
-                "UPDATE memories SET last_accessed = ?1, access_count = access_count + 1 WHERE id = ?2",
-                params![now, id],
+            .prepare_cached(
+                "UPDATE memories SET last_accessed = ?1, access_count = access_count + 1, \
+                 weight = MAX(weight, MIN(?3, weight + ?2 * (1.0 - weight))) WHERE id = ?4",
             )
+            .and_then(|mut stmt| stmt.execute(params![now, rate as f64, cap as f64, id]))
             .map_err(db_err)?;
 
         if changed == 0 {
@@ -1465,14 +2699,18 @@ impl MemoryStore for SqliteStore {
             return Ok(0);
         }
         let now = Utc::now().to_rfc3339();
-        let placeholders: Vec<String> = (2..=ids.len() + 1).map(|i| format!("?{i}")).collect();
+        let Reinforcement { rate, cap } = self.reinforcement;
+        let placeholders: Vec<String> = (4..=ids.len() + 3).map(|i| format!("?{i}")).collect();
         let sql = format!(
-            "UPDATE memories SET last_accessed = ?1, access_count = access_count + 1 WHERE id IN ({})",
+            "UPDATE memories SET last_accessed = ?1, access_count = access_count + 1, \
+             weight = MAX(weight, MIN(?3, weight + ?2 * (1.0 - weight))) WHERE id IN ({})",
             placeholders.join(", ")
         );
         let mut params_vec: Vec<Box<dyn rusqlite::types::ToSql>> =
-            Vec::with_capacity(ids.len() + 1);
+            Vec::with_capacity(ids.len() + 3);
         params_vec.push(Box::new(now));
+        params_vec.push(Box::new(rate as f64));
+        params_vec.push(Box::new(cap as f64));
         for id in ids {
             params_vec.push(Box::new(id.to_string()));
         }
@@ -1534,6 +2772,18 @@ impl MemoryStore for SqliteStore {
 
     fn prune(&self, weight_threshold: f32) -> IcmResult<usize> {
         // Never prune critical or high importance memories
+        let doomed: Vec<String> = {
+            let mut stmt = self
+                .conn
+                .prepare_cached(
+                    "SELECT id FROM memories WHERE weight < ?1 AND importance NOT IN ('critical', 'high')",
+                )
+                .map_err(db_err)?;
+            let rows = stmt
+                .query_map(params![weight_threshold], |row| row.get(0))
+                .map_err(db_err)?;
+            collect_rows(rows)?
+        };
         let _ = self.conn.execute(
             "DELETE FROM vec_memories WHERE memory_id IN (
                 SELECT id FROM memories WHERE weight < ?1 AND importance NOT IN ('critical', 'high')
@@ -1551,6 +2801,8 @@ impl MemoryStore for SqliteStore {
 
         if changed > 0 {
             self.cache_clear();
+            let doomed: Vec<&str> = doomed.iter().map(String::as_str).collect();
+            self.replace_memory_refs(&doomed, None)?;
         }
         Ok(changed)
     }
@@ -1558,8 +2810,10 @@ impl MemoryStore for SqliteStore {
     fn get_by_topic(&self, topic: &str) -> IcmResult<Vec<Memory>> {
         let mut stmt = self
             .conn
-            .prepare(&format!(
-                "SELECT {SELECT_COLS} FROM memories WHERE topic = ?1 ORDER BY weight DESC LIMIT 500"
+            .prepare_cached(concat!(
+                "SELECT ",
+                select_cols!(),
+                " FROM memories WHERE topic = ?1 ORDER BY weight DESC LIMIT 500"
             ))
             .map_err(db_err)?;
 
@@ -1573,8 +2827,10 @@ impl MemoryStore for SqliteStore {
     fn list_all(&self) -> IcmResult<Vec<Memory>> {
         let mut stmt = self
             .conn
-            .prepare(&format!(
-                "SELECT {SELECT_COLS} FROM memories ORDER BY weight DESC LIMIT 10000"
+            .prepare_cached(concat!(
+                "SELECT ",
+                select_cols!(),
+                " FROM memories ORDER BY weight DESC LIMIT 10000"
             ))
             .map_err(db_err)?;
 
@@ -1582,10 +2838,26 @@ impl MemoryStore for SqliteStore {
         collect_rows(rows)
     }
 
+    fn get_all(&self, limit: usize, offset: usize) -> IcmResult<Vec<Memory>> {
+        let mut stmt = self
+            .conn
+            .prepare_cached(concat!(
+                "SELECT ",
+                select_cols!(),
+                " FROM memories ORDER BY weight DESC, id LIMIT ?1 OFFSET ?2"
+            ))
+            .map_err(db_err)?;
+
+        let rows = stmt
+            .query_map(params![limit as i64, offset as i64], row_to_memory)
+            .map_err(db_err)?;
+        collect_rows(rows)
+    }
+
     fn list_topics(&self) -> IcmResult<Vec<(String, usize)>> {
         let mut stmt = self
             .conn
-            .prepare("SELECT topic, COUNT(*) FROM memories GROUP BY topic ORDER BY topic")
+            .prepare_cached("SELECT topic, COUNT(*) FROM memories GROUP BY topic ORDER BY topic")
             .map_err(db_err)?;
 
         let rows = stmt
@@ -1597,7 +2869,21 @@ impl MemoryStore for SqliteStore {
         collect_rows(rows)
     }
 
-    fn consolidate_topic(&self, topic: &str, consolidated: Memory) -> IcmResult<()> {
+    fn consolidate_topic(&self, topic: &str, mut consolidated: Memory) -> IcmResult<()> {
+        let doomed: Vec<String> = {
+            let mut stmt = self
+                .conn
+                .prepare_cached("SELECT id FROM memories WHERE topic = ?1")
+                .map_err(db_err)?;
+            let rows = stmt
+                .query_map(params![topic], |row| row.get(0))
+                .map_err(db_err)?;
+            collect_rows(rows)?
+        };
+        // The originals are about to go: references to them (callers list
+        // them in `related_ids`) would dangle.
+        consolidated.related_ids.retain(|id| !doomed.contains(id));
+
         self.conn
             .execute_batch("BEGIN IMMEDIATE;")
             .map_err(db_err)?;
@@ -1629,6 +2915,14 @@ impl MemoryStore for SqliteStore {
             return Err(e);
         }
 
+        // Whatever pointed at an original now points at the rollup.
+        let doomed: Vec<&str> = doomed.iter().map(String::as_str).collect();
+        if let Err(e) = self.replace_memory_refs(&doomed, Some(&consolidated.id)) {
+            tracing::warn!(topic, error = %e, "consolidate_topic: rolling back after reference rewrite failed");
+            let _ = self.conn.execute_batch("ROLLBACK;");
+            return Err(e);
+        }
+
         // Rebuild FTS index to eliminate any ghost entries from the external
         // content table.  This guarantees search results stay consistent after
         // bulk deletes (fixes #44).
@@ -1866,7 +3160,7 @@ impl MemoirStore for SqliteStore {
 
     fn get_memoir(&self, id: &str) -> IcmResult<Option<Memoir>> {
         self.conn
-            .prepare(&format!("SELECT {MEMOIR_COLS} FROM memoirs WHERE id = ?1"))
+            .prepare_cached(&format!("SELECT {MEMOIR_COLS} FROM memoirs WHERE id = ?1"))
             .map_err(db_err)?
             .query_row(params![id], row_to_memoir)
             .optional()
@@ -1875,7 +3169,7 @@ impl MemoirStore for SqliteStore {
 
     fn get_memoir_by_name(&self, name: &str) -> IcmResult<Option<Memoir>> {
         self.conn
-            .prepare(&format!(
+            .prepare_cached(&format!(
                 "SELECT {MEMOIR_COLS} FROM memoirs WHERE name = ?1"
             ))
             .map_err(db_err)?
@@ -1921,7 +3215,7 @@ impl MemoirStore for SqliteStore {
     fn list_memoirs(&self) -> IcmResult<Vec<Memoir>> {
         let mut stmt = self
             .conn
-            .prepare(&format!(
+            .prepare_cached(&format!(
                 "SELECT {MEMOIR_COLS} FROM memoirs ORDER BY name LIMIT 500"
             ))
             .map_err(db_err)?;
@@ -1961,7 +3255,7 @@ impl MemoirStore for SqliteStore {
 
     fn get_concept(&self, id: &str) -> IcmResult<Option<Concept>> {
         self.conn
-            .prepare(&format!(
+            .prepare_cached(&format!(
                 "SELECT {CONCEPT_COLS} FROM concepts WHERE id = ?1"
             ))
             .map_err(db_err)?
@@ -1972,7 +3266,7 @@ impl MemoirStore for SqliteStore {
 
     fn get_concept_by_name(&self, memoir_id: &str, name: &str) -> IcmResult<Option<Concept>> {
         self.conn
-            .prepare(&format!(
+            .prepare_cached(&format!(
                 "SELECT {CONCEPT_COLS} FROM concepts WHERE memoir_id = ?1 AND name = ?2"
             ))
             .map_err(db_err)?
@@ -2028,7 +3322,7 @@ impl MemoirStore for SqliteStore {
     fn list_concepts(&self, memoir_id: &str) -> IcmResult<Vec<Concept>> {
         let mut stmt = self
             .conn
-            .prepare(&format!(
+            .prepare_cached(&format!(
                 "SELECT {CONCEPT_COLS} FROM concepts WHERE memoir_id = ?1 ORDER BY name LIMIT 1000"
             ))
             .map_err(db_err)?;
@@ -2059,7 +3353,7 @@ impl MemoirStore for SqliteStore {
              LIMIT ?3"
         );
 
-        let mut stmt = self.conn.prepare(&sql).map_err(db_err)?;
+        let mut stmt = self.conn.prepare_cached(&sql).map_err(db_err)?;
 
         let rows = stmt
             .query_map(params![memoir_id, sanitized, limit as i64], row_to_concept)
@@ -2081,7 +3375,7 @@ impl MemoirStore for SqliteStore {
              LIMIT ?2"
         );
 
-        let mut stmt = self.conn.prepare(&sql).map_err(db_err)?;
+        let mut stmt = self.conn.prepare_cached(&sql).map_err(db_err)?;
 
         let rows = stmt
             .query_map(params![sanitized, limit as i64], row_to_concept)
@@ -2090,29 +3384,56 @@ impl MemoirStore for SqliteStore {
         collect_rows(rows)
     }
 
-    fn search_concepts_by_label(
+    fn search_concepts_by_labels(
         &self,
         memoir_id: &str,
-        label: &Label,
+        labels: &[Label],
+        match_all: bool,
         limit: usize,
     ) -> IcmResult<Vec<Concept>> {
-        // Search JSON labels column using LIKE with the serialized label pattern
-        let pattern = format!(
-            "%\"namespace\":\"{}\"%\"value\":\"{}\"%",
-            label.namespace, label.value
-        );
+        let mut wanted: Vec<&Label> = Vec::with_capacity(labels.len());
+        for label in labels {
+            if !wanted.contains(&label) {
+                wanted.push(label);
+            }
+        }
+        if wanted.is_empty() {
+            return Ok(Vec::new());
+        }
 
+        // `concept_labels` is kept in sync with `concepts.labels` by
+        // triggers (see schema.rs); each (concept, label) pair appears
+        // once, so ALL is a count of matched rows.
+        let mut values: Vec<&str> = vec![memoir_id];
+        let mut terms = Vec::with_capacity(wanted.len());
+        for label in &wanted {
+            terms.push(format!(
+                "(namespace = ?{} AND value = ?{})",
+                values.len() + 1,
+                values.len() + 2
+            ));
+            values.push(&label.namespace);
+            values.push(&label.value);
+        }
+        let having = if match_all {
+            format!(" HAVING COUNT(*) = {}", wanted.len())
+        } else {
+            String::new()
+        };
         let sql = format!(
             "SELECT {CONCEPT_COLS} FROM concepts
-             WHERE memoir_id = ?1 AND labels LIKE ?2
+             WHERE memoir_id = ?1 AND id IN (
+                 SELECT concept_id FROM concept_labels WHERE {}
+                 GROUP BY concept_id{having}
+             )
              ORDER BY confidence DESC
-             LIMIT ?3"
+             LIMIT {limit}",
+            terms.join(" OR ")
         );
 
         let mut stmt = self.conn.prepare(&sql).map_err(db_err)?;
-
         let rows = stmt
-            .query_map(params![memoir_id, pattern, limit as i64], row_to_concept)
+            .query_map(rusqlite::params_from_iter(values), row_to_concept)
             .map_err(db_err)?;
 
         collect_rows(rows)
@@ -2154,6 +3475,63 @@ impl MemoirStore for SqliteStore {
         Ok(())
     }
 
+    fn concept_revisions(&self, concept_id: &str) -> IcmResult<Vec<ConceptRevision>> {
+        let mut stmt = self
+            .conn
+            .prepare_cached(
+                "SELECT concept_id, revision, definition, confidence, updated_at
+                 FROM concept_revisions WHERE concept_id = ?1 ORDER BY revision",
+            )
+            .map_err(db_err)?;
+        let rows = stmt
+            .query_map(params![concept_id], |row| {
+                Ok(ConceptRevision {
+                    concept_id: row.get(0)?,
+                    revision: row.get(1)?,
+                    definition: row.get(2)?,
+                    confidence: row.get(3)?,
+                    updated_at: parse_dt(&row.get::<_, String>(4)?),
+                })
+            })
+            .map_err(db_err)?;
+        collect_rows(rows)
+    }
+
+    fn rollback_concept(&self, concept_id: &str, revision: u32) -> IcmResult<()> {
+        let concept = self
+            .get_concept(concept_id)?
+            .ok_or_else(|| IcmError::NotFound(concept_id.to_string()))?;
+        if revision == concept.revision {
+            return Err(IcmError::InvalidInput(format!(
+                "{} is already at revision {revision}",
+                concept.name
+            )));
+        }
+        let definition: String = self
+            .conn
+            .query_row(
+                "SELECT definition FROM concept_revisions WHERE concept_id = ?1 AND revision = ?2",
+                params![concept_id, revision],
+                |row| row.get(0),
+            )
+            .optional()
+            .map_err(db_err)?
+            .ok_or_else(|| {
+                IcmError::NotFound(format!("revision {revision} of {}", concept.name))
+            })?;
+
+        // The `concept_revisions_au` trigger records the definition being
+        // replaced, so rolling back never loses a revision.
+        self.conn
+            .execute(
+                "UPDATE concepts SET definition = ?2, revision = revision + 1, updated_at = ?3
+                 WHERE id = ?1",
+                params![concept_id, definition, Utc::now().to_rfc3339()],
+            )
+            .map_err(db_err)?;
+        Ok(())
+    }
+
     // --- Graph ---
 
     fn add_link(&self, link: ConceptLink) -> IcmResult<String> {
@@ -2199,7 +3577,7 @@ impl MemoirStore for SqliteStore {
     fn get_links_from(&self, concept_id: &str) -> IcmResult<Vec<ConceptLink>> {
         let mut stmt = self
             .conn
-            .prepare(&format!(
+            .prepare_cached(&format!(
                 "SELECT {LINK_COLS} FROM concept_links WHERE source_id = ?1"
             ))
             .map_err(db_err)?;
@@ -2214,7 +3592,7 @@ impl MemoirStore for SqliteStore {
     fn get_links_to(&self, concept_id: &str) -> IcmResult<Vec<ConceptLink>> {
         let mut stmt = self
             .conn
-            .prepare(&format!(
+            .prepare_cached(&format!(
                 "SELECT {LINK_COLS} FROM concept_links WHERE target_id = ?1"
             ))
             .map_err(db_err)?;
@@ -2238,6 +3616,35 @@ impl MemoirStore for SqliteStore {
         Ok(())
     }
 
+    fn update_link_weight(&self, id: &str, weight: f32) -> IcmResult<()> {
+        if !weight.is_finite() {
+            return Err(IcmError::InvalidInput(format!(
+                "link weight must be finite, got {weight}"
+            )));
+        }
+        let changed = self
+            .conn
+            .execute(
+                "UPDATE concept_links SET weight = ?2 WHERE id = ?1",
+                params![id, weight.clamp(0.0, MAX_LINK_WEIGHT)],
+            )
+            .map_err(db_err)?;
+
+        if changed == 0 {
+            return Err(IcmError::NotFound(id.to_string()));
+        }
+        Ok(())
+    }
+
+    fn decay_links(&self, factor: f32) -> IcmResult<usize> {
+        self.conn
+            .execute(
+                "UPDATE concept_links SET weight = weight * ?1",
+                params![factor],
+            )
+            .map_err(db_err)
+    }
+
     fn get_neighbors(
         &self,
         concept_id: &str,
@@ -2262,7 +3669,7 @@ impl MemoirStore for SqliteStore {
             sql = base.replace("{filter}", "");
         };
 
-        let mut stmt = self.conn.prepare(&sql).map_err(db_err)?;
+        let mut stmt = self.conn.prepare_cached(&sql).map_err(db_err)?;
 
         let rows = if relation.is_some() {
             stmt.query_map(params![concept_id, p_relation], row_to_concept)
@@ -2294,29 +3701,29 @@ impl MemoirStore for SqliteStore {
             return Err(IcmError::NotFound(concept_id.to_string()));
         }
 
+        let mut seen_links: HashSet<String> = HashSet::new();
         while let Some((current_id, current_depth)) = queue.pop_front() {
             if current_depth >= depth {
                 continue;
             }
 
-            // Outgoing links
-            let outgoing = self.get_links_from(&current_id)?;
-            for link in outgoing {
-                if !visited.contains(&link.target_id) {
-                    if let Some(c) = self.get_concept(&link.target_id)? {
-                        visited.insert(c.id.clone());
-                        queue.push_back((c.id.clone(), current_depth + 1));
-                        concepts.push(c);
-                    }
+            // Outgoing and incoming links, strongest first, so heavier
+            // neighbours are queued (and listed) ahead of weaker ones.
+            let mut adjacent = self.get_links_from(&current_id)?;
+            adjacent.extend(self.get_links_to(&current_id)?);
+            adjacent.sort_by(|a, b| b.weight.total_cmp(&a.weight));
+            for link in adjacent {
+                // A link between two visited concepts shows up from both ends.
+                if !seen_links.insert(link.id.clone()) {
+                    continue;
                 }
-                links.push(link);
-            }
-
-            // Incoming links
-            let incoming = self.get_links_to(&current_id)?;
-            for link in incoming {
-                if !visited.contains(&link.source_id) {
-                    if let Some(c) = self.get_concept(&link.source_id)? {
+                let other = if link.source_id == current_id {
+                    &link.target_id
+                } else {
+                    &link.source_id
+                };
+                if !visited.contains(other) {
+                    if let Some(c) = self.get_concept(other)? {
                         visited.insert(c.id.clone());
                         queue.push_back((c.id.clone(), current_depth + 1));
                         concepts.push(c);
@@ -2366,7 +3773,7 @@ impl MemoirStore for SqliteStore {
         // Count labels via SQL — avoids loading all concepts into memory
         let mut label_stmt = self
             .conn
-            .prepare("SELECT labels FROM concepts WHERE memoir_id = ?1 AND labels != '[]'")
+            .prepare_cached("SELECT labels FROM concepts WHERE memoir_id = ?1 AND labels != '[]'")
             .map_err(db_err)?;
         let label_rows = label_stmt
             .query_map(params![memoir_id], |row| row.get::<_, String>(0))
@@ -2394,7 +3801,7 @@ impl MemoirStore for SqliteStore {
     fn get_links_for_memoir(&self, memoir_id: &str) -> IcmResult<Vec<ConceptLink>> {
         let mut stmt = self
             .conn
-            .prepare(&format!(
+            .prepare_cached(&format!(
                 "SELECT {LINK_COLS} FROM concept_links
                  WHERE source_id IN (SELECT id FROM concepts WHERE memoir_id = ?1)
                  LIMIT 5000"
@@ -2411,7 +3818,7 @@ impl MemoirStore for SqliteStore {
     fn batch_memoir_concept_counts(&self) -> IcmResult<HashMap<String, usize>> {
         let mut stmt = self
             .conn
-            .prepare("SELECT memoir_id, COUNT(*) FROM concepts GROUP BY memoir_id")
+            .prepare_cached("SELECT memoir_id, COUNT(*) FROM concepts GROUP BY memoir_id")
             .map_err(db_err)?;
 
         let rows = stmt
@@ -2450,6 +3857,18 @@ fn row_to_feedback(row: &rusqlite::Row) -> rusqlite::Result<Feedback> {
 const FEEDBACK_COLS: &str =
     "id, topic, context, predicted, corrected, reason, source, created_at, applied_count";
 
+fn row_to_recall_feedback(row: &rusqlite::Row) -> rusqlite::Result<RecallFeedback> {
+    let signal: String = row.get(2)?;
+    Ok(RecallFeedback {
+        id: row.get(0)?,
+        memory_id: row.get(1)?,
+        signal: signal.parse().unwrap_or(RecallSignal::Noise),
+        query: row.get(3)?,
+        source: row.get(4)?,
+        created_at: parse_dt(&row.get::<_, String>(5)?),
+    })
+}
+
 // ---------------------------------------------------------------------------
 // FeedbackStore impl
 // ---------------------------------------------------------------------------
@@ -2517,7 +3936,7 @@ impl FeedbackStore for SqliteStore {
                 )
             };
 
-        let mut stmt = self.conn.prepare(&sql).map_err(db_err)?;
+        let mut stmt = self.conn.prepare_cached(&sql).map_err(db_err)?;
         let refs: Vec<&dyn rusqlite::types::ToSql> =
             params_vec.iter().map(|p| p.as_ref()).collect();
         let rows = stmt
@@ -2546,7 +3965,7 @@ impl FeedbackStore for SqliteStore {
             )
         };
 
-        let mut stmt = self.conn.prepare(&sql).map_err(db_err)?;
+        let mut stmt = self.conn.prepare_cached(&sql).map_err(db_err)?;
         let refs: Vec<&dyn rusqlite::types::ToSql> =
             params_vec.iter().map(|p| p.as_ref()).collect();
         let rows = stmt
@@ -2590,7 +4009,9 @@ impl FeedbackStore for SqliteStore {
 
         let mut stmt = self
             .conn
-            .prepare("SELECT topic, COUNT(*) as cnt FROM feedback GROUP BY topic ORDER BY cnt DESC")
+            .prepare_cached(
+                "SELECT topic, COUNT(*) as cnt FROM feedback GROUP BY topic ORDER BY cnt DESC",
+            )
             .map_err(db_err)?;
 
         let by_topic: Vec<(String, usize)> = stmt
@@ -2601,7 +4022,7 @@ impl FeedbackStore for SqliteStore {
 
         let mut stmt = self
             .conn
-            .prepare(
+            .prepare_cached(
                 "SELECT id, applied_count FROM feedback WHERE applied_count > 0 ORDER BY applied_count DESC LIMIT 10",
             )
             .map_err(db_err)?;
@@ -2612,12 +4033,82 @@ impl FeedbackStore for SqliteStore {
             .filter_map(|r| r.ok())
             .collect();
 
+        let (useful, noise): (usize, usize) = self
+            .conn
+            .query_row(
+                "SELECT COUNT(*) FILTER (WHERE signal = 'useful'),
+                        COUNT(*) FILTER (WHERE signal = 'noise')
+                 FROM recall_feedback",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .map_err(db_err)?;
+
         Ok(FeedbackStats {
             total,
             by_topic,
             most_applied,
+            useful,
+            noise,
         })
     }
+
+    fn record_recall_feedback(&self, feedback: RecallFeedback) -> IcmResult<f32> {
+        if self.readonly {
+            return Err(IcmError::ReadOnly("record_recall_feedback".into()));
+        }
+        let tx = self.conn.unchecked_transaction().map_err(db_err)?;
+        let weight: f32 = tx
+            .query_row(
+                "SELECT weight FROM memories WHERE id = ?1",
+                params![feedback.memory_id],
+                |row| row.get(0),
+            )
+            .optional()
+            .map_err(db_err)?
+            .ok_or_else(|| IcmError::NotFound(feedback.memory_id.clone()))?;
+        let weight = feedback.signal.adjust_weight(weight);
+        tx.execute(
+            "UPDATE memories SET weight = ?2 WHERE id = ?1",
+            params![feedback.memory_id, weight],
+        )
+        .map_err(db_err)?;
+        tx.execute(
+            "INSERT INTO recall_feedback (id, memory_id, signal, query, source, created_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![
+                feedback.id,
+                feedback.memory_id,
+                feedback.signal.to_string(),
+                feedback.query,
+                feedback.source,
+                feedback.created_at.to_rfc3339(),
+            ],
+        )
+        .map_err(db_err)?;
+        tx.commit().map_err(db_err)?;
+        self.cache_invalidate(&feedback.memory_id);
+        Ok(weight)
+    }
+
+    fn list_recall_feedback(
+        &self,
+        memory_id: Option<&str>,
+        limit: usize,
+    ) -> IcmResult<Vec<RecallFeedback>> {
+        let mut stmt = self
+            .conn
+            .prepare_cached(
+                "SELECT id, memory_id, signal, query, source, created_at FROM recall_feedback
+                 WHERE ?1 IS NULL OR memory_id = ?1
+                 ORDER BY created_at DESC, id DESC LIMIT ?2",
+            )
+            .map_err(db_err)?;
+        let rows = stmt
+            .query_map(params![memory_id, limit as i64], row_to_recall_feedback)
+            .map_err(db_err)?;
+        collect_rows(rows)
+    }
 }
 
 // ---------------------------------------------------------------------------
@@ -2721,7 +4212,7 @@ impl FactsStore for SqliteStore {
         let rows = match key_prefix {
             Some(prefix) if !prefix.is_empty() => {
                 let mut stmt = conn
-                    .prepare(
+                    .prepare_cached(
                         "SELECT id, entity, key, value, source, created_at, superseded_at
                          FROM facts
                          WHERE entity = ?1 AND key LIKE ?2 AND superseded_at IS NULL
@@ -2736,7 +4227,7 @@ impl FactsStore for SqliteStore {
             }
             _ => {
                 let mut stmt = conn
-                    .prepare(
+                    .prepare_cached(
                         "SELECT id, entity, key, value, source, created_at, superseded_at
                          FROM facts
                          WHERE entity = ?1 AND superseded_at IS NULL
@@ -2755,7 +4246,7 @@ impl FactsStore for SqliteStore {
     fn history(&self, entity: &str, key: &str) -> IcmResult<Vec<Fact>> {
         let conn = &self.conn;
         let mut stmt = conn
-            .prepare(
+            .prepare_cached(
                 "SELECT id, entity, key, value, source, created_at, superseded_at
                  FROM facts
                  WHERE entity = ?1 AND key = ?2
@@ -2802,7 +4293,7 @@ impl FactsStore for SqliteStore {
             .map_err(db_err)?;
 
         let mut stmt = conn
-            .prepare(
+            .prepare_cached(
                 "SELECT entity, COUNT(*) as n FROM facts
                  WHERE superseded_at IS NULL
                  GROUP BY entity
@@ -2940,7 +4431,7 @@ impl TranscriptStore for SqliteStore {
         match project {
             Some(p) => {
                 let mut stmt = conn
-                    .prepare(
+                    .prepare_cached(
                         "SELECT id, agent, project, started_at, updated_at, metadata
                          FROM sessions WHERE project = ?1
                          ORDER BY updated_at DESC LIMIT ?2",
@@ -2955,7 +4446,7 @@ impl TranscriptStore for SqliteStore {
             }
             None => {
                 let mut stmt = conn
-                    .prepare(
+                    .prepare_cached(
                         "SELECT id, agent, project, started_at, updated_at, metadata
                          FROM sessions ORDER BY updated_at DESC LIMIT ?1",
                     )
@@ -3036,7 +4527,7 @@ impl TranscriptStore for SqliteStore {
     ) -> IcmResult<Vec<Message>> {
         let conn = &self.conn;
         let mut stmt = conn
-            .prepare(
+            .prepare_cached(
                 "SELECT id, session_id, role, content, tool_name, tokens, ts, metadata
                  FROM messages WHERE session_id = ?1
                  ORDER BY ts ASC LIMIT ?2 OFFSET ?3",
@@ -3092,7 +4583,7 @@ impl TranscriptStore for SqliteStore {
             (false, false) => "2",
         });
 
-        let mut stmt = conn.prepare(&sql).map_err(db_err)?;
+        let mut stmt = conn.prepare_cached(&sql).map_err(db_err)?;
         let limit_i = limit as i64;
         let rows: Vec<TranscriptHit> = match (session_id, project) {
             (Some(sid), Some(p)) => stmt
@@ -3224,6 +4715,11 @@ impl TranscriptStore for SqliteStore {
         // Explicit delete of messages (in case FK cascade isn't enabled on older DBs).
         conn.execute("DELETE FROM messages WHERE session_id = ?1", params![id])
             .map_err(db_err)?;
+        conn.execute(
+            "DELETE FROM session_context WHERE session_id = ?1",
+            params![id],
+        )
+        .map_err(db_err)?;
         conn.execute("DELETE FROM sessions WHERE id = ?1", params![id])
             .map_err(db_err)?;
         Ok(())
@@ -3247,7 +4743,7 @@ impl TranscriptStore for SqliteStore {
             .map_err(db_err)? as u64;
 
         let mut stmt_role = conn
-            .prepare("SELECT role, COUNT(*) FROM messages GROUP BY role ORDER BY 2 DESC")
+            .prepare_cached("SELECT role, COUNT(*) FROM messages GROUP BY role ORDER BY 2 DESC")
             .map_err(db_err)?;
         let by_role: Vec<(String, usize)> = stmt_role
             .query_map([], |r: &rusqlite::Row<'_>| {
@@ -3258,7 +4754,7 @@ impl TranscriptStore for SqliteStore {
             .map_err(db_err)?;
 
         let mut stmt_agent = conn
-            .prepare("SELECT agent, COUNT(*) FROM sessions GROUP BY agent ORDER BY 2 DESC")
+            .prepare_cached("SELECT agent, COUNT(*) FROM sessions GROUP BY agent ORDER BY 2 DESC")
             .map_err(db_err)?;
         let by_agent: Vec<(String, usize)> = stmt_agent
             .query_map([], |r: &rusqlite::Row<'_>| {
@@ -3269,7 +4765,7 @@ impl TranscriptStore for SqliteStore {
             .map_err(db_err)?;
 
         let mut stmt_top = conn
-            .prepare(
+            .prepare_cached(
                 "SELECT session_id, COUNT(*) FROM messages
                  GROUP BY session_id ORDER BY 2 DESC LIMIT 10",
             )
@@ -3381,14 +4877,26 @@ impl SqliteStore {
         threshold: usize,
         embedder: Option<&dyn Embedder>,
     ) -> IcmResult<bool> {
+        self.auto_consolidate_with_opts(topic, threshold, embedder, false)
+    }
+
+    /// Build the rollup [`auto_consolidate`] would write for `topic`
+    /// without touching the store. `None` while the topic is below
+    /// `threshold`. Used by the "propose" mode so a user can review the
+    /// result before anything is replaced.
+    pub fn consolidation_proposal(
+        &self,
+        topic: &str,
+        threshold: usize,
+    ) -> IcmResult<Option<Memory>> {
         let count = self.count_by_topic(topic)?;
         if count < threshold {
-            return Ok(false);
+            return Ok(None);
         }
 
         let mut memories = self.get_by_topic(topic)?;
         if memories.is_empty() {
-            return Ok(false);
+            return Ok(None);
         }
 
         // Sort by weight DESC (get_by_topic already does this, but be explicit)
@@ -3427,6 +4935,24 @@ impl SqliteStore {
             Some(format!("auto-consolidated from {original_count} memories"));
         consolidated.weight = 1.0;
 
+        Ok(Some(consolidated))
+    }
+
+    /// [`auto_consolidate_with_embedder`] with the option to keep the
+    /// originals: when `keep_originals` is set they are moved to
+    /// [`archived_topic`] instead of deleted, so a bad rollup can be
+    /// undone by hand.
+    pub fn auto_consolidate_with_opts(
+        &self,
+        topic: &str,
+        threshold: usize,
+        embedder: Option<&dyn Embedder>,
+        keep_originals: bool,
+    ) -> IcmResult<bool> {
+        let Some(mut consolidated) = self.consolidation_proposal(topic, threshold)? else {
+            return Ok(false);
+        };
+
         // Embed the consolidated content if an embedder is available so
         // hybrid recall picks it up immediately. Errors are logged and
         // swallowed — a partial consolidation (no embedding) is still
@@ -3443,12 +4969,38 @@ impl SqliteStore {
             }
         }
 
-        // Replace all memories in the topic with the consolidated one
-        self.consolidate_topic(topic, consolidated)?;
+        if keep_originals {
+            self.archive_and_replace_topic(topic, consolidated)?;
+        } else {
+            // Replace all memories in the topic with the consolidated one
+            self.consolidate_topic(topic, consolidated)?;
+        }
 
         Ok(true)
     }
 
+    /// Move every memory in `topic` to [`archived_topic`] and store
+    /// `consolidated` in its place, atomically.
+    fn archive_and_replace_topic(&self, topic: &str, consolidated: Memory) -> IcmResult<()> {
+        self.conn
+            .execute_batch("BEGIN IMMEDIATE;")
+            .map_err(db_err)?;
+        if let Err(e) = self.conn.execute(
+            "UPDATE memories SET topic = ?2 WHERE topic = ?1",
+            params![topic, archived_topic(topic)],
+        ) {
+            let _ = self.conn.execute_batch("ROLLBACK;");
+            return Err(IcmError::Database(e.to_string()));
+        }
+        if let Err(e) = self.store_inner(&consolidated) {
+            let _ = self.conn.execute_batch("ROLLBACK;");
+            return Err(e);
+        }
+        self.conn.execute_batch("COMMIT;").map_err(db_err)?;
+        self.cache_clear();
+        Ok(())
+    }
+
     /// Graph expansion: given a list of `(Memory, score)` results from a
     /// primary search, follow each memory's `related_ids` one hop and fetch
     /// the neighbors that are not already in the result set.
@@ -3514,18 +5066,67 @@ impl SqliteStore {
         Ok(combined)
     }
 
-    /// Fetch many memories by id in one round-trip, deduplicated by id.
-    ///
-    /// Cache-aware: cached entries are served from memory, misses are
-    /// batched into a single `IN (?,?,…)` query. Missing ids are
-    /// silently dropped — callers expecting a strict mapping should
-    /// diff their input vs the returned map.
-    pub fn get_many(&self, ids: &[&str]) -> IcmResult<HashMap<String, Memory>> {
-        if ids.is_empty() {
-            return Ok(HashMap::new());
+    /// Point every reference to the memories `old` — other memories'
+    /// `related_ids`, concepts' `source_memory_ids` — at `new` instead, or
+    /// drop it when `new` is `None`. Called by the delete paths; returns
+    /// the number of rows rewritten.
+    pub fn replace_memory_refs(&self, old: &[&str], new: Option<&str>) -> IcmResult<usize> {
+        if old.is_empty() {
+            return Ok(0);
         }
-
-        // Dedup so the IN clause gets at most one slot per id.
+        let old_set: HashSet<&str> = old.iter().copied().collect();
+        let old_json = serde_json::to_string(old)?;
+        let mut rewritten = 0;
+        for (table, column) in [
+            ("memories", "related_ids"),
+            ("concepts", "source_memory_ids"),
+        ] {
+            let rows: Vec<(String, String)> = {
+                let mut stmt = self
+                    .conn
+                    .prepare_cached(&format!(
+                        "SELECT DISTINCT t.id, t.{column} FROM {table} t,
+                         json_each(CASE WHEN json_valid(t.{column}) THEN t.{column} END) j
+                         WHERE j.value IN (SELECT value FROM json_each(?1))"
+                    ))
+                    .map_err(db_err)?;
+                let rows = stmt
+                    .query_map(params![old_json], |row| Ok((row.get(0)?, row.get(1)?)))
+                    .map_err(db_err)?;
+                collect_rows(rows)?
+            };
+            for (id, json) in rows {
+                let ids: Vec<String> = serde_json::from_str(&json).unwrap_or_default();
+                let Some(ids) = rewrite_memory_refs(&id, &ids, &old_set, new) else {
+                    continue;
+                };
+                self.conn
+                    .execute(
+                        &format!("UPDATE {table} SET {column} = ?2 WHERE id = ?1"),
+                        params![id, serde_json::to_string(&ids)?],
+                    )
+                    .map_err(db_err)?;
+                if table == "memories" {
+                    self.cache_invalidate(&id);
+                }
+                rewritten += 1;
+            }
+        }
+        Ok(rewritten)
+    }
+
+    /// Fetch many memories by id in one round-trip, deduplicated by id.
+    ///
+    /// Cache-aware: cached entries are served from memory, misses are
+    /// batched into a single `IN (?,?,…)` query. Missing ids are
+    /// silently dropped — callers expecting a strict mapping should
+    /// diff their input vs the returned map.
+    pub fn get_many(&self, ids: &[&str]) -> IcmResult<HashMap<String, Memory>> {
+        if ids.is_empty() {
+            return Ok(HashMap::new());
+        }
+
+        // Dedup so the IN clause gets at most one slot per id.
         let mut unique: Vec<&str> = Vec::with_capacity(ids.len());
         let mut seen: HashSet<&str> = HashSet::new();
         for id in ids {
@@ -3560,6 +5161,7 @@ impl SqliteStore {
             placeholders.join(", ")
         );
 
+        // Not cached: the IN list, and so the SQL, differs per call.
         let mut stmt = self.conn.prepare(&sql).map_err(db_err)?;
         let params_vec: Vec<&dyn rusqlite::types::ToSql> = misses
             .iter()
@@ -3594,8 +5196,10 @@ impl SqliteStore {
             let pattern = format!("{prefix}%");
             let mut stmt = self
                 .conn
-                .prepare(&format!(
-                    "SELECT {SELECT_COLS} FROM memories WHERE topic LIKE ?1 ORDER BY weight DESC LIMIT 500"
+                .prepare_cached(concat!(
+                    "SELECT ",
+                    select_cols!(),
+                    " FROM memories WHERE topic LIKE ?1 ORDER BY weight DESC LIMIT 500"
                 ))
                 .map_err(db_err)?;
 
@@ -3609,6 +5213,161 @@ impl SqliteStore {
         }
     }
 
+    /// Every memory of `kind`, heaviest first. Served by the partial
+    /// index on `kind`, since facts are the bulk of the table.
+    pub fn list_by_kind(&self, kind: MemoryKind) -> IcmResult<Vec<Memory>> {
+        let mut stmt = self
+            .conn
+            .prepare_cached(concat!(
+                "SELECT ",
+                select_cols!(),
+                " FROM memories WHERE kind = ?1 ORDER BY weight DESC, id"
+            ))
+            .map_err(db_err)?;
+
+        let rows = stmt
+            .query_map(params![kind.to_string()], row_to_memory)
+            .map_err(db_err)?;
+        collect_rows(rows)
+    }
+
+    /// The `limit` most recently created memories, newest first.
+    /// `topic` matches exactly, or as a prefix when it ends with `*`;
+    /// `source` is a source kind (`claude_code`, `conversation`,
+    /// `manual`); `since` drops memories created before it.
+    pub fn list_recent(
+        &self,
+        topic: Option<&str>,
+        source: Option<&str>,
+        since: Option<DateTime<Utc>>,
+        limit: usize,
+    ) -> IcmResult<Vec<Memory>> {
+        let mut sql = String::from(concat!(
+            "SELECT ",
+            select_cols!(),
+            " FROM memories WHERE 1=1"
+        ));
+        let mut params: Vec<Box<dyn rusqlite::ToSql>> = Vec::new();
+        if let Some(t) = topic {
+            match t.strip_suffix('*') {
+                Some(prefix) => {
+                    sql.push_str(" AND topic LIKE ?");
+                    params.push(Box::new(format!("{prefix}%")));
+                }
+                None => {
+                    sql.push_str(" AND topic = ?");
+                    params.push(Box::new(t.to_string()));
+                }
+            }
+        }
+        if let Some(s) = source {
+            sql.push_str(" AND source_type = ?");
+            params.push(Box::new(s.to_string()));
+        }
+        if let Some(t) = since {
+            sql.push_str(" AND created_at >= ?");
+            params.push(Box::new(t.to_rfc3339()));
+        }
+        sql.push_str(" ORDER BY created_at DESC, id DESC LIMIT ?");
+        params.push(Box::new(limit as i64));
+
+        let mut stmt = self.conn.prepare_cached(&sql).map_err(db_err)?;
+        let param_refs: Vec<&dyn rusqlite::ToSql> = params
+            .iter()
+            .map(|p| p.as_ref() as &dyn rusqlite::ToSql)
+            .collect();
+        let rows = stmt
+            .query_map(param_refs.as_slice(), row_to_memory)
+            .map_err(db_err)?;
+        collect_rows(rows)
+    }
+
+    /// Link two memories. Linking the same pair with the same relation
+    /// again is a no-op that returns the existing link's id.
+    pub fn add_memory_link(&self, link: MemoryLink) -> IcmResult<String> {
+        if self.readonly {
+            return Err(IcmError::ReadOnly("add_memory_link".into()));
+        }
+        if link.source_id == link.target_id {
+            return Err(IcmError::InvalidInput(format!(
+                "self-link rejected: source and target are the same memory ({})",
+                link.source_id
+            )));
+        }
+        for id in [&link.source_id, &link.target_id] {
+            if self.get(id)?.is_none() {
+                return Err(IcmError::NotFound(id.clone()));
+            }
+        }
+        self.conn
+            .execute(
+                "INSERT OR IGNORE INTO memory_links (id, source_id, target_id, relation, created_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5)",
+                params![
+                    link.id,
+                    link.source_id,
+                    link.target_id,
+                    link.relation.to_string(),
+                    link.created_at.to_rfc3339(),
+                ],
+            )
+            .map_err(db_err)?;
+        self.conn
+            .query_row(
+                "SELECT id FROM memory_links
+                 WHERE source_id = ?1 AND target_id = ?2 AND relation = ?3",
+                params![link.source_id, link.target_id, link.relation.to_string()],
+                |row| row.get(0),
+            )
+            .map_err(db_err)
+    }
+
+    /// Remove the links from `source_id` to `target_id`, only those of
+    /// `relation` when given. Returns how many were removed.
+    pub fn remove_memory_link(
+        &self,
+        source_id: &str,
+        target_id: &str,
+        relation: Option<Relation>,
+    ) -> IcmResult<usize> {
+        if self.readonly {
+            return Err(IcmError::ReadOnly("remove_memory_link".into()));
+        }
+        let relation = relation.map(|r| r.to_string());
+        self.conn
+            .execute(
+                "DELETE FROM memory_links
+                 WHERE source_id = ?1 AND target_id = ?2 AND (?3 IS NULL OR relation = ?3)",
+                params![source_id, target_id, relation],
+            )
+            .map_err(db_err)
+    }
+
+    /// Every link starting or ending at `memory_id`, oldest first.
+    pub fn memory_links(&self, memory_id: &str) -> IcmResult<Vec<MemoryLink>> {
+        let mut stmt = self
+            .conn
+            .prepare_cached(
+                "SELECT id, source_id, target_id, relation, created_at FROM memory_links
+                 WHERE source_id = ?1 OR target_id = ?1
+                 ORDER BY created_at, id",
+            )
+            .map_err(db_err)?;
+        let rows = stmt
+            .query_map(params![memory_id], |row| {
+                let relation: String = row.get(3)?;
+                Ok(MemoryLink {
+                    id: row.get(0)?,
+                    source_id: row.get(1)?,
+                    target_id: row.get(2)?,
+                    relation: relation.parse().unwrap_or(Relation::RelatedTo),
+                    created_at: parse_dt(&row.get::<_, String>(4)?),
+                })
+            })
+            .map_err(db_err)?;
+        collect_rows(rows)
+    }
+
     /// List topics, optionally filtered by a prefix.
     pub fn list_topics_with_prefix(&self, prefix: Option<&str>) -> IcmResult<Vec<(String, usize)>> {
         match prefix {
@@ -3616,7 +5375,7 @@ impl SqliteStore {
                 let pattern = format!("{p}%");
                 let mut stmt = self
                     .conn
-                    .prepare(
+                    .prepare_cached(
                         "SELECT topic, COUNT(*) FROM memories WHERE topic LIKE ?1 GROUP BY topic ORDER BY topic",
                     )
                     .map_err(db_err)?;
@@ -3813,6 +5572,8 @@ pub(crate) mod test_helpers {
 mod tests {
     use super::*;
     use icm_core::Importance;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
 
     fn test_store() -> SqliteStore {
         SqliteStore::in_memory().unwrap()
@@ -3868,6 +5629,182 @@ mod tests {
         assert_eq!(got.summary, "read-only fixture summary");
     }
 
+    #[test]
+    fn author_round_trips_through_store_and_update() {
+        let store = test_store();
+        let mut mem = Memory::new("team".into(), "shared fact".into(), Importance::Medium);
+        mem.author = Some("alice".into());
+        let id = store.store(mem).unwrap();
+        let mut got = store.get(&id).unwrap().unwrap();
+        assert_eq!(got.author.as_deref(), Some("alice"));
+        assert_eq!(
+            store.search_fts("shared", 5).unwrap()[0].author.as_deref(),
+            Some("alice")
+        );
+
+        got.author = Some("bob".into());
+        store.update(&got).unwrap();
+        let got = store.get(&id).unwrap().unwrap();
+        assert_eq!(got.author.as_deref(), Some("bob"));
+    }
+
+    #[test]
+    fn metadata_round_trips_and_merges_on_duplicate_store() {
+        let store = test_store();
+        let mut mem = Memory::new("ops".into(), "billing outage".into(), Importance::Medium);
+        mem.metadata.insert("ticket".into(), "PROJ-123".into());
+        mem.metadata.insert("service".into(), "billing".into());
+        let id = store.store(mem).unwrap();
+        let got = store.get(&id).unwrap().unwrap();
+        assert_eq!(got.metadata["ticket"], "PROJ-123");
+        assert_eq!(
+            store.search_fts("outage", 5).unwrap()[0].metadata,
+            got.metadata
+        );
+
+        // Re-storing the same fact adds new keys and overwrites repeated ones.
+        let mut again = Memory::new("ops".into(), "billing outage".into(), Importance::Medium);
+        again.metadata.insert("ticket".into(), "PROJ-124".into());
+        again.metadata.insert("pr".into(), "88".into());
+        assert_eq!(store.store(again).unwrap(), id);
+        let got = store.get(&id).unwrap().unwrap();
+        assert_eq!(got.metadata["ticket"], "PROJ-124");
+        assert_eq!(got.metadata["service"], "billing");
+        assert_eq!(got.metadata["pr"], "88");
+
+        let mut got = got;
+        got.metadata.clear();
+        store.update(&got).unwrap();
+        assert!(store.get(&id).unwrap().unwrap().metadata.is_empty());
+    }
+
+    #[test]
+    fn kind_round_trips_and_lists_by_kind() {
+        let store = test_store();
+        let fact = store.store(make_memory("icm", "uses sqlite")).unwrap();
+        let mut pref = make_memory("icm", "commits use the imperative");
+        pref.kind = MemoryKind::Preference;
+        let pref = store.store(pref).unwrap();
+        assert_eq!(
+            store.get(&pref).unwrap().unwrap().kind,
+            MemoryKind::Preference
+        );
+        let prefs = store.list_by_kind(MemoryKind::Preference).unwrap();
+        assert_eq!(prefs.len(), 1);
+        assert_eq!(prefs[0].id, pref);
+
+        // Re-storing a fact as a preference re-labels it; a plain re-store
+        // does not demote it again.
+        let mut again = make_memory("icm", "uses sqlite");
+        again.kind = MemoryKind::Preference;
+        assert_eq!(store.store(again).unwrap(), fact);
+        assert_eq!(
+            store.store(make_memory("icm", "uses sqlite")).unwrap(),
+            fact
+        );
+        assert_eq!(
+            store.get(&fact).unwrap().unwrap().kind,
+            MemoryKind::Preference
+        );
+        assert_eq!(store.list_by_kind(MemoryKind::Preference).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn sqlite_errors_map_to_typed_variants() {
+        let store = test_store();
+        store.create_memoir(make_memoir("dup")).unwrap();
+        let err = store.create_memoir(make_memoir("dup")).unwrap_err();
+        assert!(matches!(err, IcmError::Constraint(_)), "{err:?}");
+
+        let busy = rusqlite::Error::SqliteFailure(
+            rusqlite::ffi::Error::new(rusqlite::ffi::SQLITE_BUSY),
+            None,
+        );
+        assert_eq!(db_err(busy).code(), "busy");
+    }
+
+    #[test]
+    fn review_queue_keeps_memories_out_of_search() {
+        let store = test_store();
+        let mut mem = Memory::new("agent".into(), "queued claim".into(), Importance::High);
+        mem.keywords = vec!["k".into()];
+        let id = store.queue_for_review(&mem, "mcp").unwrap();
+        assert_eq!(store.review_queue_count().unwrap(), 1);
+        assert!(store.get(&id).unwrap().is_none());
+        assert!(store.search_fts("queued", 5).unwrap().is_empty());
+
+        let queue = store.list_review_queue(10).unwrap();
+        assert_eq!(queue[0].origin, "mcp");
+        assert_eq!(queue[0].memory.summary, "queued claim");
+        assert_eq!(queue[0].memory.keywords, ["k"]);
+
+        assert!(store.remove_from_review_queue(&id).unwrap());
+        assert!(!store.remove_from_review_queue(&id).unwrap());
+        assert_eq!(store.review_queue_count().unwrap(), 0);
+    }
+
+    #[test]
+    fn open_readonly_reads_db_without_author_column() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("legacy.db");
+        let seeded = seed_writable_db(&path);
+        {
+            let conn = Connection::open(&path).unwrap();
+            conn.execute_batch("ALTER TABLE memories DROP COLUMN author;")
+                .unwrap();
+        }
+
+        let ro = SqliteStore::open_readonly(&path).unwrap();
+        let got = ro.get(&seeded.id).unwrap().expect("memory must be present");
+        assert_eq!(got.author, None);
+        assert_eq!(ro.search_fts("fixture", 5).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn open_readonly_reads_db_without_attachment_columns() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("legacy.db");
+        let seeded = seed_writable_db(&path);
+        {
+            let conn = Connection::open(&path).unwrap();
+            conn.execute_batch(
+                "DROP INDEX idx_memories_attachment;
+                 DROP TRIGGER memories_attachment_ad;
+                 DROP TRIGGER memories_attachment_au;
+                 ALTER TABLE memories DROP COLUMN attachment;
+                 ALTER TABLE memories DROP COLUMN compressed;
+                 DROP TABLE attachments;",
+            )
+            .unwrap();
+        }
+
+        let ro = SqliteStore::open_readonly(&path).unwrap();
+        let got = ro.get(&seeded.id).unwrap().expect("memory must be present");
+        assert_eq!(got.attachment, None);
+        assert_eq!(ro.attachment("missing").unwrap(), None);
+        assert_eq!(ro.storage_stats().unwrap().attachment_count, 0);
+    }
+
+    #[test]
+    fn open_readonly_sees_live_writers_commits() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("live.db");
+        let rw = SqliteStore::new(&path).unwrap();
+        let first = rw
+            .store(make_memory("t", "written before the reader"))
+            .unwrap();
+
+        let ro = SqliteStore::open_readonly(&path).unwrap();
+        assert!(ro.get(&first).unwrap().is_some());
+        // The reader holds no write lock: the writer carries on, and the
+        // reader sees it without reopening.
+        let second = rw
+            .store(make_memory("t", "written after the reader"))
+            .unwrap();
+        assert!(ro.get(&second).unwrap().is_some());
+        assert_eq!(ro.count().unwrap(), 2);
+    }
+
     #[test]
     fn read_only_recall_path_skips_access_bookkeeping() {
         let dir = tempfile::tempdir().unwrap();
@@ -3876,7 +5813,7 @@ mod tests {
 
         let ro = SqliteStore::open_readonly(&path).unwrap();
         // The exact methods recall depends on:
-        ro.maybe_auto_decay().unwrap();
+        assert_eq!(ro.maybe_auto_decay(0.95).unwrap(), None);
         ro.update_access(&seeded.id).unwrap();
         ro.batch_update_access(&[&seeded.id]).unwrap();
 
@@ -3973,12 +5910,196 @@ mod tests {
         );
     }
 
+    #[test]
+    fn count_stored_embeddings_peeks_without_opening() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("count.db");
+        assert_eq!(SqliteStore::count_stored_embeddings(&path).unwrap(), 0);
+
+        let store = SqliteStore::with_dims(&path, 384).unwrap();
+        let mut m = Memory::new("t".into(), "with vector".into(), Importance::Medium);
+        m.embedding = Some(vec![0.1; 384]);
+        store.store(m).unwrap();
+        store
+            .store(Memory::new("t".into(), "plain".into(), Importance::Medium))
+            .unwrap();
+        drop(store);
+
+        assert_eq!(SqliteStore::count_stored_embeddings(&path).unwrap(), 1);
+    }
+
     /// Issue #267 regression: opening the store at the *stored* dim
     /// (the path the CLI now takes when no embedder is loaded) must
     /// leave `vec_memories` and the `embedding` blobs intact. Before
     /// the fix the CLI passed `DEFAULT_EMBEDDING_DIMS` instead and the
     /// `stored != requested` branch of `init_db_with_dims` would
     /// silently DROP `vec_memories` + NULL out every embedding.
+    #[test]
+    fn hnsw_index_serves_search_and_persists() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = dir.path().join("ann.db");
+        let index = crate::vector_index::index_path(&db);
+        let axis = |i: usize| {
+            let mut v = vec![0.0_f32; 64];
+            v[i] = 1.0;
+            v
+        };
+
+        let (near, far) = {
+            let store = SqliteStore::with_dims(&db, 64)
+                .unwrap()
+                .with_vector_index(IndexKind::Hnsw, &index)
+                .unwrap();
+            assert_eq!(store.vector_index_kind(), IndexKind::Hnsw);
+            let mut near = make_memory("ann", "near");
+            near.embedding = Some(axis(0));
+            let mut far = make_memory("ann", "far");
+            far.embedding = Some(axis(1));
+            let ids = (store.store(near).unwrap(), store.store(far).unwrap());
+            let hits = store.search_by_embedding(&axis(0), 1).unwrap();
+            assert_eq!(hits[0].0.id, ids.0);
+            ids
+        };
+        assert!(index.exists(), "dirty index is written on drop");
+
+        // Reopen: the persisted graph is loaded and stays in sync with
+        // deletes.
+        let store = SqliteStore::with_dims(&db, 64)
+            .unwrap()
+            .with_vector_index(IndexKind::Hnsw, &index)
+            .unwrap();
+        store.delete(&near).unwrap();
+        let hits = store.search_by_embedding(&axis(0), 5).unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].0.id, far);
+        assert_eq!(store.rebuild_vector_index().unwrap(), 1);
+    }
+
+    #[test]
+    fn interrupt_check_stops_statements() {
+        let store = SqliteStore::in_memory().unwrap();
+        store.store(make_memory("t", "kept")).unwrap();
+        let stop = Arc::new(AtomicBool::new(false));
+        let flag = Arc::clone(&stop);
+        store.set_interrupt_check(Some(Box::new(move || flag.load(Ordering::Relaxed))));
+        assert_eq!(store.list_all().unwrap().len(), 1);
+
+        stop.store(true, Ordering::Relaxed);
+        let err = store
+            .conn
+            .query_row(
+                "WITH RECURSIVE n(i) AS (SELECT 1 UNION ALL SELECT i + 1 FROM n)
+                 SELECT COUNT(*) FROM n",
+                [],
+                |r| r.get::<_, i64>(0),
+            )
+            .unwrap_err();
+        assert!(err.to_string().contains("interrupt"), "{err}");
+
+        store.set_interrupt_check(None);
+        assert_eq!(store.list_all().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn hnsw_index_sees_writes_from_other_handles() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = dir.path().join("shared.db");
+        let index = crate::vector_index::index_path(&db);
+        let axis = |i: usize| {
+            let mut v = vec![0.0_f32; 64];
+            v[i] = 1.0;
+            v
+        };
+        let open = || {
+            SqliteStore::with_dims(&db, 64)
+                .unwrap()
+                .with_vector_index(IndexKind::Hnsw, &index)
+                .unwrap()
+        };
+
+        // Two processes (say `icm serve` and a hook) each hold a copy of
+        // the graph and both insert.
+        let a = open();
+        let b = open();
+        let mut first = make_memory("ann", "from a");
+        first.embedding = Some(axis(0));
+        let first = a.store(first).unwrap();
+        let mut second = make_memory("ann", "from b");
+        second.embedding = Some(axis(1));
+        let second = b.store(second).unwrap();
+
+        for store in [&a, &b] {
+            let hits = store.search_by_embedding(&axis(0), 1).unwrap();
+            assert_eq!(hits[0].0.id, first);
+            let hits = store.search_by_embedding(&axis(1), 1).unwrap();
+            assert_eq!(hits[0].0.id, second);
+        }
+
+        // A handle without the index (consolidate, forget, import, ...)
+        // still reaches the others.
+        let plain = SqliteStore::with_dims(&db, 64).unwrap();
+        let mut third = make_memory("ann", "from plain");
+        third.embedding = Some(axis(2));
+        let third = plain.store(third).unwrap();
+        plain.delete(&first).unwrap();
+        let hits = a.search_by_embedding(&axis(2), 1).unwrap();
+        assert_eq!(hits[0].0.id, third);
+        let hits = b.search_by_embedding(&axis(0), 5).unwrap();
+        assert!(hits.iter().all(|(m, _)| m.id != first), "{hits:?}");
+
+        // Whichever handle saves last, a fresh open sees everything.
+        drop(a);
+        drop(b);
+        let reopened = open();
+        assert_eq!(
+            reopened.search_by_embedding(&axis(1), 1).unwrap()[0].0.id,
+            second
+        );
+        assert_eq!(
+            reopened.search_by_embedding(&axis(2), 1).unwrap()[0].0.id,
+            third
+        );
+    }
+
+    #[test]
+    fn storage_stats_breaks_down_tables() {
+        let store = SqliteStore::in_memory_with_dims(64).unwrap();
+        let mut mem = make_memory("t", "storage accounting");
+        mem.embedding = Some(vec![0.5; 64]);
+        store.store(mem).unwrap();
+
+        let stats = store.storage_stats().unwrap();
+        assert!(stats.db_bytes > 0);
+        assert_eq!(stats.wal_bytes, 0);
+        assert_eq!(stats.embedding_bytes, 64 * 4);
+        assert!(stats.text_bytes >= "storage accounting".len() as u64);
+        let names: Vec<&str> = stats.objects.iter().map(|(n, _)| n.as_str()).collect();
+        assert!(names.contains(&"memories"), "{names:?}");
+        assert!(
+            names.iter().any(|n| n.starts_with("memories_fts")),
+            "{names:?}"
+        );
+        assert!(
+            names.iter().any(|n| n.starts_with("vec_memories")),
+            "{names:?}"
+        );
+        let total: u64 = stats.objects.iter().map(|(_, b)| b).sum();
+        assert!(total <= stats.db_bytes);
+    }
+
+    #[test]
+    fn rebuild_resyncs_sqlite_vec_table() {
+        let store = SqliteStore::in_memory_with_dims(64).unwrap();
+        let mut mem = make_memory("t", "vec");
+        mem.embedding = Some(vec![0.5; 64]);
+        store.store(mem).unwrap();
+        store.store(make_memory("t", "no vec")).unwrap();
+        store.conn.execute("DELETE FROM vec_memories", []).unwrap();
+        assert!(store.search_by_embedding(&[0.5; 64], 5).unwrap().is_empty());
+        assert_eq!(store.rebuild_vector_index().unwrap(), 1);
+        assert_eq!(store.search_by_embedding(&[0.5; 64], 5).unwrap().len(), 1);
+    }
+
     #[test]
     fn opening_at_stored_dims_preserves_vectors() {
         let dir = tempfile::tempdir().unwrap();
@@ -4043,6 +6164,29 @@ mod tests {
         assert_eq!(retrieved.topic, "test");
     }
 
+    #[test]
+    fn test_iter_memories_pages_in_id_order() {
+        let store = test_store();
+        let n = crate::common::MEMORY_PAGE_SIZE * 2 + 3;
+        for i in 0..n {
+            store
+                .store(make_memory("t", &format!("memory {i}")))
+                .unwrap();
+        }
+
+        let mut ids = Vec::new();
+        for m in store.iter_memories() {
+            let m = m.unwrap();
+            // Writing mid-iteration is fine: no statement is held open.
+            store.update_access(&m.id).unwrap();
+            ids.push(m.id);
+        }
+        assert_eq!(ids.len(), n);
+        assert!(ids.windows(2).all(|w| w[0] < w[1]));
+
+        assert_eq!(test_store().iter_memories().count(), 0);
+    }
+
     #[test]
     fn test_get_not_found() {
         let store = test_store();
@@ -4082,6 +6226,65 @@ mod tests {
         assert!(matches!(result, Err(IcmError::NotFound(_))));
     }
 
+    #[test]
+    fn test_delete_drops_references() {
+        let store = test_store();
+        let gone = store.store(make_memory("t", "gone")).unwrap();
+        let kept = store.store(make_memory("t", "kept")).unwrap();
+        let mut linker = make_memory("t", "linker");
+        linker.related_ids = vec![gone.clone(), kept.clone()];
+        let linker = store.store(linker).unwrap();
+        // Cached before the delete: the rewrite must invalidate it.
+        store.get(&linker).unwrap();
+        let memoir_id = store.create_memoir(make_memoir("m")).unwrap();
+        let mut concept = make_concept(&memoir_id, "c", "def");
+        concept.source_memory_ids = vec![gone.clone(), kept.clone()];
+        let concept = store.add_concept(concept).unwrap();
+
+        store.delete(&gone).unwrap();
+
+        let linker = store.get(&linker).unwrap().unwrap();
+        assert_eq!(linker.related_ids, std::slice::from_ref(&kept));
+        let concept = store.get_concept(&concept).unwrap().unwrap();
+        assert_eq!(concept.source_memory_ids, std::slice::from_ref(&kept));
+
+        let mut low = make_memory("t", "low weight");
+        low.importance = Importance::Low;
+        low.weight = 0.01;
+        let low = store.store(low).unwrap();
+        let mut linker = store.get(&linker.id).unwrap().unwrap();
+        linker.related_ids.push(low);
+        store.update(&linker).unwrap();
+        assert_eq!(store.prune(0.1).unwrap(), 1);
+        let linker = store.get(&linker.id).unwrap().unwrap();
+        assert_eq!(linker.related_ids, [kept]);
+    }
+
+    #[test]
+    fn test_consolidate_repoints_references() {
+        let store = test_store();
+        let a = store.store(make_memory("topic-a", "entry 1")).unwrap();
+        let b = store.store(make_memory("topic-a", "entry 2")).unwrap();
+        let mut linker = make_memory("topic-b", "linker");
+        linker.related_ids = vec![a.clone(), b.clone()];
+        let linker = store.store(linker).unwrap();
+        let memoir_id = store.create_memoir(make_memoir("m")).unwrap();
+        let mut concept = make_concept(&memoir_id, "c", "def");
+        concept.source_memory_ids = vec![a.clone()];
+        let concept = store.add_concept(concept).unwrap();
+
+        let mut consolidated = make_memory("topic-a", "rollup");
+        consolidated.related_ids = vec![a, b];
+        let rollup = consolidated.id.clone();
+        store.consolidate_topic("topic-a", consolidated).unwrap();
+
+        let linker = store.get(&linker).unwrap().unwrap();
+        assert_eq!(linker.related_ids, std::slice::from_ref(&rollup));
+        let concept = store.get_concept(&concept).unwrap().unwrap();
+        assert_eq!(concept.source_memory_ids, std::slice::from_ref(&rollup));
+        assert!(store.get(&rollup).unwrap().unwrap().related_ids.is_empty());
+    }
+
     #[test]
     fn test_search_fts() {
         let store = test_store();
@@ -4100,6 +6303,39 @@ mod tests {
         assert_eq!(results[0].topic, "rust");
     }
 
+    #[test]
+    fn test_search_fts_ranks_by_relevance_and_weight() {
+        let mut store = test_store();
+        let mut exact = make_memory("db", "connection pool timeout");
+        exact.weight = 0.3;
+        let exact_id = store.store(exact).unwrap();
+        let mut heavy = make_memory(
+            "notes",
+            "Long unrelated notes about deploys, release trains, on-call rotations \
+             and dashboards that mention the pool once",
+        );
+        heavy.weight = 1.0;
+        let heavy_id = store.store(heavy).unwrap();
+
+        let ids = |store: &SqliteStore| -> Vec<String> {
+            store
+                .search_fts("pool", 10)
+                .unwrap()
+                .into_iter()
+                .map(|m| m.id)
+                .collect()
+        };
+        // Default blend: the focused match outranks the heavier passing
+        // mention.
+        assert_eq!(ids(&store), [exact_id.clone(), heavy_id.clone()]);
+
+        store.set_fts_relevance(0.0);
+        assert_eq!(ids(&store), [heavy_id.clone(), exact_id.clone()]);
+
+        store.set_fts_relevance(1.0);
+        assert_eq!(ids(&store), [exact_id, heavy_id]);
+    }
+
     #[test]
     fn test_search_by_keywords() {
         let store = test_store();
@@ -4111,6 +6347,49 @@ mod tests {
         assert_eq!(results.len(), 1);
     }
 
+    #[test]
+    fn test_search_by_keywords_modes_and_syntax() {
+        let mut store = test_store();
+        let full = store
+            .store(make_memory("db", "connection pool timeout on sqlite"))
+            .unwrap();
+        let pool_only = store
+            .store(make_memory("swim", "the pool opens at nine"))
+            .unwrap();
+        let ids = |store: &SqliteStore, q: &str| -> Vec<String> {
+            let tokens: Vec<&str> = q.split_whitespace().collect();
+            store
+                .search_by_keywords(&tokens, 10)
+                .unwrap()
+                .into_iter()
+                .map(|m| m.id)
+                .collect()
+        };
+
+        // Any mode: both match, the one hitting more terms first; "the"
+        // is a stop word and matches nothing on its own.
+        assert_eq!(
+            ids(&store, "the connection pool timeout"),
+            [full.clone(), pool_only.clone()]
+        );
+        assert!(ids(&store, "the").is_empty());
+        assert_eq!(ids(&store, "pool -sqlite"), [pool_only.as_str()]);
+        assert_eq!(ids(&store, "+sqlite pool"), [full.as_str()]);
+
+        store.set_keyword_options(KeywordOptions {
+            min_match: 2,
+            ..Default::default()
+        });
+        assert_eq!(ids(&store, "connection pool timeout"), [full.as_str()]);
+
+        store.set_keyword_options(KeywordOptions {
+            mode: icm_core::KeywordMode::All,
+            ..Default::default()
+        });
+        assert_eq!(ids(&store, "connection pool timeout"), [full]);
+        assert!(ids(&store, "pool nine timeout").is_empty());
+    }
+
     #[test]
     fn test_list_topics() {
         let store = test_store();
@@ -4124,6 +6403,32 @@ mod tests {
         assert!(topics.contains(&("beta".into(), 1)));
     }
 
+    #[test]
+    fn test_get_all_pages_across_topics() {
+        let store = test_store();
+        for (i, topic) in ["alpha", "beta", "alpha", "gamma", "beta"]
+            .iter()
+            .enumerate()
+        {
+            let mut m = make_memory(topic, &format!("memory {i}"));
+            m.weight = if i % 2 == 0 { 0.5 } else { 0.9 };
+            store.store(m).unwrap();
+        }
+
+        let all = store.get_all(10, 0).unwrap();
+        assert_eq!(all.len(), 5);
+        assert!(all.windows(2).all(
+            |w| w[0].weight > w[1].weight || (w[0].weight == w[1].weight && w[0].id < w[1].id)
+        ));
+
+        let paged: Vec<Memory> = [0, 2, 4]
+            .iter()
+            .flat_map(|&offset| store.get_all(2, offset).unwrap())
+            .collect();
+        let ids = |v: &[Memory]| v.iter().map(|m| m.id.clone()).collect::<Vec<_>>();
+        assert_eq!(ids(&paged), ids(&all));
+    }
+
     #[test]
     fn test_restore_upgrades_importance_on_dedup() {
         // Audit #185 H2: re-storing the same content with a higher
@@ -4224,36 +6529,318 @@ mod tests {
         );
     }
 
+    fn attachment_rows(store: &SqliteStore) -> i64 {
+        store
+            .conn
+            .query_row("SELECT COUNT(*) FROM attachments", [], |r| r.get(0))
+            .unwrap()
+    }
+
+    #[test]
+    fn large_raw_excerpt_moves_to_attachment_store() {
+        let store = test_store();
+        let big = format!("zebracorn {}", "x".repeat(ATTACHMENT_MIN_BYTES));
+        let mut mem = make_memory("logs", "build log of the failing release job");
+        mem.raw_excerpt = Some(big.clone());
+        let id = store.store(mem).unwrap();
+
+        let got = store.get(&id).unwrap().unwrap();
+        assert_eq!(got.raw_excerpt, None);
+        let hash = got.attachment.expect("offloaded");
+        assert_eq!(hash.len(), 64);
+        assert_eq!(store.attachment(&hash).unwrap().as_deref(), Some(&*big));
+        assert!(store.search_fts("zebracorn", 10).unwrap().is_empty());
+
+        let stats = store.storage_stats().unwrap();
+        assert_eq!(stats.attachment_count, 1);
+        assert_eq!(stats.attachment_bytes, big.len() as u64);
+
+        let mut small = make_memory("logs", "short excerpt stays inline");
+        small.raw_excerpt = Some("inline".into());
+        let small = store.get(&store.store(small).unwrap()).unwrap().unwrap();
+        assert_eq!(small.raw_excerpt.as_deref(), Some("inline"));
+        assert_eq!(small.attachment, None);
+    }
+
+    #[test]
+    fn attachments_are_shared_and_dropped_with_last_reference() {
+        let store = test_store();
+        let big = "y".repeat(ATTACHMENT_MIN_BYTES + 1);
+        let mut a = make_memory("logs", "first memory quoting the same log");
+        a.raw_excerpt = Some(big.clone());
+        let mut b = make_memory("logs", "second memory quoting the same log");
+        b.raw_excerpt = Some(big);
+        let a = store.store(a).unwrap();
+        let b = store.store(b).unwrap();
+        assert_eq!(attachment_rows(&store), 1);
+
+        store.delete(&a).unwrap();
+        assert_eq!(attachment_rows(&store), 1);
+        store.delete(&b).unwrap();
+        assert_eq!(attachment_rows(&store), 0);
+    }
+
+    #[test]
+    fn update_keeps_unloaded_attachment_and_drops_replaced_one() {
+        let store = test_store();
+        let mut mem = make_memory("logs", "memory with an offloaded excerpt");
+        mem.raw_excerpt = Some("z".repeat(ATTACHMENT_MIN_BYTES));
+        let id = store.store(mem).unwrap();
+
+        // Read back (excerpt not loaded) and rewrite: the reference stays.
+        let mut got = store.get(&id).unwrap().unwrap();
+        got.summary = "memory with an offloaded excerpt, edited".into();
+        store.update(&got).unwrap();
+        let got = store.get(&id).unwrap().unwrap();
+        assert!(got.attachment.is_some());
+        assert_eq!(attachment_rows(&store), 1);
+
+        // A short excerpt replaces it inline and the old blob is dropped.
+        let mut got = got;
+        got.raw_excerpt = Some("short now".into());
+        store.update(&got).unwrap();
+        let got = store.get(&id).unwrap().unwrap();
+        assert_eq!(got.raw_excerpt.as_deref(), Some("short now"));
+        assert_eq!(got.attachment, None);
+        assert_eq!(attachment_rows(&store), 0);
+    }
+
+    #[test]
+    fn long_raw_excerpts_are_compressed_at_rest() {
+        let store = test_store();
+        let raw = "thread main panicked at src/lib.rs:42\n".repeat(60);
+        assert!(raw.len() >= compress::COMPRESS_MIN_BYTES && raw.len() < ATTACHMENT_MIN_BYTES);
+        let mut mem = make_memory("logs", "panic trace from the nightly run");
+        mem.raw_excerpt = Some(raw.clone());
+        let id = store.store(mem).unwrap();
+
+        let (kind, flag): (String, bool) = store
+            .conn
+            .query_row(
+                "SELECT typeof(raw_excerpt), compressed FROM memories WHERE id = ?1",
+                params![id],
+                |r| Ok((r.get(0)?, r.get(1)?)),
+            )
+            .unwrap();
+        assert_eq!((kind.as_str(), flag), ("blob", true));
+        let got = store.get(&id).unwrap().unwrap();
+        assert_eq!(got.raw_excerpt.as_deref(), Some(&*raw));
+        assert!(store.search_fts("nightly", 10).unwrap().len() == 1);
+
+        // Re-storing the same fact keeps the excerpt readable.
+        store
+            .store(make_memory("logs", "panic trace from the nightly run"))
+            .unwrap();
+        assert_eq!(
+            store.get(&id).unwrap().unwrap().raw_excerpt.as_deref(),
+            Some(&*raw)
+        );
+
+        // Attachments are compressed too, and both count toward savings.
+        let big = "x".repeat(ATTACHMENT_MIN_BYTES * 2);
+        let mut att = make_memory("logs", "memory with a large attachment");
+        att.raw_excerpt = Some(big.clone());
+        let att = store.get(&store.store(att).unwrap()).unwrap().unwrap();
+        let hash = att.attachment.unwrap();
+        assert_eq!(store.attachment(&hash).unwrap().as_deref(), Some(&*big));
+
+        let stats = store.storage_stats().unwrap();
+        assert_eq!(stats.uncompressed_bytes, (raw.len() + big.len()) as u64);
+        assert!(stats.compressed_bytes < stats.uncompressed_bytes / 4);
+
+        // A short update stores plain text and clears the flag.
+        let mut got = got;
+        got.raw_excerpt = Some("short".into());
+        store.update(&got).unwrap();
+        assert_eq!(
+            store.get(&id).unwrap().unwrap().raw_excerpt.as_deref(),
+            Some("short")
+        );
+    }
+
+    #[test]
+    fn restore_keeps_existing_attachment_when_new_is_none() {
+        let store = test_store();
+        let mut first = make_memory("topic", "long enough summary content for storage");
+        first.raw_excerpt = Some("w".repeat(ATTACHMENT_MIN_BYTES));
+        let id = store.store(first).unwrap();
+        store
+            .store(make_memory(
+                "topic",
+                "long enough summary content for storage",
+            ))
+            .unwrap();
+        assert!(store.get(&id).unwrap().unwrap().attachment.is_some());
+        assert_eq!(attachment_rows(&store), 1);
+    }
+
+    #[test]
+    fn test_restore_unchanged_metadata_is_noop() {
+        let store = test_store();
+        let mut first = make_memory("topic", "long enough summary content for storage");
+        first.importance = Importance::High;
+        first.keywords = vec!["alpha".into()];
+        let id1 = store.store(first.clone()).unwrap();
+        let original = store.get(&id1).unwrap().unwrap();
+
+        std::thread::sleep(std::time::Duration::from_millis(10));
+        store.store(first).unwrap();
+        let after = store.get(&id1).unwrap().unwrap();
+
+        assert_eq!(
+            original.updated_at, after.updated_at,
+            "no-op re-store must not bump updated_at"
+        );
+    }
+
+    #[test]
+    fn test_apply_decay() {
+        let store = test_store();
+        store.store(make_memory("test", "decayable")).unwrap();
+
+        let mut critical = make_memory("test", "critical memory");
+        critical.importance = Importance::Critical;
+        store.store(critical).unwrap();
+
+        let affected = store.apply_decay(0.9).unwrap();
+        assert_eq!(affected, 1); // Only the non-critical one
+    }
+
+    #[test]
+    fn test_store_idempotent_creates_once() {
+        let store = test_store();
+        let mut memory = make_memory("deploy", "uses blue/green");
+        memory.id = icm_core::idempotent_id("retry-key");
+
+        let first = store.store_idempotent(memory.clone()).unwrap();
+        assert_eq!(first.id, memory.id);
+        assert!(first.created);
+
+        // A retry with the same id writes nothing, even with new content.
+        let mut retry = memory.clone();
+        retry.summary = "uses canary".into();
+        let second = store.store_idempotent(retry).unwrap();
+        assert_eq!(
+            second,
+            StoreOutcome {
+                id: memory.id.clone(),
+                created: false
+            }
+        );
+        assert_eq!(store.count().unwrap(), 1);
+        assert_eq!(
+            store.get(&memory.id).unwrap().unwrap().summary,
+            "uses blue/green"
+        );
+
+        // Same topic and summary under another id matches the existing row.
+        let third = store
+            .store_idempotent(make_memory("deploy", "uses blue/green"))
+            .unwrap();
+        assert_eq!(
+            third,
+            StoreOutcome {
+                id: memory.id,
+                created: false
+            }
+        );
+    }
+
+    #[test]
+    fn test_store_keyed_replaces_value_and_bumps_revision() {
+        let store = test_store();
+        let first = store
+            .store_keyed(
+                "staging-url",
+                make_memory("infra", "staging is at s1.example"),
+            )
+            .unwrap();
+        assert_eq!(first.revision, 1);
+        let created_at = store.get(&first.id).unwrap().unwrap().created_at;
+
+        let mut next = make_memory("infra", "staging is at s2.example");
+        next.keywords = vec!["staging".into()];
+        let second = store.store_keyed("staging-url", next).unwrap();
+        assert_eq!(
+            second,
+            KeyedOutcome {
+                id: first.id.clone(),
+                revision: 2
+            }
+        );
+
+        let memory = store.get(&first.id).unwrap().unwrap();
+        assert_eq!(memory.summary, "staging is at s2.example");
+        assert_eq!(memory.keywords, vec!["staging".to_string()]);
+        assert_eq!(memory.created_at, created_at);
+        assert!(memory.updated_at > created_at);
+        assert_eq!(store.count().unwrap(), 1);
+
+        // Deleting the memory frees the key; the next value starts over.
+        store.delete(&first.id).unwrap();
+        let third = store
+            .store_keyed(
+                "staging-url",
+                make_memory("infra", "staging is at s3.example"),
+            )
+            .unwrap();
+        assert_eq!(third.revision, 1);
+        assert_ne!(third.id, first.id);
+    }
+
     #[test]
-    fn test_restore_unchanged_metadata_is_noop() {
+    fn test_maybe_auto_decay_runs_once_a_day_with_given_factor() {
         let store = test_store();
-        let mut first = make_memory("topic", "long enough summary content for storage");
-        first.importance = Importance::High;
-        first.keywords = vec!["alpha".into()];
-        let id1 = store.store(first.clone()).unwrap();
-        let original = store.get(&id1).unwrap().unwrap();
+        let id = store.store(make_memory("test", "decayable")).unwrap();
 
-        std::thread::sleep(std::time::Duration::from_millis(10));
-        store.store(first).unwrap();
-        let after = store.get(&id1).unwrap().unwrap();
+        assert_eq!(store.maybe_auto_decay(0.5).unwrap(), Some(1));
+        let weight = store.get(&id).unwrap().unwrap().weight;
+        assert!((weight - 0.5).abs() < 1e-6, "weight {weight}");
 
-        assert_eq!(
-            original.updated_at, after.updated_at,
-            "no-op re-store must not bump updated_at"
-        );
+        // The slot is claimed for 24 hours: a second call is a no-op.
+        assert_eq!(store.maybe_auto_decay(0.5).unwrap(), None);
+        assert_eq!(store.get(&id).unwrap().unwrap().weight, weight);
     }
 
     #[test]
-    fn test_apply_decay() {
+    fn test_decay_once_matches_apply_decay() {
+        // `icm decay --simulate` replays decay with `decay_once`; it must
+        // land on the weights a real pass writes.
         let store = test_store();
-        store.store(make_memory("test", "decayable")).unwrap();
-
-        let mut critical = make_memory("test", "critical memory");
-        critical.importance = Importance::Critical;
-        store.store(critical).unwrap();
+        let mut ids = Vec::new();
+        for (importance, accesses) in [
+            (Importance::Low, 0),
+            (Importance::Medium, 3),
+            (Importance::High, 9),
+            (Importance::Critical, 0),
+        ] {
+            let mut mem = make_memory("sim", &format!("{importance} {accesses}"));
+            mem.importance = importance;
+            let id = store.store(mem).unwrap();
+            store
+                .conn
+                .execute(
+                    "UPDATE memories SET access_count = ?1 WHERE id = ?2",
+                    params![accesses, id],
+                )
+                .unwrap();
+            ids.push(id);
+        }
 
-        let affected = store.apply_decay(0.9).unwrap();
-        assert_eq!(affected, 1); // Only the non-critical one
+        let before: Vec<Memory> = ids
+            .iter()
+            .map(|id| store.get(id).unwrap().unwrap())
+            .collect();
+        store.apply_decay(0.8).unwrap();
+        for m in before {
+            let expected = icm_core::decay_once(m.weight, m.importance, m.access_count, 0.8);
+            let actual = store.get(&m.id).unwrap().unwrap().weight;
+            assert!(
+                (actual - expected).abs() < 1e-5,
+                "{}: {actual} vs {expected}",
+                m.summary
+            );
+        }
     }
 
     #[test]
@@ -4646,6 +7233,135 @@ mod tests {
             .unwrap();
     }
 
+    #[test]
+    fn test_concept_revisions_and_rollback() {
+        let store = test_store();
+        let m_id = store.create_memoir(make_memoir("proj")).unwrap();
+        let id = store
+            .add_concept(make_concept(&m_id, "auth", "Sessions in cookies"))
+            .unwrap();
+        assert!(store.concept_revisions(&id).unwrap().is_empty());
+
+        store.refine_concept(&id, "JWT in headers", &[]).unwrap();
+        store.refine_concept(&id, "JWT with refresh", &[]).unwrap();
+        // A label-only update is not a new revision.
+        let mut c = store.get_concept(&id).unwrap().unwrap();
+        c.labels.push(Label::new("domain", "security"));
+        store.update_concept(&c).unwrap();
+
+        let history = store.concept_revisions(&id).unwrap();
+        let defs: Vec<(u32, &str)> = history
+            .iter()
+            .map(|r| (r.revision, r.definition.as_str()))
+            .collect();
+        assert_eq!(defs, [(1, "Sessions in cookies"), (2, "JWT in headers")]);
+
+        store.rollback_concept(&id, 1).unwrap();
+        let c = store.get_concept(&id).unwrap().unwrap();
+        assert_eq!(
+            (c.revision, c.definition.as_str()),
+            (4, "Sessions in cookies")
+        );
+        assert_eq!(c.labels.len(), 1);
+        let history = store.concept_revisions(&id).unwrap();
+        assert_eq!(history.last().unwrap().definition, "JWT with refresh");
+        assert_eq!(history.len(), 3);
+
+        assert!(matches!(
+            store.rollback_concept(&id, 4),
+            Err(IcmError::InvalidInput(_))
+        ));
+        assert!(matches!(
+            store.rollback_concept(&id, 9),
+            Err(IcmError::NotFound(_))
+        ));
+
+        store.delete_concept(&id).unwrap();
+        assert!(store.concept_revisions(&id).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_link_weight_reinforce_and_decay() {
+        let store = test_store();
+        let m_id = store.create_memoir(make_memoir("proj")).unwrap();
+        let a = store.add_concept(make_concept(&m_id, "A", "a")).unwrap();
+        let b = store.add_concept(make_concept(&m_id, "B", "b")).unwrap();
+        let c = store.add_concept(make_concept(&m_id, "C", "c")).unwrap();
+        let ab = store
+            .add_link(ConceptLink::new(a.clone(), b.clone(), Relation::DependsOn))
+            .unwrap();
+        store
+            .add_link(ConceptLink::new(a.clone(), c.clone(), Relation::RelatedTo))
+            .unwrap();
+        let weight = |to: &str| {
+            store
+                .get_links_from(&a)
+                .unwrap()
+                .into_iter()
+                .find(|l| l.target_id == to)
+                .unwrap()
+                .weight
+        };
+
+        // Only A → B has both endpoints in the result set.
+        assert_eq!(store.reinforce_links(&[a.as_str(), b.as_str()]).unwrap(), 1);
+        assert!((weight(&b) - 1.1).abs() < 1e-6);
+        assert_eq!(weight(&c), 1.0);
+
+        store.update_link_weight(&ab, 10.0).unwrap();
+        assert_eq!(weight(&b), MAX_LINK_WEIGHT);
+        assert!(store.update_link_weight(&ab, f32::NAN).is_err());
+        assert!(matches!(
+            store.update_link_weight("nope", 1.0),
+            Err(IcmError::NotFound(_))
+        ));
+
+        assert_eq!(store.decay_links(0.5).unwrap(), 2);
+        assert!((weight(&b) - 1.5).abs() < 1e-6);
+        assert_eq!(weight(&c), 0.5);
+    }
+
+    #[test]
+    fn test_neighborhood_strongest_links_first() {
+        let store = test_store();
+        let m_id = store.create_memoir(make_memoir("proj")).unwrap();
+        let hub = store.add_concept(make_concept(&m_id, "hub", "h")).unwrap();
+        let weak = store.add_concept(make_concept(&m_id, "weak", "w")).unwrap();
+        let strong = store
+            .add_concept(make_concept(&m_id, "strong", "s"))
+            .unwrap();
+        store
+            .add_link(ConceptLink::new(
+                hub.clone(),
+                weak.clone(),
+                Relation::RelatedTo,
+            ))
+            .unwrap();
+        let to_hub = store
+            .add_link(ConceptLink::new(
+                strong.clone(),
+                hub.clone(),
+                Relation::PartOf,
+            ))
+            .unwrap();
+        // Both neighbours are visited at depth 2; the link between them
+        // must still be listed once.
+        store
+            .add_link(ConceptLink::new(
+                strong.clone(),
+                weak.clone(),
+                Relation::RelatedTo,
+            ))
+            .unwrap();
+        store.update_link_weight(&to_hub, 2.0).unwrap();
+
+        let (concepts, links) = store.get_neighborhood(&hub, 2).unwrap();
+        let names: Vec<&str> = concepts.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, ["hub", "strong", "weak"]);
+        assert_eq!(links[0].id, to_hub);
+        assert_eq!(links.len(), 3);
+    }
+
     #[test]
     fn test_get_neighbors() {
         let store = test_store();
@@ -4809,12 +7525,53 @@ mod tests {
         store.add_concept(c2).unwrap();
 
         let results = store
-            .search_concepts_by_label(&m_id, &Label::new("domain", "arch"), 10)
+            .search_concepts_by_labels(&m_id, &[Label::new("domain", "arch")], false, 10)
             .unwrap();
         assert_eq!(results.len(), 1);
         assert_eq!(results[0].name, "es");
     }
 
+    #[test]
+    fn test_search_concepts_by_labels_any_and_all() {
+        let store = test_store();
+        let m_id = store.create_memoir(make_memoir("proj")).unwrap();
+        let arch = Label::new("domain", "arch");
+        let decision = Label::new("type", "decision");
+
+        let mut both = make_concept(&m_id, "both", "d");
+        both.labels = vec![arch.clone(), decision.clone()];
+        both.confidence = 0.9;
+        store.add_concept(both).unwrap();
+        let mut one = make_concept(&m_id, "one", "d");
+        one.labels = vec![decision.clone()];
+        let one_id = store.add_concept(one).unwrap();
+        let other = store.create_memoir(make_memoir("other")).unwrap();
+        let mut elsewhere = make_concept(&other, "elsewhere", "d");
+        elsewhere.labels = vec![arch.clone(), decision.clone()];
+        store.add_concept(elsewhere).unwrap();
+
+        let names = |labels: &[Label], all: bool| -> Vec<String> {
+            store
+                .search_concepts_by_labels(&m_id, labels, all, 10)
+                .unwrap()
+                .into_iter()
+                .map(|c| c.name)
+                .collect()
+        };
+        let pair = [arch.clone(), decision.clone()];
+        assert_eq!(names(&pair, false), ["both", "one"]);
+        assert_eq!(names(&pair, true), ["both"]);
+        // A repeated label doesn't make ALL unsatisfiable.
+        assert_eq!(names(&[arch.clone(), arch.clone()], true), ["both"]);
+        assert!(names(&[], true).is_empty());
+
+        // Updating a concept's labels moves it between result sets.
+        let mut one = store.get_concept(&one_id).unwrap().unwrap();
+        one.labels.push(arch);
+        store.update_concept(&one).unwrap();
+        assert_eq!(names(&pair, true), ["both", "one"]);
+    }
+
     // === Vector search tests ===
 
     #[test]
@@ -4935,6 +7692,64 @@ mod tests {
         assert!(results[0].1 > 0.0);
     }
 
+    #[test]
+    fn test_search_hybrid_scored_breakdown_and_explain_rank() {
+        let store = test_store();
+
+        let mut mem = make_memory("rust", "Rust is great for systems programming");
+        mem.embedding = Some(vec![0.8; 384]);
+        let rust_id = store.store(mem).unwrap();
+
+        let mut mem2 = make_memory("python", "Python is great for scripting");
+        let mut emb2 = vec![0.0; 384];
+        emb2[1] = 1.0;
+        mem2.embedding = Some(emb2);
+        let python_id = store.store(mem2).unwrap();
+
+        let query_emb = vec![0.7; 384];
+        let scored = store
+            .search_hybrid_scored("rust programming", &query_emb, 5)
+            .unwrap();
+        let plain = store
+            .search_hybrid("rust programming", &query_emb, 5)
+            .unwrap();
+        assert_eq!(scored.len(), plain.len());
+        for ((m, s), (p, score)) in scored.iter().zip(&plain) {
+            assert_eq!(m.id, p.id);
+            assert_eq!(s.combined, *score);
+        }
+
+        // The Rust memory matched the text; Python only the vector side.
+        let (_, rust) = &scored[0];
+        assert!(rust.bm25.is_some_and(|r| r < 0.0));
+        assert!(rust.fts > 0.0 && rust.similarity.is_some());
+        let (_, python) = scored.iter().find(|(m, _)| m.id == python_id).unwrap();
+        assert_eq!((python.bm25, python.fts), (None, 0.0));
+
+        let now = Utc::now();
+        let why = icm_core::explain_rank(
+            &store,
+            "rust programming",
+            Some(&query_emb),
+            &python_id,
+            5,
+            now,
+            0.0,
+        )
+        .unwrap();
+        assert_eq!(why.rank, Some(2));
+        assert_eq!(why.candidates, 2);
+        assert_eq!(why.score, Some(python.combined));
+
+        // No embedding: FTS fallback ranks without scores.
+        let why = icm_core::explain_rank(&store, "rust", None, &rust_id, 5, now, 30.0).unwrap();
+        assert_eq!((why.rank, why.hybrid, why.score), (Some(1), None, None));
+        assert!(!why.hybrid_search);
+
+        let err = icm_core::explain_rank(&store, "rust", None, "nope", 5, now, 0.0).unwrap_err();
+        assert!(matches!(err, IcmError::NotFound(_)));
+    }
+
     #[test]
     fn test_sanitize_fts_query() {
         // Normal words get quoted
@@ -6008,6 +8823,48 @@ mod tests {
         assert_eq!(stats.most_applied[0].1, 1);
     }
 
+    #[test]
+    fn test_recall_feedback_adjusts_weight_and_records_signal() {
+        let store = test_store();
+        let mut mem = make_memory("api", "retry policy");
+        mem.weight = 0.5;
+        let id = store.store(mem).unwrap();
+        // Warm the cache so a stale weight would show up below.
+        store.get(&id).unwrap();
+
+        let fb = |signal, query: Option<&str>| {
+            RecallFeedback::new(id.clone(), signal, query.map(String::from), "cli".into())
+        };
+        let w = store
+            .record_recall_feedback(fb(RecallSignal::Noise, Some("retries")))
+            .unwrap();
+        assert!((w - 0.35).abs() < 1e-6);
+        assert!((store.get(&id).unwrap().unwrap().weight - 0.35).abs() < 1e-6);
+        let w = store
+            .record_recall_feedback(fb(RecallSignal::Useful, None))
+            .unwrap();
+        assert!((w - 0.45).abs() < 1e-6);
+
+        let signals = store.list_recall_feedback(Some(&id), 10).unwrap();
+        assert_eq!(signals.len(), 2);
+        assert_eq!(signals[0].signal, RecallSignal::Useful);
+        assert_eq!(signals[1].query.as_deref(), Some("retries"));
+        assert!(store
+            .list_recall_feedback(Some("other"), 10)
+            .unwrap()
+            .is_empty());
+
+        let stats = store.feedback_stats().unwrap();
+        assert_eq!((stats.useful, stats.noise), (1, 1));
+
+        let missing = RecallFeedback::new("nope".into(), RecallSignal::Useful, None, "cli".into());
+        assert!(matches!(
+            store.record_recall_feedback(missing),
+            Err(IcmError::NotFound(_))
+        ));
+        assert_eq!(store.list_recall_feedback(None, 10).unwrap().len(), 2);
+    }
+
     // === sanitize_fts_query tests ===
 
     #[test]
@@ -6116,6 +8973,37 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[test]
+    fn test_update_access_reinforces_weight() {
+        let mut store = test_store();
+        let mut mem = make_memory("t", "useful");
+        mem.weight = 0.5;
+        let id = store.store(mem).unwrap();
+        let mut fresh = make_memory("t", "fresh");
+        fresh.weight = 1.0;
+        let fresh_id = store.store(fresh).unwrap();
+
+        store.update_access(&id).unwrap();
+        let w = store.get(&id).unwrap().unwrap().weight;
+        assert!((w - 0.525).abs() < 1e-5, "{w}");
+
+        store.set_reinforcement(Reinforcement {
+            rate: 0.5,
+            cap: 0.6,
+        });
+        store.batch_update_access(&[&id, &fresh_id]).unwrap();
+        let w = store.get(&id).unwrap().unwrap().weight;
+        assert!((w - 0.6).abs() < 1e-5, "{w}");
+        // Above the cap: access counted, weight kept.
+        let fresh = store.get(&fresh_id).unwrap().unwrap();
+        assert_eq!((fresh.weight, fresh.access_count), (1.0, 1));
+
+        store.set_reinforcement(Reinforcement::OFF);
+        store.update_access(&id).unwrap();
+        let w = store.get(&id).unwrap().unwrap().weight;
+        assert!((w - 0.6).abs() < 1e-5, "{w}");
+    }
+
     #[test]
     fn test_batch_update_access() {
         let store = test_store();
@@ -6192,6 +9080,37 @@ mod tests {
         assert!((embedding[0] - 0.42).abs() < 1e-6);
     }
 
+    #[test]
+    fn test_consolidation_proposal_leaves_topic_untouched() {
+        let store = test_store();
+        assert!(store.consolidation_proposal("p", 3).unwrap().is_none());
+        for i in 0..4 {
+            store
+                .store(make_memory("p", &format!("entry {i}")))
+                .unwrap();
+        }
+        let proposal = store.consolidation_proposal("p", 3).unwrap().unwrap();
+        assert_eq!(proposal.topic, "p");
+        assert_eq!(store.count_by_topic("p").unwrap(), 4);
+    }
+
+    #[test]
+    fn test_auto_consolidate_keeps_originals_archived() {
+        let store = test_store();
+        for i in 0..5 {
+            store
+                .store(make_memory("k", &format!("entry {i}")))
+                .unwrap();
+        }
+        assert!(store
+            .auto_consolidate_with_opts("k", 5, None, true)
+            .unwrap());
+        assert_eq!(store.count_by_topic("k").unwrap(), 1);
+        assert_eq!(store.count_by_topic(&archived_topic("k")).unwrap(), 5);
+        // Archived originals stay searchable.
+        assert!(!store.search_fts("entry", 10).unwrap().is_empty());
+    }
+
     #[test]
     fn test_apply_decay_with_aggressive_factor() {
         let store = test_store();
@@ -6247,6 +9166,107 @@ mod tests {
         assert_eq!(results.len(), 2);
     }
 
+    #[test]
+    fn test_list_recent_orders_newest_first_and_filters() {
+        let store = test_store();
+        let now = Utc::now();
+        for (topic, summary, days_ago) in [
+            ("project:web", "old web", 10),
+            ("project:api", "api", 2),
+            ("project:web", "new web", 1),
+            ("other", "unrelated", 0),
+        ] {
+            let mut m = make_memory(topic, summary);
+            m.created_at = now - chrono::Duration::days(days_ago);
+            m.updated_at = m.created_at;
+            store.store(m).unwrap();
+        }
+        let mut convo = make_memory("project:web", "from a thread");
+        convo.source = MemorySource::Conversation {
+            thread_id: "t1".into(),
+        };
+        convo.created_at = now - chrono::Duration::days(3);
+        store.store(convo).unwrap();
+
+        let summaries = |ms: Vec<Memory>| ms.into_iter().map(|m| m.summary).collect::<Vec<_>>();
+        assert_eq!(
+            summaries(store.list_recent(None, None, None, 3).unwrap()),
+            ["unrelated", "new web", "api"]
+        );
+        assert_eq!(
+            summaries(
+                store
+                    .list_recent(Some("project:*"), None, None, 10)
+                    .unwrap()
+            ),
+            ["new web", "api", "from a thread", "old web"]
+        );
+        assert_eq!(
+            summaries(
+                store
+                    .list_recent(Some("project:web"), Some("manual"), None, 10)
+                    .unwrap()
+            ),
+            ["new web", "old web"]
+        );
+        let since = now - chrono::Duration::days(5);
+        assert_eq!(
+            summaries(
+                store
+                    .list_recent(Some("project:web"), None, Some(since), 10)
+                    .unwrap()
+            ),
+            ["new web", "from a thread"]
+        );
+    }
+
+    #[test]
+    fn test_memory_links_roundtrip_and_cascade() {
+        let store = test_store();
+        let error = store.store(make_memory("errors", "deadlock")).unwrap();
+        let fix = store.store(make_memory("errors", "lock order")).unwrap();
+        let note = store.store(make_memory("notes", "aside")).unwrap();
+
+        let link = |a: &str, b: &str, r| MemoryLink::new(a.into(), b.into(), r);
+        let id = store
+            .add_memory_link(link(&error, &fix, Relation::CausedBy))
+            .unwrap();
+        // Same triple again: no duplicate, same id.
+        assert_eq!(
+            store
+                .add_memory_link(link(&error, &fix, Relation::CausedBy))
+                .unwrap(),
+            id
+        );
+        store
+            .add_memory_link(link(&note, &fix, Relation::RelatedTo))
+            .unwrap();
+        assert!(matches!(
+            store.add_memory_link(link(&fix, &fix, Relation::Refines)),
+            Err(IcmError::InvalidInput(_))
+        ));
+        assert!(matches!(
+            store.add_memory_link(link(&fix, "nope", Relation::Refines)),
+            Err(IcmError::NotFound(_))
+        ));
+
+        let links = store.memory_links(&fix).unwrap();
+        assert_eq!(links.len(), 2);
+        assert_eq!(links[0].relation, Relation::CausedBy);
+        assert_eq!(links[0].other_end(&fix), error);
+
+        assert_eq!(
+            store
+                .remove_memory_link(&note, &fix, Some(Relation::Refines))
+                .unwrap(),
+            0
+        );
+        assert_eq!(store.remove_memory_link(&note, &fix, None).unwrap(), 1);
+
+        store.delete(&error).unwrap();
+        assert!(store.memory_links(&fix).unwrap().is_empty());
+    }
+
     // ── expand_with_neighbors ────────────────────────────────────────────
 
     #[test]
@@ -7220,6 +10240,177 @@ mod tests {
         assert_eq!(store.hook_event_count().unwrap(), 1);
     }
 
+    #[test]
+    fn test_session_injections_record_and_clear() {
+        let store = test_store();
+        let a = Memory::new("t".into(), "alpha".into(), Importance::Medium);
+        let mut b = Memory::new("t".into(), "beta".into(), Importance::Medium);
+        store.record_session_injections("s1", &[a]).unwrap();
+        store.record_session_injections("s2", &[b.clone()]).unwrap();
+
+        // Re-recording overwrites the stored `updated_at`.
+        b.updated_at += chrono::Duration::minutes(5);
+        store.record_session_injections("s2", &[b.clone()]).unwrap();
+        let s2 = store.session_injections("s2").unwrap();
+        assert_eq!(s2.len(), 1);
+        assert_eq!(s2[&b.id], b.updated_at);
+
+        let past = (chrono::Utc::now() - chrono::Duration::hours(1)).to_rfc3339();
+        assert_eq!(store.clear_session_injections("s1", &past).unwrap(), 1);
+        assert!(store.session_injections("s1").unwrap().is_empty());
+        assert!(store.session_injections("s2").unwrap().contains_key(&b.id));
+
+        // A future cutoff also sweeps other sessions' stale rows.
+        let future = (chrono::Utc::now() + chrono::Duration::hours(1)).to_rfc3339();
+        assert_eq!(store.clear_session_injections("s1", &future).unwrap(), 1);
+        assert!(store.session_injections("s2").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_session_context_record_read_and_forget() {
+        let store = test_store();
+        let sid = store
+            .ensure_session("sess-ctx", "claude-code", Some("icm"), None)
+            .unwrap();
+        store
+            .record_session_context(&SessionContextInsert {
+                session_id: sid.clone(),
+                event: "start".into(),
+                injected: "## Wake-up\n- use tabs".into(),
+                ..Default::default()
+            })
+            .unwrap();
+        store
+            .record_session_context(&SessionContextInsert {
+                session_id: sid.clone(),
+                event: "prompt".into(),
+                query: Some("why tabs?".into()),
+                injected: "- use tabs".into(),
+                memory_ids: vec!["m1".into(), "m2".into()],
+                facts_extracted: 0,
+            })
+            .unwrap();
+
+        let rows = store.session_context(&sid).unwrap();
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0].event, "start");
+        assert!(rows[0].memory_ids.is_empty());
+        assert_eq!(rows[1].query.as_deref(), Some("why tabs?"));
+        assert_eq!(rows[1].memory_ids, vec!["m1", "m2"]);
+
+        // Unknown session: the FK rejects the row.
+        let orphan = SessionContextInsert {
+            session_id: "nope".into(),
+            event: "post".into(),
+            facts_extracted: 1,
+            ..Default::default()
+        };
+        assert!(store.record_session_context(&orphan).is_err());
+
+        store.forget_session(&sid).unwrap();
+        assert!(store.session_context(&sid).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_refresh_topic_digests_keeps_heaviest_summaries() {
+        let store = test_store();
+        for (summary, weight) in [("light", 0.2), ("heavy", 0.9), ("middle", 0.5)] {
+            let mut m = Memory::new("decisions-icm".into(), summary.into(), Importance::Medium);
+            m.weight = weight;
+            store.store(m).unwrap();
+        }
+        store
+            .store(Memory::new(
+                "preferences".into(),
+                "tabs over   spaces\n".into(),
+                Importance::High,
+            ))
+            .unwrap();
+
+        assert_eq!(store.refresh_topic_digests(2).unwrap(), 2);
+        let digests = store.topic_digests().unwrap();
+        assert_eq!(digests.len(), 2);
+        assert_eq!(digests[0].topic, "decisions-icm");
+        assert_eq!(digests[0].digest, "heavy; middle");
+        assert_eq!(digests[0].memory_count, 3);
+        assert_eq!(digests[1].digest, "tabs over spaces");
+
+        // A refresh replaces digests of topics that are gone.
+        let prefs = store.get_by_topic("preferences").unwrap();
+        store.delete(&prefs[0].id).unwrap();
+        assert_eq!(store.refresh_topic_digests(2).unwrap(), 1);
+        assert_eq!(store.topic_digests().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_importance_centroids_are_cached_and_sampled() {
+        let store = SqliteStore::in_memory_with_dims(64).unwrap();
+        let axis = |k: usize| {
+            let mut v = vec![0.0; 64];
+            v[k] = 1.0;
+            v
+        };
+        let next = std::cell::Cell::new(0);
+        let add = |imp: Importance, axis_k: usize, n: usize, age_days: i64| {
+            for _ in 0..n {
+                next.set(next.get() + 1);
+                let mut m = Memory::new("t".into(), format!("{imp} note {}", next.get()), imp);
+                m.embedding = Some(axis(axis_k));
+                m.updated_at = Utc::now() - chrono::Duration::days(age_days);
+                store.store(m).unwrap();
+            }
+        };
+        let centroid = |imp: Importance| {
+            let centroids = store.importance_centroids().unwrap();
+            centroids
+                .into_iter()
+                .find(|(i, _)| *i == imp)
+                .map(|(_, c)| c)
+        };
+
+        // Older high memories fall outside the per-class sample.
+        add(Importance::High, 3, 3, 30);
+        add(Importance::High, 0, CENTROID_SAMPLES_PER_CLASS, 0);
+        add(Importance::Low, 1, 3, 0);
+        assert_eq!(centroid(Importance::High), Some(axis(0)));
+        assert_eq!(store.importance_centroids().unwrap().len(), 2);
+
+        // A partial sample is not cached: the next class shows up at once.
+        add(Importance::Medium, 2, 3, 0);
+        assert_eq!(store.importance_centroids().unwrap().len(), 3);
+
+        // Complete now, so cached: a few writes do not move it...
+        add(Importance::Low, 5, 3, 0);
+        assert_eq!(centroid(Importance::Low), Some(axis(1)));
+        // ...enough embedding writes do.
+        add(Importance::Low, 5, CENTROID_REFRESH_WRITES as usize, 0);
+        assert_ne!(centroid(Importance::Low), Some(axis(1)));
+
+        // So does age.
+        add(Importance::Medium, 6, 3, 0);
+        assert_eq!(centroid(Importance::Medium), Some(axis(2)));
+        let (generation, _, centroids): CentroidCache = serde_json::from_str(
+            &store
+                .conn
+                .query_row(
+                    "SELECT value FROM icm_metadata WHERE key = 'importance_centroids'",
+                    [],
+                    |row| row.get::<_, String>(0),
+                )
+                .unwrap(),
+        )
+        .unwrap();
+        let old = (Utc::now() - chrono::Duration::hours(CENTROID_TTL_HOURS + 1)).to_rfc3339();
+        store
+            .conn
+            .execute(
+                "UPDATE icm_metadata SET value = ?1 WHERE key = 'importance_centroids'",
+                params![serde_json::to_string(&(generation, old, centroids)).unwrap()],
+            )
+            .unwrap();
+        assert_ne!(centroid(Importance::Medium), Some(axis(2)));
+    }
+
     // ── code_areas (issue #196) ────────────────────────────────────────
 
     #[test]
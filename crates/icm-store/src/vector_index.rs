@@ -0,0 +1,607 @@
+//! Pluggable approximate-nearest-neighbour indexes for memory embeddings.
+//!
+//! The SQLite backend searches vectors with `sqlite-vec`'s `vec0` table,
+//! which is an exact brute-force scan: fine at ~10k memories, linear
+//! beyond. [`IndexKind::Hnsw`] swaps in an in-process HNSW graph that is
+//! persisted beside the database file and loaded on open. The `vec0`
+//! table stays authoritative either way, so the HNSW file can always be
+//! thrown away and rebuilt (`icm index rebuild`).
+//!
+//! Several processes (`icm serve`, hooks, the CLI) share one database,
+//! each with its own copy of the graph. Every embedding write or delete
+//! bumps `icm_metadata.vector_generation` and stamps the memory in
+//! `vector_changes` (schema triggers), so a copy that is behind catches
+//! up on open and before each search, whichever process made the change.
+
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU64, Ordering as AtomicOrdering};
+
+use icm_core::{IcmError, IcmResult};
+
+/// Which vector index backs `search_by_embedding`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum IndexKind {
+    /// Exact search over the `sqlite-vec` table (default).
+    #[default]
+    SqliteVec,
+    /// HNSW graph persisted next to the database.
+    Hnsw,
+}
+
+impl FromStr for IndexKind {
+    type Err = IcmError;
+
+    fn from_str(s: &str) -> IcmResult<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "" | "sqlite-vec" | "flat" | "brute-force" => Ok(Self::SqliteVec),
+            "hnsw" => Ok(Self::Hnsw),
+            other => Err(IcmError::Config(format!(
+                "unknown vector index '{other}' (expected 'sqlite-vec' or 'hnsw')"
+            ))),
+        }
+    }
+}
+
+impl std::fmt::Display for IndexKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::SqliteVec => "sqlite-vec",
+            Self::Hnsw => "hnsw",
+        })
+    }
+}
+
+/// Where the HNSW index for the database at `db_path` lives.
+pub fn index_path(db_path: &Path) -> PathBuf {
+    let mut name = db_path.file_name().unwrap_or_default().to_os_string();
+    name.push(".hnsw");
+    db_path.with_file_name(name)
+}
+
+/// An approximate nearest-neighbour index over memory embeddings.
+///
+/// Entries may go stale (another process deleted the memory since the
+/// last catch-up); callers resolve returned IDs against the store and
+/// drop the missing ones.
+pub trait VectorIndex: Send {
+    /// Insert `vector` for `id`, replacing any previous entry.
+    fn upsert(&mut self, id: &str, vector: &[f32]);
+
+    /// Forget `id`. Unknown IDs are ignored.
+    fn remove(&mut self, id: &str);
+
+    /// Up to `k` nearest IDs with their cosine distance, closest first.
+    fn search(&self, query: &[f32], k: usize) -> Vec<(String, f32)>;
+
+    /// The `vector_generation` whose changes the index reflects.
+    fn generation(&self) -> u64;
+
+    /// Record that the index now reflects `generation`.
+    fn set_generation(&mut self, generation: u64);
+
+    /// Number of live entries.
+    fn len(&self) -> usize;
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Persist the index to `path`.
+    fn save(&self, path: &Path) -> IcmResult<()>;
+}
+
+/// Max links per node on the upper layers; layer 0 gets twice as many.
+const M: usize = 16;
+const EF_CONSTRUCTION: usize = 100;
+const EF_SEARCH: usize = 64;
+const MAGIC: &[u8; 8] = b"ICMHNSW2";
+
+struct Node {
+    id: String,
+    /// Unit-normalized, so cosine distance is `1 - dot`.
+    vector: Vec<f32>,
+    links: Vec<Vec<u32>>,
+    deleted: bool,
+}
+
+/// Hierarchical navigable small-world graph (Malkov & Yashunin).
+///
+/// Removals are tombstones: the node keeps routing searches but is never
+/// returned. A rebuild compacts them away.
+pub struct HnswIndex {
+    dims: usize,
+    generation: u64,
+    nodes: Vec<Node>,
+    by_id: HashMap<String, u32>,
+    entry: Option<u32>,
+    max_level: usize,
+}
+
+#[derive(PartialEq)]
+struct Candidate(f32, u32);
+
+impl Eq for Candidate {}
+
+impl PartialOrd for Candidate {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Candidate {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.total_cmp(&other.0).then(self.1.cmp(&other.1))
+    }
+}
+
+fn normalized(v: &[f32]) -> Vec<f32> {
+    let norm = v.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if norm == 0.0 {
+        return v.to_vec();
+    }
+    v.iter().map(|x| x / norm).collect()
+}
+
+fn distance(a: &[f32], b: &[f32]) -> f32 {
+    1.0 - a.iter().zip(b).map(|(x, y)| x * y).sum::<f32>()
+}
+
+/// 64-bit FNV-1a. Unlike `DefaultHasher` its output is fixed, so a
+/// persisted graph keeps its shape across toolchain upgrades.
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |h, &b| {
+        (h ^ u64::from(b)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+/// Deterministic layer assignment from the ID, so rebuilding the same
+/// data yields the same graph without pulling in an RNG.
+fn level_for(id: &str) -> usize {
+    let u = (fnv1a(id.as_bytes()) >> 11) as f64 / (1u64 << 53) as f64;
+    let ml = 1.0 / (M as f64).ln();
+    (-(u.max(f64::MIN_POSITIVE)).ln() * ml).floor() as usize
+}
+
+impl HnswIndex {
+    pub fn new(dims: usize) -> Self {
+        Self {
+            dims,
+            generation: 0,
+            nodes: Vec::new(),
+            by_id: HashMap::new(),
+            entry: None,
+            max_level: 0,
+        }
+    }
+
+    pub fn dims(&self) -> usize {
+        self.dims
+    }
+
+    fn dist_to(&self, query: &[f32], node: u32) -> f32 {
+        distance(query, &self.nodes[node as usize].vector)
+    }
+
+    /// Best-first search of one layer; returns up to `ef` candidates,
+    /// closest first.
+    fn search_layer(
+        &self,
+        query: &[f32],
+        entry: &[u32],
+        ef: usize,
+        level: usize,
+    ) -> Vec<Candidate> {
+        let mut visited: HashSet<u32> = entry.iter().copied().collect();
+        // Min-heap of frontier (via Reverse), max-heap of results.
+        let mut frontier: BinaryHeap<std::cmp::Reverse<Candidate>> = BinaryHeap::new();
+        let mut best: BinaryHeap<Candidate> = BinaryHeap::new();
+        for &e in entry {
+            let d = self.dist_to(query, e);
+            frontier.push(std::cmp::Reverse(Candidate(d, e)));
+            best.push(Candidate(d, e));
+        }
+        while let Some(std::cmp::Reverse(Candidate(d, node))) = frontier.pop() {
+            if best.len() >= ef && best.peek().is_some_and(|w| d > w.0) {
+                break;
+            }
+            let Some(links) = self.nodes[node as usize].links.get(level) else {
+                continue;
+            };
+            for &n in links {
+                if !visited.insert(n) {
+                    continue;
+                }
+                let dn = self.dist_to(query, n);
+                if best.len() < ef || best.peek().is_some_and(|w| dn < w.0) {
+                    frontier.push(std::cmp::Reverse(Candidate(dn, n)));
+                    best.push(Candidate(dn, n));
+                    if best.len() > ef {
+                        best.pop();
+                    }
+                }
+            }
+        }
+        best.into_sorted_vec()
+    }
+
+    /// Walk from the entry point down to `target_level + 1`, keeping only
+    /// the single closest node per layer.
+    fn descend(&self, query: &[f32], target_level: usize) -> Option<u32> {
+        let mut ep = self.entry?;
+        for level in (target_level + 1..=self.max_level).rev() {
+            if let Some(c) = self.search_layer(query, &[ep], 1, level).first() {
+                ep = c.1;
+            }
+        }
+        Some(ep)
+    }
+
+    fn prune(&mut self, node: u32, level: usize, cap: usize) {
+        let base = self.nodes[node as usize].vector.clone();
+        let mut links: Vec<Candidate> = self.nodes[node as usize].links[level]
+            .iter()
+            .map(|&n| Candidate(distance(&base, &self.nodes[n as usize].vector), n))
+            .collect();
+        links.sort();
+        links.truncate(cap);
+        self.nodes[node as usize].links[level] = links.into_iter().map(|c| c.1).collect();
+    }
+
+    /// Load an index written by [`VectorIndex::save`]. Fails if the file
+    /// is corrupt or was built for a different embedding size.
+    pub fn load(path: &Path, dims: usize) -> IcmResult<Self> {
+        let bytes = std::fs::read(path)
+            .map_err(|e| IcmError::Database(format!("cannot read {}: {e}", path.display())))?;
+        let corrupt = || IcmError::Database(format!("corrupt vector index {}", path.display()));
+        let mut r = Reader {
+            bytes: &bytes,
+            pos: 0,
+        };
+        if r.take(MAGIC.len()).ok_or_else(corrupt)? != MAGIC {
+            return Err(corrupt());
+        }
+        let stored_dims = r.u32().ok_or_else(corrupt)? as usize;
+        if stored_dims != dims {
+            return Err(IcmError::Database(format!(
+                "vector index {} has {stored_dims} dims, expected {dims}; run `icm index rebuild`",
+                path.display()
+            )));
+        }
+        let generation = r.u64().ok_or_else(corrupt)?;
+        let count = r.u32().ok_or_else(corrupt)? as usize;
+        let entry = r.u32().ok_or_else(corrupt)?;
+        let max_level = r.u32().ok_or_else(corrupt)? as usize;
+
+        let mut index = Self::new(dims);
+        index.generation = generation;
+        index.max_level = max_level;
+        index.entry = (count > 0).then_some(entry);
+        for i in 0..count {
+            let id_len = r.u32().ok_or_else(corrupt)? as usize;
+            let id = String::from_utf8(r.take(id_len).ok_or_else(corrupt)?.to_vec())
+                .map_err(|_| corrupt())?;
+            let deleted = r.take(1).ok_or_else(corrupt)?[0] != 0;
+            let vector = (0..dims)
+                .map(|_| r.f32())
+                .collect::<Option<Vec<_>>>()
+                .ok_or_else(corrupt)?;
+            let levels = r.u32().ok_or_else(corrupt)? as usize;
+            let mut links = Vec::with_capacity(levels);
+            for _ in 0..levels {
+                let n = r.u32().ok_or_else(corrupt)? as usize;
+                let layer = (0..n)
+                    .map(|_| r.u32())
+                    .collect::<Option<Vec<_>>>()
+                    .ok_or_else(corrupt)?;
+                if layer.iter().any(|&l| l as usize >= count) {
+                    return Err(corrupt());
+                }
+                links.push(layer);
+            }
+            if !deleted {
+                index.by_id.insert(id.clone(), i as u32);
+            }
+            index.nodes.push(Node {
+                id,
+                vector,
+                links,
+                deleted,
+            });
+        }
+        Ok(index)
+    }
+
+    /// The generation recorded in the index file at `path`, reading only
+    /// its header. `None` when the file is missing or not an index.
+    pub fn stored_generation(path: &Path) -> Option<u64> {
+        use std::io::Read;
+        let mut header = [0u8; 20];
+        std::fs::File::open(path)
+            .and_then(|mut f| f.read_exact(&mut header))
+            .ok()?;
+        let mut r = Reader {
+            bytes: &header,
+            pos: 0,
+        };
+        if r.take(MAGIC.len())? != MAGIC {
+            return None;
+        }
+        r.u32()?;
+        r.u64()
+    }
+}
+
+/// Distinguishes temporary files of concurrent saves within a process;
+/// the PID separates processes.
+static SAVE_SEQ: AtomicU64 = AtomicU64::new(0);
+
+impl VectorIndex for HnswIndex {
+    fn upsert(&mut self, id: &str, vector: &[f32]) {
+        if vector.len() != self.dims {
+            tracing::warn!(
+                id,
+                got = vector.len(),
+                expected = self.dims,
+                "hnsw: skipping vector with wrong dimensions"
+            );
+            return;
+        }
+        self.remove(id);
+        let query = normalized(vector);
+        let idx = self.nodes.len() as u32;
+        let level = level_for(id);
+        self.nodes.push(Node {
+            id: id.to_string(),
+            vector: query.clone(),
+            links: vec![Vec::new(); level + 1],
+            deleted: false,
+        });
+        self.by_id.insert(id.to_string(), idx);
+
+        let Some(ep) = self.descend(&query, level) else {
+            self.entry = Some(idx);
+            self.max_level = level;
+            return;
+        };
+        let mut eps = vec![ep];
+        for l in (0..=level.min(self.max_level)).rev() {
+            let found = self.search_layer(&query, &eps, EF_CONSTRUCTION, l);
+            let cap = if l == 0 { 2 * M } else { M };
+            let neighbors: Vec<u32> = found.iter().take(M).map(|c| c.1).collect();
+            self.nodes[idx as usize].links[l] = neighbors.clone();
+            for n in neighbors {
+                self.nodes[n as usize].links[l].push(idx);
+                if self.nodes[n as usize].links[l].len() > cap {
+                    self.prune(n, l, cap);
+                }
+            }
+            eps = found.into_iter().map(|c| c.1).collect();
+        }
+        if level > self.max_level {
+            self.entry = Some(idx);
+            self.max_level = level;
+        }
+    }
+
+    fn remove(&mut self, id: &str) {
+        if let Some(idx) = self.by_id.remove(id) {
+            self.nodes[idx as usize].deleted = true;
+        }
+    }
+
+    fn search(&self, query: &[f32], k: usize) -> Vec<(String, f32)> {
+        if k == 0 || query.len() != self.dims {
+            return Vec::new();
+        }
+        let query = normalized(query);
+        let Some(ep) = self.descend(&query, 0) else {
+            return Vec::new();
+        };
+        self.search_layer(&query, &[ep], EF_SEARCH.max(k), 0)
+            .into_iter()
+            .filter(|c| !self.nodes[c.1 as usize].deleted)
+            .take(k)
+            .map(|c| (self.nodes[c.1 as usize].id.clone(), c.0))
+            .collect()
+    }
+
+    fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    fn set_generation(&mut self, generation: u64) {
+        self.generation = generation;
+    }
+
+    fn len(&self) -> usize {
+        self.by_id.len()
+    }
+
+    fn save(&self, path: &Path) -> IcmResult<()> {
+        let mut out = Vec::new();
+        out.extend_from_slice(MAGIC);
+        out.extend_from_slice(&(self.dims as u32).to_le_bytes());
+        out.extend_from_slice(&self.generation.to_le_bytes());
+        for v in [
+            self.nodes.len(),
+            self.entry.unwrap_or(0) as usize,
+            self.max_level,
+        ] {
+            out.extend_from_slice(&(v as u32).to_le_bytes());
+        }
+        for node in &self.nodes {
+            out.extend_from_slice(&(node.id.len() as u32).to_le_bytes());
+            out.extend_from_slice(node.id.as_bytes());
+            out.push(u8::from(node.deleted));
+            for x in &node.vector {
+                out.extend_from_slice(&x.to_le_bytes());
+            }
+            out.extend_from_slice(&(node.links.len() as u32).to_le_bytes());
+            for layer in &node.links {
+                out.extend_from_slice(&(layer.len() as u32).to_le_bytes());
+                for n in layer {
+                    out.extend_from_slice(&n.to_le_bytes());
+                }
+            }
+        }
+        // Write-then-rename so a crash never leaves a torn index behind.
+        // The temporary name is unique per writer, so concurrent savers
+        // never write into each other's file.
+        let mut tmp = path.file_name().unwrap_or_default().to_os_string();
+        tmp.push(format!(
+            ".{}-{}.tmp",
+            std::process::id(),
+            SAVE_SEQ.fetch_add(1, AtomicOrdering::Relaxed)
+        ));
+        let tmp = path.with_file_name(tmp);
+        std::fs::write(&tmp, &out)
+            .and_then(|()| std::fs::rename(&tmp, path))
+            .map_err(|e| {
+                let _ = std::fs::remove_file(&tmp);
+                IcmError::Database(format!("cannot write {}: {e}", path.display()))
+            })
+    }
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, n: usize) -> Option<&'a [u8]> {
+        let end = self.pos.checked_add(n)?;
+        let slice = self.bytes.get(self.pos..end)?;
+        self.pos = end;
+        Some(slice)
+    }
+
+    fn u32(&mut self) -> Option<u32> {
+        self.take(4)
+            .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+    }
+
+    fn u64(&mut self) -> Option<u64> {
+        self.take(8).map(|b| {
+            let mut le = [0u8; 8];
+            le.copy_from_slice(b);
+            u64::from_le_bytes(le)
+        })
+    }
+
+    fn f32(&mut self) -> Option<f32> {
+        self.u32().map(f32::from_bits)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vector(seed: u32, dims: usize) -> Vec<f32> {
+        // Cheap deterministic pseudo-random vectors.
+        let mut x = seed.wrapping_mul(2_654_435_761).wrapping_add(1);
+        (0..dims)
+            .map(|_| {
+                x ^= x << 13;
+                x ^= x >> 17;
+                x ^= x << 5;
+                (x % 2000) as f32 / 1000.0 - 1.0
+            })
+            .collect()
+    }
+
+    fn exact_nearest(data: &[Vec<f32>], q: &[f32]) -> usize {
+        let q = normalized(q);
+        (0..data.len())
+            .min_by(|&a, &b| {
+                distance(&normalized(&data[a]), &q).total_cmp(&distance(&normalized(&data[b]), &q))
+            })
+            .unwrap()
+    }
+
+    #[test]
+    fn finds_exact_neighbours_on_small_sets() {
+        let dims = 16;
+        let data: Vec<Vec<f32>> = (0..500).map(|i| vector(i, dims)).collect();
+        let mut index = HnswIndex::new(dims);
+        for (i, v) in data.iter().enumerate() {
+            index.upsert(&format!("m{i}"), v);
+        }
+        assert_eq!(index.len(), 500);
+
+        let mut hits = 0;
+        for q in 0..50 {
+            let query = vector(10_000 + q, dims);
+            let want = format!("m{}", exact_nearest(&data, &query));
+            let got = index.search(&query, 1);
+            if got.first().map(|(id, _)| id) == Some(&want) {
+                hits += 1;
+            }
+        }
+        assert!(hits >= 47, "recall@1 too low: {hits}/50");
+    }
+
+    #[test]
+    fn upsert_replaces_and_remove_hides() {
+        let mut index = HnswIndex::new(2);
+        index.upsert("a", &[1.0, 0.0]);
+        index.upsert("b", &[0.0, 1.0]);
+        index.upsert("a", &[0.0, 1.0]);
+        assert_eq!(index.len(), 2);
+        let hits = index.search(&[0.0, 1.0], 2);
+        assert!(hits.iter().all(|(_, d)| *d < 1e-5), "{hits:?}");
+
+        index.remove("b");
+        let hits = index.search(&[0.0, 1.0], 5);
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].0, "a");
+    }
+
+    #[test]
+    fn save_and_load_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = index_path(&dir.path().join("memories.db"));
+        assert!(path.ends_with("memories.db.hnsw"));
+
+        let mut index = HnswIndex::new(8);
+        for i in 0..40 {
+            index.upsert(&format!("m{i}"), &vector(i, 8));
+        }
+        index.remove("m3");
+        index.set_generation(42);
+        index.save(&path).unwrap();
+        assert_eq!(HnswIndex::stored_generation(&path), Some(42));
+
+        let loaded = HnswIndex::load(&path, 8).unwrap();
+        assert_eq!(loaded.len(), 39);
+        assert_eq!(loaded.generation(), 42);
+        let q = vector(7, 8);
+        assert_eq!(loaded.search(&q, 3), index.search(&q, 3));
+        assert!(HnswIndex::load(&path, 16).is_err());
+    }
+
+    #[test]
+    fn levels_are_stable_across_builds() {
+        // Pinned values: a change here would reshape every saved graph.
+        assert_eq!(fnv1a(b""), 0xcbf2_9ce4_8422_2325);
+        assert_eq!(fnv1a(b"a"), 0xaf63_dc4c_8601_ec8c);
+        let levels: Vec<usize> = (0..2000).map(|i| level_for(&format!("m{i}"))).collect();
+        let upper = levels.iter().filter(|&&l| l > 0).count();
+        // With M = 16, about 1 in 16 nodes lands above layer 0.
+        assert!((60..=200).contains(&upper), "{upper} upper-layer nodes");
+    }
+
+    #[test]
+    fn parses_index_kind() {
+        assert_eq!("hnsw".parse::<IndexKind>().unwrap(), IndexKind::Hnsw);
+        assert_eq!(
+            "sqlite-vec".parse::<IndexKind>().unwrap(),
+            IndexKind::SqliteVec
+        );
+        assert!("faiss".parse::<IndexKind>().is_err());
+    }
+}
@@ -0,0 +1,159 @@
+//! Client-side recall for web agent UIs.
+//!
+//! Exposes [`PackIndex`] to JavaScript: load a memory pack (`icm pack
+//! create`) once, then recall over it in the browser with the same
+//! scoring as the CLI's SQLite search, no server round-trip. Packs are
+//! gzip'd, so unwrap them first:
+//!
+//! ```js
+//! import init, { PackIndex } from "./pkg/icm_wasm.js";
+//! await init();
+//! const body = (await fetch("team.icmpack")).body
+//!   .pipeThrough(new DecompressionStream("gzip"));
+//! const index = new PackIndex(await new Response(body).text());
+//! const hits = JSON.parse(index.recall("release sign-off", 5));
+//! ```
+//!
+//! The pack signature is **not** checked here (no ed25519 in the
+//! browser build): load packs from a source you already trust, or
+//! verify them with `icm pack mount` first.
+
+use icm_core::{HybridScore, Importance, Memory, MemoryIndex};
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+/// The outer layer of a pack file, after gunzip.
+#[derive(Deserialize)]
+struct Envelope {
+    format: String,
+    contents: String,
+}
+
+/// One recall hit, as serialized for JavaScript.
+#[derive(Serialize)]
+struct Hit<'a> {
+    id: &'a str,
+    topic: &'a str,
+    summary: &'a str,
+    keywords: &'a [String],
+    importance: Importance,
+    weight: f32,
+    updated_at: String,
+    /// Blended hybrid score (0..=1), as `icm recall` ranks.
+    score: f32,
+    /// Raw FTS rank (negative bm25), when the text matched.
+    bm25: Option<f32>,
+    /// Cosine similarity, when an embedding was compared.
+    similarity: Option<f32>,
+}
+
+/// Memories of one pack, indexed for recall.
+#[wasm_bindgen]
+#[derive(Debug)]
+pub struct PackIndex {
+    index: MemoryIndex,
+}
+
+#[wasm_bindgen]
+impl PackIndex {
+    /// Index `json`: a gunzipped pack file, the pack's contents, or a JSON
+    /// array of memories.
+    #[wasm_bindgen(constructor)]
+    pub fn new(json: &str) -> Result<PackIndex, String> {
+        let index = match serde_json::from_str::<Envelope>(json) {
+            Ok(env) if env.format.starts_with("icm-pack/") => MemoryIndex::from_json(&env.contents),
+            _ => MemoryIndex::from_json(json),
+        };
+        index
+            .map(|index| Self { index })
+            .map_err(|e| format!("invalid memory pack: {e}"))
+    }
+
+    /// Number of memories in the pack.
+    pub fn len(&self) -> usize {
+        self.index.len()
+    }
+
+    #[wasm_bindgen(js_name = isEmpty)]
+    pub fn is_empty(&self) -> bool {
+        self.index.is_empty()
+    }
+
+    /// Full-text recall: a JSON array of at most `limit` hits, best first.
+    pub fn recall(&self, query: &str, limit: usize) -> String {
+        hits_json(self.index.search(query, None, limit))
+    }
+
+    /// Hybrid recall with a query embedding computed by the caller (same
+    /// model as the pack's `embedding_model`, query prefix applied).
+    #[wasm_bindgen(js_name = recallWithEmbedding)]
+    pub fn recall_with_embedding(&self, query: &str, embedding: &[f32], limit: usize) -> String {
+        hits_json(self.index.search(query, Some(embedding), limit))
+    }
+}
+
+fn hits_json(hits: Vec<(&Memory, HybridScore)>) -> String {
+    let hits: Vec<Hit<'_>> = hits
+        .into_iter()
+        .map(|(m, score)| Hit {
+            id: &m.id,
+            topic: &m.topic,
+            summary: &m.summary,
+            keywords: &m.keywords,
+            importance: m.importance,
+            weight: m.weight,
+            updated_at: m.updated_at.to_rfc3339(),
+            score: score.combined,
+            bm25: score.bm25,
+            similarity: score.similarity,
+        })
+        .collect();
+    serde_json::to_string(&hits).unwrap_or_else(|_| "[]".into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pack_contents() -> String {
+        let memories = vec![
+            Memory::new(
+                "decisions-app".into(),
+                "Release needs sign-off".into(),
+                Importance::High,
+            ),
+            Memory::new(
+                "notes".into(),
+                "Wiki lives on Confluence".into(),
+                Importance::Low,
+            ),
+        ];
+        serde_json::json!({ "name": "team", "memories": memories, "memoirs": [] }).to_string()
+    }
+
+    #[test]
+    fn loads_envelope_or_contents_and_recalls_json() {
+        let contents = pack_contents();
+        let envelope = serde_json::json!({
+            "format": "icm-pack/1",
+            "contents": contents,
+            "public_key": "",
+            "signature": "",
+        })
+        .to_string();
+        for json in [&contents, &envelope] {
+            let index = PackIndex::new(json).unwrap();
+            assert_eq!(index.len(), 2);
+            let hits: serde_json::Value =
+                serde_json::from_str(&index.recall("sign-off", 5)).unwrap();
+            assert_eq!(hits.as_array().unwrap().len(), 1);
+            assert_eq!(hits[0]["topic"], "decisions-app");
+            assert_eq!(hits[0]["importance"], "high");
+            assert!(hits[0]["bm25"].as_f64().unwrap() < 0.0);
+            assert!(hits[0]["similarity"].is_null());
+        }
+        assert!(PackIndex::new("not json")
+            .unwrap_err()
+            .starts_with("invalid memory pack"));
+    }
+}